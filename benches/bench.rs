@@ -1,68 +1,97 @@
-#![feature(test)]
-extern crate test;
-use test::Bencher;
+//! Timings of the curve maps and compression, run with `cargo bench`.
+//!
+//! Each benchmark runs in batches of doubling size until a batch takes at least a tenth of a
+//! second, and reports the mean time per iteration of that batch. Arguments other than flags
+//! select the benchmarks whose names contain any of them.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
 
-extern crate graph_layout;
-use graph_layout::layout::*;
 use graph_layout::compression::*;
+use graph_layout::layout::*;
 
-#[bench]
-fn encode_decode_h(bencher: &mut Bencher) {
-    let tangler = Hilbert::new();
-    let mut index = 0;
-    bencher.iter(|| { index += 1; assert!(index == tangler.entangle(tangler.detangle(index))) });
-}
+fn main() {
+    let filters = ::std::env::args().skip(1).filter(|argument| !argument.starts_with("--")).collect::<Vec<_>>();
+    let bench = |name: &str, routine: &mut dyn FnMut()| {
+        if filters.is_empty() || filters.iter().any(|filter| name.contains(&filter[..])) {
+            let mut iterations = 1u64;
+            loop {
+                let start = Instant::now();
+                for _ in 0 .. iterations { routine(); }
+                let elapsed = start.elapsed();
+                if elapsed >= Duration::from_millis(100) {
+                    println!("{:<20} {:>12.2} ns/iter", name, elapsed.as_nanos() as f64 / iterations as f64);
+                    break;
+                }
+                iterations *= 2;
+            }
+        }
+    };
 
-#[bench]
-fn encode_h(bencher: &mut Bencher) {
     let tangler = Hilbert::new();
     let mut index = 0;
-    bencher.iter(|| { index += 1; tangler.entangle((index, 7u32)) });
-}
-
-#[bench]
-fn decode_h(bencher: &mut Bencher) {
-    let tangler = Hilbert::new();
+    bench("encode_decode_h", &mut || { index += 1; assert!(index == tangler.entangle(tangler.detangle(index))) });
     let mut index = 0;
-    bencher.iter(|| { index += 1; tangler.detangle(index) });
-}
-
-#[bench]
-fn encode_decode_z(bencher: &mut Bencher) {
-    let tangler = ZOrder::new();
+    bench("encode_h", &mut || { index += 1; black_box(tangler.entangle((index, 7u32))); });
     let mut index = 0;
-    bencher.iter(|| { index += 1; assert!(index == tangler.entangle(tangler.detangle(index))) });
-}
+    bench("decode_h", &mut || { index += 1; black_box(tangler.detangle(index)); });
 
-#[bench]
-fn encode_z(bencher: &mut Bencher) {
-    let tangler = ZOrder::new();
+    let hilbert = BytewiseHilbert::new();
     let mut index = 0;
-    bencher.iter(|| { index += 1; tangler.entangle((index, 7u32)) });
-}
+    bench("encode_decode_byte", &mut || {
+        let z = hilbert.entangle((index, 7u32));
+        let (x,y) = hilbert.detangle(z);
+        assert!((x,y) == (index, 7u32));
+        index += 1;
+    });
 
-#[bench]
-fn decode_z(bencher: &mut Bencher) {
     let tangler = ZOrder::new();
     let mut index = 0;
-    bencher.iter(|| { index += 1; tangler.detangle(index) });
-}
+    bench("encode_decode_z", &mut || { index += 1; assert!(index == tangler.entangle(tangler.detangle(index))) });
+    let mut index = 0;
+    bench("encode_z", &mut || { index += 1; black_box(tangler.entangle((index, 7u32))); });
+    let mut index = 0;
+    bench("decode_z", &mut || { index += 1; black_box(tangler.detangle(index)); });
 
-#[bench]
-fn compress_e(bencher: &mut Bencher) {
     let mut compressor = Compressor::with_capacity(1_000_000);
     let mut index = 0u64;
-    bencher.iter(|| {
+    bench("compress_e", &mut || {
         index += 1;
         compressor.push(index)
-    })
-}
+    });
 
-#[bench]
-fn compress_d(bencher: &mut Bencher) {
     let compressed = Compressed::from(0..1_000_000);
     let mut decompressor = compressed.decompress();
-    bencher.iter(|| {
-        decompressor.next()
+    bench("compress_d", &mut || {
+        black_box(decompressor.next());
+    });
+
+    // gaps that are mostly small, with escapes at irregular positions.
+    let mut value = 0u64;
+    let mixed = Compressed::from((0 .. 1_000_000u64).map(|index| {
+        let hash = index.wrapping_mul(0x9E3779B97F4A7C15) >> 58;
+        value += if hash < 3 { 1 << (8 + 4 * hash) } else { 1 + hash };
+        value
+    }));
+    bench("decode_ones", &mut || {
+        black_box(compressed.decompress().fold(0u64, |sum, value| sum ^ value));
+    });
+    bench("decode_mixed", &mut || {
+        black_box(mixed.decompress().fold(0u64, |sum, value| sum ^ value));
+    });
+
+    let ones = (0 .. 1_000_000u64).collect::<Vec<_>>();
+    let keys = mixed.decompress().collect::<Vec<_>>();
+    bench("compress_ones", &mut || {
+        black_box(Compressed::from(ones.iter().copied()));
+    });
+    bench("compress_ones_slice", &mut || {
+        black_box(Compressed::from_sorted_slice(&ones));
+    });
+    bench("compress_mixed", &mut || {
+        black_box(Compressed::from(keys.iter().copied()));
+    });
+    bench("compress_mixed_slice", &mut || {
+        black_box(Compressed::from_sorted_slice(&keys));
     });
 }
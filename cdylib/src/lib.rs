@@ -0,0 +1,13 @@
+//! The dynamic library of `graph_layout`, for use from C, Python, and JavaScript.
+//!
+//! The `graph_layout` crate builds only as a Rust library, so that its dependents do not each link
+//! a dynamic library they never use. This crate re-exports it as a `cdylib` named `graph_layout`,
+//! whose exported symbols are chosen by features forwarded to it:
+//!
+//! * `cdylib`, on by default: the C interface of `graph_layout::ffi`, declared in
+//!   `include/graph_layout.h`.
+//! * `python`: the Python module `graph_layout`, for example with
+//!   `maturin build -m cdylib/Cargo.toml --features python`.
+//! * `wasm`: the JavaScript bindings, for `wasm-bindgen` once built for `wasm32-unknown-unknown`.
+
+pub use graph_layout::*;
@@ -0,0 +1,47 @@
+/*
+ * C interface to graph_layout, exported by the library the cdylib/ crate builds.
+ *
+ * Kept in step with src/ffi.rs, where each function is documented, by the test
+ * `c_header_declares_exports`. Edges are interleaved source and destination pairs; functions
+ * filling a buffer return the number of values available and write them only if `capacity`
+ * suffices.
+ */
+
+#ifndef GRAPH_LAYOUT_H
+#define GRAPH_LAYOUT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A layout read from a file; release with gl_layout_free. */
+typedef struct gl_layout gl_layout;
+
+uint64_t gl_entangle(uint32_t src, uint32_t dst);
+void gl_detangle(uint64_t key, uint32_t *src, uint32_t *dst);
+void gl_entangle_batch(const uint32_t *edges, size_t count, uint64_t *keys);
+void gl_detangle_batch(const uint64_t *keys, size_t count, uint32_t *edges);
+
+/* Returns SIZE_MAX if the keys are not strictly increasing. */
+size_t gl_encode(const uint64_t *keys, size_t count, uint8_t *bytes, size_t capacity);
+/* Returns zero on success and a negative value for a malformed encoding. */
+int gl_decode(const uint8_t *bytes, size_t length, size_t count, uint64_t *keys);
+
+/* Returns NULL if the file cannot be read. */
+gl_layout *gl_layout_open(const char *path);
+void gl_layout_free(gl_layout *layout);
+size_t gl_layout_edges(const gl_layout *layout);
+uint64_t gl_layout_vertices(const gl_layout *layout);
+int gl_layout_is_weighted(const gl_layout *layout);
+size_t gl_layout_copy_edges(const gl_layout *layout, uint32_t *edges, size_t capacity);
+size_t gl_layout_copy_keys(const gl_layout *layout, uint64_t *keys, size_t capacity);
+size_t gl_layout_copy_weights(const gl_layout *layout, double *weights, size_t capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,135 @@
+//! `Abomonation` implementations, with the `abomonation` feature.
+//!
+//! Abomonation encodes a value as its own memory followed by the contents of its vectors, and
+//! decodes it in place, so that timely dataflow workers exchange layouts and compressed keys
+//! without re-encoding them. The encoding is the host's memory layout, and is read only by the
+//! same build on the same architecture; layout files remain the format for storage.
+//!
+//! Abomonation writes the contents of vectors one after another, so that a vector of `u64`s after
+//! one of bytes would be decoded from unaligned memory. The implementations here pad the contents
+//! of each field to a multiple of eight bytes, which keeps every vector aligned as long as the
+//! buffer decoded from is.
+//!
+//! Structs implement the trait with `abomonate!`, next to their definitions, except for
+//! `GraphLayout`, whose columns are padded name by name below.
+
+use std::io::{Result, Write};
+use std::mem;
+
+use abomonation::Abomonation;
+
+use crate::column::Column;
+use crate::graph::GraphLayout;
+
+/// Implements `Abomonation` for the struct `$type`, whose heap data are in the listed fields.
+macro_rules! abomonate {
+    ($type:ty $(: $($field:ident),*)?) => {
+        impl ::abomonation::Abomonation for $type {
+            unsafe fn entomb<W: ::std::io::Write>(&self, _write: &mut W) -> ::std::io::Result<()> {
+                $($( $crate::abomonate::entomb(&self.$field, _write)?; )*)?
+                Ok(())
+            }
+            unsafe fn exhume<'b>(&mut self, bytes: &'b mut [u8]) -> Option<&'b mut [u8]> {
+                $($( let bytes = $crate::abomonate::exhume(&mut self.$field, bytes)?; )*)?
+                Some(bytes)
+            }
+            fn extent(&self) -> usize {
+                0 $($( + $crate::abomonate::extent(&self.$field) )*)?
+            }
+        }
+    }
+}
+
+/// The number of bytes padding `extent` bytes to a multiple of eight.
+fn padding(extent: usize) -> usize { extent.wrapping_neg() % 8 }
+
+/// Writes the heap data of `value`, padded.
+pub(crate) unsafe fn entomb<T: Abomonation, W: Write>(value: &T, write: &mut W) -> Result<()> {
+    value.entomb(write)?;
+    write.write_all(&[0; 8][.. padding(value.extent())])
+}
+
+/// Points `value` at its heap data in `bytes`, and skips their padding.
+pub(crate) unsafe fn exhume<'b, T: Abomonation>(value: &mut T, bytes: &'b mut [u8]) -> Option<&'b mut [u8]> {
+    let bytes = value.exhume(bytes)?;
+    let padding = padding(value.extent());
+    if bytes.len() < padding { None } else { Some(&mut bytes[padding ..]) }
+}
+
+/// The number of bytes `entomb` writes.
+pub(crate) fn extent<T: Abomonation>(value: &T) -> usize {
+    let extent = value.extent();
+    extent + padding(extent)
+}
+
+impl Abomonation for Column {
+    unsafe fn entomb<W: Write>(&self, write: &mut W) -> Result<()> {
+        match *self {
+            Column::F64(ref values) => entomb(values, write),
+            Column::U64(ref values) => entomb(values, write),
+            Column::U32(ref values) => entomb(values, write),
+            Column::I64(ref values) => entomb(values, write),
+            Column::Blobs(ref values) => entomb(values, write),
+        }
+    }
+    unsafe fn exhume<'b>(&mut self, bytes: &'b mut [u8]) -> Option<&'b mut [u8]> {
+        match *self {
+            Column::F64(ref mut values) => exhume(values, bytes),
+            Column::U64(ref mut values) => exhume(values, bytes),
+            Column::U32(ref mut values) => exhume(values, bytes),
+            Column::I64(ref mut values) => exhume(values, bytes),
+            Column::Blobs(ref mut values) => exhume(values, bytes),
+        }
+    }
+    fn extent(&self) -> usize {
+        match *self {
+            Column::F64(ref values) => extent(values),
+            Column::U64(ref values) => extent(values),
+            Column::U32(ref values) => extent(values),
+            Column::I64(ref values) => extent(values),
+            Column::Blobs(ref values) => extent(values),
+        }
+    }
+}
+
+// the columns are written as `Vec` writes them, but with each name padded, which the
+// implementation for tuples would not do.
+impl Abomonation for GraphLayout {
+    unsafe fn entomb<W: Write>(&self, write: &mut W) -> Result<()> {
+        entomb(&self.keys, write)?;
+        entomb(&self.tiles, write)?;
+        entomb(&self.summaries, write)?;
+        entomb(&self.blocks, write)?;
+        entomb(&self.weights, write)?;
+        let columns = &self.columns[..];
+        write.write_all(::std::slice::from_raw_parts(columns.as_ptr() as *const u8, mem::size_of_val(columns)))?;
+        for (name, column) in columns {
+            entomb(name, write)?;
+            column.entomb(write)?;
+        }
+        Ok(())
+    }
+    unsafe fn exhume<'b>(&mut self, bytes: &'b mut [u8]) -> Option<&'b mut [u8]> {
+        let bytes = exhume(&mut self.keys, bytes)?;
+        let bytes = exhume(&mut self.tiles, bytes)?;
+        let bytes = exhume(&mut self.summaries, bytes)?;
+        let bytes = exhume(&mut self.blocks, bytes)?;
+        let bytes = exhume(&mut self.weights, bytes)?;
+        let count = self.columns.len();
+        let length = mem::size_of_val(&self.columns[..]);
+        if bytes.len() < length { return None; }
+        let (columns, mut bytes) = bytes.split_at_mut(length);
+        // the decoded vector is never dropped or grown, as `decode` hands out only a reference.
+        ::std::ptr::write(&mut self.columns, Vec::from_raw_parts(columns.as_mut_ptr() as *mut (String, Column), count, count));
+        for (name, column) in self.columns.iter_mut() {
+            bytes = exhume(name, bytes)?;
+            bytes = column.exhume(bytes)?;
+        }
+        Some(bytes)
+    }
+    fn extent(&self) -> usize {
+        let columns = self.columns.iter().map(|(name, column)| extent(name) + column.extent()).sum::<usize>();
+        extent(&self.keys) + extent(&self.tiles) + extent(&self.summaries) + extent(&self.blocks)
+            + extent(&self.weights) + mem::size_of_val(&self.columns[..]) + columns
+    }
+}
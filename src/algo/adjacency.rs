@@ -0,0 +1,43 @@
+//! Adjacency lists kept as a single compressed stream.
+
+use crate::compression::{Compressed, Compressor, Position};
+
+/// Neighbor lists of each vertex, in increasing order, compressed as the keys `vertex << 32 | neighbor`.
+pub(crate) struct Adjacency {
+    keys: Compressed,
+    /// The position of each vertex's first neighbor, and a final position past the last.
+    starts: Vec<Position>,
+}
+
+impl Adjacency {
+    /// Groups `edges` by source, for sources less than `vertices`, discarding duplicates.
+    pub fn new<I: Iterator<Item=(u32, u32)>>(edges: I, vertices: usize) -> Adjacency {
+        let mut keys = edges.map(|(src, dst)| (src as u64) << 32 | dst as u64).collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let mut compressor = Compressor::with_capacity(keys.len());
+        let mut starts = Vec::with_capacity(vertices + 1);
+        for key in keys {
+            while starts.len() <= (key >> 32) as usize {
+                starts.push(compressor.position());
+            }
+            compressor.push(key);
+        }
+        while starts.len() <= vertices {
+            starts.push(compressor.position());
+        }
+        Adjacency { keys: compressor.done(), starts }
+    }
+
+    /// The number of neighbors of `vertex`.
+    pub fn degree(&self, vertex: u32) -> usize {
+        self.starts[vertex as usize + 1].offset() - self.starts[vertex as usize].offset()
+    }
+
+    /// The neighbors of `vertex`, in increasing order, as `u64` values.
+    pub fn neighbors(&self, vertex: u32) -> impl Iterator<Item=u64> + '_ {
+        let start = self.starts[vertex as usize];
+        self.keys.decompress_from(start).take(self.degree(vertex)).map(|key| key & 0xFFFF_FFFF)
+    }
+}
@@ -0,0 +1,143 @@
+//! Breadth-first search that reads only the blocks a frontier can reach through.
+
+use crate::graph::GraphLayout;
+use crate::layout::{BytewiseCached, Hilbert, Tangle};
+
+/// The distance recorded for vertices not reachable from the root.
+pub const UNREACHED: u32 = u32::MAX;
+
+/// Computes the hop distance from `root` to each vertex along directed edges.
+///
+/// Each block of the layout covers a contiguous range of the curve, which decomposes into a few
+/// aligned squares of the adjacency matrix and so into a few ranges of source rows. A level of the
+/// search decodes only those blocks whose rows contain a vertex of the current frontier, which for
+/// small frontiers is a small fraction of the layout. Unreachable vertices have distance
+/// `UNREACHED`.
+pub fn bfs(layout: &GraphLayout, root: u32) -> Vec<u32> {
+    let vertices = ::std::cmp::max(layout.vertices(), root as u64 + 1) as usize;
+    let mut distances = vec![UNREACHED; vertices];
+    distances[root as usize] = 0;
+
+    let hilbert = Hilbert::new();
+    let rows = layout.blocks().iter().map(|block| rows(&hilbert, block.lower, block.upper)).collect::<Vec<_>>();
+
+    let mut detangler = BytewiseCached::new();
+    let mut frontier = vec![root];
+    let mut level = 0;
+    while !frontier.is_empty() {
+        let mut next = Vec::new();
+        for (block, ranges) in layout.blocks().iter().zip(&rows) {
+            let reached = ranges.iter().any(|&(lower, upper)| {
+                let index = frontier.partition_point(|&vertex| vertex < lower);
+                index < frontier.len() && frontier[index] <= upper
+            });
+            if !reached {
+                continue;
+            }
+            for key in layout.block_keys(block) {
+                let (src, dst) = detangler.detangle(key);
+                if distances[src as usize] == level && distances[dst as usize] == UNREACHED {
+                    distances[dst as usize] = level + 1;
+                    next.push(dst);
+                }
+            }
+        }
+        next.sort_unstable();
+        frontier = next;
+        level += 1;
+    }
+    distances
+}
+
+/// The disjoint inclusive ranges of source rows covered by the curve keys `lower ..= upper`.
+fn rows(hilbert: &Hilbert, lower: u64, upper: u64) -> Vec<(u32, u32)> {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    let mut key = lower;
+    loop {
+        // the largest aligned square of `4^log` keys starting at `key` and ending by `upper`.
+        let mut log = 0;
+        while log < 31 && key.trailing_zeros() >= 2 * (log + 1) && key.checked_add((1u64 << (2 * (log + 1))) - 1).is_some_and(|end| end <= upper) {
+            log += 1;
+        }
+        let row = (hilbert.detangle(key).0 >> log) << log;
+        let last = row + ((1u32 << log) - 1);
+        match ranges.iter_mut().find(|range| range.0 <= last.saturating_add(1) && row <= range.1.saturating_add(1)) {
+            Some(range) => { *range = (::std::cmp::min(range.0, row), ::std::cmp::max(range.1, last)); },
+            None => ranges.push((row, last)),
+        }
+        let end = key + ((1u64 << (2 * log)) - 1);
+        if end >= upper {
+            break;
+        }
+        key = end + 1;
+    }
+    ranges.sort_unstable();
+    ranges
+}
+
+/// A way in which a BFS parent array fails to describe a breadth-first search tree.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BfsViolation {
+    /// The parent array does not have an entry for every vertex of the layout.
+    Length,
+    /// The root is not its own parent.
+    Root,
+    /// Following parents from the vertex never reaches the root.
+    Cycle(u32),
+    /// The tree edge `(parent, child)` is not an edge of the layout.
+    MissingEdge(u32, u32),
+    /// The edge `(src, dst)` reaches `dst` in fewer steps than its depth in the tree.
+    Depth(u32, u32),
+    /// The edge `(src, dst)` leaves the reached vertex `src` for the unreached vertex `dst`.
+    Unreached(u32, u32),
+}
+
+/// Checks that `parents` is a breadth-first search tree of `layout` rooted at `root`.
+///
+/// Following the Graph500 validation rules, the root must be its own parent, parent links must
+/// lead every reached vertex to the root, every tree edge must exist in the layout, no edge may
+/// lead to a vertex more than one level deeper than its source, and no edge may lead from a reached
+/// vertex to one marked `UNREACHED`. Returns the depth of each vertex on success.
+pub fn validate_bfs(layout: &GraphLayout, root: u32, parents: &[u32]) -> Result<Vec<u32>, BfsViolation> {
+    if (parents.len() as u64) < layout.vertices() || root as usize >= parents.len() {
+        return Err(BfsViolation::Length);
+    }
+    if parents[root as usize] != root {
+        return Err(BfsViolation::Root);
+    }
+
+    // depths follow parent links, with the links of each path resolved once.
+    let mut depths = vec![UNREACHED; parents.len()];
+    depths[root as usize] = 0;
+    let mut path = Vec::new();
+    for vertex in 0 .. parents.len() as u32 {
+        let mut current = vertex;
+        while depths[current as usize] == UNREACHED && parents[current as usize] != UNREACHED {
+            path.push(current);
+            if path.len() > parents.len() || parents[current as usize] as usize >= parents.len() {
+                return Err(BfsViolation::Cycle(vertex));
+            }
+            current = parents[current as usize];
+        }
+        if depths[current as usize] == UNREACHED && !path.is_empty() {
+            return Err(BfsViolation::Cycle(vertex));
+        }
+        while let Some(child) = path.pop() {
+            let parent = parents[child as usize];
+            if !layout.has_edge(parent, child) {
+                return Err(BfsViolation::MissingEdge(parent, child));
+            }
+            depths[child as usize] = depths[parent as usize] + 1;
+        }
+    }
+
+    for (src, dst) in layout.edges() {
+        match (depths[src as usize], depths[dst as usize]) {
+            (UNREACHED, _) => { },
+            (_, UNREACHED) => return Err(BfsViolation::Unreached(src, dst)),
+            (from, to) if to > from + 1 => return Err(BfsViolation::Depth(src, dst)),
+            _ => { },
+        }
+    }
+    Ok(depths)
+}
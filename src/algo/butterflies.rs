@@ -0,0 +1,39 @@
+//! Butterfly (4-cycle) counting in bipartite graphs.
+
+use crate::algo::adjacency::Adjacency;
+use crate::graph::GraphLayout;
+
+/// Counts the butterflies of the bipartite graph whose edges run from left to right vertices.
+///
+/// Sources and destinations are separate vertex sets, even where their identifiers coincide, and
+/// duplicate edges are ignored. A butterfly is a pair of left vertices sharing a pair of right
+/// neighbors. Wedges, paths of two edges between vertices of the same side, are enumerated over
+/// compressed neighbor lists, with their endpoints on whichever side yields fewer wedges; each pair
+/// of endpoints with `c` common neighbors closes `c choose 2` butterflies.
+pub fn butterflies(layout: &GraphLayout) -> u64 {
+    let vertices = layout.vertices() as usize;
+    let forward = Adjacency::new(layout.edges(), vertices);
+    let reverse = Adjacency::new(layout.edges().map(|(src, dst)| (dst, src)), vertices);
+
+    // wedges with left endpoints are centered on right vertices, and cost their squared degrees.
+    let cost = |adjacency: &Adjacency| (0 .. vertices as u32).map(|v| (adjacency.degree(v) as u64).pow(2)).sum::<u64>();
+    let (outer, inner) = if cost(&reverse) <= cost(&forward) { (&forward, &reverse) } else { (&reverse, &forward) };
+
+    let mut counts = vec![0u32; vertices];
+    let mut touched = Vec::new();
+    let mut total = 0u64;
+    for start in 0 .. vertices as u32 {
+        for middle in outer.neighbors(start) {
+            for end in inner.neighbors(middle as u32).take_while(|&end| end < start as u64) {
+                if counts[end as usize] == 0 { touched.push(end as u32); }
+                counts[end as usize] += 1;
+            }
+        }
+        for end in touched.drain(..) {
+            let common = counts[end as usize] as u64;
+            total += common * (common - 1) / 2;
+            counts[end as usize] = 0;
+        }
+    }
+    total
+}
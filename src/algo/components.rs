@@ -0,0 +1,31 @@
+//! Connected components by union-find over streamed edges.
+
+use crate::graph::GraphLayout;
+
+/// Labels each vertex with the smallest vertex in its weakly connected component.
+///
+/// Edges are streamed once in curve order into a union-find structure with path halving, whose
+/// roots are always the smallest vertex of their set.
+pub fn connected_components(layout: &GraphLayout) -> Vec<u32> {
+    let mut roots = (0 .. layout.vertices() as u32).collect::<Vec<_>>();
+    for (src, dst) in layout.edges() {
+        let src = find(&mut roots, src);
+        let dst = find(&mut roots, dst);
+        if src < dst { roots[dst as usize] = src; }
+        if dst < src { roots[src as usize] = dst; }
+    }
+    for vertex in 0 .. roots.len() {
+        roots[vertex] = roots[roots[vertex] as usize];
+    }
+    roots
+}
+
+/// Finds the root of `vertex`, halving the path to it.
+fn find(roots: &mut [u32], mut vertex: u32) -> u32 {
+    while roots[vertex as usize] != vertex {
+        let parent = roots[vertex as usize];
+        roots[vertex as usize] = roots[parent as usize];
+        vertex = parent;
+    }
+    vertex
+}
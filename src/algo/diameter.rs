@@ -0,0 +1,51 @@
+//! Diameter estimates from breadth-first searches at sampled sources.
+
+use crate::algo::bfs::{bfs, UNREACHED};
+use crate::graph::GraphLayout;
+use crate::rng::SplitMix64;
+
+/// The outcome of `approx_diameter`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiameterEstimate {
+    /// The largest eccentricity observed, a lower bound on the diameter.
+    pub lower: u32,
+    /// Twice the smallest eccentricity among sources that reached every vertex, if any did.
+    ///
+    /// This bounds the diameter from above only when the layout is symmetric, as every vertex is
+    /// then within that eccentricity of the source in both directions.
+    pub upper: Option<u32>,
+    /// The sampled sources, and the eccentricity of each: its largest finite distance.
+    pub sources: Vec<(u32, u32)>,
+    /// The mean eccentricity over the sampled sources.
+    pub mean: f64,
+}
+
+/// Estimates the diameter of `layout` by searching from `samples` distinct sampled sources.
+///
+/// Sources are drawn uniformly at random from the vertices with a fixed seed, so results are
+/// reproducible. Distances follow edge directions.
+pub fn approx_diameter(layout: &GraphLayout, samples: usize) -> DiameterEstimate {
+    let vertices = layout.vertices() as usize;
+    let samples = ::std::cmp::min(samples, vertices);
+
+    // a partial Fisher-Yates shuffle picks distinct sources.
+    let mut candidates = (0 .. vertices as u32).collect::<Vec<_>>();
+    let mut rng = SplitMix64::new(0x9E37_79B9_7F4A_7C15);
+    let mut sources = Vec::with_capacity(samples);
+    for index in 0 .. samples {
+        let pick = index + rng.below((vertices - index) as u64) as usize;
+        candidates.swap(index, pick);
+        let source = candidates[index];
+        let distances = bfs(layout, source);
+        let eccentricity = distances.iter().cloned().filter(|&distance| distance != UNREACHED).max().unwrap_or(0);
+        let spanning = !distances.contains(&UNREACHED);
+        sources.push((source, eccentricity, spanning));
+    }
+
+    DiameterEstimate {
+        lower: sources.iter().map(|&(_, eccentricity, _)| eccentricity).max().unwrap_or(0),
+        upper: sources.iter().filter(|&&(_, _, spanning)| spanning).map(|&(_, eccentricity, _)| 2 * eccentricity).min(),
+        mean: sources.iter().map(|&(_, eccentricity, _)| eccentricity as f64).sum::<f64>() / ::std::cmp::max(samples, 1) as f64,
+        sources: sources.into_iter().map(|(source, eccentricity, _)| (source, eccentricity)).collect(),
+    }
+}
@@ -0,0 +1,53 @@
+//! Community detection by label propagation.
+
+use crate::graph::GraphLayout;
+
+/// Runs at most `iters` rounds of synchronous label propagation, returning each vertex's label.
+///
+/// Edges are treated as undirected. Every vertex starts with its own identifier as its label, and
+/// in each round adopts the label held by the most of its neighbors and itself, breaking ties
+/// towards the smallest label. Counting its own label damps the oscillation synchronous updates
+/// otherwise show on bipartite structure. Stops early once no label changes.
+pub fn label_propagation(layout: &GraphLayout, iters: usize) -> Vec<u32> {
+    let vertices = layout.vertices() as usize;
+    let mut labels = (0 .. vertices as u32).collect::<Vec<_>>();
+    let mut votes = Vec::with_capacity(2 * layout.len() + vertices);
+    for _ in 0 .. iters {
+        votes.clear();
+        votes.extend(labels.iter().enumerate().map(|(vertex, &label)| (vertex as u32, label)));
+        for (src, dst) in layout.edges() {
+            if src != dst {
+                votes.push((src, labels[dst as usize]));
+                votes.push((dst, labels[src as usize]));
+            }
+        }
+        votes.sort_unstable();
+
+        let mut changed = false;
+        let mut start = 0;
+        while start < votes.len() {
+            let vertex = votes[start].0;
+            let mut end = start;
+            let mut best = (0, 0);
+            while end < votes.len() && votes[end].0 == vertex {
+                let label = votes[end].1;
+                let mut count = 0;
+                while end < votes.len() && votes[end] == (vertex, label) {
+                    count += 1;
+                    end += 1;
+                }
+                // labels arrive in increasing order, so only a strictly larger count wins.
+                if count > best.0 { best = (count, label); }
+            }
+            if labels[vertex as usize] != best.1 {
+                labels[vertex as usize] = best.1;
+                changed = true;
+            }
+            start = end;
+        }
+        if !changed {
+            break;
+        }
+    }
+    labels
+}
@@ -0,0 +1,23 @@
+//! Graph algorithms implemented as passes over curve-ordered edges.
+//!
+//! Each kernel streams the compressed edges of a `GraphLayout` in Hilbert order, so that the
+//! per-vertex state touched by consecutive edges stays close together in memory.
+
+pub(crate) mod adjacency;
+pub mod bfs;
+pub mod butterflies;
+pub mod components;
+pub mod diameter;
+pub mod labels;
+pub mod pagerank;
+pub mod spmv;
+pub mod triangles;
+
+pub use self::bfs::bfs;
+pub use self::butterflies::butterflies;
+pub use self::components::connected_components;
+pub use self::diameter::approx_diameter;
+pub use self::labels::label_propagation;
+pub use self::pagerank::pagerank;
+pub use self::spmv::spmv;
+pub use self::triangles::triangles;
@@ -0,0 +1,30 @@
+//! PageRank by repeated streaming passes over the edges.
+
+use crate::graph::GraphLayout;
+
+/// Computes `iters` iterations of PageRank with damping factor `damping`.
+///
+/// Ranks start at `1/n` for `n` vertices, and each iteration sets the rank of every vertex to
+/// `(1 - damping) / n` plus `damping` times the sum of `rank / out_degree` over its in-neighbors.
+/// As in the classic single-threaded baseline, rank held by vertices without out-edges is dropped
+/// rather than redistributed.
+pub fn pagerank(layout: &GraphLayout, iters: usize, damping: f32) -> Vec<f32> {
+    let vertices = layout.vertices() as usize;
+    let (_, degrees) = layout.degrees();
+
+    let base = (1.0 - damping) / vertices as f32;
+    let mut ranks = vec![1.0 / vertices as f32; vertices];
+    let mut shares = vec![0f32; vertices];
+    for _ in 0 .. iters {
+        for ((share, &rank), &degree) in shares.iter_mut().zip(&ranks).zip(&degrees) {
+            *share = if degree > 0 { damping * rank / degree as f32 } else { 0.0 };
+        }
+        for rank in ranks.iter_mut() {
+            *rank = base;
+        }
+        for (src, dst) in layout.edges() {
+            ranks[dst as usize] += shares[src as usize];
+        }
+    }
+    ranks
+}
@@ -0,0 +1,33 @@
+//! Sparse matrix-vector multiplication in curve order.
+
+use crate::graph::GraphLayout;
+
+/// Adds `A x` to `y`, where `A` is the adjacency matrix of `layout` with rows indexed by source.
+///
+/// Entries are the edge weights, or one for unweighted layouts. Both `x` and `y` must have an entry
+/// for every vertex.
+///
+/// Consecutive edges in Hilbert order stay within a small square of the matrix, so the entries of
+/// `x` and `y` they touch stay in cache. Runs of edges sharing a source are also common, and each
+/// such run is summed locally before a single update to `y`.
+pub fn spmv(layout: &GraphLayout, x: &[f64], y: &mut [f64]) {
+    let vertices = layout.vertices() as usize;
+    assert!(x.len() >= vertices && y.len() >= vertices, "vectors must cover every vertex");
+
+    let mut row = None;
+    let mut sum = 0.0;
+    for (index, (src, dst)) in layout.edges().enumerate() {
+        let value = layout.weights().map(|weights| weights[index]).unwrap_or(1.0) * x[dst as usize];
+        if row != Some(src) {
+            if let Some(row) = row {
+                y[row as usize] += sum;
+            }
+            row = Some(src);
+            sum = 0.0;
+        }
+        sum += value;
+    }
+    if let Some(row) = row {
+        y[row as usize] += sum;
+    }
+}
@@ -0,0 +1,25 @@
+//! Triangle counting by intersecting compressed neighbor sets.
+
+use crate::algo::adjacency::Adjacency;
+use crate::compression::intersection_size;
+use crate::graph::GraphLayout;
+
+/// Counts the triangles of the undirected graph underlying `layout`.
+///
+/// Edge directions, self-loops, and duplicate edges are ignored. Each edge is oriented from its
+/// smaller to its larger endpoint, the oriented edges are laid out along the curve, and for each
+/// oriented edge `(u, v)` the forward neighbors of `u` and `v` are intersected. Forward neighbor
+/// sets are kept as one compressed stream of keys `u << 32 | w`, and intersected without
+/// decompressing them into memory.
+pub fn triangles(layout: &GraphLayout) -> u64 {
+    let oriented = GraphLayout::from_edges(layout.edges()
+        .filter(|&(src, dst)| src != dst)
+        .map(|(src, dst)| if src < dst { (src, dst) } else { (dst, src) }));
+    let forward = Adjacency::new(oriented.edges(), oriented.vertices() as usize);
+
+    let mut count = 0;
+    for (src, dst) in oriented.edges() {
+        count += intersection_size(forward.neighbors(src), forward.neighbors(dst)) as u64;
+    }
+    count
+}
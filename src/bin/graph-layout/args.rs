@@ -0,0 +1,48 @@
+//! Minimal parsing of positional arguments and `--name value` options.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// The arguments of one subcommand.
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    /// Splits `arguments` into positional arguments and options, rejecting options not in `known`.
+    pub fn parse(arguments: Vec<String>, known: &[&str]) -> Result<Args, String> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut arguments = arguments.into_iter();
+        while let Some(argument) = arguments.next() {
+            match argument.strip_prefix("--") {
+                Some(name) => {
+                    if !known.contains(&name) {
+                        return Err(format!("unknown option `--{}`", name));
+                    }
+                    let value = arguments.next().ok_or_else(|| format!("option `--{}` needs a value", name))?;
+                    options.insert(name.to_owned(), value);
+                },
+                None => positional.push(argument),
+            }
+        }
+        Ok(Args { positional, options })
+    }
+
+    /// The positional argument at `index`, described as `name` if it is missing.
+    pub fn positional(&self, index: usize, name: &str) -> Result<&str, String> {
+        self.positional.get(index).map(|value| &value[..]).ok_or_else(|| format!("missing {}", name))
+    }
+    /// The value of option `--name`, if given.
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|value| &value[..])
+    }
+    /// The value of option `--name` parsed as a `T`, if given.
+    pub fn parsed<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        match self.option(name) {
+            Some(value) => value.parse().map(Some).map_err(|_| format!("invalid value `{}` for `--{}`", value, name)),
+            None => Ok(None),
+        }
+    }
+}
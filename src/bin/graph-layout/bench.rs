@@ -0,0 +1,69 @@
+//! `graph-layout bench`: times graph kernels over a layout file.
+
+use std::time::Instant;
+
+use graph_layout::algo::{bfs, connected_components, pagerank, triangles};
+use graph_layout::algo::bfs::UNREACHED;
+use graph_layout::graph::GraphLayout;
+
+use crate::args::Args;
+
+pub const USAGE: &str = "graph-layout bench <layout> [options]
+
+Runs graph kernels single-threaded over a layout file, after loading it into memory, and reports
+the time of each run and its throughput in edges processed per second.
+
+Options:
+    --kernels <list>    comma-separated kernels among pagerank, bfs, components, and triangles
+                        (default: all of them)
+    --runs <n>          runs of each kernel (default 3)
+    --iters <n>         PageRank iterations per run (default 20)
+    --root <vertex>     BFS root (default 0)";
+
+const KERNELS: &[&str] = &["pagerank", "bfs", "components", "triangles"];
+
+pub fn run(arguments: Vec<String>) -> Result<(), String> {
+    let args = Args::parse(arguments, &["kernels", "runs", "iters", "root"])?;
+    let path = args.positional(0, "layout file")?;
+    let kernels = args.option("kernels").map(|list| list.split(',').collect::<Vec<_>>()).unwrap_or_else(|| KERNELS.to_vec());
+    if let Some(unknown) = kernels.iter().find(|kernel| !KERNELS.contains(kernel)) {
+        return Err(format!("unknown kernel `{}`", unknown));
+    }
+    let runs = args.parsed::<usize>("runs")?.unwrap_or(3);
+    let iters = args.parsed::<usize>("iters")?.unwrap_or(20);
+    let root = args.parsed::<u32>("root")?.unwrap_or(0);
+
+    let start = Instant::now();
+    let layout = GraphLayout::open(path).map_err(|error| format!("{}: {}", path, error))?;
+    println!("loaded {} edges in {:.3}s", layout.len(), start.elapsed().as_secs_f64());
+    if kernels.contains(&"bfs") && root as u64 >= layout.vertices() {
+        return Err(format!("BFS root {} is not a vertex", root));
+    }
+
+    for kernel in kernels {
+        for run in 1 ..= runs {
+            let start = Instant::now();
+            // each kernel reports how many edges it processed, and a result to print.
+            let (edges, result) = match kernel {
+                "pagerank" => {
+                    let ranks = pagerank(&layout, iters, 0.85);
+                    let top = ranks.iter().cloned().fold(0.0f32, f32::max);
+                    (layout.len() * iters, format!("{} iterations, largest rank {:.3e}", iters, top))
+                },
+                "bfs" => {
+                    let reached = bfs(&layout, root).iter().filter(|&&depth| depth != UNREACHED).count();
+                    (layout.len(), format!("{} vertices reached", reached))
+                },
+                "components" => {
+                    let labels = connected_components(&layout);
+                    let count = labels.iter().enumerate().filter(|&(vertex, &label)| vertex as u32 == label).count();
+                    (layout.len(), format!("{} components", count))
+                },
+                _ => (layout.len(), format!("{} triangles", triangles(&layout))),
+            };
+            let seconds = start.elapsed().as_secs_f64();
+            println!("{} run {}: {:.3}s, {:.3e} edges/s, {}", kernel, run, seconds, edges as f64 / seconds, result);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! `graph-layout convert`: builds a layout file from an edge file.
+
+use std::path::Path;
+
+use graph_layout::builder::LayoutBuilder;
+use graph_layout::io;
+use graph_layout::io::format::{Codec, Curve};
+
+use crate::args::Args;
+
+pub const USAGE: &str = "graph-layout convert <input> <output> [options]
+
+Reads edges from <input> and writes them as a layout file to <output>, sorting them externally in
+runs of at most --buffer edges. Inputs ending in .gz or .zst are decompressed on the fly, if the
+corresponding feature is enabled. The values of Matrix Market entries are not kept.
+
+Layout files ordered along another curve than the Hilbert curve, or with plain block data, are
+re-keyed along the Hilbert curve when read, and cannot be memory-mapped.
+
+Options:
+    --format <text|binary|mtx>      input format; inferred from the extension (.bin, .mtx) by default
+    --curve <hilbert|zorder|none>   order of the edges (default hilbert); none orders them by source
+                                    and then destination, and cannot be tiled
+    --codec <deltas|plain>          block data as escaped byte deltas (default) or as plain u64 keys
+    --tile <k>                      lay out edges in tiles of 2^k x 2^k vertices
+    --buffer <edges>                edges held in memory before spilling sorted runs (default 16777216)
+    --spill <directory>             directory for spilled runs (default: the system temporary directory)";
+
+pub fn run(arguments: Vec<String>) -> Result<(), String> {
+    let args = Args::parse(arguments, &["format", "curve", "codec", "tile", "buffer", "spill"])?;
+    let input = args.positional(0, "input file")?;
+    let output = args.positional(1, "output file")?;
+    let curve = match args.option("curve").unwrap_or("hilbert") {
+        "hilbert" => Curve::Hilbert,
+        "zorder" => Curve::ZOrder,
+        "none" => Curve::RowMajor,
+        other => return Err(format!("unknown curve `{}`", other)),
+    };
+    let codec = match args.option("codec").unwrap_or("deltas") {
+        "deltas" => Codec::EscapedDeltas,
+        "plain" => Codec::Plain,
+        other => return Err(format!("unknown codec `{}`", other)),
+    };
+    let tile = args.parsed::<u32>("tile")?;
+    if tile.is_some_and(|log| log >= 32) {
+        return Err("tiles must be smaller than 2^32 vertices on a side".to_owned());
+    }
+    if tile.is_some() && curve == Curve::RowMajor {
+        return Err("edges ordered by no curve cannot be tiled".to_owned());
+    }
+    let buffer = args.parsed::<usize>("buffer")?.unwrap_or(1 << 24);
+    if buffer == 0 {
+        return Err("the buffer must hold at least one edge".to_owned());
+    }
+    let failed = |error: graph_layout::Error| format!("{}: {}", input, error);
+
+    let mut builder = LayoutBuilder::new(buffer).curve(curve).codec(codec);
+    if let Some(directory) = args.option("spill") {
+        builder = builder.spill_directory(directory);
+    }
+    if let Some(log) = tile {
+        builder = builder.tiled(log);
+    }
+    match format(input, args.option("format"))? {
+        Format::Text => {
+            for edge in io::read_edge_list(input).map_err(failed)? {
+                builder.push(edge.map_err(failed)?).map_err(failed)?;
+            }
+        },
+        Format::Binary => {
+            for edge in io::read_binary_edges(input).map_err(failed)? {
+                builder.push(edge.map_err(failed)?).map_err(failed)?;
+            }
+        },
+        Format::MatrixMarket => {
+            for entry in io::read_matrix_market_entries(input).map_err(failed)? {
+                builder.push(entry.map_err(failed)?.0).map_err(failed)?;
+            }
+        },
+    }
+    let edges = builder.finish(output).map_err(|error| format!("{}: {}", output, error))?;
+    println!("wrote {} edges to {}", edges, output);
+    Ok(())
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Format {
+    Text,
+    Binary,
+    MatrixMarket,
+}
+
+/// The format named by `--format`, or else the one suggested by the extension of `input`.
+fn format(input: &str, name: Option<&str>) -> Result<Format, String> {
+    let name = match name {
+        Some(name) => name.to_owned(),
+        None => {
+            let path = Path::new(input);
+            let path = match path.extension().and_then(|extension| extension.to_str()) {
+                Some("gz") | Some("zst") => Path::new(path.file_stem().unwrap()),
+                _ => path,
+            };
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("bin") => "binary".to_owned(),
+                Some("mtx") => "mtx".to_owned(),
+                _ => "text".to_owned(),
+            }
+        },
+    };
+    match &name[..] {
+        "text" => Ok(Format::Text),
+        "binary" => Ok(Format::Binary),
+        "mtx" => Ok(Format::MatrixMarket),
+        other => Err(format!("unknown input format `{}`", other)),
+    }
+}
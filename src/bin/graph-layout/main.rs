@@ -0,0 +1,48 @@
+//! The `graph-layout` command line tool, for working with layout files without writing code.
+
+mod args;
+mod bench;
+mod convert;
+mod reorder;
+mod stats;
+
+const USAGE: &str = "graph-layout <command> [arguments]
+
+Commands:
+    bench      time graph kernels over a layout file
+    convert    build a layout file from an edge list, binary edge file, or Matrix Market file
+    reorder    relabel the vertices of a layout file by an ordering or saved permutation
+    stats      summarize the graph and compression of a layout file
+    help       print this message, or `help <command>` for a command's options";
+
+fn main() {
+    let mut arguments = ::std::env::args().skip(1);
+    let command = arguments.next();
+    let arguments = arguments.collect::<Vec<_>>();
+    let result = match command.as_deref() {
+        Some("bench") => bench::run(arguments),
+        Some("convert") => convert::run(arguments),
+        Some("reorder") => reorder::run(arguments),
+        Some("stats") => stats::run(arguments),
+        Some("help") | Some("--help") | Some("-h") | None => {
+            println!("{}", usage(arguments.first().map(|command| &command[..])));
+            Ok(())
+        },
+        Some(other) => Err(format!("unknown command `{}`; try `graph-layout help`", other)),
+    };
+    if let Err(message) = result {
+        eprintln!("graph-layout: {}", message);
+        ::std::process::exit(1);
+    }
+}
+
+/// The usage of `command`, or of the tool as a whole.
+fn usage(command: Option<&str>) -> &'static str {
+    match command {
+        Some("bench") => bench::USAGE,
+        Some("convert") => convert::USAGE,
+        Some("reorder") => reorder::USAGE,
+        Some("stats") => stats::USAGE,
+        _ => USAGE,
+    }
+}
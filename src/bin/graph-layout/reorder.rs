@@ -0,0 +1,54 @@
+//! `graph-layout reorder`: relabels the vertices of a layout file.
+
+use graph_layout::graph::GraphLayout;
+use graph_layout::order::{by_degree, gorder, random, rcm, Permutation};
+
+use crate::args::Args;
+
+pub const USAGE: &str = "graph-layout reorder <input> <output> [options]
+
+Relabels the vertices of the layout file <input> and writes the result to <output>, along with
+the permutation applied, which maps each old identifier to its new one.
+
+Options:
+    --order <name>          degree, rcm, gorder, or random (default rcm)
+    --permutation <file>    apply a permutation saved earlier instead of computing an order
+    --save <file>           where to write the permutation (default: <output>.perm)
+    --window <n>            Gorder window size (default 5)
+    --seed <n>              seed of the random order (default 0)";
+
+pub fn run(arguments: Vec<String>) -> Result<(), String> {
+    let args = Args::parse(arguments, &["order", "permutation", "save", "window", "seed"])?;
+    let input = args.positional(0, "input file")?;
+    let output = args.positional(1, "output file")?;
+    let window = args.parsed::<usize>("window")?.unwrap_or(5);
+    if window == 0 {
+        return Err("the Gorder window must hold at least one vertex".to_owned());
+    }
+    let layout = GraphLayout::open(input).map_err(|error| format!("{}: {}", input, error))?;
+
+    let permutation = match (args.option("permutation"), args.option("order")) {
+        (Some(_), Some(_)) => return Err("give either `--order` or `--permutation`, not both".to_owned()),
+        (Some(path), None) => {
+            let permutation = Permutation::load(path).map_err(|error| format!("{}: {}", path, error))?;
+            if (permutation.len() as u64) < layout.vertices() {
+                return Err(format!("{}: permutation covers {} of {} vertices", path, permutation.len(), layout.vertices()));
+            }
+            permutation
+        },
+        (None, order) => match order.unwrap_or("rcm") {
+            "degree" => by_degree(&layout),
+            "rcm" => rcm(&layout),
+            "gorder" => gorder(&layout, window),
+            "random" => random(&layout, args.parsed("seed")?.unwrap_or(0)),
+            other => return Err(format!("unknown order `{}`", other)),
+        },
+    };
+
+    let relabeled = permutation.apply_to_layout(&layout);
+    relabeled.save(output).map_err(|error| format!("{}: {}", output, error))?;
+    let save = args.option("save").map(|path| path.to_owned()).unwrap_or_else(|| format!("{}.perm", output));
+    permutation.save(&save).map_err(|error| format!("{}: {}", save, error))?;
+    println!("wrote {} edges to {} and the permutation to {}", relabeled.len(), output, save);
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! `graph-layout stats`: summarizes a layout file.
+
+use graph_layout::graph::GraphLayout;
+use graph_layout::stats::{degree_distribution, locality, Distribution, Gaps};
+
+use crate::args::Args;
+
+pub const USAGE: &str = "graph-layout stats <layout>
+
+Prints the vertex and edge counts, degree distributions, compression ratio, gaps between
+consecutive edges, and tile densities of a layout file.";
+
+pub fn run(arguments: Vec<String>) -> Result<(), String> {
+    let args = Args::parse(arguments, &[])?;
+    let path = args.positional(0, "layout file")?;
+    let layout = GraphLayout::open(path).map_err(|error| format!("{}: {}", path, error))?;
+
+    println!("vertices:          {}", layout.vertices());
+    println!("edges:             {}", layout.len());
+    println!("weighted:          {}", layout.weights().is_some());
+    let degrees = degree_distribution(&layout);
+    print_distribution("out-degrees", &degrees.out_degrees);
+    print_distribution("in-degrees", &degrees.in_degrees);
+
+    let locality = locality(&layout);
+    println!("bits per edge:     {:.3}", locality.bits_per_edge);
+    if locality.bits_per_edge > 0.0 {
+        println!("compression:       {:.2}x over 64-bit edge pairs", 64.0 / locality.bits_per_edge);
+    }
+    print_gaps("source gaps", &locality.sources);
+    print_gaps("destination gaps", &locality.destinations);
+    println!("cache lines/edge:  {:.3}", locality.lines_per_edge);
+
+    match layout.log_tile() {
+        Some(log) => {
+            let densities = layout.tile_summaries().iter().map(|summary| summary.density).collect::<Vec<_>>();
+            let mean = densities.iter().sum::<f64>() / ::std::cmp::max(densities.len(), 1) as f64;
+            let max = densities.iter().cloned().fold(0.0, f64::max);
+            let min = densities.iter().cloned().fold(if densities.is_empty() { 0.0 } else { 1.0 }, f64::min);
+            println!("tiles:             {} of side 2^{}", densities.len(), log);
+            println!("tile density:      mean {:.3e}, min {:.3e}, max {:.3e}", mean, min, max);
+        },
+        None => println!("tiles:             untiled"),
+    }
+    Ok(())
+}
+
+fn print_distribution(name: &str, distribution: &Distribution) {
+    println!("{:<19}mean {:.2}, max {}, gini {:.3}", format!("{}:", name), distribution.mean, distribution.max, distribution.gini);
+}
+
+fn print_gaps(name: &str, gaps: &Gaps) {
+    println!("{:<19}mean {:.1}, p50 {}, p90 {}, p99 {}, max {}", format!("{}:", name), gaps.mean, gaps.p50, gaps.p90, gaps.p99, gaps.max);
+}
@@ -0,0 +1,609 @@
+//! Building layout files from edge streams larger than memory.
+//!
+//! A `LayoutBuilder` entangles edges into a bounded buffer. Whenever the buffer fills, its keys are
+//! sorted, deduplicated, and spilled to disk as a compressed run. Finishing merges the runs, again
+//! removing duplicates, straight into a layout file written by `io::format::LayoutWriter`, or into
+//! any other `EdgeSink`.
+//!
+//! A run file is a sequence of chunks of at most `BLOCK_EDGES` keys, each a little-endian `u64` key
+//! count and `u64` byte length followed by the keys as encoded by `Compressed::encode`, the first
+//! chunk's deltas starting from zero and each later chunk's from the last key of the chunk before.
+//!
+//! A builder given a memory budget sizes its buffer to fit, and merges at most as many runs at once
+//! as the budget allows; with more runs than that, finishing first merges groups of the oldest runs
+//! into new, longer runs until few enough remain.
+//!
+//! A builder created with `LayoutBuilder::resume` records a checkpoint after each spill, so that an
+//! interrupted build can continue from its completed runs. The checkpoint, `checkpoint.txt`, holds
+//! the line `graph-layout checkpoint 1`, then `consumed <n>` with the number of input edges covered
+//! by the runs, then a line `run <file>` per run file. It is replaced atomically after each run is
+//! synced to disk.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::compression::{Compressor, EncodedDecompressor};
+use crate::error::{Error, Result};
+use crate::graph::BLOCK_EDGES;
+use crate::io::format::{Codec, Curve, LayoutWriter};
+use crate::io::KeyReader;
+use crate::layout::{Hilbert, Tangle};
+use crate::progress::{Phase, Progress, Reporter};
+use crate::rng::SplitMix64;
+use crate::sink::EdgeSink;
+
+/// Distinguishes the run files of builders in the same process.
+static BUILDERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Accumulates an unsorted edge stream into a layout file, using bounded memory.
+pub struct LayoutBuilder {
+    hilbert: Hilbert,
+    log_tile: Option<u32>,
+    curve: Curve,
+    codec: Codec,
+    /// The curve's tables, if it is not the Hilbert curve.
+    tangle: Option<Box<dyn Tangle + Send + Sync>>,
+    capacity: usize,
+    buffer: Vec<u64>,
+    directory: PathBuf,
+    prefix: String,
+    runs: Vec<PathBuf>,
+    /// Whether runs are kept and recorded in a checkpoint until the build finishes.
+    checkpoint: bool,
+    /// The number of edges pushed, and the number of those already in runs.
+    pushed: u64,
+    consumed: u64,
+    /// A uniform sample of the edges pushed, if requested.
+    reservoir: Option<Reservoir>,
+    /// The number of bytes written to runs.
+    spilled: u64,
+    progress: Reporter,
+    /// The most runs merged at once, and the number of run files created so far.
+    fan_in: usize,
+    created: usize,
+}
+
+/// The name of the checkpoint file within a resumable builder's directory.
+pub const CHECKPOINT: &str = "checkpoint.txt";
+const CHECKPOINT_HEADER: &str = "graph-layout checkpoint 1";
+
+impl LayoutBuilder {
+    /// A builder holding at most `buffer_edges` edges in memory, spilling to the system's
+    /// temporary directory.
+    pub fn new(buffer_edges: usize) -> LayoutBuilder {
+        assert!(buffer_edges > 0, "the buffer must hold at least one edge");
+        LayoutBuilder {
+            hilbert: Hilbert::new(),
+            log_tile: None,
+            curve: Curve::Hilbert,
+            codec: Codec::EscapedDeltas,
+            tangle: None,
+            capacity: buffer_edges,
+            buffer: Vec::new(),
+            directory: ::std::env::temp_dir(),
+            prefix: format!("graph-layout-{}-{}", ::std::process::id(), BUILDERS.fetch_add(1, Ordering::Relaxed)),
+            runs: Vec::new(),
+            checkpoint: false,
+            pushed: 0,
+            consumed: 0,
+            reservoir: None,
+            spilled: 0,
+            progress: Reporter::none(),
+            fan_in: usize::MAX,
+            created: 0,
+        }
+    }
+    /// A resumable builder spilling to `directory`, continuing from its checkpoint if one exists.
+    ///
+    /// The first `consumed()` edges of the input are already in completed runs, and should be
+    /// skipped rather than pushed again. Runs and the checkpoint are removed once `finish` succeeds.
+    pub fn resume<P: AsRef<Path>>(directory: P, buffer_edges: usize) -> Result<LayoutBuilder> {
+        let directory = directory.as_ref();
+        ::std::fs::create_dir_all(directory)?;
+        let mut builder = LayoutBuilder::new(buffer_edges);
+        builder.directory = directory.to_path_buf();
+        builder.prefix = "run".to_owned();
+        builder.checkpoint = true;
+
+        let text = match ::std::fs::read_to_string(directory.join(CHECKPOINT)) {
+            Ok(text) => text,
+            Err(ref error) if error.kind() == ErrorKind::NotFound => return Ok(builder),
+            Err(error) => return Err(error.into()),
+        };
+        let invalid = |message: &str| Error::corrupt("checkpoint", message);
+        let mut lines = text.lines();
+        if lines.next() != Some(CHECKPOINT_HEADER) {
+            return Err(invalid("missing header"));
+        }
+        builder.consumed = lines.next()
+            .and_then(|line| line.strip_prefix("consumed "))
+            .and_then(|count| count.parse().ok())
+            .ok_or_else(|| invalid("malformed consumed count"))?;
+        builder.pushed = builder.consumed;
+        for line in lines.filter(|line| !line.is_empty()) {
+            let file = line.strip_prefix("run ").ok_or_else(|| invalid("expected `run`"))?;
+            let path = directory.join(file);
+            if !path.is_file() {
+                return Err(invalid(&format!("missing run file {}", file)));
+            }
+            builder.runs.push(path);
+        }
+        builder.created = builder.runs.len();
+        Ok(builder)
+    }
+    /// Spills runs to `directory` rather than the system's temporary directory.
+    pub fn spill_directory<P: AsRef<Path>>(mut self, directory: P) -> LayoutBuilder {
+        self.directory = directory.as_ref().to_path_buf();
+        self
+    }
+    /// Lays out the edges in tiles of `2^log_side × 2^log_side` vertices.
+    pub fn tiled(mut self, log_side: u32) -> LayoutBuilder {
+        assert!(log_side < 32, "tiles must be smaller than the full 2^32 × 2^32 space");
+        assert!(self.curve != Curve::RowMajor, "row-major layouts cannot be tiled");
+        self.log_tile = Some(log_side);
+        self
+    }
+    /// Orders edges along `curve` rather than the Hilbert curve, in runs, layout files, and the
+    /// keys passed to sinks.
+    ///
+    /// Panics if edges have already been pushed, or if the curve is `Curve::RowMajor` and the
+    /// builder is tiled.
+    pub fn curve(mut self, curve: Curve) -> LayoutBuilder {
+        assert!(self.pushed == 0, "the curve must be chosen before any edges are pushed");
+        assert!(curve != Curve::RowMajor || self.log_tile.is_none(), "row-major layouts cannot be tiled");
+        self.tangle = if curve == Curve::Hilbert { None } else { Some(curve.tangle()) };
+        self.curve = curve;
+        self
+    }
+    /// Encodes the block data of layout files with `codec` rather than as escaped byte deltas.
+    pub fn codec(mut self, codec: Codec) -> LayoutBuilder {
+        self.codec = codec;
+        self
+    }
+
+    /// Bounds the memory used for sorting and merging by about `bytes`, replacing the buffer size
+    /// given to `new`.
+    ///
+    /// The buffer holds `bytes / 8` keys, allocated up front, and finishing merges at most as many
+    /// runs at once as fit in the budget, first merging groups of runs into longer runs if needed.
+    /// The budget excludes the reservoir sample and the layout writer's directories, which grow
+    /// with the number of blocks and tiles, and the edges of one tile.
+    ///
+    /// Panics if `bytes` cannot hold two runs being merged.
+    pub fn memory_budget(mut self, bytes: usize) -> LayoutBuilder {
+        assert!(bytes >= 2 * RUN_READER_BYTES, "the budget must allow merging two runs");
+        self.capacity = bytes / 8;
+        self.buffer = Vec::with_capacity(self.capacity);
+        self.fan_in = bytes / RUN_READER_BYTES;
+        self
+    }
+
+    /// Keeps a uniform random sample of at most `capacity` pushed edges, determined by `seed`, to
+    /// be returned by `finish_with_sample`.
+    ///
+    /// A resumed builder samples only the edges pushed since it resumed.
+    pub fn reservoir(mut self, capacity: usize, seed: u64) -> LayoutBuilder {
+        self.reservoir = Some(Reservoir { capacity, rng: SplitMix64::new(seed), sample: Vec::with_capacity(capacity), seen: 0 });
+        self
+    }
+
+    /// Calls `callback` about every `interval` edges pushed or merged, after each spill, and once
+    /// the layout file is complete.
+    ///
+    /// Reported bytes count everything written to disk so far, runs and layout file alike.
+    pub fn progress<F: FnMut(Progress) + Send + 'static>(mut self, interval: u64, callback: F) -> LayoutBuilder {
+        self.progress = Reporter::new(interval, callback);
+        self
+    }
+
+    /// The number of runs spilled to disk so far.
+    pub fn runs(&self) -> usize { self.runs.len() }
+    /// The number of pushed edges already written to runs, including those of resumed runs.
+    pub fn consumed(&self) -> u64 { self.consumed }
+
+    /// Adds an edge, spilling the buffer to disk if it is full.
+    pub fn push(&mut self, edge: (u32, u32)) -> Result<()> {
+        if let Some(reservoir) = self.reservoir.as_mut() {
+            reservoir.offer(edge);
+        }
+        let key = match self.tangle {
+            Some(ref tangle) => tangle.entangle(edge),
+            None => self.hilbert.entangle(edge),
+        };
+        self.buffer.push(key);
+        self.accept()
+    }
+    /// Adds the edge with Hilbert curve key `key`, spilling the buffer to disk if it is full.
+    fn push_key(&mut self, key: u64) -> Result<()> {
+        if let Some(reservoir) = self.reservoir.as_mut() {
+            reservoir.offer(self.hilbert.detangle(key));
+        }
+        let key = match self.tangle {
+            Some(ref tangle) => tangle.entangle(self.hilbert.detangle(key)),
+            None => key,
+        };
+        self.buffer.push(key);
+        self.accept()
+    }
+    /// Accounts for the edge last added to the buffer.
+    fn accept(&mut self) -> Result<()> {
+        self.pushed += 1;
+        if self.buffer.len() >= self.capacity {
+            self.spill()?;
+        }
+        self.progress.update(Phase::Reading, self.pushed, self.spilled);
+        Ok(())
+    }
+    /// Adds each edge of `edges`.
+    pub fn extend<I: Iterator<Item=(u32, u32)>>(&mut self, edges: I) -> Result<()> {
+        let span = trace_span!("ingest", edges);
+        let pushed = self.pushed;
+        for edge in edges {
+            self.push(edge)?;
+        }
+        trace_record!(span, edges, self.pushed - pushed);
+        Ok(())
+    }
+
+    /// Adds each edge of a whitespace-separated edge list, parsed and entangled in one pass by an
+    /// `io::KeyReader`.
+    pub fn extend_edge_list<R: Read>(&mut self, reader: R) -> Result<()> {
+        let span = trace_span!("ingest", edges);
+        let pushed = self.pushed;
+        let mut reader = KeyReader::new(reader);
+        let mut keys = Vec::new();
+        while reader.read_keys(&mut keys)? > 0 {
+            for &key in &keys {
+                self.push_key(key)?;
+            }
+            keys.clear();
+        }
+        trace_record!(span, edges, self.pushed - pushed);
+        Ok(())
+    }
+
+    /// Merges all edges, without duplicates, into a layout file at `path`.
+    ///
+    /// Returns the number of distinct edges written. Run files are removed once the merge succeeds,
+    /// and even if it fails unless the builder is resumable.
+    pub fn finish<P: AsRef<Path>>(mut self, path: P) -> Result<usize> {
+        let path = path.as_ref();
+        let mut writer = LayoutWriter::create(path, self.log_tile)?.curve(self.curve).codec(self.codec);
+        let edges = self.merge_into(&mut writer)?;
+        writer.finish()?;
+        let bytes = self.spilled + ::std::fs::metadata(path)?.len();
+        self.complete(edges, bytes)?;
+        Ok(edges)
+    }
+
+    /// Merges all edges, without duplicates, into `sink` in curve order.
+    ///
+    /// Returns the number of distinct keys passed to the sink. The builder's tiling applies only to
+    /// layout files, and is ignored. Run files are removed as by `finish`.
+    pub fn finish_into<S: EdgeSink>(mut self, mut sink: S) -> Result<usize> {
+        let edges = self.merge_into(&mut sink)?;
+        let bytes = self.spilled + sink.bytes();
+        self.complete(edges, bytes)?;
+        Ok(edges)
+    }
+
+    /// Passes all edges, without duplicates, to `sink`, returning their number.
+    fn merge_into<S: EdgeSink>(&mut self, sink: &mut S) -> Result<usize> {
+        let span = trace_span!("merge", runs, edges, bytes);
+        let mut edges = 0;
+        if self.runs.is_empty() {
+            self.progress.report(Phase::Merging, 0, 0);
+            self.buffer.sort_unstable();
+            self.buffer.dedup();
+            for &key in &self.buffer {
+                sink.push_key(key)?;
+                edges += 1;
+                self.progress.update(Phase::Merging, edges as u64, sink.bytes());
+            }
+        }
+        else {
+            self.spill()?;
+            // the buffer is not needed again, and its memory is part of the merge's budget.
+            self.buffer = Vec::new();
+            self.progress.report(Phase::Merging, 0, self.spilled);
+            while self.runs.len() > self.fan_in {
+                self.merge_runs()?;
+            }
+            let mut merge = RunMerge::open(&self.runs)?;
+            while let Some(key) = merge.next()? {
+                sink.push_key(key)?;
+                edges += 1;
+                self.progress.update(Phase::Merging, edges as u64, self.spilled + sink.bytes());
+            }
+        }
+        trace_record!(span, runs, self.runs.len());
+        trace_record!(span, edges, edges);
+        trace_record!(span, bytes, sink.bytes());
+        Ok(edges)
+    }
+
+    /// Removes the checkpoint of a completed build, and reports that it is done.
+    fn complete(&mut self, edges: usize, bytes: u64) -> Result<()> {
+        if self.checkpoint {
+            // a build that never spilled never wrote a checkpoint.
+            match ::std::fs::remove_file(self.directory.join(CHECKPOINT)) {
+                Err(error) if error.kind() != ErrorKind::NotFound => return Err(error.into()),
+                _ => self.checkpoint = false,
+            }
+        }
+        self.progress.report(Phase::Done, edges as u64, bytes);
+        trace_event!("build complete", edges = edges, bytes = bytes);
+        Ok(())
+    }
+
+    /// Merges all edges into a layout file at `path`, as `finish` does, and also returns the
+    /// reservoir sample of pushed edges, which is empty unless `reservoir` was called.
+    ///
+    /// The sample is drawn from the pushed edges before deduplication, so duplicates are sampled
+    /// in proportion to their multiplicity.
+    pub fn finish_with_sample<P: AsRef<Path>>(mut self, path: P) -> Result<(usize, Vec<(u32, u32)>)> {
+        let sample = self.reservoir.take().map(|reservoir| reservoir.sample).unwrap_or_default();
+        Ok((self.finish(path)?, sample))
+    }
+
+    /// Sorts and deduplicates the buffer and writes it as a new run.
+    fn spill(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let span = trace_span!("sort", edges, bytes);
+        self.buffer.sort_unstable();
+        self.buffer.dedup();
+        let path = self.next_run_path();
+        let mut writer = RunWriter::create(&path)?;
+        self.runs.push(path);
+        for chunk in self.buffer.chunks(BLOCK_EDGES) {
+            writer.write_chunk(chunk)?;
+        }
+        let written = writer.finish(self.checkpoint)?;
+        self.spilled += written;
+        trace_record!(span, edges, self.buffer.len());
+        trace_record!(span, bytes, written);
+        self.buffer.clear();
+        self.consumed = self.pushed;
+        if self.checkpoint {
+            self.write_checkpoint()?;
+        }
+        self.progress.report(Phase::Spilling, self.consumed, self.spilled);
+        Ok(())
+    }
+
+    /// Merges the first `fan_in` runs into one new run, placed after the others.
+    fn merge_runs(&mut self) -> Result<()> {
+        let span = trace_span!("compact", runs, edges, bytes);
+        let group = self.runs[.. self.fan_in].to_vec();
+        let path = self.next_run_path();
+        let mut writer = RunWriter::create(&path)?;
+        let mut merge = RunMerge::open(&group)?;
+        let mut chunk = Vec::with_capacity(BLOCK_EDGES);
+        let mut edges = 0;
+        while let Some(key) = merge.next()? {
+            edges += 1;
+            chunk.push(key);
+            if chunk.len() == BLOCK_EDGES {
+                writer.write_chunk(&chunk)?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            writer.write_chunk(&chunk)?;
+        }
+        let written = writer.finish(self.checkpoint)?;
+        self.spilled += written;
+        trace_record!(span, runs, group.len());
+        trace_record!(span, edges, edges);
+        trace_record!(span, bytes, written);
+        self.runs.drain(.. self.fan_in);
+        self.runs.push(path);
+        // the checkpoint must stop naming the merged runs before they are removed.
+        if self.checkpoint {
+            self.write_checkpoint()?;
+        }
+        for path in &group {
+            ::std::fs::remove_file(path)?;
+        }
+        self.progress.report(Phase::Spilling, self.consumed, self.spilled);
+        Ok(())
+    }
+
+    /// The path of a new run file, distinct from those of all earlier runs.
+    fn next_run_path(&mut self) -> PathBuf {
+        let mut path = self.directory.join(format!("{}-{}.run", self.prefix, self.created));
+        // runs named in a resumed checkpoint need not be numbered contiguously.
+        while self.runs.contains(&path) {
+            self.created += 1;
+            path = self.directory.join(format!("{}-{}.run", self.prefix, self.created));
+        }
+        self.created += 1;
+        path
+    }
+
+    /// Atomically replaces the checkpoint with one listing the current runs.
+    fn write_checkpoint(&self) -> Result<()> {
+        let temporary = self.directory.join(format!("{}.tmp", CHECKPOINT));
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        writeln!(writer, "{}", CHECKPOINT_HEADER)?;
+        writeln!(writer, "consumed {}", self.consumed)?;
+        for path in &self.runs {
+            writeln!(writer, "run {}", path.file_name().unwrap().to_string_lossy())?;
+        }
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        ::std::fs::rename(&temporary, self.directory.join(CHECKPOINT))?;
+        Ok(())
+    }
+}
+
+impl Drop for LayoutBuilder {
+    fn drop(&mut self) {
+        // runs of an unfinished resumable build are kept for the next attempt.
+        if self.checkpoint {
+            return;
+        }
+        for path in &self.runs {
+            let _ = ::std::fs::remove_file(path);
+        }
+    }
+}
+
+/// A uniform random sample of a stream of edges, of bounded size.
+struct Reservoir {
+    capacity: usize,
+    rng: SplitMix64,
+    sample: Vec<(u32, u32)>,
+    /// The number of edges offered so far.
+    seen: u64,
+}
+
+impl Reservoir {
+    /// Keeps the `n`th edge offered with probability `capacity / n`, replacing a random sampled edge.
+    fn offer(&mut self, edge: (u32, u32)) {
+        self.seen += 1;
+        if self.sample.len() < self.capacity {
+            self.sample.push(edge);
+        }
+        else {
+            let slot = self.rng.below(self.seen) as usize;
+            if slot < self.capacity { self.sample[slot] = edge; }
+        }
+    }
+}
+
+/// An estimate of the memory used by each run being merged: a chunk of keys, its encoding, and a
+/// read buffer.
+const RUN_READER_BYTES: usize = 8 * BLOCK_EDGES + 9 * BLOCK_EDGES + 8 * 1024;
+
+/// Writes a run file one chunk at a time.
+struct RunWriter {
+    writer: BufWriter<File>,
+    previous: u64,
+    bytes: Vec<u8>,
+    written: u64,
+}
+
+impl RunWriter {
+    fn create(path: &Path) -> Result<RunWriter> {
+        Ok(RunWriter { writer: BufWriter::new(File::create(path)?), previous: 0, bytes: Vec::new(), written: 0 })
+    }
+
+    /// Appends a non-empty chunk of at most `BLOCK_EDGES` keys, greater than those before them.
+    fn write_chunk(&mut self, chunk: &[u64]) -> Result<()> {
+        let mut compressor = Compressor::after(self.previous);
+        let from = compressor.position();
+        for &key in chunk {
+            compressor.push(key);
+        }
+        let to = compressor.position();
+        self.bytes.clear();
+        compressor.done().encode(from, to, &mut self.bytes);
+        self.writer.write_all(&(chunk.len() as u64).to_le_bytes())?;
+        self.writer.write_all(&(self.bytes.len() as u64).to_le_bytes())?;
+        self.writer.write_all(&self.bytes)?;
+        self.previous = chunk[chunk.len() - 1];
+        self.written += 16 + self.bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Flushes the run, and syncs it to disk if `sync`, returning the number of bytes written.
+    fn finish(mut self, sync: bool) -> Result<u64> {
+        self.writer.flush()?;
+        if sync {
+            self.writer.get_ref().sync_all()?;
+        }
+        Ok(self.written)
+    }
+}
+
+/// Merges the keys of several runs into one increasing sequence, without duplicates.
+struct RunMerge {
+    runs: Vec<RunReader>,
+    heap: BinaryHeap<Reverse<(u64, usize)>>,
+    last: Option<u64>,
+}
+
+impl RunMerge {
+    fn open(paths: &[PathBuf]) -> Result<RunMerge> {
+        let mut runs = Vec::with_capacity(paths.len());
+        for path in paths {
+            runs.push(RunReader::open(path)?);
+        }
+        let mut heap = BinaryHeap::with_capacity(runs.len());
+        for (index, run) in runs.iter_mut().enumerate() {
+            if let Some(key) = run.next()? {
+                heap.push(Reverse((key, index)));
+            }
+        }
+        Ok(RunMerge { runs, heap, last: None })
+    }
+
+    fn next(&mut self) -> Result<Option<u64>> {
+        while let Some(Reverse((key, index))) = self.heap.pop() {
+            if let Some(next) = self.runs[index].next()? {
+                self.heap.push(Reverse((next, index)));
+            }
+            if self.last != Some(key) {
+                self.last = Some(key);
+                return Ok(Some(key));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Reads the keys of a run file one chunk at a time.
+struct RunReader {
+    reader: BufReader<File>,
+    keys: Vec<u64>,
+    next: usize,
+    previous: u64,
+    bytes: Vec<u8>,
+}
+
+impl RunReader {
+    fn open(path: &Path) -> Result<RunReader> {
+        Ok(RunReader {
+            reader: BufReader::new(File::open(path)?),
+            keys: Vec::with_capacity(BLOCK_EDGES),
+            next: 0,
+            previous: 0,
+            bytes: Vec::new(),
+        })
+    }
+
+    fn next(&mut self) -> Result<Option<u64>> {
+        if self.next == self.keys.len() && !self.read_chunk()? {
+            return Ok(None);
+        }
+        self.next += 1;
+        Ok(Some(self.keys[self.next - 1]))
+    }
+
+    /// Decodes the next chunk, returning false at the end of the run.
+    fn read_chunk(&mut self) -> Result<bool> {
+        let mut words = [0u8; 16];
+        match self.reader.read_exact(&mut words) {
+            Ok(()) => { },
+            Err(ref error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+            Err(error) => return Err(error.into()),
+        }
+        let count = u64::from_le_bytes([words[0], words[1], words[2], words[3], words[4], words[5], words[6], words[7]]) as usize;
+        let length = u64::from_le_bytes([words[8], words[9], words[10], words[11], words[12], words[13], words[14], words[15]]) as usize;
+        self.bytes.resize(length, 0);
+        self.reader.read_exact(&mut self.bytes)?;
+        let keys = EncodedDecompressor::new(self.previous, count, &self.bytes)?;
+        self.keys.clear();
+        self.keys.extend(keys);
+        self.next = 0;
+        self.previous = self.keys.last().cloned().unwrap_or(self.previous);
+        Ok(!self.keys.is_empty())
+    }
+}
@@ -0,0 +1,180 @@
+//! Typed per-edge columns stored alongside the keys of a layout.
+//!
+//! A column holds one value per edge in curve order, so its `i`th value belongs to the `i`th edge
+//! yielded by `GraphLayout::edges`, and the values of a block or tile are the run of the column
+//! starting at its `offset`. Columns attached while building a layout are permuted along with the
+//! edges as they are sorted and deduplicated, are carried through `GraphLayout::union`, and are
+//! written to layout files as `section::COLUMN` sections.
+
+#[cfg(feature = "io")]
+use crate::error::{Error, Result};
+
+/// The name of the column holding edge weights.
+pub const WEIGHT_COLUMN: &str = "weight";
+
+/// The values of one column.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "rkyv", derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize))]
+pub enum Column {
+    F64(Vec<f64>),
+    U64(Vec<u64>),
+    U32(Vec<u32>),
+    I64(Vec<i64>),
+    /// Variable-length byte strings, such as labels or serialized features.
+    Blobs(Vec<Vec<u8>>),
+}
+
+/// The type codes of columns in layout files.
+const CODE_F64: u64 = 1;
+const CODE_U64: u64 = 2;
+const CODE_U32: u64 = 3;
+const CODE_I64: u64 = 4;
+const CODE_BLOBS: u64 = 5;
+
+impl Column {
+    /// The number of values in the column.
+    pub fn len(&self) -> usize {
+        match *self {
+            Column::F64(ref values) => values.len(),
+            Column::U64(ref values) => values.len(),
+            Column::U32(ref values) => values.len(),
+            Column::I64(ref values) => values.len(),
+            Column::Blobs(ref values) => values.len(),
+        }
+    }
+    /// Indicates that the column holds no values.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// The column of the values at `indices`, in order.
+    pub(crate) fn select(&self, indices: &[usize]) -> Column {
+        match *self {
+            Column::F64(ref values) => Column::F64(indices.iter().map(|&index| values[index]).collect()),
+            Column::U64(ref values) => Column::U64(indices.iter().map(|&index| values[index]).collect()),
+            Column::U32(ref values) => Column::U32(indices.iter().map(|&index| values[index]).collect()),
+            Column::I64(ref values) => Column::I64(indices.iter().map(|&index| values[index]).collect()),
+            Column::Blobs(ref values) => Column::Blobs(indices.iter().map(|&index| values[index].clone()).collect()),
+        }
+    }
+
+    /// The column of the values at `(part, index)` for each pick, or `None` if the parts do not
+    /// all hold values of the same type.
+    pub(crate) fn gather(parts: &[&Column], picks: &[(usize, usize)]) -> Option<Column> {
+        let first = parts.first()?;
+        if parts.iter().any(|part| part.code() != first.code()) {
+            return None;
+        }
+        let mut result = first.select(&[]);
+        for &(part, index) in picks {
+            match (&mut result, parts[part]) {
+                (Column::F64(values), Column::F64(from)) => values.push(from[index]),
+                (Column::U64(values), Column::U64(from)) => values.push(from[index]),
+                (Column::U32(values), Column::U32(from)) => values.push(from[index]),
+                (Column::I64(values), Column::I64(from)) => values.push(from[index]),
+                (Column::Blobs(values), Column::Blobs(from)) => values.push(from[index].clone()),
+                _ => unreachable!("parts were checked to share a type"),
+            }
+        }
+        Some(result)
+    }
+
+    /// The type code of the column in layout files.
+    pub(crate) fn code(&self) -> u64 {
+        match *self {
+            Column::F64(_) => CODE_F64,
+            Column::U64(_) => CODE_U64,
+            Column::U32(_) => CODE_U32,
+            Column::I64(_) => CODE_I64,
+            Column::Blobs(_) => CODE_BLOBS,
+        }
+    }
+
+    /// Appends the little-endian encoding of the values to `bytes`.
+    ///
+    /// Byte strings are encoded as the length of each string (`u64`), followed by their bytes.
+    #[cfg(feature = "io")]
+    pub(crate) fn encode(&self, bytes: &mut Vec<u8>) {
+        match *self {
+            Column::F64(ref values) => for value in values { bytes.extend_from_slice(&value.to_le_bytes()); },
+            Column::U64(ref values) => for value in values { bytes.extend_from_slice(&value.to_le_bytes()); },
+            Column::U32(ref values) => for value in values { bytes.extend_from_slice(&value.to_le_bytes()); },
+            Column::I64(ref values) => for value in values { bytes.extend_from_slice(&value.to_le_bytes()); },
+            Column::Blobs(ref values) => {
+                for value in values { bytes.extend_from_slice(&(value.len() as u64).to_le_bytes()); }
+                for value in values { bytes.extend_from_slice(value); }
+            },
+        }
+    }
+
+    /// Decodes `count` values encoded by `encode` as the column of type `code`, which must be
+    /// exactly `bytes`.
+    ///
+    /// Returns `Ok(None)` for type codes this crate does not know.
+    #[cfg(feature = "io")]
+    pub(crate) fn decode(code: u64, count: usize, bytes: &[u8]) -> Result<Option<Column>> {
+        let width = match code {
+            CODE_F64 | CODE_U64 | CODE_I64 | CODE_BLOBS => 8,
+            CODE_U32 => 4,
+            _ => return Ok(None),
+        };
+        let fixed = count.checked_mul(width).ok_or_else(|| Error::corrupt("column", "value count overflows"))?;
+        if bytes.len() < fixed || (code != CODE_BLOBS && bytes.len() != fixed) {
+            return Err(Error::corrupt("column", "length does not match its value count"));
+        }
+        let (head, mut rest) = bytes.split_at(fixed);
+        let words = head.chunks_exact(8).map(|chunk| {
+            let mut array = [0u8; 8];
+            array.copy_from_slice(chunk);
+            array
+        });
+        Ok(Some(match code {
+            CODE_F64 => Column::F64(words.map(f64::from_le_bytes).collect()),
+            CODE_U64 => Column::U64(words.map(u64::from_le_bytes).collect()),
+            CODE_I64 => Column::I64(words.map(i64::from_le_bytes).collect()),
+            CODE_U32 => Column::U32(head.chunks_exact(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()),
+            _ => {
+                let mut values = Vec::with_capacity(count);
+                for length in words.map(u64::from_le_bytes) {
+                    if length > rest.len() as u64 {
+                        return Err(Error::corrupt("column", "byte string out of range"));
+                    }
+                    let (value, remaining) = rest.split_at(length as usize);
+                    values.push(value.to_vec());
+                    rest = remaining;
+                }
+                if !rest.is_empty() {
+                    return Err(Error::corrupt("column", "length does not match its value count"));
+                }
+                Column::Blobs(values)
+            },
+        }))
+    }
+}
+
+/// The types of values held by a `Column`.
+pub trait ColumnType: Sized {
+    /// The values of `column`, if it holds values of this type.
+    fn values(column: &Column) -> Option<&[Self]>;
+}
+
+macro_rules! column_type {
+    ($type:ty, $variant:ident) => {
+        impl ColumnType for $type {
+            fn values(column: &Column) -> Option<&[$type]> {
+                match *column {
+                    Column::$variant(ref values) => Some(&values[..]),
+                    _ => None,
+                }
+            }
+        }
+        impl From<Vec<$type>> for Column {
+            fn from(values: Vec<$type>) -> Column { Column::$variant(values) }
+        }
+    }
+}
+
+column_type!(f64, F64);
+column_type!(u64, U64);
+column_type!(u32, U32);
+column_type!(i64, I64);
+column_type!(Vec<u8>, Blobs);
@@ -1,10 +1,20 @@
 //! Compression for strictly increasing sequences of `u64` values
 
+use std::slice::ChunksExact;
+use std::sync::Arc;
+
+use crate::counters::Counters;
+use crate::error::Error;
+
 /// A compressed stream of strictly increasing `u64` values.
 ///
 /// We optimistically assume that the differences will fit in a byte, with a zero byte to indicate
 /// that this is not the case, and one should consult the next byte to determine which of `u16`,
 /// `u32`, and `u64` should actually be used.
+///
+/// A finished stream is never modified, and is `Send` and `Sync`: it can be shared between threads,
+/// for example in an `Arc`, and read through any number of concurrent `Cursor`s or `Decompressor`s.
+#[cfg_attr(feature = "rkyv", derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize))]
 pub struct Compressed {
     bytes: Vec<u8>,
     other: Vec<Others>,
@@ -13,23 +23,38 @@ pub struct Compressed {
     u64s: Vec<u64>,
 }
 
+#[cfg(feature = "abomonation")]
+abomonate!(Compressed: bytes, other, u16s, u32s, u64s);
+
 impl Compressed {
     fn push(&mut self, delta: u64) {
         if 0 < delta && delta < 256 { self.bytes.push(delta as u8); }
         else {
             self.bytes.push(0);
-            if delta < (1 << 16) {
-                self.other.push(Others::Unsigned16);
-                self.u16s.push(delta as u16);
-            }
-            else if delta < (1 << 32) {
-                self.other.push(Others::Unsigned32);
-                self.u32s.push(delta as u32);
-            }
-            else {
-                self.other.push(Others::Unsigned64);
-                self.u64s.push(delta);
-            }
+            self.push_escaped(delta);
+        }
+    }
+    /// Removes all values, keeping the allocated buffers.
+    fn clear(&mut self) {
+        self.bytes.clear();
+        self.other.clear();
+        self.u16s.clear();
+        self.u32s.clear();
+        self.u64s.clear();
+    }
+    /// Records the delta of a zero byte in the narrowest width that holds it.
+    fn push_escaped(&mut self, delta: u64) {
+        if delta < (1 << 16) {
+            self.other.push(Others::Unsigned16);
+            self.u16s.push(delta as u16);
+        }
+        else if delta < (1 << 32) {
+            self.other.push(Others::Unsigned32);
+            self.u32s.push(delta as u32);
+        }
+        else {
+            self.other.push(Others::Unsigned64);
+            self.u64s.push(delta);
         }
     }
     pub fn from<I: Iterator<Item=u64>>(iterator: I) -> Compressed {
@@ -39,29 +64,600 @@ impl Compressed {
         }
         compressor.done()
     }
-    pub fn decompress(&self) -> Decompressor {
+    /// Compresses a strictly increasing slice, producing the same stream as `from`.
+    ///
+    /// Deltas are computed and truncated to bytes a chunk at a time in loops the compiler vectorizes,
+    /// and only chunks with escaped deltas are inspected value by value, without branching on them.
+    /// The escaped deltas are set aside as they are found and classified by width once all are
+    /// known, so that each vector is allocated exactly once and the input is read only once.
+    pub fn from_sorted_slice(values: &[u64]) -> Compressed {
+        Compressed::from_sorted_slice_in(values, Compressor::new().done(), &mut Vec::new())
+    }
+    /// Compresses a strictly increasing slice into the buffers of `spare`, using `escaped` to hold
+    /// escaped deltas while they are classified.
+    pub(crate) fn from_sorted_slice_in(values: &[u64], mut spare: Compressed, escaped: &mut Vec<u64>) -> Compressed {
+        let mut deltas = [0u64; ENCODE_CHUNK];
+        let mut chunk_escaped = [0u64; ENCODE_CHUNK];
+        escaped.clear();
+        spare.clear();
+        let mut bytes = spare.bytes;
+        bytes.resize(values.len(), 0);
+        let mut previous = 0;
+        for (chunk, bytes) in values.chunks(ENCODE_CHUNK).zip(bytes.chunks_mut(ENCODE_CHUNK)) {
+            let deltas = chunk_deltas(previous, chunk, &mut deltas);
+            for (byte, &delta) in bytes.iter_mut().zip(deltas.iter()) {
+                *byte = delta as u8;
+            }
+            // Deltas outside 1 .. 256 are escaped, and leave high bits set once one is subtracted.
+            if deltas.iter().fold(0, |high, &delta| high | delta.wrapping_sub(1)) >> 8 != 0 {
+                let mut count = 0;
+                for (byte, &delta) in bytes.iter_mut().zip(deltas.iter()) {
+                    let escape = delta.wrapping_sub(1) >= 255;
+                    *byte &= !escape as u8 * 0xFF;
+                    chunk_escaped[count] = delta;
+                    count += escape as usize;
+                }
+                escaped.extend_from_slice(&chunk_escaped[.. count]);
+            }
+            previous = chunk[chunk.len() - 1];
+        }
+
+        let wide = escaped.iter().filter(|&&delta| delta >= 1 << 16).count();
+        let huge = escaped.iter().filter(|&&delta| delta >= 1 << 32).count();
+        spare.other.reserve_exact(escaped.len());
+        spare.u16s.reserve_exact(escaped.len() - wide);
+        spare.u32s.reserve_exact(wide - huge);
+        spare.u64s.reserve_exact(huge);
+        let mut compressed = Compressed { bytes, ..spare };
+        for &delta in escaped.iter() {
+            compressed.push_escaped(delta);
+        }
+        compressed
+    }
+    /// A stream of the values for which `keep` returns true, re-encoded as they are decoded.
+    pub fn filter<F: FnMut(u64) -> bool>(&self, mut keep: F) -> Compressed {
+        let mut compressor = Compressor::new();
+        self.decompress().filter(|&value| keep(value)).for_each(|value| compressor.push(value));
+        compressor.done()
+    }
+    /// Keeps only the values for which `keep` returns true.
+    pub fn retain<F: FnMut(u64) -> bool>(&mut self, keep: F) {
+        *self = self.filter(keep);
+    }
+    /// A stream of `f` applied to each value, re-encoded as it is decoded.
+    ///
+    /// `f` must be strictly increasing over the values, so that its results are too; otherwise
+    /// the result is meaningless, and debug builds panic. `map_monotone_checked` verifies this.
+    pub fn map_monotone<F: FnMut(u64) -> u64>(&self, mut f: F) -> Compressed {
+        let mut compressor = Compressor::with_capacity(self.len());
+        let mut previous = None;
+        for value in self.decompress() {
+            let next = f(value);
+            debug_assert!(previous.is_none_or(|previous| previous < next), "mapped values are not strictly increasing");
+            compressor.push(next);
+            previous = Some(next);
+        }
+        compressor.done()
+    }
+    /// A stream of `f` applied to each value, failing at the first result that does not exceed
+    /// the one before it.
+    pub fn map_monotone_checked<F: FnMut(u64) -> u64>(&self, mut f: F) -> crate::Result<Compressed> {
+        let mut compressor = Compressor::with_capacity(self.len());
+        let mut previous = None;
+        for value in self.decompress() {
+            let next = f(value);
+            if let Some(previous) = previous.filter(|&previous| previous >= next) {
+                return Err(Error::Unordered { previous, next });
+            }
+            compressor.push(next);
+            previous = Some(next);
+        }
+        Ok(compressor.done())
+    }
+    pub fn decompress(&self) -> Decompressor<'_> {
+        self.decompress_from(Position::default())
+    }
+    /// Resumes decompression from a position recorded while compressing.
+    pub fn decompress_from(&self, position: Position) -> Decompressor<'_> {
         Decompressor {
-            current: 0,
-            bytes: self.bytes.iter(),
-            other: self.other.iter(),
-            u16s: self.u16s.iter(),
-            u32s: self.u32s.iter(),
-            u64s: self.u64s.iter(),
+            current: position.current,
+            bytes: &self.bytes[position.bytes..],
+            other: self.other[position.other..].iter(),
+            u16s: &self.u16s[position.u16s..],
+            u32s: &self.u32s[position.u32s..],
+            u64s: &self.u64s[position.u64s..],
+            values: [0; DECODE_CHUNK],
+            next: 0,
+            filled: 0,
+            #[cfg(feature = "counters")]
+            counters: Counters::default(),
+        }
+    }
+    /// The number of values in the stream.
+    pub fn len(&self) -> usize { self.bytes.len() }
+    /// Indicates that the stream contains no values.
+    pub fn is_empty(&self) -> bool { self.bytes.is_empty() }
+    /// The number of bytes of compressed data, including escaped deltas and their widths.
+    pub fn size_bytes(&self) -> usize {
+        self.bytes.len() + self.other.len() + 2 * self.u16s.len() + 4 * self.u32s.len() + 8 * self.u64s.len()
+    }
+    /// Advances `position` past the next `count` values, without materializing them.
+    pub fn seek(&self, mut position: Position, count: usize) -> Position {
+        assert!(position.bytes + count <= self.bytes.len(), "seek past the end of the stream");
+        for _ in 0 .. count {
+            self.step(&mut position);
+        }
+        position
+    }
+    /// Advances `position` past the next value, returning it.
+    #[inline]
+    fn step(&self, position: &mut Position) -> u64 {
+        let byte = self.bytes[position.bytes];
+        position.bytes += 1;
+        // wrapping, as `Decompressor` does, so that reading values decoded from any bytes never panics.
+        if byte > 0 {
+            position.current = position.current.wrapping_add(byte as u64);
+        }
+        else {
+            position.current = position.current.wrapping_add(match self.other[position.other] {
+                Others::Unsigned16 => { position.u16s += 1; self.u16s[position.u16s - 1] as u64 },
+                Others::Unsigned32 => { position.u32s += 1; self.u32s[position.u32s - 1] as u64 },
+                Others::Unsigned64 => { position.u64s += 1; self.u64s[position.u64s - 1] },
+            });
+            position.other += 1;
+        }
+        position.current
+    }
+    /// A cursor at the start of the stream, independent of any other cursor.
+    pub fn cursor(&self) -> Cursor<'_> {
+        self.cursor_at(Position::default())
+    }
+    /// A cursor at a position recorded while compressing or seeking.
+    pub fn cursor_at(&self, position: Position) -> Cursor<'_> {
+        Cursor {
+            compressed: self,
+            position,
+            #[cfg(feature = "counters")]
+            counters: Counters::default(),
         }
     }
+    /// A handle on the shared stream that can be moved to other threads, each opening its own
+    /// cursors over the same data.
+    pub fn reader(self: &Arc<Compressed>) -> Reader {
+        Reader { compressed: Arc::clone(self) }
+    }
+    /// Appends a little-endian encoding of the values between `from` and `to` to `buffer`.
+    ///
+    /// The encoding is the run of delta bytes, a width byte (2, 4, or 8) for each zero byte, and
+    /// then the run's `u16`, `u32`, and `u64` deltas in turn.
+    pub fn encode(&self, from: Position, to: Position, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&self.bytes[from.bytes .. to.bytes]);
+        buffer.extend(self.other[from.other .. to.other].iter().map(|other| other.width()));
+        for value in &self.u16s[from.u16s .. to.u16s] { buffer.extend_from_slice(&value.to_le_bytes()); }
+        for value in &self.u32s[from.u32s .. to.u32s] { buffer.extend_from_slice(&value.to_le_bytes()); }
+        for value in &self.u64s[from.u64s .. to.u64s] { buffer.extend_from_slice(&value.to_le_bytes()); }
+    }
+    /// Appends `count` values from `data`, which must be exactly one encoding produced by `encode`.
+    ///
+    /// Any bytes are safe to decode: the encoding is rejected unless its lengths and widths agree,
+    /// its deltas sum without overflow, and only the first delta of an empty stream is zero. The
+    /// stream's last value is not known here, so callers appending to a non-empty stream must check
+    /// that the appended values do not wrap past it, as `io::parse_layout` does against its block
+    /// directory. On error the stream is left unchanged.
+    pub fn decode(&mut self, count: usize, data: &[u8]) -> crate::Result<()> {
+        let parts = Encoding::split(count, data, !self.is_empty())?;
+        self.bytes.extend_from_slice(parts.bytes);
+        self.other.extend(parts.widths.iter().map(|&width| match width {
+            2 => Others::Unsigned16,
+            4 => Others::Unsigned32,
+            _ => Others::Unsigned64,
+        }));
+        self.u16s.extend(parts.u16s.chunks(2).map(|c| u16::from_le_bytes([c[0], c[1]])));
+        self.u32s.extend(parts.u32s.chunks(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])));
+        self.u64s.extend(parts.u64s.chunks(8).map(|c| u64::from_le_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]])));
+        Ok(())
+    }
+}
+
+/// Conversions to and from roaring bitmaps, with the `roaring` feature.
+///
+/// Both directions stream values in order, one at a time, without an intermediate `Vec<u64>`.
+#[cfg(feature = "roaring")]
+impl Compressed {
+    /// The values of the stream as a roaring bitmap.
+    pub fn to_roaring(&self) -> ::roaring::RoaringTreemap {
+        ::roaring::RoaringTreemap::from_sorted_iter(self.decompress()).expect("compressed values are strictly increasing")
+    }
+    /// Compresses the values of a roaring bitmap, which it yields in increasing order.
+    pub fn from_roaring(bitmap: &::roaring::RoaringTreemap) -> Compressed {
+        let mut compressor = Compressor::with_capacity(bitmap.len() as usize);
+        for value in bitmap.iter() {
+            compressor.push(value);
+        }
+        compressor.done()
+    }
 }
 
+/// Zero-copy access to archived streams, with the `rkyv` feature.
+///
+/// A stream accessed with `rkyv::access`, from a buffer or a memory map, is decompressed straight
+/// from the archive without first deserializing it.
+#[cfg(feature = "rkyv")]
+impl ArchivedCompressed {
+    /// The number of values in the stream.
+    pub fn len(&self) -> usize { self.bytes.len() }
+    /// Indicates that the stream contains no values.
+    pub fn is_empty(&self) -> bool { self.bytes.is_empty() }
+    pub fn decompress(&self) -> ArchivedDecompressor<'_> {
+        self.decompress_from(Position::default())
+    }
+    /// Resumes decompression from a position recorded while compressing.
+    ///
+    /// Positions read from an archive are not trusted: any beyond the end of the stream leave
+    /// nothing to decompress from, rather than panicking.
+    pub fn decompress_from(&self, position: Position) -> ArchivedDecompressor<'_> {
+        ArchivedDecompressor {
+            current: position.current,
+            bytes: self.bytes.get(position.bytes..).unwrap_or(&[]).iter(),
+            other: self.other.get(position.other..).unwrap_or(&[]).iter(),
+            u16s: self.u16s.get(position.u16s..).unwrap_or(&[]).iter(),
+            u32s: self.u32s.get(position.u32s..).unwrap_or(&[]).iter(),
+            u64s: self.u64s.get(position.u64s..).unwrap_or(&[]).iter(),
+        }
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<'a> From<&'a ArchivedPosition> for Position {
+    fn from(position: &'a ArchivedPosition) -> Position {
+        Position {
+            current: position.current.to_native(),
+            bytes: position.bytes.to_native() as usize,
+            other: position.other.to_native() as usize,
+            u16s: position.u16s.to_native() as usize,
+            u32s: position.u32s.to_native() as usize,
+            u64s: position.u64s.to_native() as usize,
+        }
+    }
+}
+
+/// Decompresses an archived stream in place, as `Decompressor` does a `Compressed` stream.
+#[cfg(feature = "rkyv")]
+pub struct ArchivedDecompressor<'a> {
+    current: u64,
+    bytes: ::std::slice::Iter<'a, u8>,
+    other: ::std::slice::Iter<'a, ArchivedOthers>,
+    u16s: ::std::slice::Iter<'a, ::rkyv::Archived<u16>>,
+    u32s: ::std::slice::Iter<'a, ::rkyv::Archived<u32>>,
+    u64s: ::std::slice::Iter<'a, ::rkyv::Archived<u64>>,
+}
+
+#[cfg(feature = "rkyv")]
+impl<'a> ArchivedDecompressor<'a> {
+    /// The next escaped delta, if the archive holds one; archives are checked for layout but not
+    /// for agreement between their vectors, so a malformed one ends the values early.
+    fn escaped(&mut self) -> Option<u64> {
+        Some(match *self.other.next()? {
+            ArchivedOthers::Unsigned16 => self.u16s.next()?.to_native() as u64,
+            ArchivedOthers::Unsigned32 => self.u32s.next()?.to_native() as u64,
+            ArchivedOthers::Unsigned64 => self.u64s.next()?.to_native(),
+        })
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<'a> Iterator for ArchivedDecompressor<'a> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let delta = match *self.bytes.next()? {
+            0 => self.escaped()?,
+            byte => byte as u64,
+        };
+        self.current = self.current.wrapping_add(delta);
+        Some(self.current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.bytes.len(), Some(self.bytes.len()))
+    }
+}
+
+/// The validated parts of one encoding produced by `Compressed::encode`.
+struct Encoding<'a> {
+    bytes: &'a [u8],
+    widths: &'a [u8],
+    u16s: &'a [u8],
+    u32s: &'a [u8],
+    u64s: &'a [u8],
+    /// The sum of all deltas.
+    total: u64,
+}
+
+impl<'a> Encoding<'a> {
+    /// Splits `data` into the parts of an encoding of `count` values, checking that the parts'
+    /// lengths agree, that the deltas sum without overflow, and that no escaped delta is zero
+    /// except a first one that does not `continues` an earlier value.
+    fn split(count: usize, data: &'a [u8], continues: bool) -> crate::Result<Encoding<'a>> {
+        if data.len() < count { return Err(Error::corrupt("encoding", "shorter than its delta bytes")); }
+        let (bytes, rest) = data.split_at(count);
+        let escapes = bytes.iter().filter(|&&byte| byte == 0).count();
+        if rest.len() < escapes { return Err(Error::corrupt("encoding", "shorter than its width bytes")); }
+        let (widths, rest) = rest.split_at(escapes);
+        let mut counts = [0usize; 3];
+        for &width in widths {
+            match width {
+                2 => counts[0] += 1,
+                4 => counts[1] += 1,
+                8 => counts[2] += 1,
+                _ => return Err(Error::corrupt("encoding", "invalid escape width")),
+            }
+        }
+        if rest.len() != 2 * counts[0] + 4 * counts[1] + 8 * counts[2] {
+            return Err(Error::corrupt("encoding", "length does not match its escapes"));
+        }
+        let (u16s, rest) = rest.split_at(2 * counts[0]);
+        let (u32s, u64s) = rest.split_at(4 * counts[1]);
+        let mut parts = Encoding { bytes, widths, u16s, u32s, u64s, total: 0 };
+
+        let mut escaped = parts.escaped();
+        let mut total = 0u64;
+        for (index, &byte) in bytes.iter().enumerate() {
+            let delta = match byte {
+                0 => escaped.next().ok_or_else(|| Error::corrupt("encoding", "missing escaped delta"))?,
+                byte => byte as u64,
+            };
+            if delta == 0 && (index > 0 || continues) {
+                return Err(Error::corrupt("encoding", "repeats a value"));
+            }
+            total = total.checked_add(delta).ok_or_else(|| Error::corrupt("encoding", "deltas overflow"))?;
+        }
+        parts.total = total;
+        Ok(parts)
+    }
+
+    /// The escaped deltas, in order.
+    fn escaped(&self) -> impl Iterator<Item=u64> + 'a {
+        let (mut u16s, mut u32s, mut u64s) = (self.u16s.chunks_exact(2), self.u32s.chunks_exact(4), self.u64s.chunks_exact(8));
+        self.widths.iter().map_while(move |&width| escaped_delta(width, &mut u16s, &mut u32s, &mut u64s))
+    }
+}
+
+/// The next escaped delta of `width` bytes, from the chunks of that width.
+fn escaped_delta(width: u8, u16s: &mut ChunksExact<'_, u8>, u32s: &mut ChunksExact<'_, u8>, u64s: &mut ChunksExact<'_, u8>) -> Option<u64> {
+    Some(match width {
+        2 => { let c = u16s.next()?; u16::from_le_bytes([c[0], c[1]]) as u64 },
+        4 => { let c = u32s.next()?; u32::from_le_bytes([c[0], c[1], c[2], c[3]]) as u64 },
+        _ => { let c = u64s.next()?; u64::from_le_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]]) },
+    })
+}
+
+/// Decompresses values directly from one encoding produced by `Compressed::encode`.
+///
+/// This reads borrowed bytes, such as a memory-mapped file, without first decoding them into a
+/// `Compressed` stream.
+pub struct EncodedDecompressor<'a> {
+    current: u64,
+    bytes: ::std::slice::Iter<'a, u8>,
+    widths: ::std::slice::Iter<'a, u8>,
+    u16s: ChunksExact<'a, u8>,
+    u32s: ChunksExact<'a, u8>,
+    u64s: ChunksExact<'a, u8>,
+    #[cfg(feature = "counters")]
+    counters: Counters,
+}
+
+impl<'a> EncodedDecompressor<'a> {
+    /// Validates `data` as the encoding of `count` strictly increasing values, the first following
+    /// `previous`, or starting the stream if `previous` is zero.
+    ///
+    /// Any bytes are safe to pass: malformed encodings, and those whose values would overflow, are
+    /// rejected here, and iteration neither panics nor wraps.
+    pub fn new(previous: u64, count: usize, data: &'a [u8]) -> crate::Result<EncodedDecompressor<'a>> {
+        let parts = Encoding::split(count, data, previous > 0)?;
+        if previous.checked_add(parts.total).is_none() {
+            return Err(Error::corrupt("encoding", "values overflow"));
+        }
+        Ok(EncodedDecompressor {
+            current: previous,
+            bytes: parts.bytes.iter(),
+            widths: parts.widths.iter(),
+            u16s: parts.u16s.chunks_exact(2),
+            u32s: parts.u32s.chunks_exact(4),
+            u64s: parts.u64s.chunks_exact(8),
+            #[cfg(feature = "counters")]
+            counters: Counters::default(),
+        })
+    }
+    /// The escaped deltas and bytes read so far, with the `counters` feature.
+    pub fn counters(&self) -> Counters { counted!(self.counters) }
+}
+
+impl<'a> Iterator for EncodedDecompressor<'a> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        // widths and escaped values were counted against each other, and the deltas summed without
+        // overflow, by `Encoding::split`.
+        let byte = *self.bytes.next()?;
+        count! { self.counters.bytes_read += 1; }
+        let delta = match byte {
+            0 => {
+                let width = *self.widths.next()?;
+                count! { self.counters += escape_counters(width); }
+                escaped_delta(width, &mut self.u16s, &mut self.u32s, &mut self.u64s)?
+            },
+            byte => byte as u64,
+        };
+        self.current += delta;
+        Some(self.current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.bytes.len(), Some(self.bytes.len()))
+    }
+}
+
+/// A compressed stream of strictly increasing `u128` values, such as the curve keys of edges
+/// between `u64` vertices.
+///
+/// Values are grouped by their upper 64 bits, which are stored once per group. The lower 64 bits
+/// are delta-compressed as in `Compressed`, with deltas restarting from zero in each group, so a
+/// stream whose values all fit in 64 bits costs only one group more than a `Compressed` stream.
+pub struct WideCompressed {
+    /// The upper 64 bits of each group, and the number of values in the group.
+    groups: Vec<(u64, usize)>,
+    lows: Compressed,
+}
+
+impl WideCompressed {
+    pub fn from<I: Iterator<Item=u128>>(iterator: I) -> WideCompressed {
+        let mut groups: Vec<(u64, usize)> = Vec::new();
+        let mut compressor = Compressor::with_capacity(iterator.size_hint().0);
+        for value in iterator {
+            let high = (value >> 64) as u64;
+            match groups.last_mut() {
+                Some(&mut (last, ref mut count)) if last == high => *count += 1,
+                _ => {
+                    groups.push((high, 1));
+                    compressor.current = 0;
+                },
+            }
+            compressor.push(value as u64);
+        }
+        WideCompressed { groups, lows: compressor.done() }
+    }
+    pub fn decompress(&self) -> WideDecompressor<'_> {
+        WideDecompressor { groups: self.groups.iter(), high: 0, remaining: 0, last: 0, base: 0, lows: self.lows.decompress() }
+    }
+    /// The number of values in the stream.
+    pub fn len(&self) -> usize { self.lows.len() }
+    /// Indicates that the stream contains no values.
+    pub fn is_empty(&self) -> bool { self.lows.is_empty() }
+    /// The number of bytes of compressed data, including the group directory.
+    pub fn size_bytes(&self) -> usize { self.lows.size_bytes() + 16 * self.groups.len() }
+}
+
+pub struct WideDecompressor<'a> {
+    groups: ::std::slice::Iter<'a, (u64, usize)>,
+    high: u64,
+    remaining: usize,
+    /// The last value decoded from `lows`, and its value when the current group began.
+    ///
+    /// `lows` decodes ahead, so rather than restart it at each group its values are taken relative
+    /// to where the group began.
+    last: u64,
+    base: u64,
+    lows: Decompressor<'a>,
+}
+
+impl<'a> Iterator for WideDecompressor<'a> {
+    type Item = u128;
+    fn next(&mut self) -> Option<u128> {
+        while self.remaining == 0 {
+            let &(high, count) = self.groups.next()?;
+            self.high = high;
+            self.remaining = count;
+            self.base = self.last;
+        }
+        self.remaining -= 1;
+        self.last = self.lows.next()?;
+        Some(((self.high as u128) << 64) | self.last.wrapping_sub(self.base) as u128)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.lows.size_hint()
+    }
+}
+
+/// Counts the values common to two strictly increasing sequences, by merging them.
+pub fn intersection_size<I: Iterator<Item=u64>, J: Iterator<Item=u64>>(a: I, b: J) -> usize {
+    let mut a = a.peekable();
+    let mut b = b.peekable();
+    let mut count = 0;
+    while let (Some(&x), Some(&y)) = (a.peek(), b.peek()) {
+        if x < y { a.next(); }
+        else if y < x { b.next(); }
+        else {
+            count += 1;
+            a.next();
+            b.next();
+        }
+    }
+    count
+}
+
+/// A point in a `Compressed` stream from which decompression can resume.
+///
+/// The `bytes` offset doubles as the index of the next value, as each value has exactly one entry
+/// in the byte stream.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "rkyv", derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize))]
+pub struct Position {
+    current: u64,
+    bytes: usize,
+    other: usize,
+    u16s: usize,
+    u32s: usize,
+    u64s: usize,
+}
+
+#[cfg(feature = "abomonation")]
+abomonate!(Position);
+
+impl Position {
+    /// The offset into the byte stream, equal to the number of preceding values.
+    pub fn offset(&self) -> usize { self.bytes }
+    /// The value preceding the position, or zero at the start of the stream.
+    pub fn previous(&self) -> u64 { self.current }
+}
+
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "rkyv", derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize))]
 enum Others {
     Unsigned16,
     Unsigned32,
     Unsigned64,
 }
 
+#[cfg(feature = "abomonation")]
+abomonate!(Others);
+
+impl Others {
+    /// The number of bytes of the escaped delta.
+    fn width(self) -> u8 {
+        match self {
+            Others::Unsigned16 => 2,
+            Others::Unsigned32 => 4,
+            Others::Unsigned64 => 8,
+        }
+    }
+}
+
+/// The counts of reading the width byte and value of one escaped delta of `width` bytes, two,
+/// four, or eight.
+#[cfg(feature = "counters")]
+fn escape_counters(width: u8) -> Counters {
+    let mut escapes = [0; 3];
+    escapes[width.trailing_zeros() as usize - 1] = 1;
+    Counters { escapes, bytes_read: 1 + width as u64, ..Counters::default() }
+}
+
+/// The counts of reading the values between positions `from` and `to` of one stream.
+#[cfg(feature = "counters")]
+fn read_between(from: Position, to: Position) -> Counters {
+    let escapes = [to.u16s - from.u16s, to.u32s - from.u32s, to.u64s - from.u64s].map(|count| count as u64);
+    let bytes_read = (to.bytes - from.bytes + to.other - from.other) as u64 + 2 * escapes[0] + 4 * escapes[1] + 8 * escapes[2];
+    Counters { escapes, bytes_read, ..Counters::default() }
+}
+
 pub struct Compressor {
     current: u64,
     compressed: Compressed,
 }
 
+impl Default for Compressor {
+    fn default() -> Compressor { Compressor::new() }
+}
+
 impl Compressor {
     pub fn with_capacity(size: usize) -> Compressor {
         Compressor {
@@ -78,46 +674,267 @@ impl Compressor {
     pub fn new() -> Compressor {
         Compressor::with_capacity(0)
     }
+    /// A compressor writing into the buffers of a finished stream, which keep their capacity.
+    pub fn recycle(mut compressed: Compressed) -> Compressor {
+        compressed.clear();
+        Compressor { current: 0, compressed }
+    }
+    /// A compressor whose first value is encoded as a delta from `previous`.
+    ///
+    /// Used to encode a run of a longer stream on its own, as `Compressed::encode` would.
+    pub fn after(previous: u64) -> Compressor {
+        let mut compressor = Compressor::new();
+        compressor.current = previous;
+        compressor
+    }
     /// Pushes the next value in the sequence. Does not check that the sequence is ordered, because
     /// we don't want to explode if you start with zero.
     pub fn push(&mut self, next: u64) {
         self.compressed.push(next - self.current);
         self.current = next;
     }
+    /// The position at which the next pushed value will be recorded.
+    pub fn position(&self) -> Position {
+        Position {
+            current: self.current,
+            bytes: self.compressed.bytes.len(),
+            other: self.compressed.other.len(),
+            u16s: self.compressed.u16s.len(),
+            u32s: self.compressed.u32s.len(),
+            u64s: self.compressed.u64s.len(),
+        }
+    }
     pub fn done(self) -> Compressed {
         self.compressed
     }
 }
 
+/// The number of values `Compressed::from_sorted_slice` encodes at a time.
+const ENCODE_CHUNK: usize = 64;
+
+/// Writes the deltas of `chunk`, the first from `previous`, to the front of `deltas`.
+#[inline]
+fn chunk_deltas<'a>(previous: u64, chunk: &[u64], deltas: &'a mut [u64; ENCODE_CHUNK]) -> &'a [u64] {
+    let deltas = &mut deltas[.. chunk.len()];
+    deltas[0] = chunk[0] - previous;
+    for ((delta, &next), &prior) in deltas[1 ..].iter_mut().zip(&chunk[1 ..]).zip(chunk) {
+        *delta = next - prior;
+    }
+    deltas
+}
+
+/// A position in a shared `Compressed` stream, advanced independently of other cursors.
+///
+/// A cursor reads values one at a time, which suits skipping and short reads; `values` decodes the
+/// remainder of the stream in bulk.
+#[derive(Copy, Clone)]
+pub struct Cursor<'a> {
+    compressed: &'a Compressed,
+    position: Position,
+    #[cfg(feature = "counters")]
+    counters: Counters,
+}
+
+impl<'a> Cursor<'a> {
+    /// The position of the cursor, from which another cursor or decompressor can resume.
+    pub fn position(&self) -> Position { self.position }
+    /// The number of values before the cursor.
+    pub fn offset(&self) -> usize { self.position.bytes }
+    /// The next value, without advancing past it.
+    pub fn peek(&self) -> Option<u64> {
+        self.clone().next()
+    }
+    /// Advances past the next `count` values.
+    pub fn seek(&mut self, count: usize) {
+        count! { let from = self.position; }
+        self.position = self.compressed.seek(self.position, count);
+        count! {
+            self.counters.seeks += 1;
+            self.counters += read_between(from, self.position);
+        }
+    }
+    /// Advances past all values less than `target`, returning the first value at least `target`
+    /// without advancing past it.
+    pub fn seek_to(&mut self, target: u64) -> Option<u64> {
+        count! { let from = self.position; }
+        let mut found = None;
+        while self.position.bytes < self.compressed.bytes.len() {
+            let mut next = self.position;
+            let value = self.compressed.step(&mut next);
+            if value >= target {
+                found = Some(value);
+                break;
+            }
+            self.position = next;
+        }
+        count! {
+            self.counters.seeks += 1;
+            self.counters += read_between(from, self.position);
+        }
+        found
+    }
+    /// The escaped deltas and bytes read, and seeks performed, by the cursor so far, with the
+    /// `counters` feature.
+    pub fn counters(&self) -> Counters { counted!(self.counters) }
+    /// Decodes the values after the cursor, without advancing it.
+    pub fn values(&self) -> Decompressor<'a> {
+        self.compressed.decompress_from(self.position)
+    }
+}
+
+impl<'a> Iterator for Cursor<'a> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        if self.position.bytes < self.compressed.bytes.len() {
+            count! { let from = self.position; }
+            let value = self.compressed.step(&mut self.position);
+            count! { self.counters += read_between(from, self.position); }
+            Some(value)
+        }
+        else {
+            None
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.compressed.bytes.len() - self.position.bytes;
+        (remaining, Some(remaining))
+    }
+}
+
+/// An owned handle on a `Compressed` stream shared through an `Arc`.
+///
+/// Cloning a reader clones only the `Arc`. Readers can be moved to other threads, each opening
+/// cursors over the same data without copying it.
+#[derive(Clone)]
+pub struct Reader {
+    compressed: Arc<Compressed>,
+}
+
+impl Reader {
+    /// The shared stream.
+    pub fn compressed(&self) -> &Compressed { &self.compressed }
+    /// A cursor at the start of the stream.
+    pub fn cursor(&self) -> Cursor<'_> { self.compressed.cursor() }
+    /// A cursor at a position recorded while compressing or seeking.
+    pub fn cursor_at(&self, position: Position) -> Cursor<'_> { self.compressed.cursor_at(position) }
+}
+
+// Finished streams, and handles on them, are shared between threads.
+const _: () = {
+    fn shared<T: Send + Sync>() {}
+    let _ = shared::<Compressed>;
+    let _ = shared::<Reader>;
+    let _ = shared::<Cursor<'static>>;
+};
+
+/// The number of values a `Decompressor` decodes at a time.
+const DECODE_CHUNK: usize = 32;
+
+/// Iterates over the values of a `Compressed` stream.
+///
+/// Values are decoded a chunk at a time. A chunk without escaped (zero) bytes is a prefix sum of
+/// its delta bytes; a chunk with escapes first reads out their deltas and then selects them at the
+/// zero bytes without branching. Neither path branches on individual bytes, so mixed gap sizes do
+/// not cost a misprediction per value.
 pub struct Decompressor<'a> {
     current: u64,
-    bytes: ::std::slice::Iter<'a, u8>,
+    bytes: &'a [u8],
     other: ::std::slice::Iter<'a, Others>,
-    u16s: ::std::slice::Iter<'a, u16>,
-    u32s: ::std::slice::Iter<'a, u32>,
-    u64s: ::std::slice::Iter<'a, u64>,
+    u16s: &'a [u16],
+    u32s: &'a [u32],
+    u64s: &'a [u64],
+    /// The decoded chunk, of which `values[next .. filled]` remain to be returned.
+    values: [u64; DECODE_CHUNK],
+    next: usize,
+    filled: usize,
+    #[cfg(feature = "counters")]
+    counters: Counters,
+}
+
+impl<'a> Decompressor<'a> {
+    /// Decodes the next chunk of values, returning false if there are none.
+    #[inline(never)]
+    fn refill(&mut self) -> bool {
+        let count = ::std::cmp::min(DECODE_CHUNK, self.bytes.len());
+        let (chunk, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        count! { self.counters.bytes_read += count as u64; }
+        if chunk.contains(&0) {
+            self.refill_escaped(chunk);
+        }
+        else {
+            let mut current = self.current;
+            for (value, &byte) in self.values.iter_mut().zip(chunk) {
+                current = current.wrapping_add(byte as u64);
+                *value = current;
+            }
+            self.current = current;
+        }
+        self.next = 0;
+        self.filled = count;
+        count > 0
+    }
+
+    /// Decodes a chunk holding escaped deltas: the escaped deltas are read out in order, and then
+    /// selected at the chunk's zero bytes without branching on them.
+    #[inline(never)]
+    fn refill_escaped(&mut self, chunk: &[u8]) {
+        let mut escaped = [0u64; DECODE_CHUNK + 1];
+        let count = chunk.iter().filter(|&&byte| byte == 0).count();
+        for delta in &mut escaped[.. count] {
+            let other = *self.other.next().unwrap();
+            count! { self.counters += escape_counters(other.width()); }
+            *delta = match other {
+                Others::Unsigned16 => { let value = self.u16s[0] as u64; self.u16s = &self.u16s[1..]; value },
+                Others::Unsigned32 => { let value = self.u32s[0] as u64; self.u32s = &self.u32s[1..]; value },
+                Others::Unsigned64 => { let value = self.u64s[0]; self.u64s = &self.u64s[1..]; value },
+            };
+        }
+        let mut current = self.current;
+        let mut next = 0;
+        for (value, &byte) in self.values.iter_mut().zip(chunk) {
+            let escape = (byte == 0) as usize;
+            current = current.wrapping_add(byte as u64 + escaped[next] * escape as u64);
+            next += escape;
+            *value = current;
+        }
+        self.current = current;
+    }
+}
+
+impl<'a> Decompressor<'a> {
+    /// The escaped deltas and bytes read by the decompressor so far, with the `counters` feature.
+    ///
+    /// Values are decoded a chunk at a time, so the counts include the chunk of the last value
+    /// returned.
+    pub fn counters(&self) -> Counters { counted!(self.counters) }
 }
 
 impl<'a> Iterator for Decompressor<'a> {
     type Item = u64;
+    #[inline]
     fn next(&mut self) -> Option<u64> {
-        self.bytes.next().map(|&byte| {
-            if byte > 0 {
-                self.current += byte as u64;
-            }
-            else {
-                self.current += match *self.other.next().unwrap() {
-                    Others::Unsigned16 => { *self.u16s.next().unwrap() as u64 },
-                    Others::Unsigned32 => { *self.u32s.next().unwrap() as u64 },
-                    Others::Unsigned64 => { *self.u64s.next().unwrap() },
-                }
-            }
-
-            self.current
-        })
+        if self.next == self.filled && !self.refill() {
+            return None;
+        }
+        self.next += 1;
+        Some(self.values[self.next - 1])
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.bytes.len(), Some(self.bytes.len()))
+        let remaining = self.filled - self.next + self.bytes.len();
+        (remaining, Some(remaining))
+    }
+
+    fn fold<B, F: FnMut(B, u64) -> B>(mut self, init: B, mut f: F) -> B {
+        let mut accumulated = init;
+        loop {
+            for &value in &self.values[self.next .. self.filled] {
+                accumulated = f(accumulated, value);
+            }
+            if !self.refill() {
+                return accumulated;
+            }
+        }
     }
 }
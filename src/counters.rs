@@ -0,0 +1,89 @@
+//! Counters of the work done by tanglers, decompressors, and layout readers, with the `counters`
+//! feature.
+//!
+//! A slow query is slow in some layer: the detangler misses its cache as keys jump between tiles,
+//! more blocks are decoded than the query touches, wide escaped deltas dominate the stream, or
+//! ranges are sought one short read at a time. Each of these readers counts its own work, and
+//! `counters()` returns the counts so far; counts from several readers add up with `+=`.
+//!
+//! Without the feature no counts are kept, the readers carry no counter fields, and `counters()`
+//! returns zeros, so uninstrumented builds pay nothing.
+
+use std::ops::{Add, AddAssign};
+
+/// Runs the enclosed statements, which update counters, only with the `counters` feature. The
+/// statements are not wrapped in a block, so that they may bind values for later counts.
+#[cfg(feature = "curves")]
+#[cfg(feature = "counters")]
+macro_rules! count {
+    ($($statement:tt)*) => { $($statement)* }
+}
+#[cfg(feature = "curves")]
+#[cfg(not(feature = "counters"))]
+macro_rules! count {
+    ($($statement:tt)*) => { }
+}
+
+/// The counts `$counters`, or zeros without the `counters` feature.
+#[cfg(feature = "curves")]
+#[cfg(feature = "counters")]
+macro_rules! counted {
+    ($counters:expr) => { $counters }
+}
+#[cfg(feature = "curves")]
+#[cfg(not(feature = "counters"))]
+macro_rules! counted {
+    ($counters:expr) => { $crate::counters::Counters::default() }
+}
+
+/// Indicates that counts are kept, which requires the `counters` feature.
+pub const ENABLED: bool = cfg!(feature = "counters");
+
+/// Counts of work done by a reader.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Counters {
+    /// Keys detangled from a cached block of the curve.
+    pub cache_hits: u64,
+    /// Keys whose block of the curve was not cached, and was detangled in full.
+    pub cache_misses: u64,
+    /// Blocks decoded, from their start.
+    pub blocks_decoded: u64,
+    /// Escaped deltas read, of two, four, and eight bytes.
+    pub escapes: [u64; 3],
+    /// Positions sought: cursors advanced past values without returning them, and curve ranges
+    /// found through a block directory.
+    pub seeks: u64,
+    /// Bytes of compressed data read, counting each escaped delta as its width byte and its value.
+    pub bytes_read: u64,
+}
+
+impl Counters {
+    /// The number of escaped deltas read, of any width.
+    pub fn escaped(&self) -> u64 { self.escapes.iter().sum() }
+    /// The fraction of detangled keys found in the cache, or zero if none were detangled.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.cache_hits + self.cache_misses;
+        if total == 0 { 0.0 } else { self.cache_hits as f64 / total as f64 }
+    }
+}
+
+impl AddAssign for Counters {
+    fn add_assign(&mut self, other: Counters) {
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+        self.blocks_decoded += other.blocks_decoded;
+        for (escapes, other) in self.escapes.iter_mut().zip(other.escapes) {
+            *escapes += other;
+        }
+        self.seeks += other.seeks;
+        self.bytes_read += other.bytes_read;
+    }
+}
+
+impl Add for Counters {
+    type Output = Counters;
+    fn add(mut self, other: Counters) -> Counters {
+        self += other;
+        self
+    }
+}
@@ -0,0 +1,66 @@
+//! Adjacency lists grouped by vertex, regrouped from the curve order of a layout.
+//!
+//! Exports to compressed sparse row formats and the per-vertex adjacency codecs all start from
+//! each vertex's neighbors listed contiguously, which a counting sort by source produces.
+
+use crate::graph::GraphLayout;
+
+/// Adjacency lists grouped by vertex.
+pub(crate) struct Csr {
+    /// Neighbors of vertex `v` are `targets[offsets[v] .. offsets[v + 1]]`.
+    pub offsets: Vec<u64>,
+    pub targets: Vec<u32>,
+    /// Weights aligned with `targets`, if the layout is weighted, which only exports read.
+    #[cfg_attr(not(feature = "io"), allow(dead_code))]
+    pub weights: Option<Vec<f64>>,
+}
+
+impl Csr {
+    /// Groups the edges of `layout` by source, or by destination if `transpose` is set.
+    ///
+    /// The vertex count is one more than the largest endpoint, or `vertices` if that is larger.
+    pub fn from_layout(layout: &GraphLayout, transpose: bool, vertices: usize) -> Csr {
+        let orient = |(src, dst): (u32, u32)| if transpose { (dst, src) } else { (src, dst) };
+        let mut counts = vec![0u64; vertices + 1];
+        for (src, _) in layout.edges().map(orient) {
+            if src as usize + 1 >= counts.len() { counts.resize(src as usize + 2, 0); }
+            counts[src as usize + 1] += 1;
+        }
+        for index in 1 .. counts.len() {
+            counts[index] += counts[index - 1];
+        }
+
+        let offsets = counts.clone();
+        let mut targets = vec![0u32; layout.len()];
+        let mut weights = layout.weights().map(|_| vec![0f64; layout.len()]);
+        for (index, (src, dst)) in layout.edges().map(orient).enumerate() {
+            let slot = counts[src as usize] as usize;
+            targets[slot] = dst;
+            if let (Some(weights), Some(source)) = (weights.as_mut(), layout.weights()) {
+                weights[slot] = source[index];
+            }
+            counts[src as usize] += 1;
+        }
+
+        // curve order does not sort neighbors within a list, so sort each list (and its weights).
+        for vertex in 0 .. offsets.len() - 1 {
+            let range = offsets[vertex] as usize .. offsets[vertex + 1] as usize;
+            match weights.as_mut() {
+                Some(weights) => {
+                    let mut pairs = targets[range.clone()].iter().cloned().zip(weights[range.clone()].iter().cloned()).collect::<Vec<_>>();
+                    pairs.sort_by_key(|&(target, _)| target);
+                    for (offset, (target, weight)) in pairs.into_iter().enumerate() {
+                        targets[range.start + offset] = target;
+                        weights[range.start + offset] = weight;
+                    }
+                },
+                None => targets[range].sort_unstable(),
+            }
+        }
+
+        Csr { offsets, targets, weights }
+    }
+
+    /// The number of vertices.
+    pub fn vertices(&self) -> usize { self.offsets.len() - 1 }
+}
@@ -0,0 +1,163 @@
+//! An ordered map keyed by `(u32, u32)` pairs, stored in Hilbert curve order.
+//!
+//! Spatial and graph indexes often need values attached to points of the plane, found by point,
+//! by stretch of the curve, or by rectangle. A `CurveMap` stores its keys as a compressed stream of
+//! curve keys with a block directory, as a `GraphLayout` stores edges, and its values in a vector
+//! aligned with the keys by rank. A cursor's offset into the stream is the rank of its next key, so
+//! any key found in the stream locates its value without further bookkeeping.
+//!
+//! Point lookups decode at most one block. Rectangle queries are planned as for layouts, by
+//! `query::plan`: the rectangle is decomposed into curve ranges read through the block directory,
+//! or the map is scanned if that is expected to be cheaper.
+
+use std::ops::RangeInclusive;
+
+use crate::compression::{Compressed, Compressor, Decompressor};
+use crate::counters::Counters;
+use crate::graph::{Block, Edges, BLOCK_EDGES};
+use crate::layout::{Hilbert, Tangle};
+use crate::query::{plan_blocks, Plan, Rect, RectKeys};
+
+/// An ordered map from `(u32, u32)` keys to values, in Hilbert curve order.
+pub struct CurveMap<V> {
+    keys: Compressed,
+    blocks: Vec<Block>,
+    /// The value of each key, by the key's rank.
+    values: Vec<V>,
+    /// One more than the largest coordinate of any key.
+    extent: u64,
+}
+
+impl<V> Default for CurveMap<V> {
+    fn default() -> CurveMap<V> { CurveMap::new() }
+}
+
+impl<V> CurveMap<V> {
+    /// An empty map.
+    pub fn new() -> CurveMap<V> {
+        CurveMap { keys: Compressor::with_capacity(0).done(), blocks: Vec::new(), values: Vec::new(), extent: 0 }
+    }
+
+    /// The number of keys.
+    pub fn len(&self) -> usize { self.values.len() }
+    /// Indicates that there are no keys.
+    pub fn is_empty(&self) -> bool { self.values.is_empty() }
+    /// The number of bytes used by the compressed keys.
+    pub fn size_bytes(&self) -> usize { self.keys.size_bytes() }
+    /// The block directory of the keys.
+    pub fn blocks(&self) -> &[Block] { &self.blocks[..] }
+
+    /// The value of `key`, if present.
+    pub fn get(&self, key: (u32, u32)) -> Option<&V> {
+        self.rank(key).map(|rank| &self.values[rank])
+    }
+    /// The value of `key` for modification, if present.
+    pub fn get_mut(&mut self, key: (u32, u32)) -> Option<&mut V> {
+        self.rank(key).map(move |rank| &mut self.values[rank])
+    }
+    /// Indicates that the map contains `key`.
+    pub fn contains_key(&self, key: (u32, u32)) -> bool {
+        self.rank(key).is_some()
+    }
+
+    /// Iterates over all keys, in curve order.
+    pub fn keys(&self) -> Edges<Decompressor<'_>> { Edges::new(self.keys.decompress()) }
+    /// The values, in the curve order of their keys.
+    pub fn values(&self) -> &[V] { &self.values[..] }
+    /// The values for modification, in the curve order of their keys.
+    pub fn values_mut(&mut self) -> &mut [V] { &mut self.values[..] }
+    /// Iterates over all keys and their values, in curve order.
+    pub fn iter(&self) -> ::std::iter::Zip<Edges<Decompressor<'_>>, ::std::slice::Iter<'_, V>> {
+        self.keys().zip(self.values.iter())
+    }
+
+    /// Iterates over the keys whose curve keys lie in `curve`, and their values, in curve order.
+    pub fn range(&self, curve: RangeInclusive<u64>) -> Entries<'_, V> {
+        let (lower, upper) = (*curve.start(), *curve.end());
+        let ranges = if lower <= upper { vec![(lower, upper)] } else { Vec::new() };
+        let all = Rect::new(0 ..= u32::MAX, 0 ..= u32::MAX);
+        Entries { keys: RectKeys::new(&self.keys, &self.blocks, all, Plan::Ranges(ranges)), values: &self.values }
+    }
+    /// Chooses how to read the keys within `rect`, as described in `query`.
+    pub fn plan(&self, rect: Rect) -> Plan {
+        plan_blocks(&self.blocks, self.extent, rect, |_, _| true)
+    }
+    /// Iterates over the keys within `rect`, and their values, in curve order, by scanning or by
+    /// reading the curve ranges covering it, whichever `plan` expects to be cheaper.
+    pub fn rect(&self, rect: Rect) -> Entries<'_, V> {
+        Entries { keys: RectKeys::new(&self.keys, &self.blocks, rect, self.plan(rect)), values: &self.values }
+    }
+
+    /// Builds a map from entries sorted by curve key, without repeated keys.
+    fn from_sorted(entries: Vec<(u64, V)>) -> CurveMap<V> {
+        let hilbert = Hilbert::shared();
+        let mut compressor = Compressor::with_capacity(entries.len());
+        let mut blocks: Vec<Block> = Vec::with_capacity(entries.len().div_ceil(BLOCK_EDGES));
+        let mut values = Vec::with_capacity(entries.len());
+        let mut extent = 0;
+        for (index, (key, value)) in entries.into_iter().enumerate() {
+            if index % BLOCK_EDGES == 0 {
+                let position = compressor.position();
+                blocks.push(Block { lower: key, upper: key, edges: 0, offset: position.offset(), position });
+            }
+            let block = blocks.last_mut().unwrap();
+            block.upper = key;
+            block.edges += 1;
+            let (x, y) = hilbert.detangle(key);
+            extent = ::std::cmp::max(extent, ::std::cmp::max(x, y) as u64 + 1);
+            compressor.push(key);
+            values.push(value);
+        }
+        CurveMap { keys: compressor.done(), blocks, values, extent }
+    }
+
+    /// The rank of `key` among the keys, if present.
+    fn rank(&self, key: (u32, u32)) -> Option<usize> {
+        let key = Hilbert::shared().entangle(key);
+        let index = self.blocks.partition_point(|block| block.upper < key);
+        let block = self.blocks.get(index).filter(|block| block.lower <= key)?;
+        let mut cursor = self.keys.cursor_at(block.position);
+        // a cursor's offset counts the keys before it.
+        match cursor.seek_to(key) {
+            Some(found) if found == key => Some(cursor.offset()),
+            _ => None,
+        }
+    }
+}
+
+/// Collects entries in any order; of entries with equal keys, the last is kept.
+impl<V> FromIterator<((u32, u32), V)> for CurveMap<V> {
+    fn from_iter<I: IntoIterator<Item=((u32, u32), V)>>(entries: I) -> CurveMap<V> {
+        let hilbert = Hilbert::shared();
+        let mut entries = entries.into_iter().map(|(key, value)| (hilbert.entangle(key), value)).collect::<Vec<_>>();
+        // a stable sort keeps entries with equal keys in the order given.
+        entries.sort_by_key(|&(key, _)| key);
+        let mut distinct: Vec<(u64, V)> = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            match distinct.last_mut() {
+                Some(last) if last.0 == key => last.1 = value,
+                _ => distinct.push((key, value)),
+            }
+        }
+        CurveMap::from_sorted(distinct)
+    }
+}
+
+/// Iterates over the keys of a `CurveMap` within a rectangle or curve range, and their values.
+pub struct Entries<'a, V> {
+    keys: RectKeys<'a>,
+    values: &'a [V],
+}
+
+impl<'a, V> Entries<'a, V> {
+    /// The blocks decoded, ranges sought, and escaped deltas and bytes read so far, with the
+    /// `counters` feature.
+    pub fn counters(&self) -> Counters { self.keys.counters() }
+}
+
+impl<'a, V> Iterator for Entries<'a, V> {
+    type Item = ((u32, u32), &'a V);
+    fn next(&mut self) -> Option<((u32, u32), &'a V)> {
+        self.keys.next().map(|(rank, key)| (key, &self.values[rank]))
+    }
+}
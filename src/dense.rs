@@ -0,0 +1,120 @@
+//! Dense tiles split from a tiled layout, for hybrid dense and sparse kernels.
+//!
+//! In many graphs a few regions of the adjacency matrix are nearly full, and kernels run faster
+//! over those as dense matrices than as lists of edges. `GraphLayout::dense_tiles` materializes
+//! each tile whose density reaches a threshold as a `DenseTile`: a bit matrix of its cells, and for
+//! weighted layouts a matrix of `f32` weights. The remaining tiles stay in a sparse layout.
+
+use crate::graph::GraphLayout;
+use crate::layout::{Hilbert, Tangle};
+
+/// One tile of the adjacency matrix, stored densely.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DenseTile {
+    /// The first source of the tile's rows.
+    pub row: u32,
+    /// The first destination of the tile's columns.
+    pub column: u32,
+    /// The number of rows and of columns.
+    pub side: u32,
+    /// The number of edges in the tile.
+    pub edges: usize,
+    /// A bit per cell in row-major order, set where the tile holds an edge: the cell of row `r`
+    /// and column `c` is bit `i % 64` of `bits[i / 64]`, for `i = r * side + c`.
+    pub bits: Vec<u64>,
+    /// For weighted layouts, the weight of each cell in row-major order, zero where there is no edge.
+    pub weights: Option<Vec<f32>>,
+}
+
+impl DenseTile {
+    /// Indicates that the tile holds the edge `(src, dst)`, which must lie within the tile.
+    pub fn contains(&self, (src, dst): (u32, u32)) -> bool {
+        let index = self.index((src, dst));
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+    /// The weight of the edge `(src, dst)`, which must lie within the tile, if it is present and
+    /// the tile is weighted.
+    pub fn weight(&self, edge: (u32, u32)) -> Option<f32> {
+        match self.weights {
+            Some(ref weights) if self.contains(edge) => Some(weights[self.index(edge)]),
+            _ => None,
+        }
+    }
+    /// Iterates over the tile's edges, in row-major order.
+    pub fn edges(&self) -> impl Iterator<Item=(u32, u32)> + '_ {
+        self.bits.iter().enumerate().flat_map(move |(word, &bits)| {
+            (0 .. 64).filter(move |bit| bits & (1 << bit) != 0).map(move |bit| {
+                let index = (64 * word + bit) as u64;
+                (self.row + (index / self.side as u64) as u32, self.column + (index % self.side as u64) as u32)
+            })
+        })
+    }
+
+    fn index(&self, (src, dst): (u32, u32)) -> usize {
+        assert!(src.wrapping_sub(self.row) < self.side && dst.wrapping_sub(self.column) < self.side, "edge outside the tile");
+        (src - self.row) as usize * self.side as usize + (dst - self.column) as usize
+    }
+}
+
+/// A layout split into dense tiles and a sparse remainder, by `GraphLayout::dense_tiles`.
+pub struct DenseSplit {
+    /// The tiles at or above the density threshold, in curve order.
+    pub dense: Vec<DenseTile>,
+    /// The edges of all other tiles, tiled as the original layout was and with their weights and
+    /// column values.
+    pub sparse: GraphLayout,
+}
+
+impl GraphLayout {
+    /// Materializes each tile whose density is at least `threshold` as a `DenseTile`, and keeps
+    /// the edges of all other tiles in a sparse layout.
+    ///
+    /// A dense tile of side `2^k` takes `4^k / 8` bytes of bits, and four times as many again for
+    /// weights if the layout is weighted. Panics if the layout is not tiled.
+    pub fn dense_tiles(&self, threshold: f64) -> DenseSplit {
+        let log = self.log_tile.expect("dense tiles require a tiled layout");
+        let side = 1u32 << log;
+        let cells = 1usize << (2 * log);
+        let hilbert = Hilbert::new();
+        let mut dense = Vec::new();
+        let mut keys = Vec::new();
+        let mut weights = self.weights.as_ref().map(|_| Vec::new());
+        let mut kept = Vec::new();
+        // the index of each tile's first edge in curve order, and so of its first weight.
+        let mut first = 0;
+        for (tile, summary) in self.tiles.iter().zip(self.summaries.iter()) {
+            let tile_weights = self.weights.as_ref().map(|weights| &weights[first .. first + tile.edges]);
+            first += tile.edges;
+            if summary.density < threshold {
+                keys.extend(self.tile_keys(tile));
+                if let (Some(weights), Some(tile_weights)) = (weights.as_mut(), tile_weights) {
+                    weights.extend_from_slice(tile_weights);
+                }
+                if !self.columns.is_empty() {
+                    kept.extend(tile.offset .. tile.offset + tile.edges);
+                }
+                continue;
+            }
+            let (x, y) = hilbert.detangle(tile.lower);
+            let mut matrix = DenseTile {
+                row: (x >> log) << log,
+                column: (y >> log) << log,
+                side,
+                edges: tile.edges,
+                bits: vec![0; cells.div_ceil(64)],
+                weights: tile_weights.map(|_| vec![0.0; cells]),
+            };
+            for (offset, edge) in self.tile_edges(tile).enumerate() {
+                let index = matrix.index(edge);
+                matrix.bits[index / 64] |= 1 << (index % 64);
+                if let (Some(values), Some(tile_weights)) = (matrix.weights.as_mut(), tile_weights) {
+                    values[index] = tile_weights[offset] as f32;
+                }
+            }
+            dense.push(matrix);
+        }
+        let mut sparse = GraphLayout::assemble(keys, weights, self.log_tile);
+        sparse.columns = self.columns.iter().map(|(name, column)| (name.clone(), column.select(&kept))).collect();
+        DenseSplit { dense, sparse }
+    }
+}
@@ -0,0 +1,394 @@
+//! Assignment of contiguous `u32` identifiers to arbitrary `u64` vertex identifiers.
+//!
+//! Layouts index per-vertex data by identifier, so identifiers should be dense. Raw datasets often
+//! use sparse, hashed, or 64-bit identifiers instead; a `Densifier` renames them in order of first
+//! appearance as edges are ingested, and keeps the mapping in both directions. For identifier
+//! spaces too large to map in memory, an `ExternalDensifier` assigns identifiers by sorting on disk.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{Error, Result};
+use crate::progress::{Phase, Progress, Reporter};
+
+/// A mapping from raw identifiers to dense identifiers, assigned in order of first appearance.
+#[derive(Clone, Debug, Default)]
+pub struct Densifier {
+    forward: HashMap<u64, u32>,
+    backward: Vec<u64>,
+}
+
+impl Densifier {
+    /// An empty mapping.
+    pub fn new() -> Densifier {
+        Densifier::default()
+    }
+
+    /// The dense identifier of `raw`, assigning the next unused one if `raw` is new.
+    ///
+    /// Panics if more than `2^32` distinct identifiers are assigned.
+    pub fn id(&mut self, raw: u64) -> u32 {
+        let backward = &mut self.backward;
+        *self.forward.entry(raw).or_insert_with(|| {
+            assert!(backward.len() <= u32::MAX as usize, "more than 2^32 distinct vertex identifiers");
+            backward.push(raw);
+            (backward.len() - 1) as u32
+        })
+    }
+    /// The dense form of a raw edge, assigning identifiers as needed.
+    pub fn edge(&mut self, (src, dst): (u64, u64)) -> (u32, u32) {
+        (self.id(src), self.id(dst))
+    }
+    /// Densifies each edge of `edges`, assigning identifiers as they are first seen.
+    pub fn densify<'a, I: Iterator<Item=(u64, u64)> + 'a>(&'a mut self, edges: I) -> impl Iterator<Item=(u32, u32)> + 'a {
+        edges.map(move |edge| self.edge(edge))
+    }
+
+    /// The dense identifier of `raw`, if one has been assigned.
+    pub fn get(&self, raw: u64) -> Option<u32> { self.forward.get(&raw).cloned() }
+    /// The raw identifier of the dense identifier `id`.
+    pub fn raw(&self, id: u32) -> u64 { self.backward[id as usize] }
+    /// The raw identifier of each dense identifier, in order.
+    pub fn raw_ids(&self) -> &[u64] { &self.backward[..] }
+    /// The number of identifiers assigned.
+    pub fn len(&self) -> usize { self.backward.len() }
+    /// Indicates that no identifiers have been assigned.
+    pub fn is_empty(&self) -> bool { self.backward.is_empty() }
+}
+
+/// Densifies identifier spaces too large for an in-memory map, by sorting on disk.
+///
+/// Dense identifiers are assigned in increasing order of raw identifier, rather than in order of
+/// appearance. The edges are sorted by source and merged against the sorted distinct identifiers
+/// to rename sources, then sorted by destination and merged again to rename destinations, with
+/// each sort spilling runs of at most `buffer_records` records to disk. The renamed edges come out
+/// grouped by destination rather than in input order, which suits `LayoutBuilder`.
+pub struct ExternalDensifier {
+    capacity: usize,
+    /// The most runs of one sort merged at once.
+    fan_in: usize,
+    directory: PathBuf,
+    progress: Reporter,
+}
+
+impl ExternalDensifier {
+    /// A densifier holding at most `buffer_records` records in memory, spilling to the system's
+    /// temporary directory.
+    pub fn new(buffer_records: usize) -> ExternalDensifier {
+        assert!(buffer_records > 0, "the buffer must hold at least one record");
+        ExternalDensifier { capacity: buffer_records, fan_in: usize::MAX, directory: ::std::env::temp_dir(), progress: Reporter::none() }
+    }
+    /// Spills runs to `directory` rather than the system's temporary directory.
+    pub fn spill_directory<P: AsRef<Path>>(mut self, directory: P) -> ExternalDensifier {
+        self.directory = directory.as_ref().to_path_buf();
+        self
+    }
+    /// Bounds the memory used for sorting and merging by about `bytes`, replacing the buffer size
+    /// given to `new`.
+    ///
+    /// At most two sorts are active at once, so each buffers `bytes / 32` records of 16 bytes and
+    /// merges at most as many runs at once as fit in half the budget, first merging groups of runs
+    /// into longer runs if needed.
+    ///
+    /// Panics if `bytes` cannot hold two runs of each sort being merged.
+    pub fn memory_budget(mut self, bytes: usize) -> ExternalDensifier {
+        assert!(bytes >= 4 * PAIR_READER_BYTES, "the budget must allow merging two runs of each sort");
+        self.capacity = bytes / 32;
+        self.fan_in = bytes / 2 / PAIR_READER_BYTES;
+        self
+    }
+    /// Calls `callback` about every `interval` edges read or renamed, after each spill, and once
+    /// all edges have been passed to the sink.
+    ///
+    /// While renaming, the reported edges are those passed to the sink so far. Reported bytes count
+    /// the runs and dictionary written to disk.
+    pub fn progress<F: FnMut(Progress) + Send + 'static>(mut self, interval: u64, callback: F) -> ExternalDensifier {
+        self.progress = Reporter::new(interval, callback);
+        self
+    }
+
+    /// Passes each edge of `edges`, densified, to `sink`, and writes the identifier mapping to
+    /// `dictionary`.
+    pub fn run<I, F, P>(&mut self, edges: I, dictionary: P, mut sink: F) -> Result<Dictionary>
+    where I: Iterator<Item=(u64, u64)>, F: FnMut((u32, u32)) -> Result<()>, P: AsRef<Path> {
+        let mut by_source = self.sorter(false);
+        let mut identifiers = self.sorter(true);
+        let mut read = 0;
+        for (src, dst) in edges {
+            let spilled = by_source.bytes + identifiers.bytes;
+            by_source.push((src, dst))?;
+            identifiers.push((src, 0))?;
+            identifiers.push((dst, 0))?;
+            read += 1;
+            if by_source.bytes + identifiers.bytes != spilled {
+                self.progress.report(Phase::Spilling, read, by_source.bytes + identifiers.bytes);
+            }
+            self.progress.update(Phase::Reading, read, by_source.bytes + identifiers.bytes);
+        }
+
+        let mut writer = BufWriter::new(File::create(dictionary.as_ref())?);
+        let mut ids = 0u64;
+        let mut merged = identifiers.finish()?;
+        let mut spilled = merged.bytes;
+        while let Some((raw, _)) = merged.next()? {
+            if ids > u32::MAX as u64 {
+                return Err(Error::Capacity("more than 2^32 distinct vertex identifiers".to_owned()));
+            }
+            writer.write_all(&raw.to_le_bytes())?;
+            ids += 1;
+        }
+        writer.flush()?;
+        drop(writer);
+        spilled += 8 * ids;
+
+        // each pass renames the first field of each record and swaps it to the second.
+        let mut by_destination = self.sorter(false);
+        let by_source = by_source.finish()?;
+        spilled += by_source.bytes;
+        rename(by_source, dictionary.as_ref(), |dst, src| by_destination.push((dst, src as u64)))?;
+        let by_destination = by_destination.finish()?;
+        spilled += by_destination.bytes;
+
+        let progress = &mut self.progress;
+        progress.report(Phase::Merging, 0, spilled);
+        let mut renamed = 0;
+        rename(by_destination, dictionary.as_ref(), |src, dst| {
+            renamed += 1;
+            progress.update(Phase::Merging, renamed, spilled);
+            sink((src as u32, dst))
+        })?;
+        progress.report(Phase::Done, renamed, spilled);
+
+        Ok(Dictionary { path: dictionary.as_ref().to_path_buf(), len: ids })
+    }
+
+    fn sorter(&self, dedup: bool) -> PairSorter {
+        // a budgeted buffer is allocated up front, rather than overshooting the budget as it grows.
+        let budgeted = self.fan_in < usize::MAX;
+        PairSorter {
+            directory: self.directory.clone(),
+            prefix: format!("graph-layout-densify-{}-{}", ::std::process::id(), SORTERS.fetch_add(1, Ordering::Relaxed)),
+            capacity: self.capacity,
+            fan_in: self.fan_in,
+            dedup,
+            buffer: if budgeted { Vec::with_capacity(self.capacity) } else { Vec::new() },
+            runs: Vec::new(),
+            created: 0,
+            bytes: 0,
+        }
+    }
+}
+
+/// Merge-joins `records`, sorted by their first field, against the sorted identifiers in
+/// `dictionary`, passing each record's second field and renamed first field to `output`.
+fn rename<F: FnMut(u64, u32) -> Result<()>>(mut records: MergedPairs, dictionary: &Path, mut output: F) -> Result<()> {
+    let mut identifiers = BufReader::new(File::open(dictionary)?);
+    let mut current = None;
+    let mut id = 0u32;
+    while let Some((raw, other)) = records.next()? {
+        while current != Some(raw) {
+            let mut bytes = [0u8; 8];
+            identifiers.read_exact(&mut bytes)?;
+            if current.is_some() { id += 1; }
+            current = Some(u64::from_le_bytes(bytes));
+        }
+        output(other, id)?;
+    }
+    Ok(())
+}
+
+/// The identifier mapping produced by an `ExternalDensifier`: the distinct raw identifiers in
+/// increasing order, as little-endian `u64` values, each at the position of its dense identifier.
+pub struct Dictionary {
+    path: PathBuf,
+    len: u64,
+}
+
+impl Dictionary {
+    /// Opens a dictionary written by an `ExternalDensifier`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Dictionary> {
+        let len = ::std::fs::metadata(path.as_ref())?.len();
+        if len % 8 != 0 {
+            return Err(Error::corrupt("dictionary", "length is not a multiple of eight"));
+        }
+        Ok(Dictionary { path: path.as_ref().to_path_buf(), len: len / 8 })
+    }
+
+    /// The number of identifiers.
+    pub fn len(&self) -> u64 { self.len }
+    /// Indicates that the dictionary holds no identifiers.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// The raw identifier of the dense identifier `id`, read from disk.
+    pub fn raw(&self, id: u32) -> Result<u64> {
+        self.read(&mut File::open(&self.path)?, id as u64)
+    }
+    /// The dense identifier of `raw`, if it occurs, found by binary search on disk.
+    pub fn get(&self, raw: u64) -> Result<Option<u32>> {
+        let mut file = File::open(&self.path)?;
+        let (mut lower, mut upper) = (0, self.len);
+        while lower < upper {
+            let middle = lower + (upper - lower) / 2;
+            let value = self.read(&mut file, middle)?;
+            if value == raw { return Ok(Some(middle as u32)); }
+            if value < raw { lower = middle + 1; } else { upper = middle; }
+        }
+        Ok(None)
+    }
+    /// Reads every raw identifier into memory, in order of dense identifier.
+    pub fn raw_ids(&self) -> Result<Vec<u64>> {
+        let bytes = ::std::fs::read(&self.path)?;
+        Ok(bytes.chunks_exact(8).map(|chunk| {
+            u64::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7]])
+        }).collect())
+    }
+
+    fn read(&self, file: &mut File, index: u64) -> Result<u64> {
+        let mut bytes = [0u8; 8];
+        file.seek(SeekFrom::Start(8 * index))?;
+        file.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+/// Distinguishes the run files of sorters in the same process.
+static SORTERS: AtomicUsize = AtomicUsize::new(0);
+
+/// An estimate of the memory used by each run being merged: a read buffer and a heap entry.
+const PAIR_READER_BYTES: usize = 8 * 1024 + 64;
+
+/// Sorts pairs with bounded memory by spilling sorted runs of 16-byte records to disk.
+struct PairSorter {
+    directory: PathBuf,
+    prefix: String,
+    capacity: usize,
+    fan_in: usize,
+    dedup: bool,
+    buffer: Vec<(u64, u64)>,
+    runs: Vec<PathBuf>,
+    /// The number of run files created so far.
+    created: usize,
+    /// The number of bytes written to runs.
+    bytes: u64,
+}
+
+impl PairSorter {
+    fn push(&mut self, pair: (u64, u64)) -> Result<()> {
+        self.buffer.push(pair);
+        if self.buffer.len() >= self.capacity {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> Result<()> {
+        self.buffer.sort_unstable();
+        if self.dedup { self.buffer.dedup(); }
+        let buffer = ::std::mem::take(&mut self.buffer);
+        self.write_run(buffer.iter().cloned().map(Ok))?;
+        self.buffer = buffer;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Writes `pairs` to a new run, placed after the others.
+    fn write_run<I: Iterator<Item=Result<(u64, u64)>>>(&mut self, pairs: I) -> Result<()> {
+        let path = self.directory.join(format!("{}-{}.run", self.prefix, self.created));
+        self.created += 1;
+        let mut writer = BufWriter::new(File::create(&path)?);
+        self.runs.push(path);
+        for pair in pairs {
+            let (first, second) = pair?;
+            writer.write_all(&first.to_le_bytes())?;
+            writer.write_all(&second.to_le_bytes())?;
+            self.bytes += 16;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Spills any buffered pairs and merges the runs, first merging groups of the oldest runs into
+    /// longer runs until at most `fan_in` remain.
+    fn finish(mut self) -> Result<MergedPairs> {
+        self.spill()?;
+        self.buffer = Vec::new();
+        while self.runs.len() > self.fan_in {
+            let group = self.runs.drain(.. self.fan_in).collect();
+            let mut merged = MergedPairs::open(group, self.dedup)?;
+            self.write_run(::std::iter::from_fn(|| merged.next().transpose()))?;
+        }
+        let mut merged = MergedPairs::open(::std::mem::take(&mut self.runs), self.dedup)?;
+        merged.bytes = self.bytes;
+        Ok(merged)
+    }
+}
+
+impl Drop for PairSorter {
+    fn drop(&mut self) {
+        for path in &self.runs {
+            let _ = ::std::fs::remove_file(path);
+        }
+    }
+}
+
+/// The sorted merge of a `PairSorter`'s runs, which are removed when it is dropped.
+struct MergedPairs {
+    readers: Vec<BufReader<File>>,
+    heap: BinaryHeap<Reverse<((u64, u64), usize)>>,
+    paths: Vec<PathBuf>,
+    dedup: bool,
+    last: Option<(u64, u64)>,
+    /// The number of bytes in the runs.
+    bytes: u64,
+}
+
+impl MergedPairs {
+    /// Merges the runs at `paths`, which are removed when the merge is dropped.
+    fn open(paths: Vec<PathBuf>, dedup: bool) -> Result<MergedPairs> {
+        let mut merged = MergedPairs { readers: Vec::new(), heap: BinaryHeap::new(), paths, dedup, last: None, bytes: 0 };
+        for index in 0 .. merged.paths.len() {
+            merged.readers.push(BufReader::new(File::open(&merged.paths[index])?));
+            if let Some(pair) = read_pair(&mut merged.readers[index])? {
+                merged.heap.push(Reverse((pair, index)));
+            }
+        }
+        Ok(merged)
+    }
+
+    fn next(&mut self) -> Result<Option<(u64, u64)>> {
+        while let Some(Reverse((pair, index))) = self.heap.pop() {
+            if let Some(next) = read_pair(&mut self.readers[index])? {
+                self.heap.push(Reverse((next, index)));
+            }
+            if !self.dedup || self.last != Some(pair) {
+                self.last = Some(pair);
+                return Ok(Some(pair));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Drop for MergedPairs {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = ::std::fs::remove_file(path);
+        }
+    }
+}
+
+fn read_pair(reader: &mut BufReader<File>) -> Result<Option<(u64, u64)>> {
+    let mut bytes = [0u8; 16];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => { },
+        Err(ref error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+    let first = u64::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]]);
+    let second = u64::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15]]);
+    Ok(Some((first, second)))
+}
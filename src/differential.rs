@@ -0,0 +1,58 @@
+//! Layouts and their differences as differential dataflow updates.
+//!
+//! Differential dataflow represents a changing collection as `(data, time, diff)` updates. A layout
+//! at one time is the insertion of each of its edges, and the change between two layouts is the
+//! insertion of the edges added and the deletion of those removed, which `GraphLayout::diff` finds
+//! without decoding either layout into memory.
+//!
+//! The `updates` iterators produce edges in curve order, which suits feeding an input session one
+//! update at a time. The `batch` functions collect the updates sorted by edge and then time, with
+//! no edge repeated at a time, which is the consolidated form differential's batch builders expect.
+
+use crate::graph::GraphLayout;
+use crate::layout::BytewiseCached;
+
+/// A differential dataflow update: an edge, a time, and a change in the edge's multiplicity.
+pub type Update<T> = ((u32, u32), T, isize);
+
+/// The insertion of each edge of `layout` at `time`, in curve order.
+pub fn updates<'a, T: Clone + 'a>(layout: &'a GraphLayout, time: T) -> impl Iterator<Item=Update<T>> + 'a {
+    layout.edges().map(move |edge| (edge, time.clone(), 1))
+}
+
+/// The insertion of each edge of `layout` at `time`, sorted by edge.
+pub fn batch<T: Clone>(layout: &GraphLayout, time: T) -> Vec<Update<T>> {
+    let mut batch = updates(layout, time).collect::<Vec<_>>();
+    batch.sort_unstable_by_key(|update| update.0);
+    batch
+}
+
+/// The updates at `time` that change `before` into `after`: an insertion for each edge only in
+/// `after` and a deletion for each edge only in `before`, in curve order.
+pub fn diff_updates<T: Clone>(before: &GraphLayout, after: &GraphLayout, time: T) -> impl Iterator<Item=Update<T>> {
+    let (added, removed) = before.diff(after);
+    // the added and removed keys are disjoint, and merge into a single curve order.
+    let mut inserted = added.decompress().map(|key| (key, 1)).peekable();
+    let mut deleted = removed.decompress().map(|key| (key, -1)).peekable();
+    let mut changes = Vec::with_capacity(added.len() + removed.len());
+    loop {
+        let next = match (inserted.peek(), deleted.peek()) {
+            (Some(&(insert, _)), Some(&(delete, _))) if delete < insert => deleted.next(),
+            (Some(_), _) => inserted.next(),
+            (None, _) => deleted.next(),
+        };
+        match next {
+            Some(change) => changes.push(change),
+            None => break,
+        }
+    }
+    let mut detangler = BytewiseCached::new();
+    changes.into_iter().map(move |(key, diff)| (detangler.detangle(key), time.clone(), diff))
+}
+
+/// The updates at `time` that change `before` into `after`, sorted by edge.
+pub fn diff_batch<T: Clone>(before: &GraphLayout, after: &GraphLayout, time: T) -> Vec<Update<T>> {
+    let mut batch = diff_updates(before, after, time).collect::<Vec<_>>();
+    batch.sort_unstable_by_key(|update| update.0);
+    batch
+}
@@ -0,0 +1,82 @@
+//! The errors of layout files, decoding, and builds.
+//!
+//! Reading and writing layout files, decoding encoded keys, and building layouts from edge streams
+//! report failures as an `Error`, distinguishing failed IO from data this crate cannot read, data
+//! that is malformed, keys supplied out of order, and requests beyond what is supported. An `Error`
+//! converts into an `std::io::Error`, so functions returning `std::io::Result` can use `?` on it.
+
+use std::fmt;
+
+/// A failure of a layout file, decoding, or build operation.
+#[derive(Debug)]
+pub enum Error {
+    /// An underlying read or write failed.
+    Io(::std::io::Error),
+    /// The data uses a version, codec, or parameter this crate does not read.
+    Unsupported {
+        /// What is unsupported, such as `"layout file version"`.
+        what: &'static str,
+        /// The value found.
+        found: u64,
+    },
+    /// The data is malformed: truncated, out of range, inconsistent, or failing a checksum.
+    Corrupt(String),
+    /// A key was supplied that is not greater than the key before it.
+    Unordered {
+        /// The key supplied before.
+        previous: u64,
+        /// The key supplied out of order.
+        next: u64,
+    },
+    /// A size or count exceeds what the operation supports.
+    Capacity(String),
+}
+
+/// The result of a layout file, decoding, or build operation.
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+impl Error {
+    /// Malformed data, described by what was being read and what is wrong with it.
+    #[cfg(feature = "codecs")]
+    pub(crate) fn corrupt(what: &str, message: &str) -> Error {
+        Error::Corrupt(format!("{}: {}", what, message))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => error.fmt(f),
+            Error::Unsupported { what, found } => write!(f, "unsupported {} {}", what, found),
+            Error::Corrupt(message) => write!(f, "corrupt {}", message),
+            Error::Unordered { previous, next } => write!(f, "key {} does not follow key {}", next, previous),
+            Error::Capacity(message) => write!(f, "capacity exceeded: {}", message),
+        }
+    }
+}
+
+impl ::std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<::std::io::Error> for Error {
+    fn from(error: ::std::io::Error) -> Error { Error::Io(error) }
+}
+
+/// IO errors are returned as they were; other errors become `InvalidData` or `InvalidInput`.
+impl From<Error> for ::std::io::Error {
+    fn from(error: Error) -> ::std::io::Error {
+        use std::io::ErrorKind;
+        let kind = match error {
+            Error::Io(error) => return error,
+            Error::Unsupported { .. } | Error::Corrupt(_) => ErrorKind::InvalidData,
+            Error::Unordered { .. } | Error::Capacity(_) => ErrorKind::InvalidInput,
+        };
+        ::std::io::Error::new(kind, error)
+    }
+}
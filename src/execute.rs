@@ -0,0 +1,181 @@
+//! Parallel execution of kernels over fine-grained runs of a layout's blocks, with work stealing.
+//!
+//! Splitting a layout into one contiguous part per thread leaves threads idle when the parts take
+//! unequal time, as they do on skewed graphs whose dense regions are costly to process. An
+//! `Executor` instead cuts the layout into many pieces of a few blocks each, and starts each thread
+//! on an equal contiguous run of them. A thread takes pieces from the front of its run; once its
+//! run is exhausted, it steals the back half of what remains of another thread's run. Threads so
+//! mostly process neighbouring pieces of the curve, and all finish at nearly the same time.
+//!
+//! Each thread is handed a `Worker`, whose decode buffer and cached detangler are reused across all
+//! the pieces the thread processes.
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+use crate::graph::{Block, GraphLayout};
+use crate::layout::BytewiseCached;
+
+/// Runs kernels over the blocks of a layout on several threads.
+#[derive(Copy, Clone, Debug)]
+pub struct Executor {
+    threads: usize,
+    grain: usize,
+}
+
+impl Default for Executor {
+    /// One thread per available core, and pieces of one block each.
+    fn default() -> Executor {
+        Executor::new(thread::available_parallelism().map_or(1, |threads| threads.get()))
+    }
+}
+
+impl Executor {
+    /// Runs kernels on `threads` threads, over pieces of one block each.
+    pub fn new(threads: usize) -> Executor {
+        assert!(threads > 0, "an executor needs at least one thread");
+        Executor { threads, grain: 1 }
+    }
+    /// Cuts layouts into pieces of `blocks` consecutive blocks.
+    pub fn grain(mut self, blocks: usize) -> Executor {
+        assert!(blocks > 0, "pieces must hold at least one block");
+        self.grain = blocks;
+        self
+    }
+
+    /// Applies `kernel` to each piece of `layout`, returning the results in curve order.
+    pub fn map<T, F>(&self, layout: &GraphLayout, kernel: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(&mut Worker<'_>, &[Block]) -> T + Sync,
+    {
+        let results = self.run(layout, Vec::new, |results: &mut Vec<(usize, T)>, worker, piece, blocks| {
+            results.push((piece, kernel(worker, blocks)));
+        });
+        let mut results = results.into_iter().flatten().collect::<Vec<_>>();
+        results.sort_unstable_by_key(|&(piece, _)| piece);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Folds the pieces of `layout` into one state per thread, each started by `init`, and returns
+    /// the states for the caller to combine.
+    ///
+    /// Which pieces a state has seen depends on the timing of the threads.
+    pub fn fold<S, I, F>(&self, layout: &GraphLayout, init: I, kernel: F) -> Vec<S>
+    where
+        S: Send,
+        I: Fn() -> S + Sync,
+        F: Fn(&mut S, &mut Worker<'_>, &[Block]) + Sync,
+    {
+        self.run(layout, init, |state, worker, _, blocks| kernel(state, worker, blocks))
+    }
+
+    /// Runs `kernel` on each piece, with the piece's index and blocks, and returns the state of
+    /// each thread.
+    fn run<S, I, F>(&self, layout: &GraphLayout, init: I, kernel: F) -> Vec<S>
+    where
+        S: Send,
+        I: Fn() -> S + Sync,
+        F: Fn(&mut S, &mut Worker<'_>, usize, &[Block]) + Sync,
+    {
+        let pieces = layout.blocks().chunks(self.grain).collect::<Vec<_>>();
+        assert!(pieces.len() < 1 << 32, "too many pieces to schedule");
+        let threads = ::std::cmp::max(1, ::std::cmp::min(self.threads, pieces.len()));
+        let runs = (0 .. threads).map(|thread| Run::new(thread * pieces.len() / threads .. (thread + 1) * pieces.len() / threads)).collect::<Vec<_>>();
+
+        let (pieces, runs, init, kernel) = (&pieces, &runs, &init, &kernel);
+        thread::scope(|scope| {
+            let handles = (0 .. threads).map(|thread| {
+                scope.spawn(move || {
+                    let mut state = init();
+                    let mut worker = Worker { layout, thread, edges: Vec::new(), detangler: BytewiseCached::new() };
+                    loop {
+                        while let Some(piece) = runs[thread].pop() {
+                            kernel(&mut state, &mut worker, piece, pieces[piece]);
+                        }
+                        // steal from the other threads in turn, starting with the next.
+                        match (1 .. threads).find_map(|offset| runs[(thread + offset) % threads].steal()) {
+                            Some(stolen) => runs[thread].refill(stolen),
+                            None => return state,
+                        }
+                    }
+                })
+            }).collect::<Vec<_>>();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        })
+    }
+}
+
+/// The pieces a thread has yet to process, as the next and the end index packed into one word, so
+/// that the owner taking from the front and thieves taking from the back agree through one atomic.
+struct Run(AtomicU64);
+
+impl Run {
+    fn new(pieces: Range<usize>) -> Run {
+        Run(AtomicU64::new(Run::pack(pieces.start, pieces.end)))
+    }
+    fn pack(next: usize, end: usize) -> u64 { (next as u64) << 32 | end as u64 }
+    fn unpack(word: u64) -> (usize, usize) { ((word >> 32) as usize, word as u32 as usize) }
+
+    /// Takes the next piece from the front of the run.
+    fn pop(&self) -> Option<usize> {
+        let mut word = self.0.load(Ordering::Acquire);
+        loop {
+            let (next, end) = Run::unpack(word);
+            if next >= end {
+                return None;
+            }
+            match self.0.compare_exchange_weak(word, Run::pack(next + 1, end), Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return Some(next),
+                Err(current) => word = current,
+            }
+        }
+    }
+    /// Takes the back half of the remaining pieces, rounded up.
+    fn steal(&self) -> Option<Range<usize>> {
+        let mut word = self.0.load(Ordering::Acquire);
+        loop {
+            let (next, end) = Run::unpack(word);
+            if next >= end {
+                return None;
+            }
+            let middle = end - (end - next).div_ceil(2);
+            match self.0.compare_exchange_weak(word, Run::pack(next, middle), Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return Some(middle .. end),
+                Err(current) => word = current,
+            }
+        }
+    }
+    /// Replaces the exhausted run with stolen pieces. Thieves leave an exhausted run alone, so only
+    /// the owner writes to it until it is refilled.
+    fn refill(&self, pieces: Range<usize>) {
+        self.0.store(Run::pack(pieces.start, pieces.end), Ordering::Release);
+    }
+}
+
+/// The context of one thread of an `Executor`, reused across the pieces the thread processes.
+pub struct Worker<'a> {
+    layout: &'a GraphLayout,
+    thread: usize,
+    edges: Vec<(u32, u32)>,
+    detangler: BytewiseCached,
+}
+
+impl<'a> Worker<'a> {
+    /// The layout being processed.
+    pub fn layout(&self) -> &'a GraphLayout { self.layout }
+    /// The index of the worker's thread, less than the number of threads.
+    pub fn thread(&self) -> usize { self.thread }
+    /// A detangler whose tables and cache persist across pieces.
+    pub fn detangler(&mut self) -> &mut BytewiseCached { &mut self.detangler }
+    /// Decodes the edges of `blocks`, in curve order, into a buffer reused across pieces.
+    pub fn edges(&mut self, blocks: &[Block]) -> &[(u32, u32)] {
+        self.edges.clear();
+        for block in blocks {
+            let detangler = &mut self.detangler;
+            self.edges.extend(self.layout.block_keys(block).map(|key| detangler.detangle(key)));
+        }
+        &self.edges[..]
+    }
+}
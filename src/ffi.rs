@@ -0,0 +1,231 @@
+//! A C interface, with the `cdylib` feature.
+//!
+//! The functions below are exported unmangled from the dynamic library built by the
+//! `graph_layout_cdylib` crate in `cdylib/`, and declared for C and C++ in
+//! `include/graph_layout.h`. Edges are passed as interleaved `uint32_t` source and destination
+//! pairs, curve keys as `uint64_t`, and layouts as opaque handles from `gl_layout_open`, which
+//! must be released with `gl_layout_free`.
+//!
+//! Functions returning `int` return zero on success and a negative value on failure. Functions
+//! that fill a caller's buffer return the number of values available, and write them only if the
+//! buffer's capacity suffices, so that callers may ask for the size with a capacity of zero.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+
+use crate::compression::{Compressed, Compressor, Position};
+use crate::graph::GraphLayout;
+use crate::layout::{Hilbert, Tangle};
+
+/// The `len` values at `data`, which may be null if `len` is zero.
+unsafe fn slice<'a, T>(data: *const T, len: usize) -> &'a [T] {
+    if len == 0 { &[] } else { ::std::slice::from_raw_parts(data, len) }
+}
+/// The `len` values at `data`, which may be null if `len` is zero.
+unsafe fn slice_mut<'a, T>(data: *mut T, len: usize) -> &'a mut [T] {
+    if len == 0 { &mut [] } else { ::std::slice::from_raw_parts_mut(data, len) }
+}
+
+/// The curve key of the edge `(src, dst)`.
+#[no_mangle]
+pub extern "C" fn gl_entangle(src: u32, dst: u32) -> u64 {
+    Hilbert::shared().entangle((src, dst))
+}
+
+/// Writes the edge with curve key `key` to `src` and `dst`.
+///
+/// # Safety
+///
+/// `src` and `dst` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn gl_detangle(key: u64, src: *mut u32, dst: *mut u32) {
+    let (x, y) = Hilbert::shared().detangle(key);
+    *src = x;
+    *dst = y;
+}
+
+/// Writes the curve keys of `count` edges to `keys`.
+///
+/// # Safety
+///
+/// `edges` must be valid for reads of `2 * count` values, and `keys` for writes of `count`.
+#[no_mangle]
+pub unsafe extern "C" fn gl_entangle_batch(edges: *const u32, count: usize, keys: *mut u64) {
+    let edges = slice(edges, 2 * count);
+    let keys = slice_mut(keys, count);
+    let hilbert = Hilbert::shared();
+    for (key, edge) in keys.iter_mut().zip(edges.chunks_exact(2)) {
+        *key = hilbert.entangle((edge[0], edge[1]));
+    }
+}
+
+/// Writes the edges of `count` curve keys to `edges`.
+///
+/// # Safety
+///
+/// `keys` must be valid for reads of `count` values, and `edges` for writes of `2 * count`.
+#[no_mangle]
+pub unsafe extern "C" fn gl_detangle_batch(keys: *const u64, count: usize, edges: *mut u32) {
+    let keys = slice(keys, count);
+    let edges = slice_mut(edges, 2 * count);
+    let hilbert = Hilbert::shared();
+    for (edge, &key) in edges.chunks_exact_mut(2).zip(keys) {
+        let (src, dst) = hilbert.detangle(key);
+        edge[0] = src;
+        edge[1] = dst;
+    }
+}
+
+/// Compresses `count` strictly increasing keys as `Compressed::encode` does, writing the encoding
+/// to `bytes` if it fits in `capacity` bytes.
+///
+/// Returns the length of the encoding, or `usize::MAX` if the keys are not strictly increasing.
+///
+/// # Safety
+///
+/// `keys` must be valid for reads of `count` values, and `bytes` for writes of `capacity`.
+#[no_mangle]
+pub unsafe extern "C" fn gl_encode(keys: *const u64, count: usize, bytes: *mut u8, capacity: usize) -> usize {
+    let keys = slice(keys, count);
+    if keys.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return usize::MAX;
+    }
+    let compressed = Compressed::from_sorted_slice(keys);
+    let mut encoding = Vec::new();
+    compressed.encode(Position::default(), compressed.seek(Position::default(), count), &mut encoding);
+    if encoding.len() <= capacity {
+        slice_mut(bytes, encoding.len()).copy_from_slice(&encoding);
+    }
+    encoding.len()
+}
+
+/// Decodes `count` keys from an encoding of `length` bytes produced by `gl_encode`, into `keys`.
+///
+/// # Safety
+///
+/// `bytes` must be valid for reads of `length` bytes, and `keys` for writes of `count` values.
+#[no_mangle]
+pub unsafe extern "C" fn gl_decode(bytes: *const u8, length: usize, count: usize, keys: *mut u64) -> c_int {
+    let mut compressed = Compressor::new().done();
+    if compressed.decode(count, slice(bytes, length)).is_err() {
+        return -1;
+    }
+    for (key, value) in slice_mut(keys, count).iter_mut().zip(compressed.decompress()) {
+        *key = value;
+    }
+    0
+}
+
+/// Reads the layout file at the null-terminated `path`, returning a handle to it, or null if the
+/// file cannot be read.
+///
+/// # Safety
+///
+/// `path` must be a valid null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gl_layout_open(path: *const c_char) -> *mut GraphLayout {
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => path,
+        Err(_) => return ::std::ptr::null_mut(),
+    };
+    match GraphLayout::open(path) {
+        Ok(layout) => Box::into_raw(Box::new(layout)),
+        Err(_) => ::std::ptr::null_mut(),
+    }
+}
+
+/// Releases a layout returned by `gl_layout_open`. Does nothing if `layout` is null.
+///
+/// # Safety
+///
+/// `layout` must be null or a handle from `gl_layout_open` not already released.
+#[no_mangle]
+pub unsafe extern "C" fn gl_layout_free(layout: *mut GraphLayout) {
+    if !layout.is_null() {
+        drop(Box::from_raw(layout));
+    }
+}
+
+/// The number of edges in `layout`.
+///
+/// # Safety
+///
+/// `layout` must be a live handle from `gl_layout_open`.
+#[no_mangle]
+pub unsafe extern "C" fn gl_layout_edges(layout: *const GraphLayout) -> usize {
+    (*layout).len()
+}
+
+/// One more than the largest vertex identifier in `layout`, or zero if it has no edges.
+///
+/// # Safety
+///
+/// `layout` must be a live handle from `gl_layout_open`.
+#[no_mangle]
+pub unsafe extern "C" fn gl_layout_vertices(layout: *const GraphLayout) -> u64 {
+    (*layout).vertices()
+}
+
+/// Indicates with a non-zero value that `layout` has edge weights.
+///
+/// # Safety
+///
+/// `layout` must be a live handle from `gl_layout_open`.
+#[no_mangle]
+pub unsafe extern "C" fn gl_layout_is_weighted(layout: *const GraphLayout) -> c_int {
+    (*layout).weights().is_some() as c_int
+}
+
+/// Writes the edges of `layout` in curve order to `edges`, if `capacity` edges fit, and returns
+/// the number of edges.
+///
+/// # Safety
+///
+/// `layout` must be a live handle from `gl_layout_open`, and `edges` valid for writes of
+/// `2 * capacity` values.
+#[no_mangle]
+pub unsafe extern "C" fn gl_layout_copy_edges(layout: *const GraphLayout, edges: *mut u32, capacity: usize) -> usize {
+    let layout = &*layout;
+    if layout.len() <= capacity {
+        let edges = slice_mut(edges, 2 * layout.len());
+        for (pair, (src, dst)) in edges.chunks_exact_mut(2).zip(layout.edges()) {
+            pair[0] = src;
+            pair[1] = dst;
+        }
+    }
+    layout.len()
+}
+
+/// Writes the curve keys of `layout` in order to `keys`, if `capacity` keys fit, and returns the
+/// number of keys.
+///
+/// # Safety
+///
+/// `layout` must be a live handle from `gl_layout_open`, and `keys` valid for writes of `capacity`
+/// values.
+#[no_mangle]
+pub unsafe extern "C" fn gl_layout_copy_keys(layout: *const GraphLayout, keys: *mut u64, capacity: usize) -> usize {
+    let layout = &*layout;
+    if layout.len() <= capacity {
+        for (slot, key) in slice_mut(keys, layout.len()).iter_mut().zip(layout.keys()) {
+            *slot = key;
+        }
+    }
+    layout.len()
+}
+
+/// Writes the edge weights of `layout` in curve order to `weights`, if `capacity` weights fit, and
+/// returns the number of weights, which is zero for unweighted layouts.
+///
+/// # Safety
+///
+/// `layout` must be a live handle from `gl_layout_open`, and `weights` valid for writes of
+/// `capacity` values.
+#[no_mangle]
+pub unsafe extern "C" fn gl_layout_copy_weights(layout: *const GraphLayout, weights: *mut f64, capacity: usize) -> usize {
+    let values = (*layout).weights().unwrap_or(&[]);
+    if values.len() <= capacity {
+        slice_mut(weights, values.len()).copy_from_slice(values);
+    }
+    values.len()
+}
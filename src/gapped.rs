@@ -0,0 +1,184 @@
+//! Adjacency lists coded per vertex as runs of consecutive successors and gaps between the rest.
+//!
+//! In social graphs the successors of a vertex often include runs of consecutive identifiers, as
+//! members of a community are numbered together. A `GappedAdjacency` extracts from each sorted
+//! successor list its maximal runs of at least `min_interval` consecutive vertices, stores each
+//! run as its start and length, and gap-codes the remaining successors. Unlike the curve-key
+//! store, whose keys interleave the rows of each tile, a vertex's successors are read from one
+//! contiguous code, without decoding any other vertex's edges.
+//!
+//! Each vertex's code is its degree; then, if it has successors, the number of runs; each run as
+//! its start, the first as the zigzag-coded difference from the vertex and the rest as the gap
+//! after the previous run minus two, and its length minus `min_interval`; and finally the residual
+//! successors, the first as the zigzag-coded difference from the vertex and the rest as gaps minus
+//! one. Everything is written as LEB128 varints into a single byte stream.
+
+use crate::graph::GraphLayout;
+use crate::csr::Csr;
+use crate::referenced::{read_varint, unzigzag, write_varint, zigzag};
+
+/// Successor lists coded as runs and gaps, with an offset per vertex.
+#[derive(Clone, Debug)]
+pub struct GappedAdjacency {
+    bytes: Vec<u8>,
+    /// The start of each vertex's code, and a final offset past the last.
+    offsets: Vec<usize>,
+    edges: usize,
+    min_interval: u32,
+}
+
+impl GappedAdjacency {
+    /// Codes the out-neighbors of each vertex of `layout`, storing runs of at least `min_interval`
+    /// consecutive successors as intervals.
+    pub fn from_layout(layout: &GraphLayout, min_interval: u32) -> GappedAdjacency {
+        assert!(min_interval >= 2, "intervals must hold at least two successors");
+        let csr = Csr::from_layout(layout, false, layout.vertices() as usize);
+
+        let mut bytes = Vec::new();
+        let mut offsets = Vec::with_capacity(csr.vertices() + 1);
+        let mut intervals = Vec::new();
+        let mut residuals = Vec::new();
+        for vertex in 0 .. csr.vertices() {
+            offsets.push(bytes.len());
+            let successors = &csr.targets[csr.offsets[vertex] as usize .. csr.offsets[vertex + 1] as usize];
+            write_varint(&mut bytes, successors.len() as u64);
+            if successors.is_empty() { continue; }
+
+            intervals.clear();
+            residuals.clear();
+            let mut start = 0;
+            while start < successors.len() {
+                let mut end = start + 1;
+                while end < successors.len() && successors[end] == successors[end - 1] + 1 {
+                    end += 1;
+                }
+                if end - start >= min_interval as usize {
+                    intervals.push((successors[start], (end - start) as u32));
+                }
+                else {
+                    residuals.extend_from_slice(&successors[start .. end]);
+                }
+                start = end;
+            }
+
+            write_varint(&mut bytes, intervals.len() as u64);
+            let mut previous: Option<u32> = None;
+            for &(first, length) in &intervals {
+                match previous {
+                    None => write_varint(&mut bytes, zigzag(first as i64 - vertex as i64)),
+                    Some(last) => write_varint(&mut bytes, (first - last - 2) as u64),
+                }
+                write_varint(&mut bytes, (length - min_interval) as u64);
+                previous = Some(first + (length - 1));
+            }
+            let mut previous: Option<u32> = None;
+            for &value in &residuals {
+                match previous {
+                    None => write_varint(&mut bytes, zigzag(value as i64 - vertex as i64)),
+                    Some(last) => write_varint(&mut bytes, (value - last - 1) as u64),
+                }
+                previous = Some(value);
+            }
+        }
+        offsets.push(bytes.len());
+        GappedAdjacency { bytes, offsets, edges: csr.targets.len(), min_interval }
+    }
+
+    /// The number of vertices.
+    pub fn vertices(&self) -> usize { self.offsets.len() - 1 }
+    /// The number of edges.
+    pub fn len(&self) -> usize { self.edges }
+    /// Indicates that there are no edges.
+    pub fn is_empty(&self) -> bool { self.edges == 0 }
+    /// The number of bytes of coded lists, excluding the per-vertex offsets.
+    pub fn size_bytes(&self) -> usize { self.bytes.len() }
+    /// The shortest run of consecutive successors stored as an interval.
+    pub fn min_interval(&self) -> u32 { self.min_interval }
+
+    /// The number of successors of `vertex`.
+    pub fn degree(&self, vertex: u32) -> usize {
+        let mut cursor = self.offsets[vertex as usize];
+        read_varint(&self.bytes, &mut cursor) as usize
+    }
+
+    /// The successors of `vertex`, in increasing order.
+    pub fn successors(&self, vertex: u32) -> Vec<u32> {
+        let mut successors = Vec::new();
+        self.successors_into(vertex, &mut successors);
+        successors
+    }
+
+    /// Replaces the contents of `successors` with the successors of `vertex`, in increasing order.
+    pub fn successors_into(&self, vertex: u32, successors: &mut Vec<u32>) {
+        successors.clear();
+        successors.reserve(self.degree(vertex));
+        let (intervals, residuals) = self.decode(vertex);
+        // intervals and residuals are each sorted; merge them.
+        let mut residuals = residuals.peekable();
+        for (first, length) in intervals {
+            while let Some(value) = residuals.next_if(|&value| value < first) {
+                successors.push(value);
+            }
+            successors.extend(first .. first + length);
+        }
+        successors.extend(residuals);
+    }
+
+    /// Indicates that `(src, dst)` is an edge.
+    pub fn has_edge(&self, src: u32, dst: u32) -> bool {
+        let (intervals, mut residuals) = self.decode(src);
+        intervals.iter().any(|&(first, length)| first <= dst && dst - first < length)
+            || residuals.find(|&value| value >= dst) == Some(dst)
+    }
+
+    /// Iterates over all edges, grouped by source in increasing order.
+    pub fn edges(&self) -> impl Iterator<Item=(u32, u32)> + '_ {
+        (0 .. self.vertices() as u32).flat_map(move |vertex| self.successors(vertex).into_iter().map(move |dst| (vertex, dst)))
+    }
+
+    /// Lays the edges out along the curve again.
+    pub fn to_layout(&self) -> GraphLayout {
+        GraphLayout::from_edges(self.edges())
+    }
+
+    /// Decodes the runs of `vertex` as first successors and lengths, and returns them with an
+    /// iterator decoding the residual successors in increasing order.
+    fn decode(&self, vertex: u32) -> (Vec<(u32, u32)>, impl Iterator<Item=u32> + '_) {
+        let mut cursor = self.offsets[vertex as usize];
+        let degree = read_varint(&self.bytes, &mut cursor) as usize;
+        let mut intervals = Vec::new();
+        if degree > 0 {
+            let count = read_varint(&self.bytes, &mut cursor) as usize;
+            let mut previous: Option<u32> = None;
+            for _ in 0 .. count {
+                let first = match previous {
+                    None => (vertex as i64 + unzigzag(read_varint(&self.bytes, &mut cursor))) as u32,
+                    Some(last) => last + 2 + read_varint(&self.bytes, &mut cursor) as u32,
+                };
+                let length = self.min_interval + read_varint(&self.bytes, &mut cursor) as u32;
+                intervals.push((first, length));
+                previous = Some(first + (length - 1));
+            }
+        }
+        let residuals = degree - intervals.iter().map(|&(_, length)| length as usize).sum::<usize>();
+        let mut previous: Option<u32> = None;
+        let residuals = (0 .. residuals).map(move |_| {
+            let value = match previous {
+                None => (vertex as i64 + unzigzag(read_varint(&self.bytes, &mut cursor))) as u32,
+                Some(last) => last + 1 + read_varint(&self.bytes, &mut cursor) as u32,
+            };
+            previous = Some(value);
+            value
+        });
+        (intervals, residuals)
+    }
+}
+
+impl GraphLayout {
+    /// Codes the layout's out-neighbor lists as runs of at least `min_interval` consecutive
+    /// successors and gaps, for neighbor-centric workloads on graphs with locally dense
+    /// neighborhoods.
+    pub fn gapped(&self, min_interval: u32) -> GappedAdjacency {
+        GappedAdjacency::from_layout(self, min_interval)
+    }
+}
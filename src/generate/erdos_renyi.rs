@@ -0,0 +1,92 @@
+//! Uniformly random directed graphs, in the Erdős–Rényi `G(n, m)` and `G(n, p)` models.
+//!
+//! Both models choose among the `n(n-1)` ordered pairs of distinct vertices, so edges have no
+//! self-loops. Uniform graphs have no locality for the curve to exploit, which makes them the
+//! worst case for compression and a natural baseline.
+
+use std::collections::HashSet;
+
+use crate::rng::SplitMix64;
+
+/// The edge at `index` among the ordered pairs of distinct vertices of `0 .. n`, by source.
+fn pair(n: u64, index: u64) -> (u32, u32) {
+    let src = index / (n - 1);
+    let dst = index % (n - 1);
+    let dst = if dst >= src { dst + 1 } else { dst };
+    (src as u32, dst as u32)
+}
+
+/// Streams `m` distinct random edges among `n` vertices, each set of `m` edges equally likely.
+///
+/// Edges come in random order. The generator remembers the edges produced, so uses memory
+/// proportional to `m`. Panics if `m` exceeds the `n(n-1)` possible edges.
+pub fn gnm(n: u32, m: usize, seed: u64) -> Gnm {
+    let pairs = (n as u64) * (n as u64).saturating_sub(1);
+    assert!(m as u64 <= pairs, "more edges requested than there are pairs of distinct vertices");
+    Gnm { n: n as u64, pairs, remaining: m, rng: SplitMix64::new(seed), produced: HashSet::with_capacity(m) }
+}
+
+/// The edges of a `G(n, m)` random graph; see `gnm`.
+pub struct Gnm {
+    n: u64,
+    pairs: u64,
+    remaining: usize,
+    rng: SplitMix64,
+    produced: HashSet<u64>,
+}
+
+impl Iterator for Gnm {
+    type Item = (u32, u32);
+    fn next(&mut self) -> Option<(u32, u32)> {
+        if self.remaining == 0 { return None; }
+        self.remaining -= 1;
+        loop {
+            let index = self.rng.below(self.pairs);
+            if self.produced.insert(index) {
+                return Some(pair(self.n, index));
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// Streams the edges among `n` vertices present independently with probability `p`.
+///
+/// Edges come in order of source and then destination. Rather than testing each pair, the
+/// generator jumps ahead by geometrically distributed skips (Batagelj and Brandes), so it takes
+/// time proportional to the number of edges and constant memory.
+pub fn gnp(n: u32, p: f64, seed: u64) -> Gnp {
+    assert!((0.0 ..= 1.0).contains(&p), "the edge probability must lie in [0, 1]");
+    let pairs = (n as u64) * (n as u64).saturating_sub(1);
+    Gnp { n: n as u64, pairs, p, next: 0, rng: SplitMix64::new(seed) }
+}
+
+/// The edges of a `G(n, p)` random graph; see `gnp`.
+pub struct Gnp {
+    n: u64,
+    pairs: u64,
+    p: f64,
+    /// The first pair index not yet considered.
+    next: u64,
+    rng: SplitMix64,
+}
+
+impl Iterator for Gnp {
+    type Item = (u32, u32);
+    fn next(&mut self) -> Option<(u32, u32)> {
+        if self.p <= 0.0 || self.next >= self.pairs { return None; }
+        if self.p < 1.0 {
+            // the number of pairs skipped before the next edge is geometric with parameter `p`.
+            let skip = ((1.0 - self.rng.next_f64()).ln() / (1.0 - self.p).ln()).floor();
+            if skip >= (self.pairs - self.next) as f64 {
+                self.next = self.pairs;
+                return None;
+            }
+            self.next += skip as u64;
+        }
+        self.next += 1;
+        Some(pair(self.n, self.next - 1))
+    }
+}
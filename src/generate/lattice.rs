@@ -0,0 +1,59 @@
+//! Regular lattices, on which curve layouts are close to optimal.
+//!
+//! Each generator lists both directions of every undirected edge, grouped by source vertex, so
+//! the output describes a symmetric graph. Vertex `(x, y)` of a grid or torus is `y * width + x`.
+
+/// Streams the edges of a `width × height` grid, joining each vertex to its horizontal and
+/// vertical neighbors.
+pub fn grid(width: u32, height: u32) -> impl Iterator<Item=(u32, u32)> {
+    lattice(width, height, false)
+}
+
+/// Streams the edges of a `width × height` torus: a grid whose rows and columns wrap around.
+///
+/// Sides of length one or two wrap onto existing neighbors, producing self-loops or duplicate
+/// edges, which layouts discard.
+pub fn torus(width: u32, height: u32) -> impl Iterator<Item=(u32, u32)> {
+    lattice(width, height, true)
+}
+
+fn lattice(width: u32, height: u32, wrap: bool) -> impl Iterator<Item=(u32, u32)> {
+    let vertices = width as u64 * height as u64;
+    assert!(vertices <= 1 << 32, "the lattice has more than 2^32 vertices");
+    (0 .. vertices).flat_map(move |vertex| {
+        let (x, y) = ((vertex % width as u64) as u32, (vertex / width as u64) as u32);
+        let mut neighbors = Vec::with_capacity(4);
+        let mut step = |nx: Option<u32>, ny: Option<u32>| {
+            if let (Some(nx), Some(ny)) = (nx, ny) {
+                neighbors.push((vertex as u32, ny * width + nx));
+            }
+        };
+        let wrapped = |value: u32, side: u32, forward: bool| match (forward, wrap) {
+            (true, _) if value + 1 < side => Some(value + 1),
+            (true, true) => Some(0),
+            (false, _) if value > 0 => Some(value - 1),
+            (false, true) => Some(side - 1),
+            _ => None,
+        };
+        step(wrapped(x, width, false), Some(y));
+        step(wrapped(x, width, true), Some(y));
+        step(Some(x), wrapped(y, height, false));
+        step(Some(x), wrapped(y, height, true));
+        neighbors.into_iter()
+    })
+}
+
+/// Streams the edges of a ring of `n` vertices, each joined to the `k` nearest vertices on either
+/// side, as in the starting point of the Watts–Strogatz model.
+///
+/// Panics unless `2k < n`, so that the neighbors on the two sides are distinct.
+pub fn ring_lattice(n: u32, k: u32) -> impl Iterator<Item=(u32, u32)> {
+    assert!(2 * (k as u64) < n as u64, "a ring lattice needs more than 2k vertices");
+    (0 .. n).flat_map(move |vertex| {
+        (1 ..= k).flat_map(move |offset| {
+            let before = (vertex as u64 + n as u64 - offset as u64) % n as u64;
+            let after = (vertex as u64 + offset as u64) % n as u64;
+            vec![(vertex, before as u32), (vertex, after as u32)]
+        })
+    })
+}
@@ -0,0 +1,20 @@
+//! Synthetic graphs, streamed as edges.
+//!
+//! Generators are iterators over `(u32, u32)` edges, so they can feed `GraphLayout::from_edges` or
+//! a `LayoutBuilder` directly.
+//!
+//! Every random generator takes an explicit `u64` seed and draws from a `SplitMix64` generator
+//! seeded with it, using only its stable methods, so a seed produces the same edges on every run,
+//! machine, and version of the crate. The same holds for the seeded orderings and samples
+//! elsewhere in the crate.
+
+pub mod erdos_renyi;
+pub mod lattice;
+pub mod parallel;
+pub mod preferential;
+
+pub use crate::rng::SplitMix64;
+pub use self::erdos_renyi::{gnm, gnp};
+pub use self::lattice::{grid, ring_lattice, torus};
+pub use self::parallel::parallel_gnp;
+pub use self::preferential::{barabasi_albert, preferential_attachment};
@@ -0,0 +1,85 @@
+//! Random graphs generated in parallel, directly in curve order.
+//!
+//! The curve visits aligned squares of the adjacency matrix one after another, so the key space
+//! can be cut into squares and each generated on its own. Threads take contiguous runs of squares,
+//! produce each square's edges, and sort only within the square; concatenating the runs in order
+//! then yields sorted keys, with no global sort.
+//!
+//! Only models whose edges within a square are independent of the rest of the graph fit this
+//! scheme, which `G(n, p)` does.
+
+use std::thread;
+
+use crate::graph::GraphLayout;
+use crate::layout::Hilbert;
+use crate::rng::SplitMix64;
+
+/// The base-four logarithm of the number of squares the key space is cut into, independent of the
+/// number of threads so that the squares, and their generators, are too.
+const LOG_SQUARES: u32 = 6;
+
+/// Generates a `G(n, p)` random graph on `threads` threads, straight into a `GraphLayout`.
+///
+/// Each square draws from its own generator derived from `seed` and its position, so the result
+/// depends on `seed` but not on the number of threads. The edges differ from those of `gnp` with
+/// the same seed, though they follow the same distribution.
+pub fn parallel_gnp(n: u32, p: f64, seed: u64, threads: usize) -> GraphLayout {
+    assert!((0.0 ..= 1.0).contains(&p), "the edge probability must lie in [0, 1]");
+    let threads = ::std::cmp::max(threads, 1);
+    // the first `4^log_side` keys cover the square of side `2^log_side` holding every vertex.
+    let log_side = 32 - n.saturating_sub(1).leading_zeros();
+    let log_squares = ::std::cmp::min(log_side, LOG_SQUARES);
+    let squares = 1u64 << (2 * log_squares);
+    let log_square_side = log_side - log_squares;
+
+    let runs = thread::scope(|scope| {
+        let handles = (0 .. threads as u64).map(|thread| {
+            let (first, last) = (thread * squares / threads as u64, (thread + 1) * squares / threads as u64);
+            scope.spawn(move || {
+                let mut keys = Vec::new();
+                for square in first .. last {
+                    generate_square(n, p, seed, square, log_square_side, &mut keys);
+                }
+                keys
+            })
+        }).collect::<Vec<_>>();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Vec<_>>()
+    });
+
+    let mut keys = Vec::with_capacity(runs.iter().map(|run| run.len()).sum());
+    for run in runs {
+        keys.extend(run);
+    }
+    GraphLayout::from_sorted_keys(keys, None)
+}
+
+/// Appends the sorted keys of the edges in square `square`, of side `2^log_side`.
+fn generate_square(n: u32, p: f64, seed: u64, square: u64, log_side: u32, keys: &mut Vec<u64>) {
+    let lower = square << (2 * log_side);
+    let (x, y) = Hilbert::detangle_wide(lower as u128);
+    let mask = (1u64 << log_side) - 1;
+    let (x0, y0) = (x & !mask, y & !mask);
+    if x0 >= n as u64 || y0 >= n as u64 || p <= 0.0 { return; }
+    let width = ::std::cmp::min(mask + 1, n as u64 - x0);
+    let height = ::std::cmp::min(mask + 1, n as u64 - y0);
+    let cells = width * height;
+
+    let mut rng = SplitMix64::new(SplitMix64::new(seed ^ square.rotate_left(32)).next_u64());
+    let start = keys.len();
+    let mut next = 0u64;
+    loop {
+        if p < 1.0 {
+            // as in `gnp`, skip a geometrically distributed number of cells before each edge.
+            let skip = ((1.0 - rng.next_f64()).ln() / (1.0 - p).ln()).floor();
+            if skip >= (cells - next) as f64 { break; }
+            next += skip as u64;
+        }
+        if next >= cells { break; }
+        let (src, dst) = (x0 + next % width, y0 + next / width);
+        if src != dst {
+            keys.push(Hilbert::bit_entangle((src as u32, dst as u32)));
+        }
+        next += 1;
+    }
+    keys[start ..].sort_unstable();
+}
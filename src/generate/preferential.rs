@@ -0,0 +1,88 @@
+//! Scale-free graphs grown by preferential attachment, after Barabási and Albert.
+//!
+//! Vertices arrive one at a time and each links to `m` distinct earlier vertices, chosen with
+//! probability proportional to their in-degree plus an initial attractiveness `a`. The in-degrees
+//! then follow a power law with exponent `2 + a / m` (Dorogovtsev, Mendes, and Samukhin), so
+//! choosing `a` controls the exponent; `a = m` gives the exponent 3 of the Barabási–Albert model.
+
+use crate::rng::SplitMix64;
+
+/// Streams the edges of a Barabási–Albert graph on `n` vertices, each linking to `m` earlier ones.
+pub fn barabasi_albert(n: u32, m: u32, seed: u64) -> PreferentialAttachment {
+    preferential_attachment(n, m, 3.0, seed)
+}
+
+/// Streams the edges of a preferential attachment graph on `n` vertices whose in-degrees follow a
+/// power law with exponent `exponent`, which must exceed 2.
+///
+/// Vertex `v` links to `min(m, v)` distinct vertices of `0 .. v`, and its edges `(v, target)` are
+/// produced together, in order of `v`. The generator remembers every edge's target, so uses memory
+/// proportional to the number of edges.
+pub fn preferential_attachment(n: u32, m: u32, exponent: f64, seed: u64) -> PreferentialAttachment {
+    assert!(exponent > 2.0, "preferential attachment exponents must exceed 2");
+    PreferentialAttachment {
+        n,
+        m,
+        attractiveness: m as f64 * (exponent - 2.0),
+        rng: SplitMix64::new(seed),
+        vertex: 1,
+        targets: Vec::new(),
+        chosen: Vec::with_capacity(m as usize),
+        next: 0,
+    }
+}
+
+/// The edges of a preferential attachment graph; see `preferential_attachment`.
+pub struct PreferentialAttachment {
+    n: u32,
+    m: u32,
+    attractiveness: f64,
+    rng: SplitMix64,
+    /// The number of vertices that have chosen their targets, including the one being produced.
+    vertex: u32,
+    /// The target of every edge produced so far, so that each vertex appears once per in-edge.
+    targets: Vec<u32>,
+    /// The targets of vertex `vertex - 1`, and the next of them to produce.
+    chosen: Vec<u32>,
+    next: usize,
+}
+
+impl PreferentialAttachment {
+    /// Chooses one of the first `vertex` vertices with probability proportional to in-degree plus
+    /// attractiveness.
+    fn choose(&mut self) -> u32 {
+        let degrees = self.targets.len() as f64;
+        let total = degrees + self.attractiveness * self.vertex as f64;
+        if total > 0.0 && self.rng.next_f64() * total < degrees {
+            self.targets[self.rng.below(self.targets.len() as u64) as usize]
+        }
+        else {
+            self.rng.below(self.vertex as u64) as u32
+        }
+    }
+}
+
+impl Iterator for PreferentialAttachment {
+    type Item = (u32, u32);
+    fn next(&mut self) -> Option<(u32, u32)> {
+        loop {
+            if self.next < self.chosen.len() {
+                self.next += 1;
+                return Some((self.vertex - 1, self.chosen[self.next - 1]));
+            }
+            // a vertex's targets count towards degrees only once all of them are chosen.
+            self.targets.extend_from_slice(&self.chosen);
+            self.chosen.clear();
+            self.next = 0;
+            if self.vertex >= self.n { return None; }
+            let count = ::std::cmp::min(self.m, self.vertex);
+            while (self.chosen.len() as u32) < count {
+                let target = self.choose();
+                if !self.chosen.contains(&target) {
+                    self.chosen.push(target);
+                }
+            }
+            self.vertex += 1;
+        }
+    }
+}
@@ -0,0 +1,880 @@
+//! Graphs stored as compressed sequences of Hilbert curve keys.
+//!
+//! A `GraphLayout` entangles each `(src, dst)` edge into a `u64` using the Hilbert curve, sorts and
+//! deduplicates the keys, and stores them in a single `Compressed` stream. Locality in the curve
+//! makes consecutive keys close together, which is what the compression relies on.
+//!
+//! Optionally, the edges can be organized into `2^k × 2^k` tiles. Each aligned square of the
+//! adjacency matrix occupies a contiguous range of `4^k` Hilbert keys, so a tile is just a run of
+//! the key stream, and the layout records a directory entry for each non-empty tile.
+//!
+//! Independently of tiling, the layout keeps a directory of blocks of `BLOCK_EDGES` edges each,
+//! from which decompression can start without decoding the preceding edges.
+//!
+//! A layout may also carry named `Column`s of per-edge values, aligned with the edges in curve order.
+
+use crate::column::{Column, ColumnType};
+use crate::compression::{Compressed, Compressor, Decompressor, Position};
+use crate::layout::{BytewiseCached, Hilbert, Tangle};
+use crate::query::{Plan, Rect, RectEdges};
+use crate::rng::SplitMix64;
+use crate::scratch::Scratch;
+
+/// A directory entry describing one non-empty tile.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "rkyv", derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize))]
+pub struct Tile {
+    /// The first curve key covered by the tile.
+    pub lower: u64,
+    /// The last curve key covered by the tile (inclusive).
+    pub upper: u64,
+    /// The number of edges stored in the tile.
+    pub edges: usize,
+    /// The index of the tile's first edge among all stored edges, in curve order, which also
+    /// indexes its weight and column values.
+    ///
+    /// Every stored key has exactly one entry in the primary byte stream of the compressed keys,
+    /// escaped or not, so this is also the byte index at which the tile's keys start in that
+    /// stream, as `Position::offset` reports.
+    pub offset: usize,
+    pub(crate) position: Position,
+}
+
+#[cfg(feature = "abomonation")]
+abomonate!(Tile);
+
+/// How the weights of duplicate edges combine when a layout is built.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Aggregate {
+    /// Keeps the weight of the first occurrence.
+    #[default]
+    First,
+    /// Adds the weights of all occurrences.
+    Sum,
+    /// Keeps the smallest weight.
+    Min,
+    /// Keeps the largest weight.
+    Max,
+    /// Replaces the weight with the number of occurrences.
+    Count,
+}
+
+impl Aggregate {
+    /// Combines the weight accumulated so far with that of another occurrence.
+    fn combine(self, accumulated: f64, weight: f64) -> f64 {
+        match self {
+            Aggregate::First => accumulated,
+            Aggregate::Sum => accumulated + weight,
+            Aggregate::Min => accumulated.min(weight),
+            Aggregate::Max => accumulated.max(weight),
+            Aggregate::Count => accumulated + 1.0,
+        }
+    }
+}
+
+/// The number of edges in each block of the block directory.
+pub const BLOCK_EDGES: usize = 4096;
+
+/// A directory entry describing a contiguous run of stored edges.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "rkyv", derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize))]
+pub struct Block {
+    /// The curve key of the first edge in the run.
+    pub lower: u64,
+    /// The curve key of the last edge in the run.
+    pub upper: u64,
+    /// The number of edges in the run.
+    pub edges: usize,
+    /// The index of the run's first edge among all stored edges, in curve order, which also
+    /// indexes its weight and column values.
+    pub offset: usize,
+    pub(crate) position: Position,
+}
+
+#[cfg(feature = "abomonation")]
+abomonate!(Block);
+
+/// Degree and density statistics for one tile.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "rkyv", derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize))]
+pub struct TileSummary {
+    /// The number of edges stored in the tile.
+    pub edges: usize,
+    /// The number of distinct sources among the tile's edges.
+    pub sources: usize,
+    /// The number of distinct destinations among the tile's edges.
+    pub destinations: usize,
+    /// The fraction of the tile's `4^k` cells that hold an edge.
+    pub density: f64,
+}
+
+#[cfg(feature = "abomonation")]
+abomonate!(TileSummary);
+
+impl TileSummary {
+    /// Summarizes the edges of `tile`.
+    pub(crate) fn from_edges<I: Iterator<Item=(u32, u32)>>(edges: I, tile: &Tile) -> TileSummary {
+        let mut sources = Vec::with_capacity(tile.edges);
+        let mut destinations = Vec::with_capacity(tile.edges);
+        for (src, dst) in edges {
+            sources.push(src);
+            destinations.push(dst);
+        }
+        sources.sort_unstable();
+        sources.dedup();
+        destinations.sort_unstable();
+        destinations.dedup();
+        TileSummary {
+            edges: tile.edges,
+            sources: sources.len(),
+            destinations: destinations.len(),
+            density: tile.edges as f64 / (tile.upper - tile.lower + 1) as f64,
+        }
+    }
+}
+
+/// The settings of a layout to build, accumulated one at a time from `GraphLayout::options()`.
+///
+/// By default the layout is untiled, has no columns, and keeps the first weight of duplicate
+/// edges. `build` and `build_weighted` lay out the edges with the settings, and are equivalent to
+/// the `GraphLayout::from_*` constructors with the same parameters.
+#[derive(Clone, Debug, Default)]
+pub struct LayoutOptions {
+    log_tile: Option<u32>,
+    aggregate: Aggregate,
+    columns: Vec<(String, Column)>,
+}
+
+impl LayoutOptions {
+    /// Lays out the edges in tiles of `2^log_side × 2^log_side` vertices.
+    pub fn tiled(mut self, log_side: u32) -> LayoutOptions {
+        assert!(log_side < 32, "tiles must be smaller than the full 2^32 × 2^32 space");
+        self.log_tile = Some(log_side);
+        self
+    }
+    /// Combines the weights of duplicate edges with `aggregate`, in `build_weighted`.
+    pub fn aggregate(mut self, aggregate: Aggregate) -> LayoutOptions {
+        self.aggregate = aggregate;
+        self
+    }
+    /// Attaches a column of per-edge values given in the order of the edges, replacing any column
+    /// of the same name.
+    ///
+    /// The values are reordered along with the edges, and duplicate edges keep the values of their
+    /// first occurrence.
+    pub fn column(mut self, name: &str, column: Column) -> LayoutOptions {
+        self.columns.retain(|(other, _)| other != name);
+        self.columns.push((name.to_owned(), column));
+        self
+    }
+
+    /// Lays out the edges.
+    ///
+    /// Panics unless each column has one value per edge.
+    pub fn build<I: Iterator<Item=(u32, u32)>>(self, edges: I) -> GraphLayout {
+        if self.columns.is_empty() {
+            GraphLayout::build(edges, self.log_tile)
+        }
+        else {
+            GraphLayout::build_with_columns(edges, self.columns, self.log_tile)
+        }
+    }
+    /// Lays out the edges as `build` does, sorting them and building the layout in the buffers of
+    /// `scratch`, including those of layouts it has recycled.
+    ///
+    /// Layouts with columns are built as `build` builds them.
+    pub fn build_in<I: Iterator<Item=(u32, u32)>>(self, edges: I, scratch: &mut Scratch) -> GraphLayout {
+        if !self.columns.is_empty() {
+            return self.build(edges);
+        }
+        let spare = scratch.spare_layout();
+        let hilbert = Hilbert::shared();
+        scratch.keys.clear();
+        scratch.keys.extend(edges.map(|edge| hilbert.entangle(edge)));
+        scratch.keys.sort_unstable();
+        scratch.keys.dedup();
+        GraphLayout::assemble_into(scratch.keys.iter().copied(), None, self.log_tile, spare, &mut scratch.detangler)
+    }
+    /// Lays out weighted edges.
+    ///
+    /// Panics unless each column has one value per edge.
+    pub fn build_weighted<I: Iterator<Item=((u32, u32), f64)>>(self, edges: I) -> GraphLayout {
+        let hilbert = Hilbert::shared();
+        if self.columns.is_empty() {
+            let pairs = edges.map(|(edge, weight)| (hilbert.entangle(edge), weight)).collect();
+            return GraphLayout::from_aggregated_keys(pairs, self.aggregate, self.log_tile);
+        }
+        let mut triples = edges.enumerate().map(|(index, (edge, weight))| (hilbert.entangle(edge), weight, index)).collect::<Vec<_>>();
+        for (name, column) in &self.columns {
+            assert_eq!(column.len(), triples.len(), "column `{}` does not have one value per edge", name);
+        }
+        // a stable sort keeps duplicates in input order, so the first occurrence is kept.
+        triples.sort_by_key(|&(key, _, _)| key);
+        let mut keys = Vec::with_capacity(triples.len());
+        let mut weights: Vec<f64> = Vec::with_capacity(triples.len());
+        let mut indices = Vec::with_capacity(triples.len());
+        for (key, weight, index) in triples {
+            if keys.last() == Some(&key) {
+                let last = weights.last_mut().unwrap();
+                *last = self.aggregate.combine(*last, weight);
+            }
+            else {
+                keys.push(key);
+                weights.push(if self.aggregate == Aggregate::Count { 1.0 } else { weight });
+                indices.push(index);
+            }
+        }
+        let mut layout = GraphLayout::assemble(keys, Some(weights), self.log_tile);
+        for (name, column) in self.columns {
+            layout.attach_column(&name, column.select(&indices));
+        }
+        layout
+    }
+}
+
+/// A set of edges stored in Hilbert curve order.
+#[cfg_attr(feature = "rkyv", derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize))]
+pub struct GraphLayout {
+    pub(crate) keys: Compressed,
+    pub(crate) vertices: u64,
+    pub(crate) log_tile: Option<u32>,
+    pub(crate) tiles: Vec<Tile>,
+    pub(crate) summaries: Vec<TileSummary>,
+    pub(crate) blocks: Vec<Block>,
+    pub(crate) weights: Option<Vec<f64>>,
+    pub(crate) columns: Vec<(String, Column)>,
+}
+
+impl GraphLayout {
+    /// Default settings for a layout, to be configured and then built with the edges.
+    pub fn options() -> LayoutOptions { LayoutOptions::default() }
+
+    /// Lays out the edges as a single untiled stream of curve keys.
+    pub fn from_edges<I: Iterator<Item=(u32, u32)>>(edges: I) -> GraphLayout {
+        GraphLayout::build(edges, None)
+    }
+    /// Lays out the edges in tiles of `2^log_side × 2^log_side` vertices.
+    pub fn from_edges_tiled<I: Iterator<Item=(u32, u32)>>(edges: I, log_side: u32) -> GraphLayout {
+        assert!(log_side < 32, "tiles must be smaller than the full 2^32 × 2^32 space");
+        GraphLayout::build(edges, Some(log_side))
+    }
+
+    /// Lays out weighted edges as a single untiled stream, keeping the first weight of duplicates.
+    pub fn from_weighted_edges<I: Iterator<Item=((u32, u32), f64)>>(edges: I) -> GraphLayout {
+        let hilbert = Hilbert::new();
+        GraphLayout::from_weighted_keys(edges.map(|(edge, weight)| (hilbert.entangle(edge), weight)).collect(), None)
+    }
+    /// Lays out weighted edges in tiles of `2^log_side × 2^log_side` vertices.
+    pub fn from_weighted_edges_tiled<I: Iterator<Item=((u32, u32), f64)>>(edges: I, log_side: u32) -> GraphLayout {
+        assert!(log_side < 32, "tiles must be smaller than the full 2^32 × 2^32 space");
+        let hilbert = Hilbert::new();
+        GraphLayout::from_weighted_keys(edges.map(|(edge, weight)| (hilbert.entangle(edge), weight)).collect(), Some(log_side))
+    }
+
+    /// Lays out weighted edges as a single untiled stream, combining the weights of duplicates.
+    pub fn from_aggregated_edges<I: Iterator<Item=((u32, u32), f64)>>(edges: I, aggregate: Aggregate) -> GraphLayout {
+        let hilbert = Hilbert::new();
+        GraphLayout::from_aggregated_keys(edges.map(|(edge, weight)| (hilbert.entangle(edge), weight)).collect(), aggregate, None)
+    }
+    /// Lays out weighted edges in tiles of `2^log_side × 2^log_side` vertices, combining the
+    /// weights of duplicates.
+    pub fn from_aggregated_edges_tiled<I: Iterator<Item=((u32, u32), f64)>>(edges: I, aggregate: Aggregate, log_side: u32) -> GraphLayout {
+        assert!(log_side < 32, "tiles must be smaller than the full 2^32 × 2^32 space");
+        let hilbert = Hilbert::new();
+        GraphLayout::from_aggregated_keys(edges.map(|(edge, weight)| (hilbert.entangle(edge), weight)).collect(), aggregate, Some(log_side))
+    }
+
+    /// Lays out the edges as a single untiled stream, with columns of per-edge values given in the
+    /// order of `edges`.
+    ///
+    /// The values are reordered along with the edges, and duplicate edges keep the values of their
+    /// first occurrence.
+    pub fn from_edges_with_columns<I: Iterator<Item=(u32, u32)>>(edges: I, columns: Vec<(String, Column)>) -> GraphLayout {
+        GraphLayout::build_with_columns(edges, columns, None)
+    }
+    /// Lays out the edges in tiles of `2^log_side × 2^log_side` vertices, with columns of per-edge
+    /// values given in the order of `edges`.
+    pub fn from_edges_with_columns_tiled<I: Iterator<Item=(u32, u32)>>(edges: I, columns: Vec<(String, Column)>, log_side: u32) -> GraphLayout {
+        assert!(log_side < 32, "tiles must be smaller than the full 2^32 × 2^32 space");
+        GraphLayout::build_with_columns(edges, columns, Some(log_side))
+    }
+
+    fn build_with_columns<I: Iterator<Item=(u32, u32)>>(edges: I, columns: Vec<(String, Column)>, log_tile: Option<u32>) -> GraphLayout {
+        let hilbert = Hilbert::new();
+        let mut pairs = edges.enumerate().map(|(index, edge)| (hilbert.entangle(edge), index)).collect::<Vec<_>>();
+        for (name, column) in &columns {
+            assert_eq!(column.len(), pairs.len(), "column `{}` does not have one value per edge", name);
+        }
+        // a stable sort keeps duplicates in input order, so the first occurrence is kept.
+        pairs.sort_by_key(|&(key, _)| key);
+        pairs.dedup_by_key(|&mut (key, _)| key);
+        let indices = pairs.iter().map(|&(_, index)| index).collect::<Vec<_>>();
+        let mut layout = GraphLayout::assemble(pairs.into_iter().map(|(key, _)| key).collect(), None, log_tile);
+        for (name, column) in columns {
+            let column = column.select(&indices);
+            layout.attach_column(&name, column);
+        }
+        layout
+    }
+
+    fn build<I: Iterator<Item=(u32, u32)>>(edges: I, log_tile: Option<u32>) -> GraphLayout {
+        let hilbert = Hilbert::new();
+        GraphLayout::from_keys(edges.map(|edge| hilbert.entangle(edge)).collect(), log_tile)
+    }
+
+    /// Lays out edges already entangled into Hilbert curve keys.
+    pub(crate) fn from_keys(mut keys: Vec<u64>, log_tile: Option<u32>) -> GraphLayout {
+        let span = trace_span!("sort", edges);
+        keys.sort_unstable();
+        keys.dedup();
+        trace_record!(span, edges, keys.len());
+        drop(span);
+        GraphLayout::assemble(keys, None, log_tile)
+    }
+
+    /// Lays out this layout's edges under new keys, given in the current order, carrying their
+    /// weights and column values along. The new keys must be distinct.
+    #[cfg(any(feature = "io", feature = "algos"))]
+    pub(crate) fn rekeyed(&self, keys: Vec<u64>) -> GraphLayout {
+        let mut keys = keys.into_iter().zip(0 ..).collect::<Vec<_>>();
+        keys.sort_unstable();
+        let order = keys.iter().map(|&(_, index)| index).collect::<Vec<usize>>();
+        let weights = self.weights.as_ref().map(|weights| order.iter().map(|&index| weights[index]).collect());
+        let mut layout = GraphLayout::assemble(keys.into_iter().map(|(key, _)| key).collect(), weights, self.log_tile);
+        layout.columns = self.columns.iter().map(|(name, column)| (name.clone(), column.select(&order))).collect();
+        layout
+    }
+
+    /// Lays out curve keys that are already strictly increasing, skipping the sort.
+    #[cfg(feature = "algos")]
+    pub(crate) fn from_sorted_keys(keys: Vec<u64>, log_tile: Option<u32>) -> GraphLayout {
+        debug_assert!(keys.windows(2).all(|pair| pair[0] < pair[1]), "keys are not strictly increasing");
+        GraphLayout::assemble(keys, None, log_tile)
+    }
+
+    /// Lays out weighted curve keys, keeping the first weight among duplicate keys.
+    pub(crate) fn from_weighted_keys(pairs: Vec<(u64, f64)>, log_tile: Option<u32>) -> GraphLayout {
+        GraphLayout::from_aggregated_keys(pairs, Aggregate::First, log_tile)
+    }
+
+    /// Lays out weighted curve keys, combining the weights of duplicate keys with `aggregate`.
+    fn from_aggregated_keys(mut pairs: Vec<(u64, f64)>, aggregate: Aggregate, log_tile: Option<u32>) -> GraphLayout {
+        // a stable sort keeps duplicates in input order, which `Aggregate::First` relies on.
+        pairs.sort_by_key(|&(key, _)| key);
+        let mut keys = Vec::with_capacity(pairs.len());
+        let mut weights: Vec<f64> = Vec::with_capacity(pairs.len());
+        for (key, weight) in pairs {
+            if keys.last() == Some(&key) {
+                let last = weights.last_mut().unwrap();
+                *last = aggregate.combine(*last, weight);
+            }
+            else {
+                keys.push(key);
+                weights.push(if aggregate == Aggregate::Count { 1.0 } else { weight });
+            }
+        }
+        GraphLayout::assemble(keys, Some(weights), log_tile)
+    }
+
+    /// Compresses sorted, distinct keys and builds the block and tile directories.
+    pub(crate) fn assemble(keys: Vec<u64>, weights: Option<Vec<f64>>, log_tile: Option<u32>) -> GraphLayout {
+        let spare = GraphLayout::spare(Compressor::with_capacity(keys.len()).done());
+        GraphLayout::assemble_into(keys, weights, log_tile, spare, &mut BytewiseCached::new())
+    }
+
+    /// An empty layout holding `keys`, whose buffers a later `assemble_into` reuses.
+    pub(crate) fn spare(keys: Compressed) -> GraphLayout {
+        GraphLayout {
+            keys,
+            vertices: 0,
+            log_tile: None,
+            tiles: Vec::new(),
+            summaries: Vec::new(),
+            blocks: Vec::new(),
+            weights: None,
+            columns: Vec::new(),
+        }
+    }
+
+    /// Compresses sorted, distinct keys and builds the directories in the buffers of `spare`.
+    pub(crate) fn assemble_into<I: IntoIterator<Item=u64>>(keys: I, weights: Option<Vec<f64>>, log_tile: Option<u32>, spare: GraphLayout, detangler: &mut BytewiseCached) -> GraphLayout {
+        let span = trace_span!("compress", edges, bytes);
+        let GraphLayout { keys: buffers, mut tiles, mut summaries, mut blocks, .. } = spare;
+        tiles.clear();
+        summaries.clear();
+        blocks.clear();
+        let mut compressor = Compressor::recycle(buffers);
+        for (index, key) in keys.into_iter().enumerate() {
+            if index % BLOCK_EDGES == 0 {
+                let position = compressor.position();
+                blocks.push(Block {
+                    lower: key,
+                    upper: key,
+                    edges: 0,
+                    offset: position.offset(),
+                    position,
+                });
+            }
+            let block = blocks.last_mut().unwrap();
+            block.upper = key;
+            block.edges += 1;
+            if let Some(log) = log_tile {
+                let lower = (key >> (2 * log)) << (2 * log);
+                if tiles.last().map(|tile| tile.lower) != Some(lower) {
+                    let position = compressor.position();
+                    tiles.push(Tile {
+                        lower,
+                        upper: lower + ((1 << (2 * log)) - 1),
+                        edges: 0,
+                        offset: position.offset(),
+                        position,
+                    });
+                }
+                tiles.last_mut().unwrap().edges += 1;
+            }
+            compressor.push(key);
+        }
+
+        let mut layout = GraphLayout {
+            keys: compressor.done(),
+            vertices: 0,
+            log_tile,
+            tiles,
+            summaries: Vec::new(),
+            blocks,
+            weights,
+            columns: Vec::new(),
+        };
+        layout.vertices = layout.keys.decompress().map(|key| {
+            let (src, dst) = detangler.detangle(key);
+            ::std::cmp::max(src, dst) as u64 + 1
+        }).max().unwrap_or(0);
+        summaries.extend(layout.tiles.iter().map(|tile| layout.summarize(tile, detangler)));
+        layout.summaries = summaries;
+        trace_record!(span, edges, layout.len());
+        trace_record!(span, bytes, layout.keys.size_bytes());
+        layout
+    }
+
+    fn summarize(&self, tile: &Tile, detangler: &mut BytewiseCached) -> TileSummary {
+        TileSummary::from_edges(self.tile_keys(tile).map(|key| detangler.detangle(key)), tile)
+    }
+
+    /// Writes the layout to `path` in the format described in `io::format`, with the `io` feature.
+    #[cfg(feature = "io")]
+    pub fn save<P: AsRef<::std::path::Path>>(&self, path: P) -> crate::Result<()> {
+        crate::io::format::write_layout(self, path)
+    }
+    /// Reads a layout written by `save`, with the `io` feature.
+    #[cfg(feature = "io")]
+    pub fn open<P: AsRef<::std::path::Path>>(path: P) -> crate::Result<GraphLayout> {
+        crate::io::format::read_layout(path)
+    }
+    /// Writes the layout as `save` does, around the page cache, with the `direct` feature.
+    #[cfg(feature = "direct")]
+    pub fn save_direct<P: AsRef<::std::path::Path>>(&self, path: P) -> crate::Result<()> {
+        crate::io::format::write_layout_direct(self, path)
+    }
+    /// Reads a layout written by `save` around the page cache, with the `direct` feature.
+    #[cfg(feature = "direct")]
+    pub fn open_direct<P: AsRef<::std::path::Path>>(path: P) -> crate::Result<GraphLayout> {
+        crate::io::format::read_layout_direct(path)
+    }
+    /// Maps a layout written by `save` into memory, decoding its blocks only as they are read.
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap<P: AsRef<::std::path::Path>>(path: P) -> crate::Result<crate::io::mapped::MappedLayout> {
+        crate::io::mapped::MappedLayout::open(path)
+    }
+
+    /// The number of edges in the layout.
+    pub fn len(&self) -> usize { self.keys.len() }
+    /// Indicates that the layout contains no edges.
+    pub fn is_empty(&self) -> bool { self.keys.is_empty() }
+    /// The number of vertices, one more than the largest endpoint of any edge.
+    pub fn vertices(&self) -> u64 { self.vertices }
+    /// The base-two logarithm of the tile side length, if the layout is tiled.
+    pub fn log_tile(&self) -> Option<u32> { self.log_tile }
+    /// The directory of non-empty tiles, in curve order. Empty if the layout is not tiled.
+    pub fn tiles(&self) -> &[Tile] { &self.tiles[..] }
+    /// Summaries of the non-empty tiles, aligned with `tiles()`.
+    pub fn tile_summaries(&self) -> &[TileSummary] { &self.summaries[..] }
+
+    /// Edge weights in curve order, if the layout is weighted.
+    pub fn weights(&self) -> Option<&[f64]> { self.weights.as_ref().map(|weights| &weights[..]) }
+
+    /// The named columns of per-edge values, in curve order.
+    pub fn columns(&self) -> &[(String, Column)] { &self.columns[..] }
+    /// The values of the column `name`, if it exists and holds values of type `T`.
+    pub fn column<T: ColumnType>(&self, name: &str) -> Option<&[T]> {
+        self.columns.iter().find(|(other, _)| other == name).and_then(|(_, column)| T::values(column))
+    }
+    /// Attaches a column of per-edge values already in curve order, returning any column it
+    /// replaces of the same name.
+    ///
+    /// Panics unless the column has one value per edge, or if it is named `column::WEIGHT_COLUMN`,
+    /// which is reserved for the weights.
+    pub fn attach_column(&mut self, name: &str, column: Column) -> Option<Column> {
+        assert_eq!(column.len(), self.len(), "column `{}` does not have one value per edge", name);
+        assert!(name != crate::column::WEIGHT_COLUMN, "the weight column name is reserved");
+        let previous = self.detach_column(name);
+        self.columns.push((name.to_owned(), column));
+        previous
+    }
+    /// Removes and returns the column `name`.
+    pub fn detach_column(&mut self, name: &str) -> Option<Column> {
+        let index = self.columns.iter().position(|(other, _)| other == name)?;
+        Some(self.columns.remove(index).1)
+    }
+    /// Iterates over all edges, in curve order, with their values in the column `name`, if it
+    /// exists and holds values of type `T`.
+    pub fn edges_with<T: ColumnType>(&self, name: &str) -> Option<::std::iter::Zip<Edges<::std::iter::Take<Decompressor<'_>>>, ::std::slice::Iter<'_, T>>> {
+        self.column::<T>(name).map(|values| self.edges().zip(values.iter()))
+    }
+
+    /// The directory of fixed-size blocks, in curve order.
+    pub fn blocks(&self) -> &[Block] { &self.blocks[..] }
+
+    /// Splits the edges into at most `parts` contiguous runs with nearly equal edge counts.
+    ///
+    /// The runs are in curve order, cover every edge exactly once, and differ in size by at most
+    /// one edge. Fewer than `parts` runs are returned only if there are fewer edges than parts.
+    pub fn split(&self, parts: usize) -> Vec<Block> {
+        assert!(parts > 0, "cannot split into zero parts");
+        let parts = ::std::cmp::min(parts, self.len());
+        let position = |index: usize| {
+            let block = &self.blocks[::std::cmp::min(index / BLOCK_EDGES, self.blocks.len() - 1)];
+            self.keys.seek(block.position, index - block.offset)
+        };
+        (0 .. parts).map(|part| {
+            let start = part * self.len() / parts;
+            let end = (part + 1) * self.len() / parts;
+            let first = position(start);
+            Block {
+                lower: self.keys.seek(first, 1).previous(),
+                upper: position(end).previous(),
+                edges: end - start,
+                offset: start,
+                position: first,
+            }
+        }).collect()
+    }
+
+    /// The in-degree and out-degree of each vertex, computed in one pass over the keys.
+    pub fn degrees(&self) -> (Vec<u32>, Vec<u32>) {
+        let mut in_degrees = vec![0u32; self.vertices as usize];
+        let mut out_degrees = vec![0u32; self.vertices as usize];
+        for (src, dst) in self.edges() {
+            out_degrees[src as usize] += 1;
+            in_degrees[dst as usize] += 1;
+        }
+        (in_degrees, out_degrees)
+    }
+
+    /// Picks `k` distinct edges uniformly at random, determined by `seed`, returned in curve order.
+    ///
+    /// Edge positions are drawn first, and only the blocks holding a drawn position are decoded.
+    /// If `k` is at least the number of edges, every edge is returned.
+    pub fn sample_edges(&self, k: usize, seed: u64) -> Vec<(u32, u32)> {
+        let count = self.len();
+        let k = ::std::cmp::min(k, count);
+        // Floyd's algorithm draws `k` distinct positions from `0 .. count` with `k` draws.
+        let mut rng = SplitMix64::new(seed);
+        let mut drawn = ::std::collections::HashSet::with_capacity(k);
+        for bound in count - k .. count {
+            let position = rng.below(bound as u64 + 1) as usize;
+            if !drawn.insert(position) {
+                drawn.insert(bound);
+            }
+        }
+        let mut positions = drawn.into_iter().collect::<Vec<_>>();
+        positions.sort_unstable();
+
+        let mut detangler = BytewiseCached::new();
+        let mut sample = Vec::with_capacity(k);
+        let mut next = 0;
+        while next < positions.len() {
+            let block = &self.blocks[positions[next] / BLOCK_EDGES];
+            for (offset, key) in self.block_keys(block).enumerate() {
+                if next < positions.len() && positions[next] == block.offset + offset {
+                    sample.push(detangler.detangle(key));
+                    next += 1;
+                }
+            }
+        }
+        sample
+    }
+
+    /// Merges `layouts` into one layout holding each of their edges once, by a k-way merge of their
+    /// key streams.
+    ///
+    /// The result is tiled if every input shares the same tiling, and weighted if every input is
+    /// weighted, in which case an edge in several inputs keeps its weight from the earliest. It
+    /// carries each column that every input holds with the same name and type, whose values are
+    /// likewise taken from the earliest input holding the edge.
+    pub fn union(layouts: &[&GraphLayout]) -> GraphLayout {
+        let log_tile = layouts.first().and_then(|first| first.log_tile);
+        let log_tile = if layouts.iter().all(|layout| layout.log_tile == log_tile) { log_tile } else { None };
+        let weighted = !layouts.is_empty() && layouts.iter().all(|layout| layout.weights.is_some());
+        let shared = layouts.first().map(|first| first.columns.iter().filter_map(|(name, _)| {
+            let parts = layouts.iter().map(|layout| layout.columns.iter().find(|(other, _)| other == name).map(|(_, column)| column)).collect::<Option<Vec<_>>>()?;
+            Some((name.clone(), parts))
+        }).collect::<Vec<_>>()).unwrap_or_default();
+
+        let span = trace_span!("merge", runs, edges);
+        let mut streams = layouts.iter().map(|layout| layout.keys().take(layout.len())).collect::<Vec<_>>();
+        let mut heap = ::std::collections::BinaryHeap::with_capacity(streams.len());
+        let mut offsets = vec![0usize; streams.len()];
+        for (index, stream) in streams.iter_mut().enumerate() {
+            if let Some(key) = stream.next() {
+                heap.push(::std::cmp::Reverse((key, index)));
+            }
+        }
+        let capacity = layouts.iter().map(|layout| layout.len()).max().unwrap_or(0);
+        let mut keys = Vec::with_capacity(capacity);
+        let mut weights = Vec::new();
+        let mut picks = Vec::new();
+        while let Some(::std::cmp::Reverse((key, index))) = heap.pop() {
+            // ties pop in input order, so the first copy of a key comes from the earliest layout.
+            if keys.last() != Some(&key) {
+                keys.push(key);
+                if weighted {
+                    weights.push(layouts[index].weights.as_ref().unwrap()[offsets[index]]);
+                }
+                if !shared.is_empty() {
+                    picks.push((index, offsets[index]));
+                }
+            }
+            offsets[index] += 1;
+            if let Some(next) = streams[index].next() {
+                heap.push(::std::cmp::Reverse((next, index)));
+            }
+        }
+        trace_record!(span, runs, layouts.len());
+        trace_record!(span, edges, keys.len());
+        drop(span);
+        let mut layout = GraphLayout::assemble(keys, if weighted { Some(weights) } else { None }, log_tile);
+        for (name, parts) in shared {
+            if let Some(column) = Column::gather(&parts, &picks) {
+                layout.columns.push((name, column));
+            }
+        }
+        layout
+    }
+
+    /// Keeps only the edges `(src, dst)` for which `keep` returns true.
+    ///
+    /// The kept keys are re-encoded as they are decoded, without collecting them first, and the
+    /// weights and columns of the kept edges stay aligned with them.
+    pub fn retain_edges<F: FnMut(u32, u32) -> bool>(&mut self, mut keep: F) {
+        let mut detangler = BytewiseCached::new();
+        self.retain_keys(false, |_, key| {
+            let (src, dst) = detangler.detangle(key);
+            keep(src, dst)
+        });
+    }
+    /// Keeps only the edges `(src, dst)` with weight `weight` for which `keep` returns true.
+    ///
+    /// Panics if the layout has no weights.
+    pub fn retain_weighted_edges<F: FnMut(u32, u32, f64) -> bool>(&mut self, mut keep: F) {
+        let weights = self.weights.take().expect("the layout has no weights");
+        let mut detangler = BytewiseCached::new();
+        let kept = self.retain_keys(true, |index, key| {
+            let (src, dst) = detangler.detangle(key);
+            keep(src, dst, weights[index])
+        });
+        self.weights = Some(kept.iter().map(|&index| weights[index]).collect());
+    }
+    /// Keeps only the keys for which `keep`, given each key's index, returns true, and the weights
+    /// and column values of the kept keys.
+    ///
+    /// Returns the indices of the kept keys if `indices` is set or the layout has weights or
+    /// columns, and nothing otherwise.
+    fn retain_keys<F: FnMut(usize, u64) -> bool>(&mut self, indices: bool, mut keep: F) -> Vec<usize> {
+        let indices = indices || self.weights.is_some() || !self.columns.is_empty();
+        let mut kept = Vec::new();
+        let keys = self.keys.decompress().enumerate().filter(|&(index, key)| {
+            let retained = keep(index, key);
+            if retained && indices {
+                kept.push(index);
+            }
+            retained
+        });
+        let mut layout = GraphLayout::assemble_into(keys.map(|(_, key)| key), None, self.log_tile, GraphLayout::spare(Compressor::new().done()), &mut BytewiseCached::new());
+        layout.weights = self.weights.take().map(|weights| kept.iter().map(|&index| weights[index]).collect());
+        layout.columns = self.columns.drain(..).map(|(name, column)| (name, column.select(&kept))).collect();
+        *self = layout;
+        kept
+    }
+
+    /// The curve keys added and removed in going from this layout to `other`.
+    ///
+    /// The first stream holds the keys of `other` missing from `self`, and the second the keys of
+    /// `self` missing from `other`. Both layouts are merged in one streaming pass over their keys.
+    pub fn diff(&self, other: &GraphLayout) -> (Compressed, Compressed) {
+        let mut added = Compressor::new();
+        let mut removed = Compressor::new();
+        let mut ours = self.keys().take(self.len()).peekable();
+        let mut theirs = other.keys().take(other.len()).peekable();
+        loop {
+            match (ours.peek().cloned(), theirs.peek().cloned()) {
+                (Some(x), Some(y)) if x == y => { ours.next(); theirs.next(); },
+                (Some(x), Some(y)) if x < y => { removed.push(x); ours.next(); },
+                (Some(x), None) => { removed.push(x); ours.next(); },
+                (_, Some(y)) => { added.push(y); theirs.next(); },
+                (None, None) => break,
+            }
+        }
+        (added.done(), removed.done())
+    }
+
+    /// Indicates that the layout contains the edge `(src, dst)`.
+    ///
+    /// Finds the one block whose key range could hold the edge and decodes at most that block.
+    pub fn has_edge(&self, src: u32, dst: u32) -> bool {
+        self.contains_key(Hilbert::bit_entangle((src, dst)))
+    }
+    /// Indicates that the layout contains the curve key `key`.
+    pub fn contains_key(&self, key: u64) -> bool {
+        let index = self.blocks.partition_point(|block| block.upper < key);
+        match self.blocks.get(index) {
+            Some(block) if block.lower <= key => self.block_keys(block).take_while(|&other| other <= key).any(|other| other == key),
+            _ => false,
+        }
+    }
+
+    /// Chooses how to read the edges within `rect`, as described in `query`.
+    pub fn plan(&self, rect: Rect) -> Plan {
+        crate::query::plan(self, rect)
+    }
+    /// Iterates over the edges within `rect`, in curve order, by scanning or by reading the curve
+    /// ranges covering it, whichever `plan` expects to be cheaper.
+    pub fn edges_in(&self, rect: Rect) -> RectEdges<'_> {
+        RectEdges::new(self, rect, self.plan(rect))
+    }
+    /// Iterates over the edges whose source and destination lie between those of the corners
+    /// `(x0, y0)` and `(x1, y1)`, inclusive, in curve order.
+    ///
+    /// The iterator is lazy: it plans as `edges_in` does, skipping blocks outside the curve ranges
+    /// covering the rectangle, and decodes blocks only as edges are requested.
+    pub fn edges_in_rect(&self, from: (u32, u32), to: (u32, u32)) -> RectEdges<'_> {
+        self.edges_in(Rect::corners(from, to))
+    }
+    /// The destinations of the edges from `vertex`, in increasing order.
+    pub fn neighbors(&self, vertex: u32) -> Vec<u32> {
+        let mut neighbors = self.edges_in(Rect::row(vertex)).map(|(_, dst)| dst).collect::<Vec<_>>();
+        neighbors.sort_unstable();
+        neighbors
+    }
+
+    /// Iterates over the curve keys of all edges, in order.
+    pub fn keys(&self) -> Decompressor<'_> { self.keys.decompress() }
+    /// Iterates over all edges, in curve order.
+    pub fn edges(&self) -> Edges<::std::iter::Take<Decompressor<'_>>> {
+        Edges::new(self.keys.decompress().take(self.len()))
+    }
+    /// Iterates over all edges in parallel, with the `rayon` feature.
+    ///
+    /// Blocks of the directory are decoded independently, and each worker reuses one cached
+    /// detangler across the blocks it takes. Collecting the edges yields them in curve order.
+    #[cfg(feature = "rayon")]
+    pub fn par_edges(&self) -> impl ::rayon::iter::ParallelIterator<Item=(u32, u32)> + '_ {
+        use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+        self.blocks.par_iter()
+            .map_init(BytewiseCached::new, move |detangler, block| {
+                self.block_keys(block).map(|key| detangler.detangle(key)).collect::<Vec<_>>()
+            })
+            .flat_map_iter(|edges| edges.into_iter())
+    }
+    /// Iterates over the curve keys of the edges in `tile`.
+    pub fn tile_keys(&self, tile: &Tile) -> ::std::iter::Take<Decompressor<'_>> {
+        self.keys.decompress_from(tile.position).take(tile.edges)
+    }
+    /// Iterates over the curve keys of the edges in `block`.
+    pub fn block_keys(&self, block: &Block) -> ::std::iter::Take<Decompressor<'_>> {
+        self.keys.decompress_from(block.position).take(block.edges)
+    }
+    /// Iterates over the edges in `block`, in curve order.
+    pub fn block_edges(&self, block: &Block) -> Edges<::std::iter::Take<Decompressor<'_>>> {
+        Edges::new(self.block_keys(block))
+    }
+    /// Iterates over the edges in `tile`, in curve order.
+    pub fn tile_edges(&self, tile: &Tile) -> Edges<::std::iter::Take<Decompressor<'_>>> {
+        Edges::new(self.tile_keys(tile))
+    }
+}
+
+/// Zero-copy access to archived layouts, with the `rkyv` feature.
+///
+/// A layout accessed with `rkyv::access`, from a buffer or a memory map, reads its directories and
+/// decompresses its keys straight from the archive without first deserializing it.
+#[cfg(feature = "rkyv")]
+impl ArchivedGraphLayout {
+    /// The number of edges in the layout.
+    pub fn len(&self) -> usize { self.keys.len() }
+    /// Indicates that the layout contains no edges.
+    pub fn is_empty(&self) -> bool { self.keys.is_empty() }
+    /// The number of vertices, one more than the largest endpoint of any edge.
+    pub fn vertices(&self) -> u64 { self.vertices.to_native() }
+    /// The base-two logarithm of the tile side length, if the layout is tiled.
+    pub fn log_tile(&self) -> Option<u32> { self.log_tile.as_ref().map(|log| log.to_native()) }
+    /// The directory of non-empty tiles, in curve order. Empty if the layout is not tiled.
+    pub fn tiles(&self) -> &[ArchivedTile] { &self.tiles[..] }
+    /// The directory of fixed-size blocks, in curve order.
+    pub fn blocks(&self) -> &[ArchivedBlock] { &self.blocks[..] }
+    /// The weight of the edge at `index` in curve order, if the layout is weighted.
+    pub fn weight(&self, index: usize) -> Option<f64> {
+        self.weights.as_ref().map(|weights| weights[index].to_native())
+    }
+
+    /// Indicates that the layout contains the edge `(src, dst)`.
+    pub fn has_edge(&self, src: u32, dst: u32) -> bool {
+        self.contains_key(Hilbert::bit_entangle((src, dst)))
+    }
+    /// Indicates that the layout contains the curve key `key`.
+    pub fn contains_key(&self, key: u64) -> bool {
+        let index = self.blocks.partition_point(|block| block.upper.to_native() < key);
+        match self.blocks.get(index) {
+            Some(block) if block.lower.to_native() <= key => self.block_keys(block).take_while(|&other| other <= key).any(|other| other == key),
+            _ => false,
+        }
+    }
+
+    /// Iterates over the curve keys of all edges, in order.
+    pub fn keys(&self) -> crate::compression::ArchivedDecompressor<'_> { self.keys.decompress() }
+    /// Iterates over all edges, in curve order.
+    pub fn edges(&self) -> Edges<crate::compression::ArchivedDecompressor<'_>> { Edges::new(self.keys()) }
+    /// Iterates over the curve keys of the edges in `block`.
+    pub fn block_keys(&self, block: &ArchivedBlock) -> ::std::iter::Take<crate::compression::ArchivedDecompressor<'_>> {
+        self.keys.decompress_from((&block.position).into()).take(block.edges.to_native() as usize)
+    }
+    /// Iterates over the curve keys of the edges in `tile`.
+    pub fn tile_keys(&self, tile: &ArchivedTile) -> ::std::iter::Take<crate::compression::ArchivedDecompressor<'_>> {
+        self.keys.decompress_from((&tile.position).into()).take(tile.edges.to_native() as usize)
+    }
+}
+
+/// Iterates over edges by detangling a run of curve keys.
+pub struct Edges<K> {
+    keys: K,
+    detangler: BytewiseCached,
+}
+
+impl<K: Iterator<Item=u64>> Edges<K> {
+    /// Detangles the Hilbert curve keys produced by `keys`.
+    pub fn new(keys: K) -> Edges<K> {
+        Edges { keys, detangler: BytewiseCached::new() }
+    }
+}
+
+impl<K: Iterator<Item=u64>> Iterator for Edges<K> {
+    type Item = (u32, u32);
+    fn next(&mut self) -> Option<(u32, u32)> {
+        let detangler = &mut self.detangler;
+        self.keys.next().map(|key| detangler.detangle(key))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
@@ -0,0 +1,179 @@
+//! Strictly increasing sequences stored as sorted, disjoint runs of consecutive values.
+//!
+//! The curve keys of a dense subgraph are largely consecutive, and a few runs describe them as
+//! well as millions of delta bytes. `Intervals` stores a sequence as its maximal runs, each a
+//! first value and a length, and answers membership by binary search and set operations by merging
+//! runs, in time proportional to the number of runs rather than of values.
+
+use crate::compression::{Compressed, Compressor};
+
+/// A strictly increasing sequence of `u64` values, stored as maximal runs of consecutive values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Intervals {
+    /// The first value and the length of each run, in increasing order, with a gap between runs.
+    runs: Vec<(u64, u64)>,
+    /// The number of values in all runs.
+    len: usize,
+}
+
+impl Intervals {
+    /// Collects strictly increasing values into runs.
+    pub fn from<I: Iterator<Item=u64>>(iterator: I) -> Intervals {
+        let mut intervals = Intervals::default();
+        for value in iterator {
+            intervals.push_run(value, value);
+        }
+        intervals
+    }
+    /// Collects a strictly increasing slice into runs.
+    pub fn from_sorted_slice(values: &[u64]) -> Intervals {
+        Intervals::from(values.iter().copied())
+    }
+    /// The first value and length of each run, in increasing order.
+    pub fn runs(&self) -> &[(u64, u64)] { &self.runs[..] }
+    /// The number of values.
+    pub fn len(&self) -> usize { self.len }
+    /// Indicates that there are no values.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+    /// The number of bytes used by the runs.
+    pub fn size_bytes(&self) -> usize { 16 * self.runs.len() }
+
+    /// Iterates over the values in increasing order.
+    pub fn decompress(&self) -> IntervalValues<'_> {
+        IntervalValues { runs: self.runs.iter(), next: 0, remaining: 0, len: self.len }
+    }
+    /// Indicates that `value` is one of the values.
+    pub fn contains(&self, value: u64) -> bool {
+        let index = self.runs.partition_point(|&(start, _)| start <= value);
+        index > 0 && value - self.runs[index - 1].0 < self.runs[index - 1].1
+    }
+
+    /// The values in either `self` or `other`.
+    pub fn union(&self, other: &Intervals) -> Intervals {
+        let mut union = Intervals::default();
+        let (mut these, mut those) = (self.bounds().peekable(), other.bounds().peekable());
+        loop {
+            let next = match (these.peek(), those.peek()) {
+                (Some(this), Some(that)) if this.0 <= that.0 => these.next(),
+                (Some(_), Some(_)) => those.next(),
+                (Some(_), None) => these.next(),
+                (None, _) => those.next(),
+            };
+            match next {
+                Some((first, last)) => union.push_run(first, last),
+                None => return union,
+            }
+        }
+    }
+    /// The values in both `self` and `other`.
+    pub fn intersection(&self, other: &Intervals) -> Intervals {
+        let mut intersection = Intervals::default();
+        let (mut these, mut those) = (self.bounds().peekable(), other.bounds().peekable());
+        while let (Some(&this), Some(&that)) = (these.peek(), those.peek()) {
+            let (first, last) = (::std::cmp::max(this.0, that.0), ::std::cmp::min(this.1, that.1));
+            if first <= last {
+                intersection.push_run(first, last);
+            }
+            if this.1 <= that.1 { these.next(); } else { those.next(); }
+        }
+        intersection
+    }
+    /// The values in `self` but not in `other`.
+    pub fn difference(&self, other: &Intervals) -> Intervals {
+        let mut difference = Intervals::default();
+        let mut those = other.bounds().peekable();
+        for (mut first, last) in self.bounds() {
+            // cut each overlapping run of `other` out of the run, leaving those that extend past it.
+            let mut covered = false;
+            while let Some(&(start, end)) = those.peek() {
+                if end < first {
+                    those.next();
+                    continue;
+                }
+                if start > last {
+                    break;
+                }
+                if start > first {
+                    difference.push_run(first, start - 1);
+                }
+                if end >= last {
+                    covered = true;
+                    break;
+                }
+                first = end + 1;
+                those.next();
+            }
+            if !covered {
+                difference.push_run(first, last);
+            }
+        }
+        difference
+    }
+
+    /// The first and last value of each run.
+    fn bounds(&self) -> impl Iterator<Item=(u64, u64)> + '_ {
+        self.runs.iter().map(|&(start, length)| (start, start + (length - 1)))
+    }
+    /// Appends the values `first ..= last`, which follow all values so far in order, or extend
+    /// the last run if they overlap or adjoin it.
+    fn push_run(&mut self, first: u64, last: u64) {
+        if let Some(run) = self.runs.last_mut() {
+            let end = run.0 + (run.1 - 1);
+            if first <= end.saturating_add(1) {
+                if last > end {
+                    run.1 += last - end;
+                    self.len += (last - end) as usize;
+                }
+                return;
+            }
+        }
+        self.runs.push((first, last - first + 1));
+        self.len += (last - first + 1) as usize;
+    }
+}
+
+/// Iterates over the values of an `Intervals`, in increasing order.
+pub struct IntervalValues<'a> {
+    runs: ::std::slice::Iter<'a, (u64, u64)>,
+    next: u64,
+    /// The values remaining in the current run.
+    remaining: u64,
+    /// The values remaining in all runs.
+    len: usize,
+}
+
+impl<'a> Iterator for IntervalValues<'a> {
+    type Item = u64;
+    #[inline]
+    fn next(&mut self) -> Option<u64> {
+        while self.remaining == 0 {
+            let &(start, length) = self.runs.next()?;
+            self.next = start;
+            self.remaining = length;
+        }
+        let value = self.next;
+        self.next = self.next.wrapping_add(1);
+        self.remaining -= 1;
+        self.len -= 1;
+        Some(value)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+/// Conversions to and from interval sets, streaming values in order without collecting them.
+impl Compressed {
+    /// The values of the stream as runs of consecutive values.
+    pub fn to_intervals(&self) -> Intervals {
+        Intervals::from(self.decompress())
+    }
+    /// Compresses the values of an interval set.
+    pub fn from_intervals(intervals: &Intervals) -> Compressed {
+        let mut compressor = Compressor::with_capacity(intervals.len());
+        for value in intervals.decompress() {
+            compressor.push(value);
+        }
+        compressor.done()
+    }
+}
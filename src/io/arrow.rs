@@ -0,0 +1,80 @@
+//! Export of curve-ordered edges as Arrow record batches and Parquet files.
+//!
+//! Batches (and Parquet row groups) hold contiguous runs of the curve, so each one covers a compact
+//! region of the adjacency matrix and their statistics remain selective.
+
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+
+use crate::graph::GraphLayout;
+
+/// Which columns identify each edge.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EdgeColumns {
+    /// A single `key` column of Hilbert curve keys.
+    Keys,
+    /// `src` and `dst` columns of detangled endpoints.
+    Endpoints,
+}
+
+/// The schema of batches produced for `layout`, including a `weight` column if it is weighted.
+pub fn schema(layout: &GraphLayout, columns: EdgeColumns) -> SchemaRef {
+    let mut fields = match columns {
+        EdgeColumns::Keys => vec![Field::new("key", DataType::UInt64, false)],
+        EdgeColumns::Endpoints => vec![
+            Field::new("src", DataType::UInt32, false),
+            Field::new("dst", DataType::UInt32, false),
+        ],
+    };
+    if layout.weights().is_some() {
+        fields.push(Field::new("weight", DataType::Float64, false));
+    }
+    Arc::new(Schema::new(fields))
+}
+
+/// Converts `layout` into record batches of at most `batch_rows` edges each, in curve order.
+pub fn record_batches(layout: &GraphLayout, columns: EdgeColumns, batch_rows: usize) -> Result<Vec<RecordBatch>, ArrowError> {
+    assert!(batch_rows > 0, "batches must hold at least one row");
+    let schema = schema(layout, columns);
+    let mut batches = Vec::with_capacity(layout.len().div_ceil(batch_rows));
+    let mut keys = layout.keys();
+    let mut edges = layout.edges();
+    let mut start = 0;
+    while start < layout.len() {
+        let rows = ::std::cmp::min(batch_rows, layout.len() - start);
+        let mut arrays: Vec<ArrayRef> = match columns {
+            EdgeColumns::Keys => {
+                vec![Arc::new(UInt64Array::from_iter_values(keys.by_ref().take(rows)))]
+            },
+            EdgeColumns::Endpoints => {
+                let (src, dst): (Vec<u32>, Vec<u32>) = edges.by_ref().take(rows).unzip();
+                vec![Arc::new(UInt32Array::from(src)), Arc::new(UInt32Array::from(dst))]
+            },
+        };
+        if let Some(weights) = layout.weights() {
+            arrays.push(Arc::new(Float64Array::from(weights[start .. start + rows].to_vec())));
+        }
+        batches.push(RecordBatch::try_new(schema.clone(), arrays)?);
+        start += rows;
+    }
+    Ok(batches)
+}
+
+/// Writes `layout` as a Parquet file with row groups of at most `group_rows` edges, in curve order.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<P: AsRef<::std::path::Path>>(layout: &GraphLayout, path: P, columns: EdgeColumns, group_rows: usize) -> ::parquet::errors::Result<()> {
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+
+    let file = ::std::fs::File::create(path)?;
+    let properties = WriterProperties::builder().set_max_row_group_size(group_rows).build();
+    let mut writer = ArrowWriter::try_new(file, schema(layout, columns), Some(properties))?;
+    for batch in record_batches(layout, columns, group_rows)? {
+        writer.write(&batch)?;
+        writer.flush()?;
+    }
+    writer.close()?;
+    Ok(())
+}
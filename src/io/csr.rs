@@ -0,0 +1,76 @@
+//! Export to the compressed sparse row formats of Ligra and the GAP benchmark suite.
+//!
+//! Both formats list each vertex's neighbors contiguously, so the curve-ordered edges are first
+//! regrouped by source (or by destination, for in-neighbors) with a counting sort.
+
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+use crate::csr::Csr;
+use crate::graph::GraphLayout;
+
+/// Writes `layout` in Ligra's `AdjacencyGraph` text format.
+///
+/// Weighted layouts are written as `WeightedAdjacencyGraph`, with weights following the targets.
+/// Ligra reads integer weights, so weights should be integral.
+pub fn write_ligra<P: AsRef<Path>>(layout: &GraphLayout, path: P) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    format_ligra(layout, &mut writer)?;
+    writer.flush()
+}
+
+/// Formats `layout` in Ligra's adjacency text format to any writer.
+pub fn format_ligra<W: Write>(layout: &GraphLayout, writer: &mut W) -> Result<()> {
+    let csr = Csr::from_layout(layout, false, layout.vertices() as usize);
+    let header = if csr.weights.is_some() { "WeightedAdjacencyGraph" } else { "AdjacencyGraph" };
+    writeln!(writer, "{}", header)?;
+    writeln!(writer, "{}", csr.vertices())?;
+    writeln!(writer, "{}", csr.targets.len())?;
+    for offset in &csr.offsets[.. csr.vertices()] {
+        writeln!(writer, "{}", offset)?;
+    }
+    for target in &csr.targets {
+        writeln!(writer, "{}", target)?;
+    }
+    if let Some(weights) = csr.weights.as_ref() {
+        for weight in weights {
+            writeln!(writer, "{}", weight)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes `layout` in the GAP benchmark suite's serialized graph format (`.sg` or `.wsg`).
+///
+/// A directed graph stores both out- and in-neighbors; an undirected graph stores only the
+/// out-neighbors, and `layout` should then contain both directions of each edge. Weighted layouts
+/// produce the `.wsg` format, whose weights are `i32`, so weights are truncated towards zero.
+/// Vertex identifiers are also written as `i32`, and so should be less than `2^31`.
+pub fn write_gap<P: AsRef<Path>>(layout: &GraphLayout, path: P, directed: bool) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    format_gap(layout, &mut writer, directed)?;
+    writer.flush()
+}
+
+/// Formats `layout` in the GAP serialized graph format to any writer.
+pub fn format_gap<W: Write>(layout: &GraphLayout, writer: &mut W, directed: bool) -> Result<()> {
+    let vertices = layout.vertices() as usize;
+    writer.write_all(&[directed as u8])?;
+    writer.write_all(&(layout.len() as i64).to_le_bytes())?;
+    writer.write_all(&(vertices as i64).to_le_bytes())?;
+    let transposes: &[bool] = if directed { &[false, true] } else { &[false] };
+    for &transpose in transposes {
+        let csr = Csr::from_layout(layout, transpose, vertices);
+        for &offset in &csr.offsets {
+            writer.write_all(&(offset as i64).to_le_bytes())?;
+        }
+        for (index, &target) in csr.targets.iter().enumerate() {
+            writer.write_all(&(target as i32).to_le_bytes())?;
+            if let Some(weights) = csr.weights.as_ref() {
+                writer.write_all(&(weights[index] as i32).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
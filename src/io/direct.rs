@@ -0,0 +1,255 @@
+//! Reading and writing files around the page cache, with the `direct` feature.
+//!
+//! A scan of a layout file larger than memory passes every page of it through the page cache,
+//! evicting whatever else was cached, only for the pages to go unread again. A `DirectReader` or
+//! `DirectWriter` opens its file with `O_DIRECT` on Linux, and with `F_NOCACHE` set on macOS, so
+//! that transfers go between the device and the reader's own buffer.
+//!
+//! Direct transfers must start at aligned file offsets, from aligned memory, in lengths that are
+//! multiples of the device's block size. Both types move whole `AlignedBuffer`s, which are aligned
+//! to and sized in multiples of `ALIGNMENT`; only the final read may come up short, at the end of
+//! the file, and the final write is padded and the padding then truncated away.
+//!
+//! Not every file system supports direct transfers: tmpfs, for one, refuses `O_DIRECT`. Where the
+//! file cannot be opened for them, or on other platforms, the file is opened as usual and read or
+//! written through the page cache, and `is_direct` reports which happened.
+
+use std::alloc::{self, Layout};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, Read, Result, Seek, SeekFrom, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::ptr::NonNull;
+
+/// The alignment of direct transfers: their file offsets, memory, and lengths.
+///
+/// Page sized, which is a multiple of the logical block size of common devices.
+pub const ALIGNMENT: usize = 4096;
+
+/// The default size of the buffers of readers and writers.
+pub const BUFFER_BYTES: usize = 1 << 20;
+
+/// Zeroed bytes aligned to `ALIGNMENT`, in a multiple of `ALIGNMENT` bytes.
+pub struct AlignedBuffer {
+    bytes: NonNull<u8>,
+    len: usize,
+}
+
+// The buffer owns its bytes, as a `Vec<u8>` would.
+unsafe impl Send for AlignedBuffer { }
+unsafe impl Sync for AlignedBuffer { }
+
+impl AlignedBuffer {
+    /// A buffer of at least `bytes` bytes, rounded up to a non-zero multiple of `ALIGNMENT`.
+    pub fn new(bytes: usize) -> AlignedBuffer {
+        let len = ::std::cmp::max(bytes, 1).div_ceil(ALIGNMENT) * ALIGNMENT;
+        let layout = AlignedBuffer::layout(len);
+        // the layout has a non-zero size.
+        let bytes = unsafe { alloc::alloc_zeroed(layout) };
+        match NonNull::new(bytes) {
+            Some(bytes) => AlignedBuffer { bytes, len },
+            None => alloc::handle_alloc_error(layout),
+        }
+    }
+    fn layout(len: usize) -> Layout {
+        Layout::from_size_align(len, ALIGNMENT).expect("buffer too large")
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        unsafe { ::std::slice::from_raw_parts(self.bytes.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { ::std::slice::from_raw_parts_mut(self.bytes.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        unsafe { alloc::dealloc(self.bytes.as_ptr(), AlignedBuffer::layout(self.len)) }
+    }
+}
+
+/// Opens `path` for direct reading, as described in the module documentation.
+pub fn open<P: AsRef<Path>>(path: P) -> Result<DirectReader> {
+    let (file, direct) = open_with(path.as_ref(), OpenOptions::new().read(true))?;
+    Ok(DirectReader { file, direct, buffer: AlignedBuffer::new(BUFFER_BYTES), start: 0, end: 0, done: false })
+}
+
+/// Creates or truncates `path` for direct writing, as described in the module documentation.
+pub fn create<P: AsRef<Path>>(path: P) -> Result<DirectWriter> {
+    let (file, direct) = open_with(path.as_ref(), OpenOptions::new().write(true).create(true).truncate(true))?;
+    Ok(DirectWriter { file, direct, buffer: AlignedBuffer::new(BUFFER_BYTES), head: None, filled: 0, written: 0, finished: false })
+}
+
+/// Opens `path` with `options` and `O_DIRECT`, or without if the file system refuses it.
+#[cfg(target_os = "linux")]
+fn open_with(path: &Path, options: &mut OpenOptions) -> Result<(File, bool)> {
+    use std::os::unix::fs::OpenOptionsExt;
+    match options.clone().custom_flags(::libc::O_DIRECT).open(path) {
+        Ok(file) => Ok((file, true)),
+        Err(ref error) if error.raw_os_error() == Some(::libc::EINVAL) => Ok((options.open(path)?, false)),
+        Err(error) => Err(error),
+    }
+}
+
+/// Opens `path` with `options`, and turns off caching of its pages.
+#[cfg(target_os = "macos")]
+fn open_with(path: &Path, options: &mut OpenOptions) -> Result<(File, bool)> {
+    use std::os::unix::io::AsRawFd;
+    let file = options.open(path)?;
+    let direct = unsafe { ::libc::fcntl(file.as_raw_fd(), ::libc::F_NOCACHE, 1) } != -1;
+    Ok((file, direct))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn open_with(path: &Path, options: &mut OpenOptions) -> Result<(File, bool)> {
+    Ok((options.open(path)?, false))
+}
+
+/// Reads a file a whole aligned buffer at a time.
+pub struct DirectReader {
+    file: File,
+    direct: bool,
+    buffer: AlignedBuffer,
+    /// The unread bytes of the buffer.
+    start: usize,
+    end: usize,
+    /// Whether the end of the file has been read.
+    done: bool,
+}
+
+impl DirectReader {
+    /// Indicates that reads bypass the page cache.
+    pub fn is_direct(&self) -> bool { self.direct }
+}
+
+impl BufRead for DirectReader {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        if self.start == self.end && !self.done {
+            // only a read reaching the end of the file may leave the next offset unaligned.
+            self.start = 0;
+            self.end = 0;
+            while self.end < self.buffer.len() {
+                match self.file.read(&mut self.buffer[self.end ..]) {
+                    Ok(0) => { self.done = true; break; },
+                    Ok(read) => self.end += read,
+                    Err(ref error) if error.kind() == ::std::io::ErrorKind::Interrupted => { },
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+        Ok(&self.buffer[self.start .. self.end])
+    }
+    fn consume(&mut self, amount: usize) {
+        self.start = ::std::cmp::min(self.start + amount, self.end);
+    }
+}
+
+impl Read for DirectReader {
+    fn read(&mut self, into: &mut [u8]) -> Result<usize> {
+        let available = self.fill_buf()?;
+        let read = ::std::cmp::min(available.len(), into.len());
+        into[.. read].copy_from_slice(&available[.. read]);
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+/// Writes a file a whole aligned buffer at a time.
+///
+/// Bytes short of a whole buffer are written by `finish`, or when the writer is dropped, in which
+/// case errors go unreported; `flush` writes only whole buffers.
+pub struct DirectWriter {
+    file: File,
+    direct: bool,
+    buffer: AlignedBuffer,
+    /// The first `ALIGNMENT` bytes of the file, once written, to support `finish_with_prefix`.
+    head: Option<AlignedBuffer>,
+    /// The bytes of the buffer holding data, and the bytes of the file before them.
+    filled: usize,
+    written: u64,
+    finished: bool,
+}
+
+impl DirectWriter {
+    /// Indicates that writes bypass the page cache.
+    pub fn is_direct(&self) -> bool { self.direct }
+    /// The number of bytes written so far, including those still buffered.
+    pub fn len(&self) -> u64 { self.written + self.filled as u64 }
+    /// Indicates that no bytes have been written.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Writes any buffered bytes, and truncates the file to the bytes written.
+    pub fn finish(mut self) -> Result<()> {
+        self.complete(&[])
+    }
+    /// Replaces the first bytes of the file with `prefix`, then finishes the file as `finish`.
+    ///
+    /// Supports headers whose contents are known only at the end. Panics if `prefix` is longer than
+    /// `ALIGNMENT` bytes or than the bytes written.
+    pub fn finish_with_prefix(mut self, prefix: &[u8]) -> Result<()> {
+        assert!(prefix.len() <= ALIGNMENT && prefix.len() as u64 <= self.len(), "prefix too long");
+        self.complete(prefix)
+    }
+
+    /// Writes the full buffer.
+    fn write_buffer(&mut self) -> Result<()> {
+        if self.written == 0 {
+            let mut head = AlignedBuffer::new(ALIGNMENT);
+            head.copy_from_slice(&self.buffer[.. ALIGNMENT]);
+            self.head = Some(head);
+        }
+        self.file.write_all(&self.buffer)?;
+        self.written += self.buffer.len() as u64;
+        self.filled = 0;
+        Ok(())
+    }
+
+    fn complete(&mut self, prefix: &[u8]) -> Result<()> {
+        self.finished = true;
+        if self.written == 0 {
+            self.buffer[.. prefix.len()].copy_from_slice(prefix);
+        }
+        // the padding is written along with the data, and then cut off.
+        let padded = self.filled.div_ceil(ALIGNMENT) * ALIGNMENT;
+        self.buffer[self.filled .. padded].fill(0);
+        self.file.write_all(&self.buffer[.. padded])?;
+        let length = self.len();
+        if let Some(head) = self.head.as_mut().filter(|_| !prefix.is_empty()) {
+            head[.. prefix.len()].copy_from_slice(prefix);
+            self.file.seek(SeekFrom::Start(0))?;
+            self.file.write_all(head)?;
+        }
+        self.file.set_len(length)?;
+        self.file.flush()
+    }
+}
+
+impl Write for DirectWriter {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize> {
+        let written = ::std::cmp::min(bytes.len(), self.buffer.len() - self.filled);
+        self.buffer[self.filled .. self.filled + written].copy_from_slice(&bytes[.. written]);
+        self.filled += written;
+        if self.filled == self.buffer.len() {
+            self.write_buffer()?;
+        }
+        Ok(written)
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for DirectWriter {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.complete(&[]);
+        }
+    }
+}
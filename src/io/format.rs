@@ -0,0 +1,855 @@
+//! A self-describing single-file container for a `GraphLayout`.
+//!
+//! All integers are little-endian. A file consists of
+//!
+//! * a 64 byte header: the magic `GLAYOUT\0`, the format version (`u32`) and codec (`u32`), the
+//!   vertex, edge, and block-size counts (`u64` each), the log tile side (`u32`, or `u32::MAX` if
+//!   untiled), flags (`u32`), and curve (`u32`), zero padded;
+//! * a sequence of sections, whose kinds are listed below;
+//! * a footer listing each section's kind, offset, length, and checksum (`u64` each), preceded by
+//!   the number of sections and followed by a checksum of the header and section table;
+//! * a 24 byte trailer with the footer's offset and length, and the magic `GLAYEND\0`.
+//!
+//! Checksums are 64-bit FNV-1a hashes. Readers locate the footer through the trailer, so sections
+//! may appear in any order, and readers skip sections of kinds they do not know.
+//!
+//! Layouts written by `write_layout` order their edges along the Hilbert curve and encode their
+//! keys as escaped byte deltas. A `LayoutWriter` may instead use another `Curve`, whose keys fill the
+//! block and tile directories in its place, and another `Codec`. Such files are read back into a
+//! `GraphLayout` by re-keying their edges along the Hilbert curve, and cannot be memory-mapped.
+
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub use crate::column::WEIGHT_COLUMN;
+
+use crate::column::Column;
+use crate::error::{Error, Result};
+use crate::compression::{Compressor, Position};
+use crate::graph::{Block, GraphLayout, Tile, TileSummary, BLOCK_EDGES};
+use crate::layout::{BytewiseCached, Hilbert, RowMajor, Tangle, ZOrder};
+
+/// Identifies the start of a layout file.
+pub const MAGIC: &[u8; 8] = b"GLAYOUT\0";
+/// Identifies the end of a layout file.
+pub const TRAILER_MAGIC: &[u8; 8] = b"GLAYEND\0";
+/// The version of the format written by this crate.
+pub const VERSION: u32 = 1;
+/// The codec of block data: escaped byte deltas, as produced by `Compressed::encode`.
+pub const CODEC_ESCAPED_DELTAS: u32 = 0;
+/// The codec of block data: each key as a `u64`, for readers without an escaped delta decoder.
+pub const CODEC_PLAIN: u32 = 1;
+
+const HEADER_BYTES: usize = 64;
+const FLAG_WEIGHTED: u32 = 1;
+const UNTILED: u32 = u32::MAX;
+
+/// The order of the edges in a layout file, and so the meaning of its keys.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Curve {
+    /// Keys along the Hilbert curve, as in every `GraphLayout`.
+    Hilbert,
+    /// Keys along the Z-order curve, as `layout::ZOrder` computes them.
+    ZOrder,
+    /// No curve: keys hold the source in their high bits, as `layout::RowMajor` computes them, so
+    /// edges are ordered by source and then destination. Such layouts cannot be tiled.
+    RowMajor,
+}
+
+impl Curve {
+    /// Tables mapping edges to the curve's keys and back.
+    pub fn tangle(self) -> Box<dyn Tangle + Send + Sync> {
+        match self {
+            Curve::Hilbert => Box::new(Hilbert::new()),
+            Curve::ZOrder => Box::new(ZOrder::new()),
+            Curve::RowMajor => Box::new(RowMajor),
+        }
+    }
+    pub(crate) fn code(self) -> u32 {
+        match self {
+            Curve::Hilbert => 0,
+            Curve::ZOrder => 1,
+            Curve::RowMajor => 2,
+        }
+    }
+    fn from_code(code: u32) -> Result<Curve> {
+        match code {
+            0 => Ok(Curve::Hilbert),
+            1 => Ok(Curve::ZOrder),
+            2 => Ok(Curve::RowMajor),
+            _ => Err(Error::Unsupported { what: "layout curve", found: code as u64 }),
+        }
+    }
+}
+
+/// The encoding of the keys in a layout file's block data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Codec {
+    /// `CODEC_ESCAPED_DELTAS`, as `write_layout` writes.
+    EscapedDeltas,
+    /// `CODEC_PLAIN`.
+    Plain,
+}
+
+impl Codec {
+    pub(crate) fn code(self) -> u32 {
+        match self {
+            Codec::EscapedDeltas => CODEC_ESCAPED_DELTAS,
+            Codec::Plain => CODEC_PLAIN,
+        }
+    }
+    fn from_code(code: u32) -> Result<Codec> {
+        match code {
+            CODEC_ESCAPED_DELTAS => Ok(Codec::EscapedDeltas),
+            CODEC_PLAIN => Ok(Codec::Plain),
+            _ => Err(Error::Unsupported { what: "layout codec", found: code as u64 }),
+        }
+    }
+}
+
+/// The kinds of sections in a layout file.
+pub mod section {
+    /// Block directory: per block, the first and last key, edge count, edge offset, and the offset
+    /// and length of its data within the block data section (six `u64`).
+    pub const BLOCKS: u64 = 1;
+    /// Block data: each block's keys encoded by `Compressed::encode`, back to back.
+    pub const BLOCK_DATA: u64 = 2;
+    /// Tile directory: per tile, the first and last key, edge count, and edge offset, followed by
+    /// the tile's edge count, distinct sources, distinct destinations, and density (eight `u64`,
+    /// the density as `f64` bits).
+    pub const TILES: u64 = 3;
+    /// A named column of per-edge values in curve order: the name's length and UTF-8 bytes, a type
+    /// code (1 for `f64`, 2 for `u64`, 3 for `u32`, 4 for `i64`, 5 for byte strings), the value
+    /// count, and the values. Byte strings are stored as each string's length (`u64`), followed by
+    /// the strings' bytes.
+    pub const COLUMN: u64 = 4;
+}
+
+#[cfg(feature = "mmap")]
+const COLUMN_F64: u64 = 1;
+
+/// Writes `layout` to `path` in the layout file format.
+pub fn write_layout<P: AsRef<Path>>(layout: &GraphLayout, path: P) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    format_layout(layout, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes `layout` to `path` as `write_layout` does, around the page cache as described in
+/// `io::direct`, with the `direct` feature.
+#[cfg(feature = "direct")]
+pub fn write_layout_direct<P: AsRef<Path>>(layout: &GraphLayout, path: P) -> Result<()> {
+    let mut writer = crate::io::direct::create(path)?;
+    format_layout(layout, &mut writer)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Writes `layout` in the layout file format to any writer.
+pub fn format_layout<W: Write>(layout: &GraphLayout, writer: &mut W) -> Result<()> {
+    let span = trace_span!("write_layout", edges, bytes);
+    let header = header(layout.vertices(), layout.len(), layout.log_tile(), layout.weights().is_some(), Curve::Hilbert, Codec::EscapedDeltas);
+    writer.write_all(&header)?;
+    let mut offset = HEADER_BYTES as u64;
+    let mut table = Vec::new();
+    for (kind, bytes) in sections(layout) {
+        writer.write_all(&bytes)?;
+        table.push([kind, offset, bytes.len() as u64, checksum(&bytes)]);
+        offset += bytes.len() as u64;
+    }
+    trace_record!(span, edges, layout.len());
+    trace_record!(span, bytes, offset);
+    write_footer(writer, &header, &table, offset)
+}
+
+/// Writes the footer listing the sections in `table`, starting at `offset`, and the trailer.
+fn write_footer<W: Write>(writer: &mut W, header: &[u8], table: &[[u64; 4]], offset: u64) -> Result<()> {
+    let mut footer = Vec::new();
+    put(&mut footer, table.len() as u64);
+    for entry in table {
+        for &value in entry { put(&mut footer, value); }
+    }
+    let mut summed = header.to_vec();
+    summed.extend_from_slice(&footer);
+    put(&mut footer, checksum(&summed));
+    writer.write_all(&footer)?;
+
+    let mut trailer = Vec::new();
+    put(&mut trailer, offset);
+    put(&mut trailer, footer.len() as u64);
+    trailer.extend_from_slice(TRAILER_MAGIC);
+    writer.write_all(&trailer)?;
+    Ok(())
+}
+
+/// Writes a layout file from curve keys supplied in increasing order.
+///
+/// Unlike `write_layout`, the layout is never held in memory: block data is written as it fills,
+/// and only the block and tile directories and the edges of the current tile are retained.
+pub struct LayoutWriter {
+    file: Output,
+    log_tile: Option<u32>,
+    curve: Curve,
+    codec: Codec,
+    vertices: u64,
+    edges: usize,
+    last: Option<u64>,
+    /// The keys of the block being filled, and the key preceding them.
+    block: Vec<u64>,
+    previous: u64,
+    data_length: u64,
+    data_checksum: u64,
+    directory: Vec<u8>,
+    /// The tile being filled, and the edges it holds so far.
+    tile: Option<Tile>,
+    tile_edges: Vec<(u32, u32)>,
+    tiles: Vec<u8>,
+    detangler: BytewiseCached,
+    /// The curve's tables, if it is not the Hilbert curve `detangler` serves.
+    tangle: Option<Box<dyn Tangle + Send + Sync>>,
+}
+
+impl LayoutWriter {
+    /// Creates a layout file at `path`, tiled with tiles of side `2^log_tile` if supplied.
+    ///
+    /// Fails with `Error::Capacity` if the tiles would not be smaller than the full `2^32 × 2^32`
+    /// space.
+    pub fn create<P: AsRef<Path>>(path: P, log_tile: Option<u32>) -> Result<LayoutWriter> {
+        LayoutWriter::with_output(Output::Buffered(BufWriter::new(File::create(path)?)), log_tile)
+    }
+    /// Creates a layout file at `path` as `create` does, written around the page cache as
+    /// described in `io::direct`, with the `direct` feature.
+    #[cfg(feature = "direct")]
+    pub fn create_direct<P: AsRef<Path>>(path: P, log_tile: Option<u32>) -> Result<LayoutWriter> {
+        LayoutWriter::with_output(Output::Direct(crate::io::direct::create(path)?), log_tile)
+    }
+    fn with_output(mut file: Output, log_tile: Option<u32>) -> Result<LayoutWriter> {
+        if let Some(log) = log_tile.filter(|&log| log >= 32) {
+            return Err(Error::Capacity(format!("tiles of side 2^{} exceed the 2^32 × 2^32 space", log)));
+        }
+        // the header is rewritten once the counts are known.
+        file.write_all(&[0u8; HEADER_BYTES])?;
+        Ok(LayoutWriter {
+            file,
+            log_tile,
+            curve: Curve::Hilbert,
+            codec: Codec::EscapedDeltas,
+            vertices: 0,
+            edges: 0,
+            last: None,
+            block: Vec::with_capacity(BLOCK_EDGES),
+            previous: 0,
+            data_length: 0,
+            data_checksum: checksum(&[]),
+            directory: Vec::new(),
+            tile: None,
+            tile_edges: Vec::new(),
+            tiles: Vec::new(),
+            detangler: BytewiseCached::new(),
+            tangle: None,
+        })
+    }
+
+    /// Writes keys of `curve` rather than of the Hilbert curve.
+    ///
+    /// Panics if keys have already been written, or if the curve is `Curve::RowMajor` and the
+    /// layout is tiled.
+    pub fn curve(mut self, curve: Curve) -> LayoutWriter {
+        assert!(self.is_empty(), "the curve must be chosen before any keys are written");
+        assert!(curve != Curve::RowMajor || self.log_tile.is_none(), "row-major layouts cannot be tiled");
+        self.tangle = if curve == Curve::Hilbert { None } else { Some(curve.tangle()) };
+        self.curve = curve;
+        self
+    }
+    /// Encodes block data with `codec` rather than as escaped byte deltas.
+    ///
+    /// Panics if keys have already been written.
+    pub fn codec(mut self, codec: Codec) -> LayoutWriter {
+        assert!(self.is_empty(), "the codec must be chosen before any keys are written");
+        self.codec = codec;
+        self
+    }
+
+    /// The number of keys written so far.
+    pub fn len(&self) -> usize { self.edges }
+    /// Indicates that no keys have been written.
+    pub fn is_empty(&self) -> bool { self.edges == 0 }
+    /// The number of bytes of block data written so far.
+    pub fn data_bytes(&self) -> u64 { self.data_length }
+
+    /// Appends the next key, which must be greater than all previous keys.
+    ///
+    /// Fails with `Error::Unordered`, writing nothing, if it is not.
+    pub fn push(&mut self, key: u64) -> Result<()> {
+        if let Some(previous) = self.last.filter(|&last| last >= key) {
+            return Err(Error::Unordered { previous, next: key });
+        }
+        self.last = Some(key);
+        self.edges += 1;
+        let (src, dst) = match self.tangle {
+            Some(ref tangle) => tangle.detangle(key),
+            None => self.detangler.detangle(key),
+        };
+        self.vertices = ::std::cmp::max(self.vertices, ::std::cmp::max(src, dst) as u64 + 1);
+
+        if let Some(log) = self.log_tile {
+            let lower = (key >> (2 * log)) << (2 * log);
+            if self.tile.map(|tile| tile.lower) != Some(lower) {
+                self.finish_tile();
+                self.tile = Some(Tile {
+                    lower,
+                    upper: lower + ((1 << (2 * log)) - 1),
+                    edges: 0,
+                    offset: self.edges - 1,
+                    position: Default::default(),
+                });
+            }
+            self.tile.as_mut().unwrap().edges += 1;
+            self.tile_edges.push((src, dst));
+        }
+
+        self.block.push(key);
+        if self.block.len() == BLOCK_EDGES {
+            self.finish_block()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the remaining sections, footer, and header, and flushes the file.
+    pub fn finish(mut self) -> Result<()> {
+        self.finish_block()?;
+        self.finish_tile();
+
+        let mut table = vec![[section::BLOCK_DATA, HEADER_BYTES as u64, self.data_length, self.data_checksum]];
+        let mut offset = HEADER_BYTES as u64 + self.data_length;
+        let mut sections = vec![(section::BLOCKS, &self.directory)];
+        if self.log_tile.is_some() {
+            sections.push((section::TILES, &self.tiles));
+        }
+        for (kind, bytes) in sections {
+            self.file.write_all(bytes)?;
+            table.push([kind, offset, bytes.len() as u64, checksum(bytes)]);
+            offset += bytes.len() as u64;
+        }
+        let header = header(self.vertices, self.edges, self.log_tile, false, self.curve, self.codec);
+        write_footer(&mut self.file, &header, &table, offset)?;
+
+        match self.file {
+            Output::Buffered(file) => {
+                let mut file = file.into_inner().map_err(|error| error.into_error())?;
+                file.seek(SeekFrom::Start(0))?;
+                file.write_all(&header)?;
+                file.flush()?;
+            },
+            #[cfg(feature = "direct")]
+            Output::Direct(file) => file.finish_with_prefix(&header)?,
+        }
+        Ok(())
+    }
+
+    fn finish_block(&mut self) -> Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        let mut bytes = Vec::new();
+        match self.codec {
+            Codec::EscapedDeltas => {
+                let mut compressor = Compressor::after(self.previous);
+                let from = compressor.position();
+                for &key in &self.block {
+                    compressor.push(key);
+                }
+                let to = compressor.position();
+                compressor.done().encode(from, to, &mut bytes);
+            },
+            Codec::Plain => {
+                for &key in &self.block {
+                    put(&mut bytes, key);
+                }
+            },
+        }
+        self.file.write_all(&bytes)?;
+
+        let upper = self.block[self.block.len() - 1];
+        let offset = self.edges - self.block.len();
+        for &value in &[self.block[0], upper, self.block.len() as u64, offset as u64, self.data_length, bytes.len() as u64] {
+            put(&mut self.directory, value);
+        }
+        self.data_checksum = checksum_extend(self.data_checksum, &bytes);
+        self.data_length += bytes.len() as u64;
+        self.previous = upper;
+        self.block.clear();
+        Ok(())
+    }
+
+    fn finish_tile(&mut self) {
+        if let Some(tile) = self.tile.take() {
+            let summary = TileSummary::from_edges(self.tile_edges.drain(..), &tile);
+            for &value in &[tile.lower, tile.upper, tile.edges as u64, tile.offset as u64] {
+                put(&mut self.tiles, value);
+            }
+            for &value in &[summary.edges as u64, summary.sources as u64, summary.destinations as u64, summary.density.to_bits()] {
+                put(&mut self.tiles, value);
+            }
+        }
+    }
+}
+
+/// The file written by a `LayoutWriter`.
+enum Output {
+    Buffered(BufWriter<File>),
+    #[cfg(feature = "direct")]
+    Direct(crate::io::direct::DirectWriter),
+}
+
+impl Write for Output {
+    fn write(&mut self, bytes: &[u8]) -> ::std::io::Result<usize> {
+        match self {
+            Output::Buffered(file) => file.write(bytes),
+            #[cfg(feature = "direct")]
+            Output::Direct(file) => file.write(bytes),
+        }
+    }
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        match self {
+            Output::Buffered(file) => file.flush(),
+            #[cfg(feature = "direct")]
+            Output::Direct(file) => file.flush(),
+        }
+    }
+}
+
+/// Reads a layout written by `write_layout` from `path`.
+pub fn read_layout<P: AsRef<Path>>(path: P) -> Result<GraphLayout> {
+    let mut file = File::open(path)?;
+    let length = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(0))?;
+    let mut bytes = Vec::with_capacity(length as usize);
+    file.read_to_end(&mut bytes)?;
+    parse_layout(&bytes)
+}
+
+/// Reads a layout from `path` as `read_layout` does, around the page cache as described in
+/// `io::direct`, with the `direct` feature.
+#[cfg(feature = "direct")]
+pub fn read_layout_direct<P: AsRef<Path>>(path: P) -> Result<GraphLayout> {
+    let length = ::std::fs::metadata(path.as_ref())?.len();
+    let mut bytes = Vec::with_capacity(length as usize);
+    crate::io::direct::open(path)?.read_to_end(&mut bytes)?;
+    parse_layout(&bytes)
+}
+
+/// Parses a layout from the complete contents of a layout file.
+pub fn parse_layout(bytes: &[u8]) -> Result<GraphLayout> {
+    let span = trace_span!("read_layout", edges, bytes);
+    trace_record!(span, bytes, bytes.len());
+    let file = LayoutFile::parse(bytes)?;
+
+    let mut keys = Compressor::new().done();
+    let mut blocks = Vec::new();
+    let mut detangler = BytewiseCached::new();
+    let tangle = if file.curve == Curve::Hilbert { None } else { Some(file.curve.tangle()) };
+    let mut vertices = 0;
+    if let Some(directory) = file.section(section::BLOCKS)? {
+        let data = file.section(section::BLOCK_DATA)?.ok_or_else(|| corrupt("missing block data"))?;
+        let mut words = Words::new(directory);
+        let mut position = Position::default();
+        while !words.is_empty() {
+            let (lower, upper, edges, offset) = (words.next()?, words.next()?, words.next()? as usize, words.next()? as usize);
+            let start = words.next()? as usize;
+            let end = start.checked_add(words.next()? as usize).ok_or_else(|| corrupt("block data overflows"))?;
+            if end > data.len() || offset != keys.len() || offset != blocks.len() * BLOCK_EDGES || edges > BLOCK_EDGES {
+                return Err(corrupt("block out of range"));
+            }
+            match file.codec {
+                Codec::EscapedDeltas => keys.decode(edges, &data[start .. end])?,
+                Codec::Plain => keys.decode(edges, &plain_to_deltas(blocks.last().map(|block: &Block| block.upper), edges, &data[start .. end])?)?,
+            }
+            // deltas are positive after the first key and sum without overflow, so the keys
+            // increase strictly unless they wrap, which would leave `upper` below `lower`.
+            let first = keys.cursor_at(position).next();
+            blocks.push(Block { lower, upper, edges, offset, position });
+            position = keys.seek(position, edges);
+            if edges == 0 || first != Some(lower) || position.previous() != upper || upper < lower {
+                return Err(corrupt("block keys do not match the directory"));
+            }
+            if blocks.len() > 1 && lower <= blocks[blocks.len() - 2].upper {
+                return Err(corrupt("blocks out of order"));
+            }
+            vertices = keys.cursor_at(blocks[blocks.len() - 1].position).take(edges).map(|key| {
+                let (src, dst) = match tangle {
+                    Some(ref tangle) => tangle.detangle(key),
+                    None => detangler.detangle(key),
+                };
+                ::std::cmp::max(src, dst) as u64 + 1
+            }).fold(vertices, ::std::cmp::max);
+        }
+    }
+    if keys.len() as u64 != file.edges {
+        return Err(corrupt("edge count does not match the header"));
+    }
+    if vertices != file.vertices {
+        return Err(corrupt("vertex count does not match the edges"));
+    }
+    trace_record!(span, edges, keys.len());
+
+    let mut tiles = Vec::new();
+    let mut summaries = Vec::new();
+    if let Some(directory) = file.section(section::TILES)? {
+        let mut words = Words::new(directory);
+        while !words.is_empty() {
+            let (lower, upper, edges, offset) = (words.next()?, words.next()?, words.next()? as usize, words.next()? as usize);
+            if offset.checked_add(edges).is_none_or(|end| end > keys.len()) || edges == 0 {
+                return Err(corrupt("tile out of range"));
+            }
+            let block = &blocks[offset / BLOCK_EDGES];
+            let position = keys.seek(block.position, offset - block.offset);
+            tiles.push(Tile { lower, upper, edges, offset, position });
+            summaries.push(TileSummary {
+                edges: words.next()? as usize,
+                sources: words.next()? as usize,
+                destinations: words.next()? as usize,
+                density: f64::from_bits(words.next()?),
+            });
+        }
+    }
+    if tiles.is_empty() != (file.log_tile.is_none() || keys.is_empty()) {
+        return Err(corrupt("tile directory does not match the header"));
+    }
+
+    let mut weights = None;
+    let mut columns = Vec::new();
+    for (name, values) in file.columns()? {
+        if values.len() != keys.len() {
+            return Err(corrupt("column length does not match the edge count"));
+        }
+        match values {
+            Column::F64(values) if name == WEIGHT_COLUMN => weights = Some(values),
+            _ if name == WEIGHT_COLUMN => return Err(corrupt("weight column is not `f64`")),
+            values => columns.push((name, values)),
+        }
+    }
+    if weights.is_some() != file.weighted {
+        return Err(corrupt("weight column does not match the header"));
+    }
+
+    let layout = GraphLayout {
+        keys,
+        vertices: file.vertices,
+        log_tile: file.log_tile,
+        tiles,
+        summaries,
+        blocks,
+        weights,
+        columns,
+    };
+    Ok(match tangle {
+        Some(tangle) => {
+            let hilbert = Hilbert::shared();
+            layout.rekeyed(layout.keys.decompress().map(|key| hilbert.entangle(tangle.detangle(key))).collect())
+        },
+        None => layout,
+    })
+}
+
+/// Re-encodes the `count` plain keys in `data` as escaped byte deltas, for `Compressed::decode`,
+/// the first as a delta from `previous` if there is one.
+fn plain_to_deltas(previous: Option<u64>, count: usize, data: &[u8]) -> Result<Vec<u8>> {
+    if count.checked_mul(8) != Some(data.len()) {
+        return Err(corrupt("block length does not match its edge count"));
+    }
+    let mut compressor = Compressor::after(previous.unwrap_or(0));
+    let from = compressor.position();
+    let mut last = previous;
+    for bytes in data.chunks_exact(8) {
+        let key = u64::from_le_bytes(to_array(bytes));
+        if last.is_some_and(|last| last >= key) {
+            return Err(corrupt("keys are not strictly increasing"));
+        }
+        compressor.push(key);
+        last = Some(key);
+    }
+    let to = compressor.position();
+    let mut bytes = Vec::new();
+    compressor.done().encode(from, to, &mut bytes);
+    Ok(bytes)
+}
+
+fn header(vertices: u64, edges: usize, log_tile: Option<u32>, weighted: bool, curve: Curve, codec: Codec) -> [u8; HEADER_BYTES] {
+    let mut header = Vec::with_capacity(HEADER_BYTES);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header.extend_from_slice(&codec.code().to_le_bytes());
+    put(&mut header, vertices);
+    put(&mut header, edges as u64);
+    put(&mut header, BLOCK_EDGES as u64);
+    header.extend_from_slice(&log_tile.unwrap_or(UNTILED).to_le_bytes());
+    let flags = if weighted { FLAG_WEIGHTED } else { 0 };
+    header.extend_from_slice(&flags.to_le_bytes());
+    header.extend_from_slice(&curve.code().to_le_bytes());
+    header.resize(HEADER_BYTES, 0);
+    let mut result = [0u8; HEADER_BYTES];
+    result.copy_from_slice(&header);
+    result
+}
+
+/// The sections describing `layout`, as `(kind, bytes)` pairs.
+fn sections(layout: &GraphLayout) -> Vec<(u64, Vec<u8>)> {
+    let mut directory = Vec::new();
+    let mut data = Vec::new();
+    let blocks = layout.blocks();
+    for (index, block) in blocks.iter().enumerate() {
+        let end = blocks.get(index + 1).map(|next| next.position).unwrap_or_else(|| layout.keys.seek(block.position, block.edges));
+        let start = data.len();
+        layout.keys.encode(block.position, end, &mut data);
+        for &value in &[block.lower, block.upper, block.edges as u64, block.offset as u64, start as u64, (data.len() - start) as u64] {
+            put(&mut directory, value);
+        }
+    }
+    let mut sections = vec![(section::BLOCKS, directory), (section::BLOCK_DATA, data)];
+
+    if layout.log_tile().is_some() {
+        let mut tiles = Vec::new();
+        for (tile, summary) in layout.tiles().iter().zip(layout.tile_summaries()) {
+            for &value in &[tile.lower, tile.upper, tile.edges as u64, tile.offset as u64] {
+                put(&mut tiles, value);
+            }
+            for &value in &[summary.edges as u64, summary.sources as u64, summary.destinations as u64, summary.density.to_bits()] {
+                put(&mut tiles, value);
+            }
+        }
+        sections.push((section::TILES, tiles));
+    }
+
+    if let Some(weights) = layout.weights() {
+        sections.push((section::COLUMN, column(WEIGHT_COLUMN, &Column::F64(weights.to_vec()))));
+    }
+    for (name, values) in layout.columns() {
+        sections.push((section::COLUMN, column(name, values)));
+    }
+    sections
+}
+
+/// Encodes a named column section.
+fn column(name: &str, values: &Column) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(32 + name.len() + 8 * values.len());
+    put(&mut bytes, name.len() as u64);
+    bytes.extend_from_slice(name.as_bytes());
+    put(&mut bytes, values.code());
+    put(&mut bytes, values.len() as u64);
+    values.encode(&mut bytes);
+    bytes
+}
+
+/// The validated header and section table of a layout file.
+pub(crate) struct LayoutFile<'a> {
+    bytes: &'a [u8],
+    pub vertices: u64,
+    pub edges: u64,
+    pub log_tile: Option<u32>,
+    pub weighted: bool,
+    pub curve: Curve,
+    pub codec: Codec,
+    /// `(kind, offset, length, checksum)` for each section.
+    table: Vec<[u64; 4]>,
+}
+
+/// The name, type code, value count, and value bytes of a column section.
+type RawColumn<'a> = (String, u64, usize, &'a [u8]);
+
+impl<'a> LayoutFile<'a> {
+    /// Validates the header, trailer, and footer of a complete layout file.
+    pub fn parse(bytes: &'a [u8]) -> Result<LayoutFile<'a>> {
+        if bytes.len() < HEADER_BYTES + 24 || &bytes[.. 8] != MAGIC || &bytes[bytes.len() - 8 ..] != TRAILER_MAGIC {
+            return Err(corrupt("not a layout file"));
+        }
+        let header = &bytes[.. HEADER_BYTES];
+        let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        let codec = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+        if version != VERSION {
+            return Err(Error::Unsupported { what: "layout file version", found: version as u64 });
+        }
+        let codec = Codec::from_code(codec)?;
+        let curve = Curve::from_code(u32::from_le_bytes([header[48], header[49], header[50], header[51]]))?;
+        let mut words = Words::new(&header[16 .. 40]);
+        let (vertices, edges, block_edges) = (words.next()?, words.next()?, words.next()?);
+        if block_edges != BLOCK_EDGES as u64 {
+            return Err(Error::Unsupported { what: "block size", found: block_edges });
+        }
+        let log_tile = u32::from_le_bytes([header[40], header[41], header[42], header[43]]);
+        let flags = u32::from_le_bytes([header[44], header[45], header[46], header[47]]);
+        if log_tile != UNTILED && log_tile >= 32 {
+            return Err(corrupt("tiles must be smaller than the full 2^32 × 2^32 space"));
+        }
+        if log_tile != UNTILED && curve == Curve::RowMajor {
+            return Err(corrupt("row-major layouts cannot be tiled"));
+        }
+        if vertices > 1 << 32 {
+            return Err(corrupt("vertex count out of range"));
+        }
+
+        let mut trailer = Words::new(&bytes[bytes.len() - 24 .. bytes.len() - 8]);
+        let (footer_offset, footer_length) = (trailer.next()? as usize, trailer.next()? as usize);
+        if footer_offset.checked_add(footer_length) != Some(bytes.len() - 24) || footer_length < 16 {
+            return Err(corrupt("footer out of range"));
+        }
+        let footer = &bytes[footer_offset .. footer_offset + footer_length];
+        let (table_bytes, stored) = footer.split_at(footer_length - 8);
+        let mut summed = header.to_vec();
+        summed.extend_from_slice(table_bytes);
+        if Words::new(stored).next()? != checksum(&summed) {
+            return Err(corrupt("footer checksum mismatch"));
+        }
+
+        let mut words = Words::new(table_bytes);
+        let count = words.next()?;
+        let mut table = Vec::new();
+        for _ in 0 .. count {
+            let entry = [words.next()?, words.next()?, words.next()?, words.next()?];
+            if entry[1].checked_add(entry[2]).is_none_or(|end| end > footer_offset as u64) {
+                return Err(corrupt("section out of range"));
+            }
+            table.push(entry);
+        }
+
+        Ok(LayoutFile {
+            bytes,
+            vertices,
+            edges,
+            log_tile: if log_tile == UNTILED { None } else { Some(log_tile) },
+            weighted: flags & FLAG_WEIGHTED != 0,
+            curve,
+            codec,
+            table,
+        })
+    }
+
+    /// The contents of the first section of `kind`, after verifying its checksum.
+    pub fn section(&self, kind: u64) -> Result<Option<&'a [u8]>> {
+        match self.table.iter().find(|entry| entry[0] == kind) {
+            Some(entry) => self.contents(entry, true).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The contents of the first section of `kind`, without reading it to verify its checksum.
+    #[cfg(feature = "mmap")]
+    pub fn section_unverified(&self, kind: u64) -> Option<&'a [u8]> {
+        self.table.iter().find(|entry| entry[0] == kind).map(|entry| self.section_bytes(entry))
+    }
+
+    /// Verifies the checksum of every section.
+    #[cfg(feature = "mmap")]
+    pub fn verify(&self) -> Result<()> {
+        for entry in &self.table {
+            self.contents(entry, true)?;
+        }
+        Ok(())
+    }
+
+    /// The named columns in the file, skipping those of types this crate does not know.
+    pub fn columns(&self) -> Result<Vec<(String, Column)>> {
+        let mut columns = Vec::new();
+        for (name, code, count, values) in self.raw_columns(true)? {
+            if let Some(column) = Column::decode(code, count, values)? {
+                columns.push((name, column));
+            }
+        }
+        Ok(columns)
+    }
+
+    /// The named `f64` columns in the file, as their little-endian value bytes.
+    #[cfg(feature = "mmap")]
+    pub fn column_bytes(&self, verify: bool) -> Result<Vec<(String, &'a [u8])>> {
+        let mut columns = Vec::new();
+        for (name, code, count, values) in self.raw_columns(verify)? {
+            if code != COLUMN_F64 {
+                continue;
+            }
+            if Some(values.len()) != count.checked_mul(8) {
+                return Err(corrupt("column length does not match its value count"));
+            }
+            columns.push((name, values));
+        }
+        Ok(columns)
+    }
+
+    /// The name, type code, value count, and value bytes of each column section.
+    fn raw_columns(&self, verify: bool) -> Result<Vec<RawColumn<'a>>> {
+        let mut columns = Vec::new();
+        for entry in self.table.iter().filter(|entry| entry[0] == section::COLUMN) {
+            let contents = self.contents(entry, verify)?;
+            let mut words = Words::new(contents);
+            let length = words.next()? as usize;
+            let name = words.take(length)?;
+            let name = String::from_utf8(name.to_vec()).map_err(|_| corrupt("column name is not UTF-8"))?;
+            let code = words.next()?;
+            let count = words.next()? as usize;
+            columns.push((name, code, count, words.bytes));
+        }
+        Ok(columns)
+    }
+
+    fn section_bytes(&self, entry: &[u64; 4]) -> &'a [u8] {
+        &self.bytes[entry[1] as usize .. (entry[1] + entry[2]) as usize]
+    }
+
+    fn contents(&self, entry: &[u64; 4], verify: bool) -> Result<&'a [u8]> {
+        let contents = self.section_bytes(entry);
+        if verify && checksum(contents) != entry[3] {
+            return Err(corrupt("section checksum mismatch"));
+        }
+        Ok(contents)
+    }
+}
+
+/// Reads little-endian `u64` values from a byte slice, failing on truncation.
+pub(crate) struct Words<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Words<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Words<'a> { Words { bytes } }
+    pub(crate) fn is_empty(&self) -> bool { self.bytes.is_empty() }
+    pub(crate) fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < count {
+            return Err(corrupt("truncated section"));
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+    pub(crate) fn next(&mut self) -> Result<u64> {
+        self.take(8).map(|bytes| u64::from_le_bytes(to_array(bytes)))
+    }
+}
+
+fn to_array(bytes: &[u8]) -> [u8; 8] {
+    let mut array = [0u8; 8];
+    array.copy_from_slice(bytes);
+    array
+}
+
+fn put(bytes: &mut Vec<u8>, value: u64) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+/// The 64-bit FNV-1a hash of `bytes`.
+pub fn checksum(bytes: &[u8]) -> u64 {
+    checksum_extend(0xcbf29ce484222325u64, bytes)
+}
+
+/// Continues an FNV-1a hash over further bytes.
+fn checksum_extend(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub(crate) fn corrupt(message: &str) -> Error {
+    Error::corrupt("layout file", message)
+}
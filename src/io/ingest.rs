@@ -0,0 +1,179 @@
+//! Text edge lists parsed and entangled in one pass over raw byte buffers.
+//!
+//! Reading an edge list a line at a time, building a tuple per edge, and entangling the tuples
+//! afterwards passes over every edge three times, and the passes, not the disk, bound ingestion. A
+//! `KeyReader` reads its input in large buffers and, for each line, parses both identifiers and
+//! pushes their curve key in the same loop, so that the keys go straight to the sort.
+//!
+//! Identifiers are parsed eight digits at a time where eight bytes remain in the buffer: the bytes
+//! are loaded as one little-endian word, the length of the leading run of digits is found from the
+//! first byte that is not one, and the digits are combined pairwise by three multiplications. The
+//! syntax accepted is that of `io::read_edge_list`, and malformed lines report the same errors.
+
+use std::io::{ErrorKind, Read};
+
+use crate::error::Result;
+use crate::layout::{Hilbert, Tangle};
+
+/// The number of bytes read from the input at a time.
+const BUFFER_BYTES: usize = 1 << 20;
+
+/// Parses a whitespace-separated edge list straight into curve keys.
+pub struct KeyReader<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// The bytes of `buffer` read but not yet parsed, which start a line.
+    start: usize,
+    end: usize,
+    /// The number of lines parsed so far.
+    lines: usize,
+    done: bool,
+}
+
+impl<R: Read> KeyReader<R> {
+    /// Parses edges from any reader, which need not be buffered.
+    pub fn new(reader: R) -> KeyReader<R> {
+        KeyReader { reader, buffer: vec![0; BUFFER_BYTES], start: 0, end: 0, lines: 0, done: false }
+    }
+
+    /// Parses the lines of the next buffer of input, appending the curve key of each edge to
+    /// `keys`, and returns the number of keys appended, zero only at the end of the input.
+    pub fn read_keys(&mut self, keys: &mut Vec<u64>) -> Result<usize> {
+        let before = keys.len();
+        while keys.len() == before && !self.done {
+            self.fill()?;
+            // parse the complete lines, or everything once the input is exhausted.
+            let last = if self.done { self.end } else {
+                match self.buffer[self.start .. self.end].iter().rposition(|&byte| byte == b'\n') {
+                    Some(newline) => self.start + newline + 1,
+                    None => continue,
+                }
+            };
+            self.lines += parse_lines(&self.buffer[self.start .. last], self.lines, keys)?;
+            self.start = last;
+        }
+        Ok(keys.len() - before)
+    }
+
+    /// Appends the curve key of every remaining edge to `keys`, returning their number.
+    pub fn read_all(&mut self, keys: &mut Vec<u64>) -> Result<usize> {
+        let before = keys.len();
+        while self.read_keys(keys)? > 0 { }
+        Ok(keys.len() - before)
+    }
+
+    /// Moves unparsed bytes to the front of the buffer and reads more after them, growing the
+    /// buffer if one line fills it.
+    fn fill(&mut self) -> Result<()> {
+        self.buffer.copy_within(self.start .. self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        if self.end == self.buffer.len() {
+            self.buffer.resize(2 * self.buffer.len(), 0);
+        }
+        loop {
+            match self.reader.read(&mut self.buffer[self.end ..]) {
+                Ok(0) => {
+                    self.done = true;
+                    return Ok(());
+                },
+                Ok(read) => {
+                    self.end += read;
+                    return Ok(());
+                },
+                Err(ref error) if error.kind() == ErrorKind::Interrupted => { },
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+}
+
+/// Parses whole lines of `bytes`, the first of which is line `lines + 1`, appending the curve key
+/// of each edge to `keys`, and returns the number of lines parsed.
+fn parse_lines(bytes: &[u8], lines: usize, keys: &mut Vec<u64>) -> Result<usize> {
+    let hilbert = Hilbert::shared();
+    let mut number = lines;
+    let mut cursor = 0;
+    while cursor < bytes.len() {
+        number += 1;
+        cursor = skip_separators(bytes, cursor);
+        if cursor == bytes.len() {
+            break;
+        }
+        if !matches!(bytes[cursor], b'\n' | b'#' | b'%') {
+            let src = parse_u32(bytes, &mut cursor);
+            cursor = skip_separators(bytes, cursor);
+            let dst = parse_u32(bytes, &mut cursor);
+            match (src, dst) {
+                (Some(src), Some(dst)) => keys.push(hilbert.entangle((src, dst))),
+                _ => return Err(super::invalid(number, "expected two unsigned integers")),
+            }
+        }
+        // skip the rest of the line, and its newline.
+        cursor = match bytes[cursor ..].iter().position(|&byte| byte == b'\n') {
+            Some(newline) => cursor + newline + 1,
+            None => bytes.len(),
+        };
+    }
+    Ok(number - lines)
+}
+
+/// Advances `cursor` past spaces, tabs, commas and carriage returns, stopping at newlines.
+#[inline]
+fn skip_separators(bytes: &[u8], mut cursor: usize) -> usize {
+    while cursor < bytes.len() && matches!(bytes[cursor], b' ' | b'\t' | b',' | b'\r') {
+        cursor += 1;
+    }
+    cursor
+}
+
+/// Parses a decimal `u32` at `cursor`, advancing past its digits, which must be followed by a
+/// separator, a newline, or the end of `bytes`.
+#[inline]
+fn parse_u32(bytes: &[u8], cursor: &mut usize) -> Option<u32> {
+    let start = *cursor;
+    let mut value = 0u64;
+    if let Some(word) = bytes.get(start .. start + 8) {
+        let (digits, parsed) = parse_eight(u64::from_le_bytes(word.try_into().unwrap()));
+        value = parsed;
+        *cursor += digits;
+        if digits < 8 {
+            return finish(bytes, start, *cursor, value);
+        }
+    }
+    while *cursor < bytes.len() && bytes[*cursor].is_ascii_digit() {
+        value = 10 * value + (bytes[*cursor] - b'0') as u64;
+        if value > u32::MAX as u64 {
+            return None;
+        }
+        *cursor += 1;
+    }
+    finish(bytes, start, *cursor, value)
+}
+
+/// Checks that digits were found from `start` to `cursor`, and are followed by a separator.
+#[inline]
+fn finish(bytes: &[u8], start: usize, cursor: usize, value: u64) -> Option<u32> {
+    let separated = cursor == bytes.len() || matches!(bytes[cursor], b' ' | b'\t' | b',' | b'\r' | b'\n');
+    if cursor > start && separated { Some(value as u32) } else { None }
+}
+
+/// The number of leading digits among the eight bytes of `word`, first byte lowest, and their value.
+#[inline]
+fn parse_eight(word: u64) -> (usize, u64) {
+    const ONES: u64 = 0x0101_0101_0101_0101;
+    // each byte less '0', correct up to and including the first byte that is not a digit, as
+    // borrows and carries only reach later bytes.
+    let values = word.wrapping_sub(0x30 * ONES);
+    let above = values | values.wrapping_add(0x76 * ONES);
+    let digits = ((above & (0x80 * ONES)).trailing_zeros() / 8) as usize;
+    if digits == 0 {
+        return (0, 0);
+    }
+    // keep the digits, most significant first, as the last bytes of the word behind zeros.
+    let values = values << (8 * (8 - digits));
+    let values = ((values & (0x0F * ONES)).wrapping_mul(10 << 8 | 1)) >> 8;
+    let values = ((values & 0x00FF_00FF_00FF_00FF).wrapping_mul(100 << 16 | 1)) >> 16;
+    let values = ((values & 0x0000_FFFF_0000_FFFF).wrapping_mul(10_000 << 32 | 1)) >> 32;
+    (digits, values)
+}
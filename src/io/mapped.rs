@@ -0,0 +1,297 @@
+//! Read-only layouts served directly from a memory-mapped layout file.
+//!
+//! Opening a `MappedLayout` reads only the header, footer, and the block and tile directories.
+//! Block data and weights stay in the mapping, and blocks are decoded as iteration reaches them, so
+//! start-up time and memory do not grow with the number of edges.
+
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+#[cfg(feature = "counters")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use memmap2::Mmap;
+
+use crate::compression::EncodedDecompressor;
+use crate::counters::Counters;
+use crate::error::{Error, Result};
+use crate::graph::{Block, Edges, GraphLayout, Tile, TileSummary, BLOCK_EDGES};
+use crate::io::format::{corrupt, parse_layout, section, Codec, Curve, LayoutFile, Words, WEIGHT_COLUMN};
+use crate::layout::{BytewiseCached, Hilbert, Tangle};
+
+/// A layout file mapped into memory.
+///
+/// Block data is not checksummed when opened, as that would read the whole file. Iteration stops
+/// early at a block whose encoding is malformed, leaving the error in `MappedKeys::error`; call
+/// `verify` first to check the entire file.
+pub struct MappedLayout {
+    map: Mmap,
+    vertices: u64,
+    edges: usize,
+    log_tile: Option<u32>,
+    blocks: Vec<Block>,
+    /// The range of the mapping holding each block's encoded keys.
+    data: Vec<Range<usize>>,
+    tiles: Vec<Tile>,
+    summaries: Vec<TileSummary>,
+    weights: Option<Range<usize>>,
+    /// Blocks decoded and their bytes, counted by every reader of the layout.
+    #[cfg(feature = "counters")]
+    decoded: (AtomicU64, AtomicU64),
+}
+
+impl MappedLayout {
+    /// Maps the layout file at `path`, reading its directories but none of its edges.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MappedLayout> {
+        let file = File::open(path)?;
+        let map = unsafe { Mmap::map(&file)? };
+        let (vertices, edges, log_tile, blocks, data, tiles, summaries, weights) = {
+            let file = LayoutFile::parse(&map[..])?;
+            // iteration decodes blocks in place, so only Hilbert keys in escaped deltas are served.
+            if file.curve != Curve::Hilbert {
+                return Err(Error::Unsupported { what: "mapped layout curve", found: file.curve.code() as u64 });
+            }
+            if file.codec != Codec::EscapedDeltas {
+                return Err(Error::Unsupported { what: "mapped layout codec", found: file.codec.code() as u64 });
+            }
+            let base = map.as_ptr() as usize;
+            let start = |bytes: &[u8]| bytes.as_ptr() as usize - base;
+
+            let mut blocks = Vec::new();
+            let mut data = Vec::new();
+            if let Some(directory) = file.section(section::BLOCKS)? {
+                let section = file.section_unverified(section::BLOCK_DATA).ok_or_else(|| corrupt("missing block data"))?;
+                let mut words = Words::new(directory);
+                while !words.is_empty() {
+                    let (lower, upper, edges, offset) = (words.next()?, words.next()?, words.next()? as usize, words.next()? as usize);
+                    let first = words.next()? as usize;
+                    let last = first.checked_add(words.next()? as usize).ok_or_else(|| corrupt("block data overflows"))?;
+                    if last > section.len() || offset != blocks.len() * BLOCK_EDGES || edges == 0 || edges > BLOCK_EDGES {
+                        return Err(corrupt("block out of range"));
+                    }
+                    if upper < lower || blocks.last().is_some_and(|block: &Block| lower <= block.upper) {
+                        return Err(corrupt("blocks out of order"));
+                    }
+                    // edges are found in their block by division, so only the last may be short.
+                    if blocks.last().is_some_and(|block: &Block| block.edges != BLOCK_EDGES) {
+                        return Err(corrupt("block out of range"));
+                    }
+                    blocks.push(Block { lower, upper, edges, offset, position: Default::default() });
+                    data.push(start(section) + first .. start(section) + last);
+                }
+            }
+            let edges = blocks.last().map(|block| block.offset + block.edges).unwrap_or(0);
+            if edges as u64 != file.edges {
+                return Err(corrupt("edge count does not match the header"));
+            }
+            // the largest endpoint is only known once every block is decoded, as `verify` does, but
+            // the first and last keys of each block bound it from below.
+            let hilbert = Hilbert::shared();
+            let bound = blocks.iter().flat_map(|block| [block.lower, block.upper]).map(|key| {
+                let (src, dst) = hilbert.detangle(key);
+                ::std::cmp::max(src, dst) as u64 + 1
+            }).max().unwrap_or(0);
+            if bound > file.vertices || (edges == 0 && file.vertices > 0) {
+                return Err(corrupt("vertex count does not match the edges"));
+            }
+
+            let mut tiles = Vec::new();
+            let mut summaries = Vec::new();
+            if let Some(directory) = file.section(section::TILES)? {
+                let mut words = Words::new(directory);
+                while !words.is_empty() {
+                    let (lower, upper, count, offset) = (words.next()?, words.next()?, words.next()? as usize, words.next()? as usize);
+                    if offset.checked_add(count).is_none_or(|end| end > edges) || count == 0 {
+                        return Err(corrupt("tile out of range"));
+                    }
+                    tiles.push(Tile { lower, upper, edges: count, offset, position: Default::default() });
+                    summaries.push(TileSummary {
+                        edges: words.next()? as usize,
+                        sources: words.next()? as usize,
+                        destinations: words.next()? as usize,
+                        density: f64::from_bits(words.next()?),
+                    });
+                }
+            }
+
+            let mut weights = None;
+            for (name, values) in file.column_bytes(false)? {
+                if name == WEIGHT_COLUMN {
+                    if values.len() != 8 * edges {
+                        return Err(corrupt("weight column length does not match the edge count"));
+                    }
+                    weights = Some(start(values) .. start(values) + values.len());
+                }
+            }
+            if weights.is_some() != file.weighted {
+                return Err(corrupt("weight column does not match the header"));
+            }
+
+            (file.vertices, edges, file.log_tile, blocks, data, tiles, summaries, weights)
+        };
+
+        Ok(MappedLayout {
+            map,
+            vertices,
+            edges,
+            log_tile,
+            blocks,
+            data,
+            tiles,
+            summaries,
+            weights,
+            #[cfg(feature = "counters")]
+            decoded: Default::default(),
+        })
+    }
+
+    /// Checks every section checksum and decodes every block against the block directory, and the
+    /// vertex count against the edges.
+    pub fn verify(&self) -> Result<()> {
+        LayoutFile::parse(&self.map[..])?.verify()?;
+        let mut detangler = BytewiseCached::new();
+        let mut vertices = 0;
+        for (index, block) in self.blocks.iter().enumerate() {
+            let (mut first, mut last) = (None, None);
+            for key in self.decode(index)? {
+                first = first.or(Some(key));
+                last = Some(key);
+                let (src, dst) = detangler.detangle(key);
+                vertices = ::std::cmp::max(vertices, ::std::cmp::max(src, dst) as u64 + 1);
+            }
+            if first != Some(block.lower) || last != Some(block.upper) {
+                return Err(corrupt("block keys do not match the directory"));
+            }
+        }
+        if vertices != self.vertices {
+            return Err(corrupt("vertex count does not match the edges"));
+        }
+        Ok(())
+    }
+
+    /// Reads the whole mapped file into an in-memory `GraphLayout`.
+    pub fn load(&self) -> Result<GraphLayout> {
+        parse_layout(&self.map[..])
+    }
+
+    /// The number of edges in the layout.
+    pub fn len(&self) -> usize { self.edges }
+    /// Indicates that the layout contains no edges.
+    pub fn is_empty(&self) -> bool { self.edges == 0 }
+    /// The number of vertices, one more than the largest endpoint of any edge.
+    pub fn vertices(&self) -> u64 { self.vertices }
+    /// The base-two logarithm of the tile side length, if the layout is tiled.
+    pub fn log_tile(&self) -> Option<u32> { self.log_tile }
+    /// The directory of non-empty tiles, in curve order. Empty if the layout is not tiled.
+    pub fn tiles(&self) -> &[Tile] { &self.tiles[..] }
+    /// Summaries of the non-empty tiles, aligned with `tiles()`.
+    pub fn tile_summaries(&self) -> &[TileSummary] { &self.summaries[..] }
+    /// The directory of fixed-size blocks, in curve order.
+    pub fn blocks(&self) -> &[Block] { &self.blocks[..] }
+    /// Indicates that the layout has edge weights.
+    pub fn is_weighted(&self) -> bool { self.weights.is_some() }
+
+    /// The weight of the edge at `index` in curve order, if the layout is weighted.
+    pub fn weight(&self, index: usize) -> Option<f64> {
+        assert!(index < self.edges, "edge index out of range");
+        self.weights.as_ref().map(|range| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&self.map[range.start + 8 * index ..][.. 8]);
+            f64::from_le_bytes(bytes)
+        })
+    }
+
+    /// Iterates over the curve keys of all edges, in order.
+    pub fn keys(&self) -> MappedKeys<'_> { self.range_keys(0, self.edges) }
+    /// Iterates over all edges, in curve order.
+    pub fn edges(&self) -> Edges<MappedKeys<'_>> { Edges::new(self.keys()) }
+    /// Iterates over the curve keys of the edges in `tile`.
+    pub fn tile_keys(&self, tile: &Tile) -> MappedKeys<'_> { self.range_keys(tile.offset, tile.edges) }
+    /// Iterates over the curve keys of the edges in `block`.
+    pub fn block_keys(&self, block: &Block) -> MappedKeys<'_> { self.range_keys(block.offset, block.edges) }
+    /// Iterates over the edges in `tile`, in curve order.
+    pub fn tile_edges(&self, tile: &Tile) -> Edges<MappedKeys<'_>> { Edges::new(self.tile_keys(tile)) }
+    /// Iterates over the edges in `block`, in curve order.
+    pub fn block_edges(&self, block: &Block) -> Edges<MappedKeys<'_>> { Edges::new(self.block_keys(block)) }
+
+    /// Iterates over `count` keys starting from the edge at `offset`.
+    fn range_keys(&self, offset: usize, count: usize) -> MappedKeys<'_> {
+        assert!(offset + count <= self.edges, "edge range out of bounds");
+        let mut keys = MappedKeys { layout: self, block: offset / BLOCK_EDGES, current: None, remaining: count, error: None };
+        if count > 0 {
+            if let Some(mut current) = keys.open(keys.block) {
+                for _ in 0 .. offset % BLOCK_EDGES { current.next(); }
+                keys.current = Some(current);
+            }
+        }
+        keys
+    }
+
+    /// The blocks decoded, and their bytes read, by all readers of the layout so far, with the
+    /// `counters` feature.
+    pub fn counters(&self) -> Counters {
+        count! {
+            let (blocks, bytes) = &self.decoded;
+            let counters = Counters {
+                blocks_decoded: blocks.load(Ordering::Relaxed),
+                bytes_read: bytes.load(Ordering::Relaxed),
+                ..Counters::default()
+            };
+        }
+        counted!(counters)
+    }
+
+    /// Decodes the keys of block `index`.
+    fn decode(&self, index: usize) -> Result<EncodedDecompressor<'_>> {
+        count! {
+            self.decoded.0.fetch_add(1, Ordering::Relaxed);
+            self.decoded.1.fetch_add(self.data[index].len() as u64, Ordering::Relaxed);
+        }
+        let previous = if index == 0 { 0 } else { self.blocks[index - 1].upper };
+        EncodedDecompressor::new(previous, self.blocks[index].edges, &self.map[self.data[index].clone()])
+    }
+}
+
+/// Iterates over a run of curve keys in a `MappedLayout`, decoding blocks as they are reached.
+pub struct MappedKeys<'a> {
+    layout: &'a MappedLayout,
+    block: usize,
+    current: Option<EncodedDecompressor<'a>>,
+    remaining: usize,
+    error: Option<Error>,
+}
+
+impl<'a> MappedKeys<'a> {
+    /// The error that ended iteration early, if a block's encoding was malformed.
+    pub fn error(&self) -> Option<&Error> { self.error.as_ref() }
+
+    fn open(&mut self, block: usize) -> Option<EncodedDecompressor<'a>> {
+        match self.layout.decode(block) {
+            Ok(keys) => Some(keys),
+            Err(error) => {
+                self.remaining = 0;
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for MappedKeys<'a> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        if self.remaining == 0 { return None; }
+        loop {
+            if let Some(key) = self.current.as_mut().and_then(|current| current.next()) {
+                self.remaining -= 1;
+                return Some(key);
+            }
+            self.block += 1;
+            self.current = Some(self.open(self.block)?);
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // a malformed block ends iteration early, unless the file has been verified.
+        (0, Some(self.remaining))
+    }
+}
@@ -0,0 +1,665 @@
+//! Reading and writing edges in common file formats.
+//!
+//! The text loaders parse integers by hand rather than through `str::parse`, as parsing is often
+//! the bottleneck when ingesting large edge lists. Malformed input is reported as `Error::Corrupt`,
+//! naming the offending line, and failed reads as `Error::Io`.
+//!
+//! Inputs ending in `.gz` or `.zst` are decompressed on the fly when the `gzip` or `zstd` features
+//! are enabled, respectively.
+//!
+//! Binary edge files are flat sequences of little-endian `u32` pairs. With the `mmap` feature they
+//! can be memory-mapped and entangled straight from the mapping.
+//!
+//! With the `direct` feature, `direct::open` reads any file around the page cache, for scans too
+//! large to be worth caching; its reader serves the edge readers here as any `BufRead` does.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::graph::GraphLayout;
+use crate::progress::{Phase, Progress, Reporter};
+use crate::wide::WideLayout;
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod csr;
+#[cfg(feature = "direct")]
+pub mod direct;
+pub mod format;
+pub mod ingest;
+#[cfg(feature = "mmap")]
+pub mod mapped;
+pub mod npy;
+pub mod packed;
+pub mod shards;
+pub mod webgraph;
+
+pub use self::csr::{format_gap, format_ligra, write_gap, write_ligra};
+pub use self::ingest::KeyReader;
+pub use self::npy::write_npy;
+pub use self::packed::{pack_tiles, write_packed};
+pub use self::shards::{read_manifest, write_shards};
+pub use self::webgraph::{load_webgraph, read_webgraph};
+
+/// Opens a whitespace-separated "src dst" edge list, as distributed by SNAP.
+///
+/// Lines starting with `#` or `%` are comments, blank lines are ignored, and any fields after the
+/// first two on a line are skipped.
+pub fn read_edge_list<P: AsRef<Path>>(path: P) -> Result<EdgeList<Box<dyn BufRead>>> {
+    Ok(EdgeList::new(open(path.as_ref())?))
+}
+
+/// Opens a file for buffered reading, decompressing it according to its extension.
+pub fn open(path: &Path) -> Result<Box<dyn BufRead>> {
+    let file = File::open(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("gz") => open_gzip(file),
+        Some("zst") => open_zstd(file),
+        _ => Ok(Box::new(BufReader::new(file))),
+    }
+}
+
+/// Indicates that `open` decompresses the file at `path`, according to its extension.
+#[cfg(feature = "mmap")]
+fn is_compressed(path: &Path) -> bool {
+    matches!(path.extension().and_then(|extension| extension.to_str()), Some("gz") | Some("zst"))
+}
+
+#[cfg(feature = "gzip")]
+fn open_gzip(file: File) -> Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(::flate2::read::MultiGzDecoder::new(file))))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn open_gzip(_file: File) -> Result<Box<dyn BufRead>> {
+    Err(::std::io::Error::new(ErrorKind::Unsupported, "reading .gz files requires the `gzip` feature").into())
+}
+
+#[cfg(feature = "zstd")]
+fn open_zstd(file: File) -> Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(::zstd::stream::read::Decoder::new(file)?)))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn open_zstd(_file: File) -> Result<Box<dyn BufRead>> {
+    Err(::std::io::Error::new(ErrorKind::Unsupported, "reading .zst files requires the `zstd` feature").into())
+}
+
+/// Reads a whitespace-separated edge list directly into a `GraphLayout`.
+///
+/// Edges are parsed and entangled in one pass by a `KeyReader`, and only their curve keys held.
+pub fn load_edge_list<P: AsRef<Path>>(path: P) -> Result<GraphLayout> {
+    let span = trace_span!("ingest", edges);
+    let mut keys = Vec::new();
+    KeyReader::new(open(path.as_ref())?).read_all(&mut keys)?;
+    trace_record!(span, edges, keys.len());
+    drop(span);
+    Ok(GraphLayout::from_keys(keys, None))
+}
+
+/// Iterates over the edges of a whitespace-separated edge list.
+pub struct EdgeList<R: BufRead> {
+    reader: R,
+    line: Vec<u8>,
+    number: usize,
+    /// The edges parsed and bytes read so far, for progress reports.
+    edges: u64,
+    bytes: u64,
+    progress: Reporter,
+}
+
+impl<R: BufRead> EdgeList<R> {
+    /// Parses edges from any buffered reader.
+    pub fn new(reader: R) -> EdgeList<R> {
+        EdgeList { reader, line: Vec::new(), number: 0, edges: 0, bytes: 0, progress: Reporter::none() }
+    }
+    /// Calls `callback` about every `interval` edges read, and at the end of the input.
+    pub fn progress<F: FnMut(Progress) + Send + 'static>(mut self, interval: u64, callback: F) -> EdgeList<R> {
+        self.progress = Reporter::new(interval, callback);
+        self
+    }
+}
+
+impl<R: BufRead> EdgeList<R> {
+    /// Parses the next pair of identifiers no greater than `bound`.
+    fn next_pair(&mut self, bound: u64) -> Option<Result<(u64, u64)>> {
+        loop {
+            self.line.clear();
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => {
+                    // reports the end once, even if called again.
+                    if self.number != usize::MAX {
+                        self.number = usize::MAX;
+                        self.progress.report(Phase::Done, self.edges, self.bytes);
+                    }
+                    return None;
+                },
+                Ok(_) => { },
+                Err(error) => return Some(Err(error.into())),
+            }
+            self.number += 1;
+            self.bytes += self.line.len() as u64;
+
+            let mut cursor = skip_whitespace(&self.line, 0);
+            if cursor == self.line.len() || self.line[cursor] == b'#' || self.line[cursor] == b'%' {
+                continue;
+            }
+
+            let src = parse_bounded(&self.line, &mut cursor, bound);
+            cursor = skip_whitespace(&self.line, cursor);
+            let dst = parse_bounded(&self.line, &mut cursor, bound);
+            self.edges += 1;
+            self.progress.update(Phase::Reading, self.edges, self.bytes);
+            return Some(match (src, dst) {
+                (Some(src), Some(dst)) => Ok((src, dst)),
+                _ => Err(invalid(self.number, "expected two unsigned integers")),
+            });
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for EdgeList<R> {
+    type Item = Result<(u32, u32)>;
+    fn next(&mut self) -> Option<Result<(u32, u32)>> {
+        self.next_pair(u32::MAX as u64).map(|pair| pair.map(|(src, dst)| (src as u32, dst as u32)))
+    }
+}
+
+/// Opens a whitespace-separated edge list whose identifiers may need all 64 bits.
+pub fn read_wide_edge_list<P: AsRef<Path>>(path: P) -> Result<WideEdgeList<Box<dyn BufRead>>> {
+    Ok(WideEdgeList::new(open(path.as_ref())?))
+}
+
+/// Reads a whitespace-separated edge list with `u64` identifiers directly into a `WideLayout`.
+pub fn load_wide_edge_list<P: AsRef<Path>>(path: P) -> Result<WideLayout> {
+    let mut edges = Vec::new();
+    for edge in read_wide_edge_list(path)? {
+        edges.push(edge?);
+    }
+    Ok(WideLayout::from_edges(edges.into_iter()))
+}
+
+/// Iterates over the edges of a whitespace-separated edge list with `u64` identifiers.
+pub struct WideEdgeList<R: BufRead> {
+    list: EdgeList<R>,
+}
+
+impl<R: BufRead> WideEdgeList<R> {
+    /// Parses edges from any buffered reader.
+    pub fn new(reader: R) -> WideEdgeList<R> {
+        WideEdgeList { list: EdgeList::new(reader) }
+    }
+}
+
+impl<R: BufRead> Iterator for WideEdgeList<R> {
+    type Item = Result<(u64, u64)>;
+    fn next(&mut self) -> Option<Result<(u64, u64)>> {
+        self.list.next_pair(u64::MAX)
+    }
+}
+
+/// Opens a binary file of little-endian `(u32, u32)` pairs.
+pub fn read_binary_edges<P: AsRef<Path>>(path: P) -> Result<BinaryEdges<Box<dyn BufRead>>> {
+    Ok(BinaryEdges::new(open(path.as_ref())?))
+}
+
+/// Reads a binary edge file directly into a `GraphLayout`.
+///
+/// With the `mmap` feature the file is memory-mapped rather than read, and edges are decoded from
+/// the mapping as they are entangled. Compressed files, which cannot be mapped, are read through
+/// `read_binary_edges` in either case.
+pub fn load_binary_edges<P: AsRef<Path>>(path: P) -> Result<GraphLayout> {
+    let _span = trace_span!("ingest");
+    load_binary_edges_inner(path.as_ref())
+}
+
+#[cfg(feature = "mmap")]
+fn load_binary_edges_inner(path: &Path) -> Result<GraphLayout> {
+    if is_compressed(path) {
+        return read_binary_layout(path);
+    }
+    let file = File::open(path)?;
+    let mapped = unsafe { ::memmap2::Mmap::map(&file)? };
+    if mapped.len() % 8 != 0 {
+        return Err(Error::corrupt("binary edge file", "length is not a multiple of eight"));
+    }
+    Ok(GraphLayout::from_edges(mapped.chunks_exact(8).map(decode_edge)))
+}
+
+#[cfg(not(feature = "mmap"))]
+fn load_binary_edges_inner(path: &Path) -> Result<GraphLayout> {
+    read_binary_layout(path)
+}
+
+fn read_binary_layout(path: &Path) -> Result<GraphLayout> {
+    let mut edges = Vec::new();
+    for edge in read_binary_edges(path)? {
+        edges.push(edge?);
+    }
+    Ok(GraphLayout::from_edges(edges.into_iter()))
+}
+
+/// Writes the edges of `layout`, in curve order, as little-endian `(u32, u32)` pairs.
+pub fn write_binary_edges<P: AsRef<Path>>(layout: &GraphLayout, path: P) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for (src, dst) in layout.edges() {
+        writer.write_all(&src.to_le_bytes())?;
+        writer.write_all(&dst.to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Iterates over the edges of a binary edge file.
+pub struct BinaryEdges<R: BufRead> {
+    reader: R,
+    /// The edges decoded so far, or `None` once the end has been reached.
+    edges: Option<u64>,
+    progress: Reporter,
+}
+
+impl<R: BufRead> BinaryEdges<R> {
+    /// Decodes edges from any buffered reader.
+    pub fn new(reader: R) -> BinaryEdges<R> {
+        BinaryEdges { reader, edges: Some(0), progress: Reporter::none() }
+    }
+    /// Calls `callback` about every `interval` edges read, and at the end of the input.
+    pub fn progress<F: FnMut(Progress) + Send + 'static>(mut self, interval: u64, callback: F) -> BinaryEdges<R> {
+        self.progress = Reporter::new(interval, callback);
+        self
+    }
+}
+
+impl<R: BufRead> Iterator for BinaryEdges<R> {
+    type Item = Result<(u32, u32)>;
+    fn next(&mut self) -> Option<Result<(u32, u32)>> {
+        let mut bytes = [0u8; 8];
+        let mut filled = 0;
+        while filled < bytes.len() {
+            match self.reader.read(&mut bytes[filled..]) {
+                Ok(0) if filled == 0 => {
+                    if let Some(edges) = self.edges.take() {
+                        self.progress.report(Phase::Done, edges, 8 * edges);
+                    }
+                    return None;
+                },
+                Ok(0) => return Some(Err(Error::corrupt("binary edge file", "truncated edge"))),
+                Ok(read) => filled += read,
+                Err(ref error) if error.kind() == ErrorKind::Interrupted => { },
+                Err(error) => return Some(Err(error.into())),
+            }
+        }
+        if let Some(edges) = self.edges.as_mut() {
+            *edges += 1;
+            self.progress.update(Phase::Reading, *edges, 8 * *edges);
+        }
+        Some(Ok(decode_edge(&bytes)))
+    }
+}
+
+fn decode_edge(bytes: &[u8]) -> (u32, u32) {
+    (u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+     u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]))
+}
+
+/// Whether a Matrix Market file stores every entry, or only the lower triangle of a symmetric matrix.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Every entry is stored.
+    General,
+    /// Only entries with `row >= col` are stored; each implies its mirror image.
+    Symmetric,
+}
+
+/// Reads a Matrix Market coordinate file into a `GraphLayout`.
+///
+/// Entry `(i, j)` becomes the edge `(i - 1, j - 1)`. Pattern matrices produce unweighted layouts,
+/// while `real` and `integer` matrices produce weighted layouts. Symmetric matrices are expanded to
+/// include both directions of each off-diagonal entry.
+pub fn read_matrix_market<P: AsRef<Path>>(path: P) -> Result<GraphLayout> {
+    parse_matrix_market(open(path.as_ref())?)
+}
+
+/// Parses a Matrix Market coordinate matrix from any buffered reader.
+pub fn parse_matrix_market<R: BufRead>(reader: R) -> Result<GraphLayout> {
+    let entries = MatrixMarket::new(reader)?;
+    Ok(if entries.is_weighted() {
+        GraphLayout::from_weighted_edges(entries.collect::<Result<Vec<_>>>()?.into_iter())
+    }
+    else {
+        GraphLayout::from_edges(entries.map(|entry| entry.map(|(edge, _)| edge)).collect::<Result<Vec<_>>>()?.into_iter())
+    })
+}
+
+/// Opens a Matrix Market coordinate file to read its entries one at a time.
+pub fn read_matrix_market_entries<P: AsRef<Path>>(path: P) -> Result<MatrixMarket<Box<dyn BufRead>>> {
+    MatrixMarket::new(open(path.as_ref())?)
+}
+
+/// Iterates over the entries of a Matrix Market coordinate matrix as weighted edges, as
+/// `read_matrix_market` lays them out, without holding them in memory.
+///
+/// Entries of pattern matrices have weight one, and the mirror image of each off-diagonal entry of
+/// a symmetric matrix follows it. Indices are checked against the size line as entries are read, as
+/// is the number of stored entries once the input ends.
+pub struct MatrixMarket<R: BufRead> {
+    reader: R,
+    line: String,
+    number: usize,
+    weighted: bool,
+    symmetry: Symmetry,
+    /// The number of rows and columns, which bound the one-based indices of entries.
+    rows: u64,
+    cols: u64,
+    /// The number of stored entries the size line announces, not counting mirror images.
+    nnz: u64,
+    /// The number of stored entries read so far.
+    read: u64,
+    /// The mirror image of the entry last returned, if it is still to come.
+    mirror: Option<((u32, u32), f64)>,
+}
+
+impl<R: BufRead> MatrixMarket<R> {
+    /// Parses the header and size line from any buffered reader.
+    pub fn new(mut reader: R) -> Result<MatrixMarket<R>> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let header = line.to_lowercase().split_whitespace().map(|word| word.to_owned()).collect::<Vec<_>>();
+        if header.len() != 5 || header[0] != "%%matrixmarket" || header[1] != "matrix" || header[2] != "coordinate" {
+            return Err(invalid(1, "expected a `%%MatrixMarket matrix coordinate` header"));
+        }
+        let weighted = match &header[3][..] {
+            "pattern" => false,
+            "real" | "integer" => true,
+            _ => return Err(invalid(1, "unsupported field type")),
+        };
+        let symmetry = match &header[4][..] {
+            "general" => Symmetry::General,
+            "symmetric" => Symmetry::Symmetric,
+            _ => return Err(invalid(1, "unsupported symmetry")),
+        };
+
+        let mut entries = MatrixMarket { reader, line, number: 1, weighted, symmetry, rows: 0, cols: 0, nnz: 0, read: 0, mirror: None };
+        if !entries.next_line()? {
+            return Err(invalid(entries.number, "missing size line"));
+        }
+        let number = entries.number;
+        let bytes = entries.line.trim().as_bytes();
+        let mut cursor = 0;
+        let rows = parse_u64(bytes, &mut cursor);
+        cursor = skip_whitespace(bytes, cursor);
+        let cols = parse_u64(bytes, &mut cursor);
+        cursor = skip_whitespace(bytes, cursor);
+        let nnz = parse_u64(bytes, &mut cursor);
+        let (rows, cols) = match (rows, cols) {
+            (Some(rows), Some(cols)) => (rows, cols),
+            _ => return Err(invalid(number, "expected two unsigned integers")),
+        };
+        if rows > 1 << 32 || cols > 1 << 32 {
+            return Err(invalid(number, "more than 2^32 rows or columns"));
+        }
+        entries.rows = rows;
+        entries.cols = cols;
+        entries.nnz = nnz.ok_or_else(|| invalid(number, "expected the entry count"))?;
+        Ok(entries)
+    }
+    /// Indicates that entries carry values, rather than the matrix being a pattern.
+    pub fn is_weighted(&self) -> bool { self.weighted }
+    /// Whether the file stores every entry or only the lower triangle.
+    pub fn symmetry(&self) -> Symmetry { self.symmetry }
+
+    /// Reads the next line that is neither blank nor a comment, returning false at the end.
+    fn next_line(&mut self) -> Result<bool> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 { return Ok(false); }
+            self.number += 1;
+            let trimmed = self.line.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with('%') { return Ok(true); }
+        }
+    }
+
+    fn next_entry(&mut self) -> Result<Option<((u32, u32), f64)>> {
+        if !self.next_line()? {
+            if self.read != self.nnz {
+                return Err(invalid(self.number, &format!("found {} entries, expected {}", self.read, self.nnz)));
+            }
+            return Ok(None);
+        }
+        let number = self.number;
+        if self.read == self.nnz {
+            return Err(invalid(number, &format!("more than the expected {} entries", self.nnz)));
+        }
+        self.read += 1;
+        let trimmed = self.line.trim();
+        let bytes = trimmed.as_bytes();
+        let mut cursor = 0;
+        let row = parse_u32(bytes, &mut cursor);
+        cursor = skip_whitespace(bytes, cursor);
+        let col = parse_u32(bytes, &mut cursor);
+        cursor = skip_whitespace(bytes, cursor);
+        let (row, col) = match (row, col) {
+            (Some(row), Some(col)) => (row, col),
+            _ => return Err(invalid(number, "expected two unsigned integers")),
+        };
+        if row == 0 || col == 0 {
+            return Err(invalid(number, "indices are one-based"));
+        }
+        if row as u64 > self.rows || col as u64 > self.cols {
+            return Err(invalid(number, "entry outside of the matrix"));
+        }
+        let weight = if self.weighted {
+            trimmed[cursor..].split_whitespace().next().and_then(|text| text.parse::<f64>().ok())
+                .ok_or_else(|| invalid(number, "expected a numeric value"))?
+        }
+        else { 1.0 };
+        Ok(Some(((row - 1, col - 1), weight)))
+    }
+}
+
+impl<R: BufRead> Iterator for MatrixMarket<R> {
+    type Item = Result<((u32, u32), f64)>;
+    fn next(&mut self) -> Option<Result<((u32, u32), f64)>> {
+        if let Some(mirror) = self.mirror.take() {
+            return Some(Ok(mirror));
+        }
+        let entry = self.next_entry().transpose()?;
+        if let Ok(((src, dst), weight)) = entry {
+            if self.symmetry == Symmetry::Symmetric && src != dst {
+                self.mirror = Some(((dst, src), weight));
+            }
+        }
+        Some(entry)
+    }
+}
+
+/// Writes `layout` as a Matrix Market coordinate file.
+///
+/// Weighted layouts are written as `real` matrices and unweighted layouts as `pattern` matrices.
+/// With `Symmetry::Symmetric` only edges with `src >= dst` are written, and the layout is assumed
+/// to contain the mirror image of each of them.
+pub fn write_matrix_market<P: AsRef<Path>>(layout: &GraphLayout, path: P, symmetry: Symmetry) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    format_matrix_market(layout, &mut writer, symmetry)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Formats `layout` as a Matrix Market coordinate matrix to any writer.
+pub fn format_matrix_market<W: Write>(layout: &GraphLayout, writer: &mut W, symmetry: Symmetry) -> Result<()> {
+    let keep = |(src, dst): (u32, u32)| symmetry == Symmetry::General || src >= dst;
+    let mut rows = 0u64;
+    let mut cols = 0u64;
+    let mut count = 0;
+    for (src, dst) in layout.edges().filter(|&edge| keep(edge)) {
+        rows = ::std::cmp::max(rows, src as u64 + 1);
+        cols = ::std::cmp::max(cols, dst as u64 + 1);
+        count += 1;
+    }
+    if symmetry == Symmetry::Symmetric {
+        rows = ::std::cmp::max(rows, cols);
+        cols = rows;
+    }
+
+    let field = if layout.weights().is_some() { "real" } else { "pattern" };
+    let shape = if symmetry == Symmetry::Symmetric { "symmetric" } else { "general" };
+    writeln!(writer, "%%MatrixMarket matrix coordinate {} {}", field, shape)?;
+    writeln!(writer, "{} {} {}", rows, cols, count)?;
+    for (index, (src, dst)) in layout.edges().enumerate() {
+        if !keep((src, dst)) { continue; }
+        match layout.weights() {
+            Some(weights) => writeln!(writer, "{} {} {}", src as u64 + 1, dst as u64 + 1, weights[index])?,
+            None => writeln!(writer, "{} {}", src as u64 + 1, dst as u64 + 1)?,
+        }
+    }
+    Ok(())
+}
+
+/// A graph read from a METIS file, with any vertex sizes and weights it carried.
+pub struct Metis {
+    /// The edges, weighted if the file carried edge weights.
+    pub layout: GraphLayout,
+    /// The number of weights per vertex, zero if the file carried no vertex weights.
+    pub constraints: usize,
+    /// Vertex weights, `constraints` consecutive values per vertex.
+    pub vertex_weights: Vec<u64>,
+    /// Vertex sizes, if the file carried them.
+    pub vertex_sizes: Option<Vec<u64>>,
+}
+
+/// Reads a graph in the METIS adjacency format.
+///
+/// The header holds the vertex count `n`, the undirected edge count `m`, and optionally a format
+/// code and the number of vertex weights. Line `i` of the body lists the one-based neighbors of
+/// vertex `i`, each followed by an edge weight if the format code calls for them. Each undirected
+/// edge appears in both directions, and is stored as two edges.
+pub fn read_metis<P: AsRef<Path>>(path: P) -> Result<Metis> {
+    parse_metis(open(path.as_ref())?)
+}
+
+/// Parses a METIS graph from any buffered reader.
+pub fn parse_metis<R: BufRead>(mut reader: R) -> Result<Metis> {
+    let mut line = Vec::new();
+    let mut number = 0;
+    // reads the next non-comment line into `line`, returning false at the end of the input.
+    let mut next_line = |line: &mut Vec<u8>, number: &mut usize| -> Result<bool> {
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', line)? == 0 { return Ok(false); }
+            *number += 1;
+            if line.first() != Some(&b'%') { return Ok(true); }
+        }
+    };
+
+    if !next_line(&mut line, &mut number)? {
+        return Err(invalid(number, "missing header"));
+    }
+    let mut cursor = skip_whitespace(&line, 0);
+    let mut header = Vec::new();
+    while cursor < line.len() {
+        header.push(parse_u64(&line, &mut cursor).ok_or_else(|| invalid(number, "malformed header"))?);
+        cursor = skip_whitespace(&line, cursor);
+    }
+    if header.len() < 2 || header.len() > 4 {
+        return Err(invalid(number, "expected `n m [fmt [ncon]]`"));
+    }
+    let vertices = header[0];
+    let edges = header[1];
+    if vertices > 1 << 32 {
+        return Err(invalid(number, "more than 2^32 vertices"));
+    }
+    // the format code is written in decimal digits, as in `011`.
+    let format = header.get(2).cloned().unwrap_or(0);
+    if format % 10 > 1 || (format / 10) % 10 > 1 || format / 100 > 1 {
+        return Err(invalid(number, "unrecognized format code"));
+    }
+    let edge_weights = format % 10 == 1;
+    let has_vertex_weights = (format / 10) % 10 == 1;
+    let has_sizes = format / 100 == 1;
+    let constraints = if has_vertex_weights { header.get(3).cloned().unwrap_or(1) } else { 0 };
+    // the counts come from the input, so they are checked but not trusted to size allocations.
+    let constraints = vertices.checked_mul(constraints)
+        .and_then(|weights| usize::try_from(weights).ok())
+        .and_then(|_| usize::try_from(constraints).ok())
+        .ok_or_else(|| invalid(number, "too many vertex weights"))?;
+
+    let mut vertex_weights = Vec::new();
+    let mut vertex_sizes = if has_sizes { Some(Vec::new()) } else { None };
+    let mut entries = Vec::new();
+    for vertex in 0 .. vertices {
+        if !next_line(&mut line, &mut number)? {
+            return Err(invalid(number, "fewer adjacency lines than vertices"));
+        }
+        let mut cursor = skip_whitespace(&line, 0);
+        let field = |cursor: &mut usize, what: &str| {
+            let value = parse_u64(&line, cursor).ok_or_else(|| invalid(number, &format!("expected {}", what)));
+            *cursor = skip_whitespace(&line, *cursor);
+            value
+        };
+        if let Some(sizes) = vertex_sizes.as_mut() {
+            sizes.push(field(&mut cursor, "a vertex size")?);
+        }
+        for _ in 0 .. constraints {
+            vertex_weights.push(field(&mut cursor, "a vertex weight")?);
+        }
+        while cursor < line.len() {
+            let neighbor = field(&mut cursor, "a neighbor")?;
+            if neighbor == 0 || neighbor > vertices {
+                return Err(invalid(number, "neighbor outside of the vertex range"));
+            }
+            let weight = if edge_weights { field(&mut cursor, "an edge weight")? as f64 } else { 1.0 };
+            entries.push(((vertex as u32, (neighbor - 1) as u32), weight));
+        }
+    }
+    if entries.len() as u64 != 2 * edges {
+        return Err(invalid(number, &format!("found {} directed edges, expected {}", entries.len(), 2 * edges)));
+    }
+
+    let layout = if edge_weights {
+        GraphLayout::from_weighted_edges(entries.into_iter())
+    }
+    else {
+        GraphLayout::from_edges(entries.into_iter().map(|(edge, _)| edge))
+    };
+    Ok(Metis { layout, constraints, vertex_weights, vertex_sizes })
+}
+
+/// Advances `cursor` past spaces, tabs, commas and line endings.
+fn skip_whitespace(bytes: &[u8], mut cursor: usize) -> usize {
+    while cursor < bytes.len() && matches!(bytes[cursor], b' ' | b'\t' | b',' | b'\r' | b'\n') {
+        cursor += 1;
+    }
+    cursor
+}
+
+/// Parses a decimal `u32` at `cursor`, advancing past its digits.
+///
+/// Returns `None` if there are no digits, if the value overflows, or if the digits are followed by
+/// something other than a separator.
+fn parse_u32(bytes: &[u8], cursor: &mut usize) -> Option<u32> {
+    parse_bounded(bytes, cursor, u32::MAX as u64).map(|value| value as u32)
+}
+
+/// Parses a decimal `u64` at `cursor`, advancing past its digits, as `parse_u32` does.
+fn parse_u64(bytes: &[u8], cursor: &mut usize) -> Option<u64> {
+    parse_bounded(bytes, cursor, u64::MAX)
+}
+
+fn parse_bounded(bytes: &[u8], cursor: &mut usize, bound: u64) -> Option<u64> {
+    let start = *cursor;
+    let mut value = 0u64;
+    while *cursor < bytes.len() && bytes[*cursor].is_ascii_digit() {
+        value = value.checked_mul(10)?.checked_add((bytes[*cursor] - b'0') as u64)?;
+        if value > bound { return None; }
+        *cursor += 1;
+    }
+    if *cursor == start { return None; }
+    if *cursor < bytes.len() && skip_whitespace(bytes, *cursor) == *cursor { return None; }
+    Some(value)
+}
+
+fn invalid(line: usize, message: &str) -> Error {
+    Error::corrupt(&format!("input at line {}", line), message)
+}
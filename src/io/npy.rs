@@ -0,0 +1,52 @@
+//! Export of edge arrays in NumPy's `.npy` format.
+//!
+//! Each array is written as a version 1.0 `.npy` file: a magic string, a Python dictionary literal
+//! describing the element type and shape, padded to a multiple of 64 bytes, and then the raw
+//! little-endian elements.
+
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+use crate::graph::GraphLayout;
+
+/// Writes the edges of `layout` into `directory` as one-dimensional arrays, in curve order.
+///
+/// Sources go to `src.npy` and destinations to `dst.npy`, both as `uint32`. Weighted layouts also
+/// produce `weight.npy`, as `float64`. The directory must already exist.
+pub fn write_npy<P: AsRef<Path>>(layout: &GraphLayout, directory: P) -> Result<()> {
+    let directory = directory.as_ref();
+    let mut src = BufWriter::new(File::create(directory.join("src.npy"))?);
+    let mut dst = BufWriter::new(File::create(directory.join("dst.npy"))?);
+    write_header(&mut src, "<u4", layout.len())?;
+    write_header(&mut dst, "<u4", layout.len())?;
+    for (source, target) in layout.edges() {
+        src.write_all(&source.to_le_bytes())?;
+        dst.write_all(&target.to_le_bytes())?;
+    }
+    src.flush()?;
+    dst.flush()?;
+
+    if let Some(weights) = layout.weights() {
+        let mut weight = BufWriter::new(File::create(directory.join("weight.npy"))?);
+        write_header(&mut weight, "<f8", weights.len())?;
+        for value in weights {
+            weight.write_all(&value.to_le_bytes())?;
+        }
+        weight.flush()?;
+    }
+    Ok(())
+}
+
+/// Writes the preamble of a one-dimensional array of `length` elements of type `descr`.
+fn write_header<W: Write>(writer: &mut W, descr: &str, length: usize) -> Result<()> {
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': ({},), }}", descr, length);
+    // magic (6) + version (2) + header length (2) + header, padded with spaces and a newline.
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())
+}
@@ -0,0 +1,216 @@
+//! Tiles packed into fixed-shape, aligned arrays for upload to a GPU.
+//!
+//! Each tile of a tiled layout becomes a small sparse matrix with local row and column indices,
+//! in one of two shapes:
+//!
+//! * `Packing::Csr`: `side + 1` row pointers, relative to the tile's first entry, and the column
+//!   of each edge, grouped by row and sorted within rows;
+//! * `Packing::Ell`: each row padded to the tile's longest row, `width` entries, stored column-major
+//!   so that the `j`th entries of consecutive rows are adjacent, with `PADDING` in unused slots.
+//!
+//! The tiles' entries are concatenated into shared arrays, each tile's run (and, for CSR, each run
+//! of row pointers) starting at a multiple of the alignment and padded to one. Weights, as `f32`,
+//! are aligned with the entries and zero in padding.
+//!
+//! `write_packed` writes the arrays as raw little-endian files `pointers.bin`, `indices.bin`, and
+//! `weights.bin` (if weighted), and a text manifest `manifest.txt`: the line `graph-layout packed 1`,
+//! then `packing <csr|ell>`, `log_tile <k>`, `align <a>`, and `weighted <true|false>`, then a line
+//! `tile <row> <column> <edges> <width> <pointers> <entries> <length>` per tile, in curve order.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::error::Result;
+use crate::graph::GraphLayout;
+use crate::layout::BytewiseCached;
+
+/// The local column index of padding entries.
+pub const PADDING: u32 = u32::MAX;
+/// The name of the manifest file within a packed directory.
+pub const MANIFEST: &str = "manifest.txt";
+const MANIFEST_HEADER: &str = "graph-layout packed 1";
+
+/// The shape of each packed tile.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Packing {
+    /// Compressed sparse rows within each tile.
+    Csr,
+    /// Rows padded to the tile's longest row, stored column-major.
+    Ell,
+}
+
+/// Where one tile's data lies in the packed arrays.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PackedTile {
+    /// The first source of the tile's rows.
+    pub row: u32,
+    /// The first destination of the tile's columns.
+    pub column: u32,
+    /// The number of edges in the tile.
+    pub edges: usize,
+    /// For `Packing::Ell`, the number of entries per row; zero for `Packing::Csr`.
+    pub width: usize,
+    /// For `Packing::Csr`, the offset of the tile's `side + 1` row pointers; zero for `Packing::Ell`.
+    pub pointers: usize,
+    /// The offset of the tile's first entry in the indices and weights.
+    pub entries: usize,
+    /// The number of entries reserved for the tile, including padding.
+    pub length: usize,
+}
+
+/// The tiles of a layout packed into shared arrays, by `pack_tiles`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackedTiles {
+    /// The shape of each tile.
+    pub packing: Packing,
+    /// The base-two logarithm of the tile side length.
+    pub log_tile: u32,
+    /// The alignment, in elements, of each tile's runs.
+    pub align: usize,
+    /// The non-empty tiles, in curve order.
+    pub tiles: Vec<PackedTile>,
+    /// Row pointers of `Packing::Csr` tiles, relative to each tile's first entry.
+    pub pointers: Vec<u32>,
+    /// Local column indices, `PADDING` where there is no edge.
+    pub indices: Vec<u32>,
+    /// Weights aligned with `indices`, if the layout is weighted.
+    pub weights: Option<Vec<f32>>,
+}
+
+/// Packs the tiles of `layout` in the shape `packing`, aligning each run to `align` elements.
+///
+/// Panics if the layout is not tiled or `align` is zero.
+pub fn pack_tiles(layout: &GraphLayout, packing: Packing, align: usize) -> PackedTiles {
+    let log = layout.log_tile().expect("packing requires a tiled layout");
+    assert!(align > 0, "the alignment must be at least one element");
+    let side = 1usize << log;
+    let mut detangler = BytewiseCached::new();
+    let mut packed = PackedTiles {
+        packing,
+        log_tile: log,
+        align,
+        tiles: Vec::with_capacity(layout.tiles().len()),
+        pointers: Vec::new(),
+        indices: Vec::new(),
+        weights: layout.weights().map(|_| Vec::new()),
+    };
+    let mut rows = vec![Vec::new(); side];
+    for tile in layout.tiles() {
+        let (x, y) = detangler.detangle(tile.lower);
+        let (row, column) = ((x >> log) << log, (y >> log) << log);
+        for (offset, key) in layout.tile_keys(tile).enumerate() {
+            let (src, dst) = detangler.detangle(key);
+            let weight = layout.weights().map(|weights| weights[tile.offset + offset] as f32).unwrap_or(0.0);
+            rows[(src - row) as usize].push((dst - column, weight));
+        }
+        for entries in rows.iter_mut() {
+            entries.sort_unstable_by_key(|&(index, _)| index);
+        }
+
+        let entries = packed.indices.len();
+        let mut packed_tile = PackedTile { row, column, edges: tile.edges, width: 0, pointers: 0, entries, length: 0 };
+        match packing {
+            Packing::Csr => {
+                packed_tile.pointers = packed.pointers.len();
+                packed.pointers.push(0);
+                for entries in &rows {
+                    for &(index, weight) in entries {
+                        packed.push(index, weight);
+                    }
+                    packed.pointers.push((packed.indices.len() - packed_tile.entries) as u32);
+                }
+                let length = packed.pointers.len().div_ceil(align) * align;
+                packed.pointers.resize(length, 0);
+            },
+            Packing::Ell => {
+                packed_tile.width = rows.iter().map(|entries| entries.len()).max().unwrap_or(0);
+                for slot in 0 .. packed_tile.width {
+                    for entries in &rows {
+                        let (index, weight) = entries.get(slot).cloned().unwrap_or((PADDING, 0.0));
+                        packed.push(index, weight);
+                    }
+                }
+            },
+        }
+        let end = packed.indices.len().div_ceil(align) * align;
+        while packed.indices.len() < end {
+            packed.push(PADDING, 0.0);
+        }
+        packed_tile.length = end - packed_tile.entries;
+        packed.tiles.push(packed_tile);
+        for entries in rows.iter_mut() {
+            entries.clear();
+        }
+    }
+    packed
+}
+
+impl PackedTiles {
+    fn push(&mut self, index: u32, weight: f32) {
+        self.indices.push(index);
+        if let Some(weights) = self.weights.as_mut() {
+            weights.push(weight);
+        }
+    }
+
+    /// The edges of tile `index`, by row and then by column, as stored.
+    pub fn tile_edges(&self, index: usize) -> Vec<(u32, u32)> {
+        let tile = &self.tiles[index];
+        let side = 1usize << self.log_tile;
+        let entries = &self.indices[tile.entries .. tile.entries + tile.length];
+        let mut edges = Vec::with_capacity(tile.edges);
+        match self.packing {
+            Packing::Csr => {
+                let pointers = &self.pointers[tile.pointers .. tile.pointers + side + 1];
+                for row in 0 .. side {
+                    for &column in &entries[pointers[row] as usize .. pointers[row + 1] as usize] {
+                        edges.push((tile.row + row as u32, tile.column + column));
+                    }
+                }
+            },
+            Packing::Ell => {
+                for row in 0 .. side {
+                    for slot in 0 .. tile.width {
+                        let column = entries[slot * side + row];
+                        if column != PADDING {
+                            edges.push((tile.row + row as u32, tile.column + column));
+                        }
+                    }
+                }
+            },
+        }
+        edges
+    }
+}
+
+/// Writes the arrays and manifest of `packed` into `directory`, which must already exist.
+pub fn write_packed<P: AsRef<Path>>(packed: &PackedTiles, directory: P) -> Result<()> {
+    let directory = directory.as_ref();
+    write_array(directory.join("pointers.bin"), packed.pointers.iter().map(|value| value.to_le_bytes()))?;
+    write_array(directory.join("indices.bin"), packed.indices.iter().map(|value| value.to_le_bytes()))?;
+    if let Some(ref weights) = packed.weights {
+        write_array(directory.join("weights.bin"), weights.iter().map(|value| value.to_le_bytes()))?;
+    }
+
+    let mut writer = BufWriter::new(File::create(directory.join(MANIFEST))?);
+    writeln!(writer, "{}", MANIFEST_HEADER)?;
+    writeln!(writer, "packing {}", match packed.packing { Packing::Csr => "csr", Packing::Ell => "ell" })?;
+    writeln!(writer, "log_tile {}", packed.log_tile)?;
+    writeln!(writer, "align {}", packed.align)?;
+    writeln!(writer, "weighted {}", packed.weights.is_some())?;
+    for tile in &packed.tiles {
+        writeln!(writer, "tile {} {} {} {} {} {} {}", tile.row, tile.column, tile.edges, tile.width, tile.pointers, tile.entries, tile.length)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_array<P: AsRef<Path>, I: Iterator<Item=[u8; 4]>>(path: P, values: I) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for bytes in values {
+        writer.write_all(&bytes)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! Layouts split across several files by ranges of the curve.
+//!
+//! A sharded layout is a directory holding a text manifest and one layout file per shard. Each
+//! shard holds the edges whose curve keys fall in a contiguous range, so shards can be stored and
+//! processed independently. For tiled layouts, shard boundaries fall on tile boundaries.
+//!
+//! The manifest, `manifest.txt`, starts with the line `graph-layout shards 1`, followed by lines
+//! `vertices <n>`, `edges <m>`, `log_tile <k>` (or `none`), and `weighted <true|false>`. Each shard
+//! then has a line `shard <lower> <upper> <edges> <path>`, in curve order, with an inclusive key
+//! range. Relative paths are resolved against the manifest's directory, and absolute paths allow
+//! shards to live elsewhere.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::graph::GraphLayout;
+
+/// The name of the manifest file within a shard directory.
+pub const MANIFEST: &str = "manifest.txt";
+const MANIFEST_HEADER: &str = "graph-layout shards 1";
+
+/// The contents of a shard manifest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Manifest {
+    /// The number of vertices in the whole layout.
+    pub vertices: u64,
+    /// The number of edges in the whole layout.
+    pub edges: usize,
+    /// The log tile side of each shard, if tiled.
+    pub log_tile: Option<u32>,
+    /// Whether the shards are weighted.
+    pub weighted: bool,
+    /// The shards, in curve order.
+    pub shards: Vec<Shard>,
+}
+
+/// One shard of a sharded layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shard {
+    /// The first curve key the shard may contain.
+    pub lower: u64,
+    /// The last curve key the shard may contain (inclusive).
+    pub upper: u64,
+    /// The number of edges in the shard.
+    pub edges: usize,
+    /// The shard's layout file, as written in the manifest.
+    pub path: PathBuf,
+}
+
+/// Writes `layout` into `directory` as at most `shards` shards of nearly equal edge counts.
+///
+/// The directory is created if needed. Returns the manifest written to `directory/manifest.txt`.
+pub fn write_shards<P: AsRef<Path>>(layout: &GraphLayout, directory: P, shards: usize) -> Result<Manifest> {
+    let directory = directory.as_ref();
+    ::std::fs::create_dir_all(directory)?;
+
+    // boundaries start at the first key of each balanced run, rounded down to a tile boundary.
+    let align = layout.log_tile().map(|log| !((1u64 << (2 * log)) - 1)).unwrap_or(!0);
+    let mut lowers = layout.split(::std::cmp::max(shards, 1)).iter().map(|run| run.lower & align).collect::<Vec<_>>();
+    lowers.dedup();
+    if lowers.is_empty() { lowers.push(0); }
+    lowers[0] = 0;
+
+    let mut keys = layout.keys().zip(0 ..).peekable();
+    let mut manifest = Manifest {
+        vertices: layout.vertices(),
+        edges: layout.len(),
+        log_tile: layout.log_tile(),
+        weighted: layout.weights().is_some(),
+        shards: Vec::with_capacity(lowers.len()),
+    };
+    for (index, &lower) in lowers.iter().enumerate() {
+        let upper = lowers.get(index + 1).map(|next| next - 1).unwrap_or(u64::MAX);
+        let mut shard_keys = Vec::new();
+        let mut shard_weights = Vec::new();
+        let mut positions = Vec::new();
+        while let Some(&(key, position)) = keys.peek() {
+            if key > upper { break; }
+            shard_keys.push(key);
+            if let Some(weights) = layout.weights() {
+                shard_weights.push(weights[position]);
+            }
+            if !layout.columns().is_empty() {
+                positions.push(position);
+            }
+            keys.next();
+        }
+
+        let mut shard = match layout.weights() {
+            Some(_) => GraphLayout::from_weighted_keys(shard_keys.into_iter().zip(shard_weights).collect(), layout.log_tile()),
+            None => GraphLayout::from_keys(shard_keys, layout.log_tile()),
+        };
+        shard.columns = layout.columns().iter().map(|(name, column)| (name.clone(), column.select(&positions))).collect();
+        let path = PathBuf::from(format!("shard-{:05}.glayout", index));
+        shard.save(directory.join(&path))?;
+        manifest.shards.push(Shard { lower, upper, edges: shard.len(), path });
+    }
+
+    write_manifest(&manifest, directory.join(MANIFEST))?;
+    Ok(manifest)
+}
+
+/// Writes `manifest` to `path`.
+pub fn write_manifest<P: AsRef<Path>>(manifest: &Manifest, path: P) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", MANIFEST_HEADER)?;
+    writeln!(writer, "vertices {}", manifest.vertices)?;
+    writeln!(writer, "edges {}", manifest.edges)?;
+    match manifest.log_tile {
+        Some(log) => writeln!(writer, "log_tile {}", log)?,
+        None => writeln!(writer, "log_tile none")?,
+    }
+    writeln!(writer, "weighted {}", manifest.weighted)?;
+    for shard in &manifest.shards {
+        writeln!(writer, "shard {} {} {} {}", shard.lower, shard.upper, shard.edges, shard.path.display())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads the manifest of the sharded layout in `directory`.
+///
+/// Fails unless the shard ranges partition the whole curve in order and the shard edge counts sum
+/// to the layout's.
+pub fn read_manifest<P: AsRef<Path>>(directory: P) -> Result<Manifest> {
+    let text = ::std::fs::read_to_string(directory.as_ref().join(MANIFEST))?;
+    let mut lines = text.lines();
+    if lines.next() != Some(MANIFEST_HEADER) {
+        return Err(invalid("missing manifest header"));
+    }
+    let mut field = |name: &str| -> Result<String> {
+        let line = lines.next().unwrap_or("");
+        match line.split_once(' ') {
+            Some((key, value)) if key == name => Ok(value.to_owned()),
+            _ => Err(invalid(&format!("expected `{}`", name))),
+        }
+    };
+    let vertices = field("vertices")?.parse().map_err(|_| invalid("malformed vertex count"))?;
+    let edges = field("edges")?.parse().map_err(|_| invalid("malformed edge count"))?;
+    let log_tile = match &field("log_tile")?[..] {
+        "none" => None,
+        log => Some(log.parse().map_err(|_| invalid("malformed log tile"))?),
+    };
+    let weighted = field("weighted")?.parse().map_err(|_| invalid("malformed weighted flag"))?;
+
+    let mut shards = Vec::new();
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        let mut parts = line.splitn(5, ' ');
+        if parts.next() != Some("shard") {
+            return Err(invalid("expected `shard`"));
+        }
+        let mut number = || parts.next().and_then(|part| part.parse::<u64>().ok()).ok_or_else(|| invalid("malformed shard"));
+        let (lower, upper, count) = (number()?, number()?, number()?);
+        let path = parts.next().ok_or_else(|| invalid("missing shard path"))?;
+        shards.push(Shard { lower, upper, edges: count as usize, path: PathBuf::from(path) });
+    }
+    // the ranges must cover every key exactly once, and the shards every edge.
+    if shards.first().map(|shard| shard.lower) != Some(0) || shards.last().map(|shard| shard.upper) != Some(u64::MAX) {
+        return Err(invalid("shard ranges do not cover the curve"));
+    }
+    if shards.iter().any(|shard| shard.lower > shard.upper) {
+        return Err(invalid("shard range is empty"));
+    }
+    if shards.windows(2).any(|pair| pair[0].upper.checked_add(1) != Some(pair[1].lower)) {
+        return Err(invalid("shard ranges are not contiguous"));
+    }
+    if shards.iter().try_fold(0usize, |sum, shard| sum.checked_add(shard.edges)) != Some(edges) {
+        return Err(invalid("shard edge counts do not sum to the edge count"));
+    }
+
+    Ok(Manifest { vertices, edges, log_tile, weighted, shards })
+}
+
+impl Manifest {
+    /// Reads shard `index` of the sharded layout in `directory`, failing if there is no such shard.
+    pub fn open_shard<P: AsRef<Path>>(&self, directory: P, index: usize) -> Result<GraphLayout> {
+        let shard = self.shards.get(index).ok_or_else(|| invalid(&format!("no shard {} among {}", index, self.shards.len())))?;
+        let layout = GraphLayout::open(directory.as_ref().join(&shard.path))?;
+        if layout.len() != shard.edges || layout.keys().any(|key| key < shard.lower || key > shard.upper) {
+            return Err(invalid(&format!("shard {} does not match the manifest", index)));
+        }
+        Ok(layout)
+    }
+
+    /// Reads every shard of the sharded layout in `directory` into a single layout.
+    pub fn load<P: AsRef<Path>>(&self, directory: P) -> Result<GraphLayout> {
+        let shards = (0 .. self.shards.len()).map(|index| self.open_shard(directory.as_ref(), index)).collect::<Result<Vec<_>>>()?;
+        // shards hold disjoint ranges of keys, so their union concatenates them and their columns.
+        let layout = GraphLayout::union(&shards.iter().collect::<Vec<_>>());
+        if layout.weights().is_some() != self.weighted {
+            return Err(invalid("shard weights do not match the manifest"));
+        }
+        Ok(layout)
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::corrupt("shard manifest", message)
+}
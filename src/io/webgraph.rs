@@ -0,0 +1,356 @@
+//! Sequential decoding of the WebGraph BVGraph format.
+//!
+//! A BVGraph named `basename` consists of `basename.properties`, describing the graph and the codes
+//! used, and `basename.graph`, a big-endian bit stream holding each node's successor list in turn.
+//! Successor lists are coded relative to the list of a recent node (the reference) by alternately
+//! copying and skipping blocks of it, with the remaining successors coded as intervals of
+//! consecutive nodes and gap-coded residuals.
+//!
+//! Only sequential decoding is supported, so the `.offsets` file is not consulted.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::graph::GraphLayout;
+
+/// Opens the BVGraph `basename`, iterating over its edges in node order.
+pub fn read_webgraph<P: AsRef<Path>>(basename: P) -> Result<WebGraph<BufReader<File>>> {
+    let properties = ::std::fs::read_to_string(sibling(basename.as_ref(), ".properties"))?;
+    let graph = File::open(sibling(basename.as_ref(), ".graph"))?;
+    WebGraph::new(&properties, BufReader::new(graph))
+}
+
+/// Reads the BVGraph `basename` directly into a `GraphLayout`.
+pub fn load_webgraph<P: AsRef<Path>>(basename: P) -> Result<GraphLayout> {
+    let mut edges = Vec::new();
+    for edge in read_webgraph(basename)? {
+        edges.push(edge?);
+    }
+    Ok(GraphLayout::from_edges(edges.into_iter()))
+}
+
+fn sibling(basename: &Path, extension: &str) -> ::std::path::PathBuf {
+    let mut name = basename.as_os_str().to_owned();
+    name.push(extension);
+    name.into()
+}
+
+/// Instantaneous codes used for the components of a successor list.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Code {
+    Unary,
+    Gamma,
+    Delta,
+    Zeta(u32),
+}
+
+/// Decodes the edges of a BVGraph from its properties and bit stream.
+pub struct WebGraph<R: BufRead> {
+    bits: BitReader<R>,
+    nodes: u64,
+    window: usize,
+    min_interval: u64,
+    outdegrees: Code,
+    references: Code,
+    blocks: Code,
+    block_count: Code,
+    residuals: Code,
+    /// Successor lists of the most recent `window + 1` nodes, indexed by node modulo `window + 1`.
+    recent: Vec<Vec<u32>>,
+    node: u64,
+    cursor: usize,
+}
+
+impl<R: BufRead> WebGraph<R> {
+    /// Prepares to decode the bit stream `graph` using the contents of a `.properties` file.
+    pub fn new(properties: &str, graph: R) -> Result<WebGraph<R>> {
+        let properties = parse_properties(properties);
+        let number = |key: &str, default: Option<u64>| -> Result<u64> {
+            match properties.get(key) {
+                Some(value) => value.parse().map_err(|_| invalid(&format!("malformed property `{}`", key))),
+                None => default.ok_or_else(|| invalid(&format!("missing property `{}`", key))),
+            }
+        };
+
+        let nodes = number("nodes", None)?;
+        if nodes > 1 << 32 {
+            return Err(invalid("graphs with more than 2^32 nodes are not supported"));
+        }
+        let window = number("windowsize", Some(7))? as usize;
+        let min_interval = number("minintervallength", Some(4))?;
+        let zeta = number("zetak", Some(3))? as u32;
+
+        let mut graph = WebGraph {
+            bits: BitReader::new(graph),
+            nodes,
+            window,
+            min_interval,
+            outdegrees: Code::Gamma,
+            references: Code::Unary,
+            blocks: Code::Gamma,
+            block_count: Code::Gamma,
+            residuals: Code::Zeta(zeta),
+            recent: vec![Vec::new(); window + 1],
+            node: 0,
+            cursor: 0,
+        };
+
+        let flags = properties.get("compressionflags").map(|flags| &flags[..]).unwrap_or("");
+        for flag in flags.split('|').map(|flag| flag.trim()).filter(|flag| !flag.is_empty()) {
+            let split = flag.rfind('_').ok_or_else(|| invalid(&format!("unknown compression flag `{}`", flag)))?;
+            let code = match &flag[split + 1 ..] {
+                "UNARY" => Code::Unary,
+                "GAMMA" => Code::Gamma,
+                "DELTA" => Code::Delta,
+                "ZETA" => Code::Zeta(zeta),
+                _ => return Err(invalid(&format!("unsupported compression flag `{}`", flag))),
+            };
+            match &flag[.. split] {
+                "OUTDEGREES" => graph.outdegrees = code,
+                "REFERENCES" => graph.references = code,
+                "BLOCKS" => graph.blocks = code,
+                "BLOCK_COUNT" => graph.block_count = code,
+                "RESIDUALS" => graph.residuals = code,
+                "OFFSETS" => { },
+                _ => return Err(invalid(&format!("unknown compression flag `{}`", flag))),
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// The number of nodes in the graph.
+    pub fn nodes(&self) -> u64 { self.nodes }
+
+    /// Decodes the successor list of the next node into its slot in `recent`.
+    fn decode(&mut self) -> Result<()> {
+        let node = self.node;
+        let slot = (node % (self.window as u64 + 1)) as usize;
+        let mut successors = ::std::mem::take(&mut self.recent[slot]);
+        successors.clear();
+
+        let degree = self.bits.read(self.outdegrees)?;
+        if degree > 0 {
+            let reference = if self.window > 0 { self.bits.read(self.references)? } else { 0 };
+            if reference > 0 {
+                if reference > self.window as u64 || reference > node {
+                    return Err(invalid("reference outside of the window"));
+                }
+                let referenced = &self.recent[((node - reference) % (self.window as u64 + 1)) as usize];
+                let count = self.bits.read(self.block_count)?;
+                let mut index = 0;
+                for block in 0 .. count {
+                    // blocks after the first are never empty, and are coded less one.
+                    let length = (self.bits.read(self.blocks)? + if block > 0 { 1 } else { 0 }) as usize;
+                    if index + length > referenced.len() {
+                        return Err(invalid("copy block exceeds the referenced list"));
+                    }
+                    if block % 2 == 0 {
+                        successors.extend_from_slice(&referenced[index .. index + length]);
+                    }
+                    index += length;
+                }
+                // an even number of blocks means the remainder of the list is copied.
+                if count % 2 == 0 {
+                    successors.extend_from_slice(&referenced[index ..]);
+                }
+            }
+
+            if (successors.len() as u64) > degree {
+                return Err(invalid("copied more successors than the outdegree"));
+            }
+            let mut extra = degree - successors.len() as u64;
+            if extra > 0 && self.min_interval > 0 {
+                let intervals = self.bits.read(Code::Gamma)?;
+                let mut previous = node as i64;
+                for interval in 0 .. intervals {
+                    let left = if interval == 0 {
+                        node as i64 + nat_to_int(self.bits.read(Code::Gamma)?)
+                    }
+                    else {
+                        previous + self.bits.read(Code::Gamma)? as i64 + 1
+                    };
+                    let length = self.bits.read(Code::Gamma)? + self.min_interval;
+                    if length > extra || left < 0 || left as u64 + length > self.nodes {
+                        return Err(invalid("interval exceeds the outdegree or node range"));
+                    }
+                    successors.extend((left as u64 .. left as u64 + length).map(|target| target as u32));
+                    previous = left + length as i64;
+                    extra -= length;
+                }
+            }
+
+            let mut previous = node as i64;
+            for residual in 0 .. extra {
+                let target = if residual == 0 {
+                    node as i64 + nat_to_int(self.bits.read(self.residuals)?)
+                }
+                else {
+                    previous + self.bits.read(self.residuals)? as i64 + 1
+                };
+                if target < 0 || target as u64 >= self.nodes {
+                    return Err(invalid("residual outside of the node range"));
+                }
+                successors.push(target as u32);
+                previous = target;
+            }
+
+            successors.sort_unstable();
+        }
+
+        self.recent[slot] = successors;
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for WebGraph<R> {
+    type Item = Result<(u32, u32)>;
+    fn next(&mut self) -> Option<Result<(u32, u32)>> {
+        loop {
+            if self.node > 0 {
+                let slot = ((self.node - 1) % (self.window as u64 + 1)) as usize;
+                if let Some(&target) = self.recent[slot].get(self.cursor) {
+                    self.cursor += 1;
+                    return Some(Ok(((self.node - 1) as u32, target)));
+                }
+            }
+            if self.node == self.nodes {
+                return None;
+            }
+            if let Err(error) = self.decode() {
+                self.node = self.nodes;
+                return Some(Err(error));
+            }
+            self.node += 1;
+            self.cursor = 0;
+        }
+    }
+}
+
+/// Maps the natural numbers onto the integers, as `0, -1, 1, -2, 2, ...`.
+fn nat_to_int(value: u64) -> i64 {
+    if value.is_multiple_of(2) { (value / 2) as i64 } else { -(value.div_ceil(2) as i64) }
+}
+
+/// Parses `key=value` lines of a Java properties file, ignoring comments and escapes.
+fn parse_properties(text: &str) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    for line in text.lines().map(|line| line.trim()) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') { continue; }
+        if let Some(split) = line.find(['=', ':']) {
+            properties.insert(line[.. split].trim().to_lowercase(), line[split + 1 ..].trim().to_owned());
+        }
+    }
+    properties
+}
+
+fn invalid(message: &str) -> Error {
+    Error::corrupt("webgraph", message)
+}
+
+/// Reads a big-endian bit stream.
+struct BitReader<R: BufRead> {
+    reader: R,
+    /// The next `bits` bits of the stream, in the low-order bits.
+    buffer: u64,
+    bits: u32,
+}
+
+impl<R: BufRead> BitReader<R> {
+    fn new(reader: R) -> BitReader<R> {
+        BitReader { reader, buffer: 0, bits: 0 }
+    }
+
+    /// Tops up the buffer to at least 57 bits, or as many as remain in the stream.
+    fn refill(&mut self) -> Result<()> {
+        while self.bits <= 56 {
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() { break; }
+            let take = ::std::cmp::min(available.len(), ((64 - self.bits) / 8) as usize);
+            for &byte in &available[.. take] {
+                self.buffer = (self.buffer << 8) | byte as u64;
+            }
+            self.bits += 8 * take as u32;
+            self.reader.consume(take);
+        }
+        Ok(())
+    }
+
+    /// Reads `count` bits, at most 56, as an unsigned integer.
+    fn read_bits(&mut self, count: u32) -> Result<u64> {
+        debug_assert!(count <= 56);
+        if count == 0 { return Ok(0); }
+        if self.bits < count {
+            self.refill()?;
+            if self.bits < count {
+                return Err(invalid("truncated bit stream"));
+            }
+        }
+        self.bits -= count;
+        let result = self.buffer >> self.bits;
+        self.buffer &= (1u64 << self.bits) - 1;
+        Ok(result)
+    }
+
+    /// Reads a value coded as that many zeros followed by a one.
+    fn read_unary(&mut self) -> Result<u64> {
+        let mut zeros = 0;
+        loop {
+            if self.bits == 0 {
+                self.refill()?;
+                if self.bits == 0 {
+                    return Err(invalid("truncated bit stream"));
+                }
+            }
+            if self.buffer == 0 {
+                zeros += self.bits as u64;
+                self.bits = 0;
+            }
+            else {
+                let leading = self.buffer.leading_zeros() - (64 - self.bits);
+                zeros += leading as u64;
+                self.bits -= leading + 1;
+                self.buffer &= (1u64 << self.bits) - 1;
+                return Ok(zeros);
+            }
+        }
+    }
+
+    /// Reads `count` bits, for counts up to 64.
+    fn read_long(&mut self, count: u32) -> Result<u64> {
+        if count > 32 {
+            let high = self.read_bits(count - 32)?;
+            Ok((high << 32) | self.read_bits(32)?)
+        }
+        else {
+            self.read_bits(count)
+        }
+    }
+
+    fn read(&mut self, code: Code) -> Result<u64> {
+        match code {
+            Code::Unary => self.read_unary(),
+            Code::Gamma => {
+                let width = self.read_unary()? as u32;
+                if width > 63 { return Err(invalid("gamma code too long")); }
+                Ok(((1u64 << width) | self.read_long(width)?) - 1)
+            },
+            Code::Delta => {
+                let width = self.read(Code::Gamma)?;
+                if width > 63 { return Err(invalid("delta code too long")); }
+                Ok(((1u64 << width) | self.read_long(width as u32)?) - 1)
+            },
+            Code::Zeta(k) => {
+                let height = self.read_unary()? as u32;
+                if (height + 1) * k > 63 { return Err(invalid("zeta code too long")); }
+                let left = 1u64 << (height * k);
+                let value = self.read_long(height * k + k - 1)?;
+                if value < left { Ok(value + left - 1) }
+                else { Ok((value << 1) + self.read_bits(1)? - 1) }
+            },
+        }
+    }
+}
@@ -8,14 +8,21 @@
 //! The Z-Order is easily understood as interleaving the bits of the `(u32, u32)` pair into a `u64`,
 //! and de-interleaving the bits from the `u64` back to a `(u32, u32)` pair. The Hilbert curve is
 //! more complicated.
+//!
+//! `RowMajor` follows no curve, concatenating the pair with its first element in the high bits, so
+//! that keys order pairs as adjacency lists do.
+//!
+//! `Hilbert3D` maps `(u32, u32, u32)` triples to `u128` keys along a three-dimensional Hilbert
+//! curve, for laying out tensors and ternary relations as pairs are laid out here.
 
+use crate::counters::Counters;
 
 /// Map between `(u32, u32)` and `u64`.
 pub trait Tangle {
     /// Maps a `(u32, u32)` pair to a `u64`.
-    fn entangle(&self, (u32, u32)) -> u64;
+    fn entangle(&self, pair: (u32, u32)) -> u64;
     /// Maps a `u64` to a `(u32, u32)` pair.
-    fn detangle(&self, u64) -> (u32, u32);
+    fn detangle(&self, tangle: u64) -> (u32, u32);
 }
 
 /// Tangles u32 pairs by interleaving their bits
@@ -43,18 +50,22 @@ impl ZOrder {
         }
 
         ZOrder {
-            entangle: entangle,
-            detangle: detangle,
+            entangle,
+            detangle,
         }
     }
 }
+impl Default for ZOrder {
+    fn default() -> ZOrder { ZOrder::new() }
+}
+
 impl Tangle for ZOrder {
     // entangles byte at a time
     #[inline]
     fn entangle(&self, (x, y): (u32, u32)) -> u64 {
         let x = x as usize;
         let y = y as usize;
-          ((self.entangle[(((x >>  0) % 256) << 8) + ((y >>  0) % 256)] as u64) << 0)
+           (self.entangle[((x % 256) << 8) + (y % 256)] as u64)
         + ((self.entangle[(((x >>  8) % 256) << 8) + ((y >>  8) % 256)] as u64) << 16)
         + ((self.entangle[(((x >> 16) % 256) << 8) + ((y >> 16) % 256)] as u64) << 32)
         + ((self.entangle[(((x >> 24) % 256) << 8) + ((y >> 24) % 256)] as u64) << 48)
@@ -62,7 +73,7 @@ impl Tangle for ZOrder {
     // detangles byte at a time
     #[inline]
     fn detangle(&self, tangle: u64) -> (u32, u32) {
-        let (x0,y0) = self.detangle[(tangle as usize >>  0) % 65536];
+        let (x0,y0) = self.detangle[tangle as usize % 65536];
         let (x1,y1) = self.detangle[(tangle as usize >> 16) % 65536];
         let (x2,y2) = self.detangle[(tangle as usize >> 32) % 65536];
         let (x3,y3) = self.detangle[(tangle as usize >> 48) % 65536];
@@ -71,6 +82,17 @@ impl Tangle for ZOrder {
     }
 }
 
+/// Tangles u32 pairs by concatenating them, first element high
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RowMajor;
+
+impl Tangle for RowMajor {
+    #[inline]
+    fn entangle(&self, (x, y): (u32, u32)) -> u64 { ((x as u64) << 32) | y as u64 }
+    #[inline]
+    fn detangle(&self, tangle: u64) -> (u32, u32) { ((tangle >> 32) as u32, tangle as u32) }
+}
+
 /// Tangles u32 pairs along a Hilbert space-filling curve
 pub struct Hilbert {
     entangle: Vec<u16>,         // entangle[x_byte << 16 + y_byte] -> tangle
@@ -83,8 +105,8 @@ impl Hilbert {
         let mut entangle = Vec::new();
         let mut detangle: Vec<_> = (0..65536).map(|_| (0u8, 0u8)).collect();
         let mut rotation = Vec::new();
-        for x in (0u32..256) {
-            for y in (0u32..256) {
+        for x in 0u32..256 {
+            for y in 0u32..256 {
                 let entangled = Hilbert::bit_entangle(((x << 24), (y << 24) + (1 << 23)));
                 entangle.push((entangled >> 48) as u16);
                 detangle[(entangled >> 48) as usize] = (x as u8, y as u8);
@@ -99,13 +121,19 @@ impl Hilbert {
             }
         }
 
-        return Hilbert {entangle: entangle, detangle: detangle, rotation: rotation};
+        Hilbert {entangle, detangle, rotation}
+    }
+
+    /// Tables shared by the whole process, built on first use.
+    pub(crate) fn shared() -> &'static Hilbert {
+        static SHARED: ::std::sync::OnceLock<Hilbert> = ::std::sync::OnceLock::new();
+        SHARED.get_or_init(Hilbert::new)
     }
 
     // entangle operator implemented bitwise
-    fn bit_entangle(mut pair: (u32, u32)) -> u64 {
+    pub(crate) fn bit_entangle(mut pair: (u32, u32)) -> u64 {
         let mut result = 0u64;
-        for log_s_rev in (0 .. 32) {
+        for log_s_rev in 0 .. 32 {
             let log_s = 31 - log_s_rev;
             let rx = (pair.0 >> log_s) & 1u32;
             let ry = (pair.1 >> log_s) & 1u32;
@@ -113,13 +141,13 @@ impl Hilbert {
             pair = Hilbert::bit_rotate(log_s, pair, rx, ry);
         }
 
-        return result;
+        result
     }
 
     // detangle operator implemented bitwise
-    fn bit_detangle(tangle: u64) -> (u32, u32) {
+    pub(crate) fn bit_detangle(tangle: u64) -> (u32, u32) {
         let mut result = (0u32, 0u32);
-        for log_s in (0 .. 32) {
+        for log_s in 0 .. 32 {
             let shifted = ((tangle >> (2 * log_s)) & 3u64) as u32;
 
             let rx = (shifted >> 1) & 1u32;
@@ -128,7 +156,47 @@ impl Hilbert {
             result = (result.0 + (rx << log_s), result.1 + (ry << log_s));
         }
 
-        return result;
+        result
+    }
+
+    /// Maps a `(u64, u64)` pair to its position along the Hilbert curve over the `2^64 × 2^64`
+    /// square.
+    ///
+    /// Pairs of values below `2^32` map to the same keys as `entangle`.
+    pub fn entangle_wide(mut pair: (u64, u64)) -> u128 {
+        let mut result = 0u128;
+        for log_s in (0 .. 64).rev() {
+            let rx = (pair.0 >> log_s) & 1u64;
+            let ry = (pair.1 >> log_s) & 1u64;
+            result += (((3 * rx) ^ ry) as u128) << (2 * log_s);
+            pair = Hilbert::wide_rotate(log_s, pair, rx, ry);
+        }
+        result
+    }
+
+    /// Maps a position along the Hilbert curve over the `2^64 × 2^64` square back to its pair.
+    pub fn detangle_wide(tangle: u128) -> (u64, u64) {
+        let mut result = (0u64, 0u64);
+        for log_s in 0 .. 64 {
+            let shifted = ((tangle >> (2 * log_s)) & 3u128) as u64;
+            let rx = (shifted >> 1) & 1u64;
+            let ry = (shifted ^ rx) & 1u64;
+            result = Hilbert::wide_rotate(log_s, result, rx, ry);
+            result = (result.0 + (rx << log_s), result.1 + (ry << log_s));
+        }
+        result
+    }
+
+    // rotation of a wide pair, as `bit_rotate`
+    fn wide_rotate(logn: usize, pair: (u64, u64), rx: u64, ry: u64) -> (u64, u64) {
+        if ry == 0 {
+            if rx != 0 {
+                let off = (1u64 << logn).wrapping_sub(1);
+                (off.wrapping_sub(pair.1), off.wrapping_sub(pair.0))
+            }
+            else { (pair.1, pair.0) }
+        }
+        else { pair }
     }
 
     // rotation of pair based on residual bits rx and ry
@@ -136,7 +204,7 @@ impl Hilbert {
         if ry == 0 {
             if rx != 0 {
                 let ::std::num::Wrapping(off) = (::std::num::Wrapping(1u32) << logn) - ::std::num::Wrapping(1u32);
-                (off - pair.1, off - pair.0)
+                (off.wrapping_sub(pair.1), off.wrapping_sub(pair.0))
             }
             else { (pair.1, pair.0) }
         }
@@ -144,6 +212,10 @@ impl Hilbert {
     }
 }
 
+impl Default for Hilbert {
+    fn default() -> Hilbert { Hilbert::new() }
+}
+
 impl Tangle for Hilbert {
     // entangles byte at a time
     #[inline]
@@ -156,12 +228,12 @@ impl Tangle for Hilbert {
             let y_byte = (y >> (24 - (8 * i))) as u8;
             result = (result << 16) + self.entangle[(((x_byte as u16) << 8) + y_byte as u16) as usize] as u64;
             let rotation = self.rotation[(((x_byte as u16) << 8) + y_byte as u16) as usize];
-            if (rotation & 0x2) > 0 { let temp = x; x = y; y = temp; }
+            if (rotation & 0x2) > 0 { ::std::mem::swap(&mut x, &mut y); }
             if rotation == 12 || rotation == 6 { x = 0xFFFFFFFF - x; y = 0xFFFFFFFF - y }
         }
 
         debug_assert!(Hilbert::bit_entangle((init_x, init_y)) == result);
-        return result;
+        result
     }
 
     // detangles byte at a time
@@ -174,11 +246,11 @@ impl Tangle for Hilbert {
             let (x_byte, y_byte) = self.detangle[shifted as usize];
             let rotation = self.rotation[(((x_byte as u16) << 8) + y_byte as u16) as usize];
             if rotation == 12 || rotation == 6 {
-                result.0 = (1 << 8 * log_s) - result.0 - 1;
-                result.1 = (1 << 8 * log_s) - result.1 - 1;
+                result.0 = (1 << (8 * log_s)) - result.0 - 1;
+                result.1 = (1 << (8 * log_s)) - result.1 - 1;
             }
             if (rotation & 0x2) > 0 {
-                let temp = result.0; result.0 = result.1; result.1 = temp;
+                ::std::mem::swap(&mut result.0, &mut result.1);
             }
 
             result.0 += (x_byte as u32) << (8 * log_s);
@@ -186,15 +258,23 @@ impl Tangle for Hilbert {
         }
 
         debug_assert!(Hilbert::bit_detangle(init_tangle) == result);
-        return result;
+        result
     }
 }
 
+/// The name `Hilbert` had in the `hilbert_curve` crate, from which this crate grew.
+///
+/// Its tables and keys are unchanged, so data keyed with `hilbert_curve::BytewiseHilbert` is read
+/// with `Hilbert` as is, without conversion.
+pub type BytewiseHilbert = Hilbert;
+
 pub struct BytewiseCached {
-    hilbert:    Hilbert,
+    hilbert:    &'static Hilbert,
     prev_hi:    u64,
     prev_out:   (u32, u32),
     prev_rot:   (bool, bool),
+    #[cfg(feature = "counters")]
+    counters:   Counters,
 }
 
 impl BytewiseCached {
@@ -202,6 +282,11 @@ impl BytewiseCached {
     pub fn detangle(&mut self, tangle: u64) -> (u32, u32) {
         let (mut x_byte, mut y_byte) = unsafe { *self.hilbert.detangle.get_unchecked(tangle as u16 as usize) };
 
+        count! {
+            if self.prev_hi == (tangle >> 16) { self.counters.cache_hits += 1; }
+            else { self.counters.cache_misses += 1; }
+        }
+
         // validate self.prev_rot, self.prev_out
         if self.prev_hi != (tangle >> 16) {
             self.prev_hi = tangle >> 16;
@@ -216,31 +301,124 @@ impl BytewiseCached {
                 (0x00, 0x0F) => (true, false),  // swapped
                 (0xF0, 0xFF) => (false, true),  // flipped
                 (0xFF, 0xF0) => (true, true),   // flipped & swapped
-                val => panic!(format!("Found : ({:x}, {:x})", val.0, val.1)),
+                val => panic!("Found : ({:x}, {:x})", val.0, val.1),
             };
             self.prev_out = (x & 0xFFFFFF00, y & 0xFFFFFF00);
         }
 
-
         if self.prev_rot.1 {
             x_byte = 255 - x_byte;
             y_byte = 255 - y_byte;
         }
         if self.prev_rot.0 {
-            let temp = x_byte; x_byte = y_byte; y_byte = temp;
+            ::std::mem::swap(&mut x_byte, &mut y_byte);
         }
 
-        return (self.prev_out.0 + x_byte as u32, self.prev_out.1 + y_byte as u32);
+        (self.prev_out.0 + x_byte as u32, self.prev_out.1 + y_byte as u32)
     }
     pub fn new() -> BytewiseCached {
         let mut result = BytewiseCached {
-            hilbert: Hilbert::new(),
+            hilbert: Hilbert::shared(),
             prev_hi: 0xFFFFFFFFFFFFFFFF,
             prev_out: (0,0),
             prev_rot: (false, false),
+            #[cfg(feature = "counters")]
+            counters: Counters::default(),
         };
 
         result.detangle(0); // ensures that we set the cached stuff correctly
-        return result;
+        count! { result.counters = Counters::default(); }
+        result
+    }
+    /// The cache hits and misses of the keys detangled so far, with the `counters` feature.
+    pub fn counters(&self) -> Counters { counted!(self.counters) }
+}
+
+impl Default for BytewiseCached {
+    fn default() -> BytewiseCached { BytewiseCached::new() }
+}
+
+/// Tangles u32 triples along a Hilbert space-filling curve through the `2^32` cube.
+///
+/// Keys are 96 bits, three per level of the cube from the top, and consecutive keys are always
+/// adjacent points, a unit step along one axis apart. The curve starts at the origin and fills each
+/// aligned sub-cube before leaving it, so the `2^21` cube at the origin holds exactly the keys
+/// below `2^63`, which `entangle_narrow` and `detangle_narrow` represent as `u64`. The curve is
+/// computed a bit at a time, following Skilling's transposed form.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Hilbert3D;
+
+impl Hilbert3D {
+    pub fn new() -> Hilbert3D { Hilbert3D }
+
+    /// Maps a `(u32, u32, u32)` triple to its position along the curve.
+    pub fn entangle(&self, (x, y, z): (u32, u32, u32)) -> u128 {
+        let mut axes = [x, y, z];
+        // undo the rotations and reflections of each level, from the top.
+        for log_q in (1 .. 32).rev() {
+            let (q, p) = (1u32 << log_q, (1u32 << log_q) - 1);
+            for i in 0 .. 3 {
+                if axes[i] & q != 0 { axes[0] ^= p; }
+                else {
+                    let swapped = (axes[0] ^ axes[i]) & p;
+                    axes[0] ^= swapped;
+                    axes[i] ^= swapped;
+                }
+            }
+        }
+        // gray encode.
+        axes[1] ^= axes[0];
+        axes[2] ^= axes[1];
+        let mut flips = 0;
+        for log_q in (1 .. 32).rev() {
+            if axes[2] & (1 << log_q) != 0 { flips ^= (1 << log_q) - 1; }
+        }
+        let mut result = 0u128;
+        for log_s in (0 .. 32).rev() {
+            for axis in axes {
+                result = (result << 1) | (((axis ^ flips) >> log_s) & 1) as u128;
+            }
+        }
+        result
+    }
+
+    /// Maps a position along the curve back to its triple.
+    ///
+    /// Only the low 96 bits of `tangle` are read.
+    pub fn detangle(&self, tangle: u128) -> (u32, u32, u32) {
+        let mut axes = [0u32; 3];
+        for log_s in 0 .. 32 {
+            for (i, axis) in axes.iter_mut().enumerate() {
+                *axis |= (((tangle >> (3 * log_s + 2 - i)) & 1) as u32) << log_s;
+            }
+        }
+        // gray decode.
+        let flips = axes[2] >> 1;
+        axes[2] ^= axes[1];
+        axes[1] ^= axes[0];
+        axes[0] ^= flips;
+        // redo the rotations and reflections of each level, from the bottom.
+        for log_q in 1 .. 32 {
+            let (q, p) = (1u32 << log_q, (1u32 << log_q) - 1);
+            for i in (0 .. 3).rev() {
+                if axes[i] & q != 0 { axes[0] ^= p; }
+                else {
+                    let swapped = (axes[0] ^ axes[i]) & p;
+                    axes[0] ^= swapped;
+                    axes[i] ^= swapped;
+                }
+            }
+        }
+        (axes[0], axes[1], axes[2])
+    }
+
+    /// Maps a triple of values below `2^21` to its position along the curve, which is below `2^63`.
+    pub fn entangle_narrow(&self, triple: (u32, u32, u32)) -> u64 {
+        debug_assert!(triple.0 < (1 << 21) && triple.1 < (1 << 21) && triple.2 < (1 << 21));
+        self.entangle(triple) as u64
+    }
+    /// Maps a position below `2^63` along the curve back to its triple, of values below `2^21`.
+    pub fn detangle_narrow(&self, tangle: u64) -> (u32, u32, u32) {
+        self.detangle(tangle as u128)
     }
 }
@@ -1,3 +1,104 @@
+//! Graphs laid out along the Hilbert curve, as compressed streams of curve keys.
+//!
+//! The crate is split into cargo features, all enabled by default, so that builds for embedded
+//! and `wasm` targets can compile only what they use:
+//!
+//! * `curves`: the curves themselves, in `layout`.
+//! * `codecs`: compressed key streams and the layouts built on them, their queries, and the other
+//!   compressed representations of graphs. Requires `curves`.
+//! * `io`: file formats, layout files, and the external-memory `builder`. Requires `codecs`.
+//! * `algos`: graph kernels, vertex orderings, and generators. Requires `codecs`.
+//! * `cli`: the `graph-layout` command-line tool. Requires `io` and `algos`.
+//!
+//! The features adding dependencies, such as `rayon`, `mmap`, and `serde`, are off by default and
+//! enable those they build on.
+//!
+//! Everything this crate writes, from encoded key streams and layout files with their block and
+//! tile directories to run files, permutations, and binary edge files, stores integers and floats
+//! little-endian, whatever the byte order of the host. Values are converted with `to_le_bytes` and
+//! `from_le_bytes` as they are written and read, never by reinterpreting memory, and the
+//! `clippy::host_endian_bytes` lint rules out the native-order conversions. Streams archived with
+//! the `rkyv` feature are little-endian as well. Files written on one architecture therefore read
+//! unchanged on any other. Only the `abomonation` feature copies memory as it is, for exchange
+//! between processes of one build rather than for storage.
 
+#![deny(clippy::host_endian_bytes)]
+
+#[cfg(feature = "codecs")]
+#[macro_use]
+mod trace;
+#[macro_use]
+pub mod counters;
+#[cfg(feature = "abomonation")]
+#[macro_use]
+mod abomonate;
+
+pub mod error;
+#[cfg(feature = "curves")]
 pub mod layout;
+#[cfg(feature = "codecs")]
 pub mod compression;
+#[cfg(feature = "codecs")]
+pub mod column;
+#[cfg(feature = "codecs")]
+pub mod graph;
+#[cfg(feature = "codecs")]
+pub mod wide;
+#[cfg(feature = "codecs")]
+pub mod referenced;
+#[cfg(feature = "codecs")]
+pub mod dense;
+#[cfg(feature = "codecs")]
+pub mod gapped;
+#[cfg(feature = "codecs")]
+pub mod intervals;
+#[cfg(feature = "io")]
+pub mod builder;
+#[cfg(feature = "codecs")]
+pub mod query;
+#[cfg(feature = "codecs")]
+pub mod curvemap;
+#[cfg(feature = "codecs")]
+pub mod nearest;
+#[cfg(feature = "codecs")]
+pub mod execute;
+#[cfg(feature = "codecs")]
+pub mod scratch;
+#[cfg(feature = "io")]
+pub mod sink;
+#[cfg(feature = "codecs")]
+pub mod densify;
+#[cfg(feature = "codecs")]
+pub mod partition;
+#[cfg(feature = "codecs")]
+pub mod differential;
+#[cfg(feature = "algos")]
+pub mod algo;
+#[cfg(feature = "codecs")]
+pub mod stats;
+#[cfg(feature = "codecs")]
+pub mod viz;
+#[cfg(feature = "algos")]
+pub mod generate;
+#[cfg(feature = "algos")]
+pub mod order;
+#[cfg(feature = "io")]
+pub mod io;
+#[cfg(feature = "codecs")]
+pub mod progress;
+
+#[cfg(feature = "cdylib")]
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "python")]
+pub mod python;
+
+pub use crate::error::{Error, Result};
+
+#[cfg(feature = "codecs")]
+mod csr;
+#[cfg(feature = "codecs")]
+mod rng;
+#[cfg(feature = "serde")]
+mod serialize;
@@ -0,0 +1,108 @@
+//! Nearest-neighbor probes against the stored edges of a layout.
+//!
+//! Points near each other on the Hilbert curve are near each other in the plane, although points
+//! near each other in the plane may lie far apart on the curve, on either side of the boundary of
+//! a large aligned square. `GraphLayout::nearest` returns the edges whose curve keys are closest to
+//! a probe's, which decodes only the blocks around the probe's place in the stream and suits
+//! approximate probes.
+//!
+//! `GraphLayout::nearest_l1` refines these to the edges nearest by L1 distance. The farthest of
+//! the `k` curve neighbors bounds the distance of the `k` nearest edges, which then all lie in the
+//! square of that radius around the probe; the square's edges are read through the curve ranges
+//! covering it, as planned by `query`, and the nearest `k` of them kept.
+
+use crate::graph::GraphLayout;
+use crate::layout::{Hilbert, Tangle};
+use crate::query::Rect;
+
+/// A point to probe for nearby edges, given by its curve key or its coordinates.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Probe {
+    /// The point with this curve key.
+    Key(u64),
+    /// The point with this source and destination.
+    Point(u32, u32),
+}
+
+impl Probe {
+    /// The curve key of the point.
+    pub fn key(&self) -> u64 {
+        match *self {
+            Probe::Key(key) => key,
+            Probe::Point(x, y) => Hilbert::shared().entangle((x, y)),
+        }
+    }
+    /// The coordinates of the point.
+    pub fn point(&self) -> (u32, u32) {
+        match *self {
+            Probe::Key(key) => Hilbert::shared().detangle(key),
+            Probe::Point(x, y) => (x, y),
+        }
+    }
+}
+
+impl From<u64> for Probe {
+    fn from(key: u64) -> Probe { Probe::Key(key) }
+}
+
+impl From<(u32, u32)> for Probe {
+    fn from((x, y): (u32, u32)) -> Probe { Probe::Point(x, y) }
+}
+
+impl GraphLayout {
+    /// The `k` edges whose curve keys are closest to the probe's, nearest first, with ties going
+    /// to the smaller key.
+    pub fn nearest<P: Into<Probe>>(&self, probe: P, k: usize) -> Vec<(u32, u32)> {
+        let hilbert = Hilbert::shared();
+        self.nearest_keys(probe.into().key(), k).into_iter().map(|key| hilbert.detangle(key)).collect()
+    }
+
+    /// The `k` edges closest to the probe by L1 distance in coordinate space, nearest first, with
+    /// ties in curve order.
+    pub fn nearest_l1<P: Into<Probe>>(&self, probe: P, k: usize) -> Vec<(u32, u32)> {
+        let (x, y) = probe.into().point();
+        let distance = |(src, dst): (u32, u32)| (src as u64).abs_diff(x as u64) + (dst as u64).abs_diff(y as u64);
+        let radius = match self.nearest(Probe::Point(x, y), k).into_iter().map(distance).max() {
+            Some(radius) => radius,
+            None => return Vec::new(),
+        };
+        let side = ::std::cmp::min(radius, u32::MAX as u64) as u32;
+        let rect = Rect::new(x.saturating_sub(side) ..= x.saturating_add(side), y.saturating_sub(side) ..= y.saturating_add(side));
+        let mut nearest = self.edges_in(rect).filter(|&edge| distance(edge) <= radius).collect::<Vec<_>>();
+        // a stable sort keeps edges at equal distances in curve order.
+        nearest.sort_by_key(|&edge| distance(edge));
+        nearest.truncate(k);
+        nearest
+    }
+
+    /// The `k` curve keys closest to `key`, nearest first, with ties going to the smaller key.
+    fn nearest_keys(&self, key: u64, k: usize) -> Vec<u64> {
+        // the block holding the first key at least `key`, or none if all keys are less.
+        let index = self.blocks.partition_point(|block| block.upper < key);
+        let above = self.blocks[index ..].first().into_iter().flat_map(|block| {
+            self.keys.decompress_from(block.position).take(self.len() - block.offset)
+        });
+        let above = above.skip_while(|&other| other < key).take(k).collect::<Vec<_>>();
+
+        // the stream decodes only forward, so keys below are read a block at a time, backward.
+        let mut below = Vec::with_capacity(k);
+        for block in self.blocks[.. ::std::cmp::min(index + 1, self.blocks.len())].iter().rev() {
+            if below.len() >= k { break; }
+            let keys = self.block_keys(block).take_while(|&other| other < key).collect::<Vec<_>>();
+            below.extend(keys.into_iter().rev());
+        }
+
+        let mut nearest = Vec::with_capacity(k);
+        let (mut above, mut below) = (above.into_iter().peekable(), below.into_iter().peekable());
+        while nearest.len() < k {
+            let next = match (above.peek(), below.peek()) {
+                (Some(&up), Some(&down)) if up - key < key - down => above.next(),
+                (_, Some(_)) => below.next(),
+                (Some(_), None) => above.next(),
+                (None, None) => break,
+            };
+            nearest.extend(next);
+        }
+        nearest
+    }
+}
@@ -0,0 +1,15 @@
+//! Relabeling by decreasing degree.
+
+use crate::graph::GraphLayout;
+use crate::order::Permutation;
+
+/// Labels vertices in decreasing order of total degree, breaking ties by identifier.
+///
+/// Hubs receive the smallest identifiers, so the many edges incident on them fall in a narrow band
+/// of the adjacency matrix.
+pub fn by_degree(layout: &GraphLayout) -> Permutation {
+    let (in_degrees, out_degrees) = layout.degrees();
+    let mut order = (0 .. layout.vertices() as u32).collect::<Vec<_>>();
+    order.sort_by_key(|&vertex| ::std::cmp::Reverse(in_degrees[vertex as usize] as u64 + out_degrees[vertex as usize] as u64));
+    Permutation::from_order(&order).unwrap()
+}
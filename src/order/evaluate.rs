@@ -0,0 +1,43 @@
+//! Side-by-side comparison of candidate orderings.
+
+use crate::graph::GraphLayout;
+use crate::order::Permutation;
+use crate::stats::{locality, Locality};
+
+/// The quality of one candidate ordering, measured on the relabeled layout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation {
+    /// The name the candidate was given.
+    pub name: String,
+    /// Gap, cache, and compression statistics of the relabeled curve order.
+    pub locality: Locality,
+    /// Bytes of compressed key data.
+    pub bytes: usize,
+    /// The largest difference between the endpoints of an edge.
+    pub bandwidth: u32,
+    /// The mean difference between the endpoints of an edge.
+    pub mean_span: f64,
+}
+
+/// Relabels `layout` by each named candidate and measures the result, in the order given.
+///
+/// Include `Permutation::identity` among the candidates to compare against the current labels.
+pub fn evaluate(layout: &GraphLayout, candidates: &[(&str, Permutation)]) -> Vec<Evaluation> {
+    candidates.iter().map(|(name, permutation)| {
+        let relabeled = permutation.apply_to_layout(layout);
+        let mut bandwidth = 0;
+        let mut total = 0u64;
+        for (src, dst) in relabeled.edges() {
+            let span = src.abs_diff(dst);
+            bandwidth = ::std::cmp::max(bandwidth, span);
+            total += span as u64;
+        }
+        Evaluation {
+            name: name.to_string(),
+            locality: locality(&relabeled),
+            bytes: relabeled.keys.size_bytes(),
+            bandwidth,
+            mean_span: total as f64 / ::std::cmp::max(relabeled.len(), 1) as f64,
+        }
+    }).collect()
+}
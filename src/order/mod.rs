@@ -0,0 +1,155 @@
+//! Vertex relabelings that improve the locality of a layout.
+//!
+//! The curve keeps edges between nearby identifiers close together, so relabeling vertices such
+//! that neighbors receive nearby identifiers shortens the gaps between consecutive keys, which in
+//! turn improves both compression and the cache behavior of kernels. Each ordering produces a
+//! `Permutation`, which can then relabel the layout and any per-vertex data, and `evaluate`
+//! compares the effect of several candidates. The `relabel` module applies a permutation to edge
+//! files as they stream past, for graphs too large to load, with the `io` feature.
+//!
+//! A permutation saved with `Permutation::save` is the eight bytes `GLPERM\0\0`, the vertex count
+//! as a little-endian `u64`, and then the new identifier of each old identifier as a little-endian
+//! `u32`.
+
+pub mod degree;
+pub mod evaluate;
+pub mod gorder;
+mod permute;
+pub mod random;
+pub mod rcm;
+#[cfg(feature = "io")]
+pub mod relabel;
+
+pub use self::degree::by_degree;
+pub use self::evaluate::{evaluate, Evaluation};
+pub use self::gorder::gorder;
+pub use self::random::random;
+pub use self::rcm::rcm;
+#[cfg(feature = "io")]
+pub use self::relabel::{relabel, relabel_binary_edges, relabel_edge_list, relabel_into_builder, Relabeling};
+#[cfg(feature = "mmap")]
+pub use self::relabel::MappedPermutation;
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::graph::GraphLayout;
+use crate::layout::{Hilbert, Tangle};
+
+const MAGIC: &[u8; 8] = b"GLPERM\0\0";
+
+/// A relabeling of vertices `0 .. len` to new identifiers `0 .. len`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permutation {
+    /// The new identifier of each old identifier.
+    new_ids: Vec<u32>,
+}
+
+impl Permutation {
+    /// The permutation leaving each of `len` vertices in place.
+    pub fn identity(len: usize) -> Permutation {
+        Permutation { new_ids: (0 .. len as u32).collect() }
+    }
+    /// The permutation sending each old identifier `v` to `new_ids[v]`.
+    ///
+    /// Returns `None` unless `new_ids` contains each of `0 .. new_ids.len()` exactly once.
+    pub fn from_new_ids(new_ids: Vec<u32>) -> Option<Permutation> {
+        let mut seen = vec![false; new_ids.len()];
+        for &id in &new_ids {
+            if id as usize >= seen.len() || seen[id as usize] {
+                return None;
+            }
+            seen[id as usize] = true;
+        }
+        Some(Permutation { new_ids })
+    }
+    /// The permutation assigning new identifiers in the order old identifiers appear in `order`.
+    ///
+    /// Returns `None` unless `order` contains each of `0 .. order.len()` exactly once.
+    pub fn from_order(order: &[u32]) -> Option<Permutation> {
+        let mut new_ids = vec![u32::MAX; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            if old as usize >= new_ids.len() || new_ids[old as usize] != u32::MAX {
+                return None;
+            }
+            new_ids[old as usize] = new as u32;
+        }
+        Some(Permutation { new_ids })
+    }
+
+    /// The number of vertices relabeled.
+    pub fn len(&self) -> usize { self.new_ids.len() }
+    /// Indicates that the permutation relabels no vertices.
+    pub fn is_empty(&self) -> bool { self.new_ids.is_empty() }
+    /// The new identifier of `old`.
+    pub fn new_id(&self, old: u32) -> u32 { self.new_ids[old as usize] }
+    /// The new identifier of each old identifier.
+    pub fn new_ids(&self) -> &[u32] { &self.new_ids[..] }
+
+    /// The permutation undoing this one, sending each new identifier back to its old identifier.
+    pub fn inverse(&self) -> Permutation {
+        let mut old_ids = vec![0u32; self.len()];
+        for (old, &new) in self.new_ids.iter().enumerate() {
+            old_ids[new as usize] = old as u32;
+        }
+        Permutation { new_ids: old_ids }
+    }
+    /// The permutation applying this one and then `next`.
+    ///
+    /// Panics if the permutations relabel different numbers of vertices.
+    pub fn compose(&self, next: &Permutation) -> Permutation {
+        assert_eq!(self.len(), next.len(), "composed permutations must have equal lengths");
+        Permutation { new_ids: self.new_ids.iter().map(|&new| next.new_id(new)).collect() }
+    }
+
+    /// Relabels the edges of `layout`, keeping its tiling, weights, and columns.
+    ///
+    /// Panics if the layout has vertices the permutation does not cover.
+    pub fn apply_to_layout(&self, layout: &GraphLayout) -> GraphLayout {
+        assert!(layout.vertices() <= self.len() as u64, "permutation does not cover every vertex");
+        let hilbert = Hilbert::new();
+        // relabeling is a bijection on edges, so the new keys stay distinct.
+        layout.rekeyed(layout.edges().map(|(src, dst)| hilbert.entangle((self.new_id(src), self.new_id(dst)))).collect())
+    }
+
+    /// Moves per-vertex values indexed by old identifier to their new identifiers.
+    ///
+    /// Panics if `column` does not have one value per vertex.
+    pub fn apply_to_column<T>(&self, column: Vec<T>) -> Vec<T> {
+        assert_eq!(column.len(), self.len(), "column length does not match the permutation");
+        let mut slots = column.into_iter().zip(self.new_ids.iter().cloned()).collect::<Vec<_>>();
+        slots.sort_unstable_by_key(|&(_, new)| new);
+        slots.into_iter().map(|(value, _)| value).collect()
+    }
+
+    /// Writes the permutation to `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.len() as u64).to_le_bytes())?;
+        for &id in &self.new_ids {
+            writer.write_all(&id.to_le_bytes())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+    /// Reads a permutation written by `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Permutation> {
+        let mut bytes = Vec::new();
+        BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+        let invalid = |message: &str| Error::corrupt("permutation file", message);
+        if bytes.len() < 16 || &bytes[.. 8] != MAGIC {
+            return Err(invalid("missing header"));
+        }
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&bytes[8 .. 16]);
+        let count = u64::from_le_bytes(count);
+        if (bytes.len() as u64 - 16) != count.saturating_mul(4) {
+            return Err(invalid("length does not match the vertex count"));
+        }
+        let new_ids = bytes[16 ..].chunks_exact(4).map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]])).collect();
+        Permutation::from_new_ids(new_ids).ok_or_else(|| invalid("not a permutation"))
+    }
+}
@@ -0,0 +1,132 @@
+//! Moving per-vertex values into the order of a permutation, in memory, in place, or on disk.
+//!
+//! Each relabeling of a graph must also be applied to its vertex properties, such as ranks, labels
+//! and feature vectors, moving the value of each old identifier to its new identifier.
+//! `Permutation::permute_slice` gathers the values into a new vector, and
+//! `Permutation::permute_in_place` follows the cycles of the permutation to move them within their
+//! slice, using one bit per vertex.
+//!
+//! `Permutation::permute_file` moves fixed-size records of a file larger than memory. It
+//! distributes the records to bucket files, each covering a range of new identifiers small enough
+//! to fit the memory budget, and then reads each bucket back, places its records, and appends the
+//! range to the output. Every record is so read and written twice, sequentially but for the
+//! buckets' interleaved appends, however large the file. Bucket files are created next to the
+//! output, as they need as much space, and are removed as they are consumed.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::order::Permutation;
+
+impl Permutation {
+    /// The values of `values`, indexed by old identifier, moved to their new identifiers.
+    ///
+    /// Panics if `values` does not have one value per vertex.
+    pub fn permute_slice<T: Clone>(&self, values: &[T]) -> Vec<T> {
+        assert_eq!(values.len(), self.len(), "slice length does not match the permutation");
+        self.inverse().new_ids().iter().map(|&old| values[old as usize].clone()).collect()
+    }
+
+    /// Moves each value of `values`, indexed by old identifier, to its new identifier.
+    ///
+    /// Panics if `values` does not have one value per vertex.
+    pub fn permute_in_place<T>(&self, values: &mut [T]) {
+        assert_eq!(values.len(), self.len(), "slice length does not match the permutation");
+        let mut placed = vec![0u64; self.len().div_ceil(64)];
+        for start in 0 .. self.len() {
+            if placed[start / 64] & (1 << (start % 64)) != 0 {
+                continue;
+            }
+            // position `start` holds the value destined for `next`; swap it there, and repeat with
+            // the value displaced until the cycle closes.
+            let mut next = self.new_id(start as u32) as usize;
+            while next != start {
+                values.swap(start, next);
+                placed[next / 64] |= 1 << (next % 64);
+                next = self.new_id(next as u32) as usize;
+            }
+            placed[start / 64] |= 1 << (start % 64);
+        }
+    }
+
+    /// Moves the records of the file at `input`, `record_bytes` bytes for each old identifier, to
+    /// their new identifiers in a file at `output`, holding at most about `memory_budget` bytes of
+    /// records in memory.
+    pub fn permute_file<P: AsRef<Path>, Q: AsRef<Path>>(&self, input: P, output: Q, record_bytes: usize, memory_budget: usize) -> Result<()> {
+        assert!(record_bytes > 0, "records must hold at least one byte");
+        let (input, output) = (input.as_ref(), output.as_ref());
+        let expected = (self.len() as u64).checked_mul(record_bytes as u64);
+        if expected != Some(::std::fs::metadata(input)?.len()) {
+            return Err(Error::corrupt("property file", "length does not match the permutation"));
+        }
+        // the new identifiers each bucket covers.
+        let chunk = ::std::cmp::max(1, memory_budget / record_bytes);
+        let buckets = ::std::cmp::max(1, self.len().div_ceil(chunk));
+        let paths = (0 .. buckets).map(|bucket| bucket_path(output, bucket)).collect::<Vec<_>>();
+
+        let result = self.permute_buckets(input, output, record_bytes, chunk, &paths);
+        for path in &paths {
+            if let Err(error) = ::std::fs::remove_file(path) {
+                if error.kind() != ErrorKind::NotFound && result.is_ok() {
+                    return Err(error.into());
+                }
+            }
+        }
+        result
+    }
+
+    fn permute_buckets(&self, input: &Path, output: &Path, record_bytes: usize, chunk: usize, paths: &[PathBuf]) -> Result<()> {
+        let mut reader = BufReader::new(File::open(input)?);
+        let mut record = vec![0u8; record_bytes];
+        let mut writer = BufWriter::new(File::create(output)?);
+        if paths.len() == 1 {
+            // everything fits in memory; skip the buckets.
+            let mut placed = vec![0u8; self.len() * record_bytes];
+            for old in 0 .. self.len() {
+                let new = self.new_id(old as u32) as usize;
+                reader.read_exact(&mut placed[new * record_bytes .. (new + 1) * record_bytes])?;
+            }
+            writer.write_all(&placed)?;
+            return Ok(writer.flush()?);
+        }
+
+        // each bucket holds the records of its range of new identifiers, each after its new identifier.
+        let mut buckets = paths.iter().map(|path| Ok(BufWriter::new(File::create(path)?))).collect::<Result<Vec<_>>>()?;
+        for old in 0 .. self.len() {
+            reader.read_exact(&mut record)?;
+            let new = self.new_id(old as u32);
+            let bucket = &mut buckets[new as usize / chunk];
+            bucket.write_all(&new.to_le_bytes())?;
+            bucket.write_all(&record)?;
+        }
+        for bucket in buckets {
+            bucket.into_inner().map_err(|error| error.into_error())?;
+        }
+
+        let mut placed = vec![0u8; chunk * record_bytes];
+        for (index, path) in paths.iter().enumerate() {
+            let first = index * chunk;
+            let records = ::std::cmp::min(chunk, self.len() - first);
+            let mut bucket = BufReader::new(File::open(path)?);
+            let mut new = [0u8; 4];
+            for _ in 0 .. records {
+                bucket.read_exact(&mut new)?;
+                let offset = (u32::from_le_bytes(new) as usize - first) * record_bytes;
+                bucket.read_exact(&mut placed[offset .. offset + record_bytes])?;
+            }
+            drop(bucket);
+            ::std::fs::remove_file(path)?;
+            writer.write_all(&placed[.. records * record_bytes])?;
+        }
+        Ok(writer.flush()?)
+    }
+}
+
+/// The path of a bucket file of `permute_file`, beside `output`.
+fn bucket_path(output: &Path, bucket: usize) -> PathBuf {
+    let mut name = output.file_name().map(|name| name.to_os_string()).unwrap_or_default();
+    name.push(format!(".bucket{}", bucket));
+    output.with_file_name(name)
+}
@@ -0,0 +1,15 @@
+//! Uniformly random relabeling, as a baseline for the other orderings.
+
+use crate::graph::GraphLayout;
+use crate::order::Permutation;
+use crate::rng::SplitMix64;
+
+/// Labels the vertices of `layout` by a uniformly random permutation determined by `seed`.
+///
+/// Scrambling destroys whatever locality the original identifiers had, which makes the result the
+/// natural baseline against which to measure other orderings.
+pub fn random(layout: &GraphLayout, seed: u64) -> Permutation {
+    let mut order = (0 .. layout.vertices() as u32).collect::<Vec<_>>();
+    SplitMix64::new(seed).shuffle(&mut order);
+    Permutation::from_order(&order).unwrap()
+}
@@ -0,0 +1,90 @@
+//! Reverse Cuthill-McKee relabeling.
+
+use crate::algo::adjacency::Adjacency;
+use crate::graph::GraphLayout;
+use crate::order::Permutation;
+
+/// Labels vertices in reverse Cuthill-McKee order, which tends to reduce the bandwidth of the
+/// adjacency matrix.
+///
+/// Edges are treated as undirected. Each connected component is searched breadth-first from a
+/// pseudo-peripheral vertex, found by repeated searches from a vertex of minimum degree, visiting
+/// the neighbors of each vertex in increasing order of degree. The concatenated visit order is
+/// then reversed. Components are taken in order of their smallest vertex.
+pub fn rcm(layout: &GraphLayout) -> Permutation {
+    let vertices = layout.vertices() as usize;
+    let adjacency = Adjacency::new(layout.edges().filter(|&(src, dst)| src != dst).flat_map(|(src, dst)| vec![(src, dst), (dst, src)]), vertices);
+
+    let mut order = Vec::with_capacity(vertices);
+    let mut visited = vec![false; vertices];
+    let mut levels = vec![u32::MAX; vertices];
+    for vertex in 0 .. vertices as u32 {
+        if visited[vertex as usize] {
+            continue;
+        }
+        let start = peripheral(&adjacency, vertex, &mut levels);
+        let first = order.len();
+        order.push(start);
+        visited[start as usize] = true;
+        let mut next = first;
+        let mut neighbors = Vec::new();
+        while next < order.len() {
+            let current = order[next];
+            next += 1;
+            neighbors.clear();
+            neighbors.extend(adjacency.neighbors(current).map(|neighbor| neighbor as u32).filter(|&neighbor| !visited[neighbor as usize]));
+            neighbors.sort_by_key(|&neighbor| (adjacency.degree(neighbor), neighbor));
+            for &neighbor in &neighbors {
+                visited[neighbor as usize] = true;
+                order.push(neighbor);
+            }
+        }
+    }
+
+    order.reverse();
+    Permutation::from_order(&order).unwrap()
+}
+
+/// Finds a pseudo-peripheral vertex in the component of `vertex`, by the method of George and Liu.
+///
+/// `levels` must hold `u32::MAX` for every vertex of the component, and is restored on return.
+fn peripheral(adjacency: &Adjacency, vertex: u32, levels: &mut [u32]) -> u32 {
+    // start from a vertex of minimum degree in the component.
+    let component = search(adjacency, vertex, levels);
+    for &v in &component {
+        levels[v as usize] = u32::MAX;
+    }
+    let mut start = component.into_iter().min_by_key(|&v| (adjacency.degree(v), v)).unwrap();
+    let mut height = 0;
+    loop {
+        let reached = search(adjacency, start, levels);
+        let depth = levels[*reached.last().unwrap() as usize];
+        let candidate = reached.iter().cloned().filter(|&v| levels[v as usize] == depth).min_by_key(|&v| (adjacency.degree(v), v)).unwrap();
+        for &v in &reached {
+            levels[v as usize] = u32::MAX;
+        }
+        if depth <= height {
+            return start;
+        }
+        height = depth;
+        start = candidate;
+    }
+}
+
+/// Records breadth-first levels from `root` in `levels`, returning the vertices in visit order.
+fn search(adjacency: &Adjacency, root: u32, levels: &mut [u32]) -> Vec<u32> {
+    let mut reached = vec![root];
+    levels[root as usize] = 0;
+    let mut next = 0;
+    while next < reached.len() {
+        let current = reached[next];
+        next += 1;
+        for neighbor in adjacency.neighbors(current) {
+            if levels[neighbor as usize] == u32::MAX {
+                levels[neighbor as usize] = levels[current as usize] + 1;
+                reached.push(neighbor as u32);
+            }
+        }
+    }
+    reached
+}
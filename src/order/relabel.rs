@@ -0,0 +1,174 @@
+//! Relabeling edge files as they stream past, without loading the graph.
+//!
+//! Applying a permutation to a layout requires the layout in memory, which rules out edge files
+//! much larger than memory. The functions here instead read an edge file one edge at a time,
+//! relabel both endpoints, and write each edge out at once, either to another edge file or into a
+//! `LayoutBuilder`, which sorts the relabeled edges in bounded memory. Only the permutation is held,
+//! at four bytes per vertex; with the `mmap` feature a `MappedPermutation` serves the new
+//! identifiers straight from a file written by `Permutation::save`, so that even that is paged in
+//! on demand.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::builder::LayoutBuilder;
+use crate::error::{Error, Result};
+use crate::io::{read_binary_edges, read_edge_list};
+use crate::order::Permutation;
+
+/// A source of new identifiers for the vertices `0 .. len`.
+pub trait Relabeling {
+    /// The number of vertices relabeled.
+    fn len(&self) -> usize;
+    /// Indicates that no vertices are relabeled.
+    fn is_empty(&self) -> bool { self.len() == 0 }
+    /// The new identifier of `old`, which must be less than `len`.
+    fn new_id(&self, old: u32) -> u32;
+}
+
+impl Relabeling for Permutation {
+    fn len(&self) -> usize { Permutation::len(self) }
+    fn new_id(&self, old: u32) -> u32 { Permutation::new_id(self, old) }
+}
+
+/// Relabels a stream of edges, failing on the first error of the stream and on the first vertex
+/// the relabeling does not cover.
+pub fn relabel<R, I, E>(relabeling: &R, edges: I) -> Relabeled<'_, R, I>
+where
+    R: Relabeling + ?Sized,
+    I: Iterator<Item=::std::result::Result<(u32, u32), E>>,
+    E: Into<Error>,
+{
+    Relabeled { relabeling, edges }
+}
+
+/// Relabels the whitespace-separated edge list at `input`, writing the relabeled edges in the same
+/// order to `output`, one `src dst` line each.
+///
+/// Returns the number of edges written. Inputs are decompressed according to their extension, as
+/// by `io::read_edge_list`.
+pub fn relabel_edge_list<R, P, Q>(relabeling: &R, input: P, output: Q) -> Result<u64>
+where
+    R: Relabeling + ?Sized,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let mut writer = BufWriter::new(File::create(output)?);
+    let mut count = 0;
+    for edge in relabel(relabeling, read_edge_list(input)?) {
+        let (src, dst) = edge?;
+        writeln!(writer, "{} {}", src, dst)?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Relabels the binary edge file at `input`, writing the relabeled edges in the same order to
+/// `output` as little-endian `(u32, u32)` pairs.
+///
+/// Returns the number of edges written.
+pub fn relabel_binary_edges<R, P, Q>(relabeling: &R, input: P, output: Q) -> Result<u64>
+where
+    R: Relabeling + ?Sized,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let mut writer = BufWriter::new(File::create(output)?);
+    let mut count = 0;
+    for edge in relabel(relabeling, read_binary_edges(input)?) {
+        let (src, dst) = edge?;
+        writer.write_all(&src.to_le_bytes())?;
+        writer.write_all(&dst.to_le_bytes())?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Relabels a stream of edges straight into `builder`, which spills them to sorted runs as its
+/// buffer fills.
+///
+/// Returns the number of edges pushed. Pass the result of `io::read_edge_list` or
+/// `io::read_binary_edges` to relabel an edge file into a layout file.
+pub fn relabel_into_builder<R, I, E>(relabeling: &R, edges: I, builder: &mut LayoutBuilder) -> Result<u64>
+where
+    R: Relabeling + ?Sized,
+    I: Iterator<Item=::std::result::Result<(u32, u32), E>>,
+    E: Into<Error>,
+{
+    let mut count = 0;
+    for edge in relabel(relabeling, edges) {
+        builder.push(edge?)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Iterates over the relabeled edges of a stream, by `relabel`.
+pub struct Relabeled<'a, R: ?Sized, I> {
+    relabeling: &'a R,
+    edges: I,
+}
+
+impl<'a, R, I, E> Iterator for Relabeled<'a, R, I>
+where
+    R: Relabeling + ?Sized,
+    I: Iterator<Item=::std::result::Result<(u32, u32), E>>,
+    E: Into<Error>,
+{
+    type Item = Result<(u32, u32)>;
+    fn next(&mut self) -> Option<Result<(u32, u32)>> {
+        let (src, dst) = match self.edges.next()? {
+            Ok(edge) => edge,
+            Err(error) => return Some(Err(error.into())),
+        };
+        let len = self.relabeling.len();
+        for vertex in [src, dst] {
+            if vertex as usize >= len {
+                let message = format!("vertex {} outside a relabeling of {} vertices", vertex, len);
+                return Some(Err(Error::corrupt("edge", &message)));
+            }
+        }
+        Some(Ok((self.relabeling.new_id(src), self.relabeling.new_id(dst))))
+    }
+}
+
+/// A permutation read on demand from a memory-mapped file written by `Permutation::save`, with the
+/// `mmap` feature.
+///
+/// Opening the file checks its header and length but not that it holds a permutation, as that
+/// would read the whole file; `Permutation::load` performs that check.
+#[cfg(feature = "mmap")]
+pub struct MappedPermutation {
+    map: ::memmap2::Mmap,
+    len: usize,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedPermutation {
+    /// Maps the permutation file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MappedPermutation> {
+        let file = File::open(path)?;
+        let map = unsafe { ::memmap2::Mmap::map(&file)? };
+        if map.len() < 16 || &map[.. 8] != super::MAGIC {
+            return Err(Error::corrupt("permutation file", "missing header"));
+        }
+        let count = u64::from_le_bytes([map[8], map[9], map[10], map[11], map[12], map[13], map[14], map[15]]);
+        if (map.len() as u64 - 16) != count.saturating_mul(4) || count > 1 << 32 {
+            return Err(Error::corrupt("permutation file", "length does not match the vertex count"));
+        }
+        Ok(MappedPermutation { map, len: count as usize })
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Relabeling for MappedPermutation {
+    fn len(&self) -> usize { self.len }
+    fn new_id(&self, old: u32) -> u32 {
+        let offset = 16 + 4 * old as usize;
+        let id = &self.map[offset .. offset + 4];
+        u32::from_le_bytes([id[0], id[1], id[2], id[3]])
+    }
+}
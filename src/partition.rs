@@ -0,0 +1,210 @@
+//! Assignment of Hilbert tiles to workers.
+//!
+//! A tiled `GraphLayout` can be distributed by handing each worker a set of tiles. Two placements
+//! are supported: contiguous runs of the curve, balanced by edge count, and a 2D grid of blocks of
+//! the adjacency matrix, which bounds the number of distinct vertices each worker touches.
+//!
+//! For each worker we produce the sub-layout of its tiles, along with the source and destination
+//! vertex ranges it reads from and writes to, which are what it must exchange with other workers.
+//!
+//! Edges still in flight can be routed the same way. `tile_route` and `assigned_route` build the
+//! `Fn(&(u32, u32)) -> u64` routing functions that timely dataflow's `Exchange` pact expects, and
+//! each worker gathers the edges it receives, batch by batch, into an `Accumulator` that builds
+//! its layout.
+
+use crate::graph::GraphLayout;
+use crate::layout::{Hilbert, Tangle};
+
+/// How tiles should be placed on workers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// Contiguous ranges of the curve with nearly equal edge counts.
+    Contiguous,
+    /// Rectangular blocks of the adjacency matrix, arranged in a near-square grid of workers.
+    Blocks,
+}
+
+/// The tiles assigned to one worker.
+pub struct Part {
+    /// Indices of the assigned tiles in the layout's tile directory.
+    pub tiles: Vec<usize>,
+    /// The edges of the assigned tiles.
+    pub layout: GraphLayout,
+    /// Disjoint inclusive ranges of source vertices touched by the assigned tiles, in order.
+    pub sources: Vec<(u32, u32)>,
+    /// Disjoint inclusive ranges of destination vertices touched by the assigned tiles, in order.
+    pub destinations: Vec<(u32, u32)>,
+}
+
+/// Assigns each tile of `layout` to one of `workers` workers.
+///
+/// The result is aligned with `layout.tiles()`. Panics if the layout is not tiled.
+pub fn assign(layout: &GraphLayout, workers: usize, placement: Placement) -> Vec<usize> {
+    assert!(workers > 0, "at least one worker is required");
+    let log = layout.log_tile().expect("partitioning requires a tiled layout");
+    let tiles = layout.tiles();
+    match placement {
+        Placement::Contiguous => {
+            // each tile goes to the worker whose share of the edges contains the tile's midpoint.
+            let total = layout.len().max(1) as u64;
+            let mut before = 0u64;
+            tiles.iter().map(|tile| {
+                let middle = before + (tile.edges as u64) / 2;
+                before += tile.edges as u64;
+                ::std::cmp::min(workers - 1, (middle * workers as u64 / total) as usize)
+            }).collect()
+        },
+        Placement::Blocks => {
+            let mut rows = (workers as f64).sqrt() as usize;
+            while !workers.is_multiple_of(rows) { rows -= 1; }
+            let cols = workers / rows;
+
+            let corners = corners(layout);
+            let side = 1u64 << log;
+            let extent_x = corners.iter().map(|&(x, _)| x as u64 + side).max().unwrap_or(side);
+            let extent_y = corners.iter().map(|&(_, y)| y as u64 + side).max().unwrap_or(side);
+            // block dimensions are rounded up to whole tiles, so no tile straddles two blocks.
+            let height = round_up(extent_x.div_ceil(rows as u64), side);
+            let width = round_up(extent_y.div_ceil(cols as u64), side);
+            corners.iter().map(|&(x, y)| {
+                (x as u64 / height) as usize * cols + (y as u64 / width) as usize
+            }).collect()
+        },
+    }
+}
+
+/// Splits `layout` among `workers` workers, producing a sub-layout and exchange ranges for each.
+///
+/// Sub-layouts use the same tile size as `layout`. Panics if the layout is not tiled.
+pub fn partition(layout: &GraphLayout, workers: usize, placement: Placement) -> Vec<Part> {
+    let assignment = assign(layout, workers, placement);
+    let log = layout.log_tile().unwrap();
+    let side = 1u64 << log;
+    let corners = corners(layout);
+
+    let mut parts = Vec::with_capacity(workers);
+    for worker in 0 .. workers {
+        let tiles = (0 .. assignment.len()).filter(|&i| assignment[i] == worker).collect::<Vec<_>>();
+
+        let mut keys = Vec::new();
+        let mut weights = Vec::new();
+        let mut kept = Vec::new();
+        for &index in &tiles {
+            let tile = &layout.tiles()[index];
+            keys.extend(layout.tile_keys(tile));
+            if let Some(all) = layout.weights() {
+                weights.extend_from_slice(&all[tile.offset .. tile.offset + tile.edges]);
+            }
+            if !layout.columns().is_empty() {
+                kept.extend(tile.offset .. tile.offset + tile.edges);
+            }
+        }
+        let mut sublayout = match layout.weights() {
+            Some(_) => GraphLayout::from_weighted_keys(keys.into_iter().zip(weights).collect(), Some(log)),
+            None => GraphLayout::from_keys(keys, Some(log)),
+        };
+        // tiles are taken in curve order, so the sorted keys stay aligned with `kept`.
+        sublayout.columns = layout.columns().iter().map(|(name, column)| (name.clone(), column.select(&kept))).collect();
+
+        let mut sources = tiles.iter().map(|&i| corners[i].0 as u64).collect::<Vec<_>>();
+        let mut destinations = tiles.iter().map(|&i| corners[i].1 as u64).collect::<Vec<_>>();
+        parts.push(Part {
+            layout: sublayout,
+            sources: ranges(&mut sources, side),
+            destinations: ranges(&mut destinations, side),
+            tiles,
+        });
+    }
+
+    parts
+}
+
+/// The minimum `(src, dst)` corner of each tile in the layout.
+fn corners(layout: &GraphLayout) -> Vec<(u32, u32)> {
+    let log = layout.log_tile().unwrap();
+    let hilbert = Hilbert::new();
+    layout.tiles().iter().map(|tile| {
+        let (x, y) = hilbert.detangle(tile.lower);
+        ((x >> log) << log, (y >> log) << log)
+    }).collect()
+}
+
+/// Merges tile-aligned starting offsets into disjoint inclusive vertex ranges.
+fn ranges(starts: &mut [u64], side: u64) -> Vec<(u32, u32)> {
+    starts.sort_unstable();
+    let mut result: Vec<(u32, u32)> = Vec::new();
+    for &start in starts.iter() {
+        let last = (start + side - 1) as u32;
+        match result.last_mut() {
+            Some(range) if range.1 as u64 + 1 >= start => { range.1 = ::std::cmp::max(range.1, last); },
+            _ => { result.push((start as u32, last)); },
+        }
+    }
+    result
+}
+
+fn round_up(value: u64, multiple: u64) -> u64 {
+    value.div_ceil(multiple) * multiple
+}
+
+/// A routing function sending each edge to the index of its tile along the curve, for tiles of
+/// side `2^log_tile`.
+///
+/// Timely dataflow reduces the result modulo the number of workers, so tiles are dealt to workers
+/// in turn and every edge of a tile reaches the same worker.
+pub fn tile_route(log_tile: u32) -> impl Fn(&(u32, u32)) -> u64 + Send + Sync + 'static {
+    assert!(log_tile < 32, "tiles must be smaller than the full 2^32 × 2^32 space");
+    let hilbert = Hilbert::new();
+    move |&edge| hilbert.entangle(edge) >> (2 * log_tile)
+}
+
+/// A routing function sending each edge to the worker `assign` gives its tile of `layout`, so that
+/// updates to a partitioned layout reach the worker holding the rest of their tile.
+///
+/// Edges outside the layout's tiles are dealt by tile index, as `tile_route` does. The result is
+/// less than `workers`, and so is unchanged by timely dataflow's reduction modulo `workers`.
+/// Panics if the layout is not tiled.
+pub fn assigned_route(layout: &GraphLayout, workers: usize, placement: Placement) -> impl Fn(&(u32, u32)) -> u64 + Send + Sync + 'static {
+    let assignment = assign(layout, workers, placement);
+    let log = layout.log_tile().unwrap();
+    let tiles = layout.tiles().iter().map(|tile| tile.lower >> (2 * log)).collect::<Vec<_>>();
+    let hilbert = Hilbert::new();
+    move |&edge| {
+        let tile = hilbert.entangle(edge) >> (2 * log);
+        match tiles.binary_search(&tile) {
+            Ok(index) => assignment[index] as u64,
+            Err(_) => tile % workers as u64,
+        }
+    }
+}
+
+/// Gathers the edges a worker receives, in any order and in batches of any size, into a layout.
+pub struct Accumulator {
+    hilbert: Hilbert,
+    log_tile: Option<u32>,
+    keys: Vec<u64>,
+}
+
+impl Accumulator {
+    /// An empty accumulator, whose layout will be tiled with tiles of side `2^log_tile` if supplied.
+    pub fn new(log_tile: Option<u32>) -> Accumulator {
+        if let Some(log) = log_tile {
+            assert!(log < 32, "tiles must be smaller than the full 2^32 × 2^32 space");
+        }
+        Accumulator { hilbert: Hilbert::new(), log_tile, keys: Vec::new() }
+    }
+    /// Adds a batch of received edges.
+    pub fn push_batch(&mut self, batch: &[(u32, u32)]) {
+        let hilbert = &self.hilbert;
+        self.keys.extend(batch.iter().map(|&edge| hilbert.entangle(edge)));
+    }
+    /// The number of edges received, including duplicates.
+    pub fn len(&self) -> usize { self.keys.len() }
+    /// Indicates that no edges have been received.
+    pub fn is_empty(&self) -> bool { self.keys.is_empty() }
+    /// Lays out the received edges, discarding duplicates. Batches carry bare edges, so the layout
+    /// has no weights or columns.
+    pub fn finish(self) -> GraphLayout {
+        GraphLayout::from_keys(self.keys, self.log_tile)
+    }
+}
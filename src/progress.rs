@@ -0,0 +1,63 @@
+//! Progress reports from long-running operations.
+//!
+//! Loaders, `LayoutBuilder`, and `ExternalDensifier` accept a callback that receives a `Progress`
+//! each time roughly another `interval` edges have been processed, at each spill of a sorted run
+//! to disk, and once when the operation completes.
+
+/// The stage a long-running operation has reached.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Reading input edges.
+    Reading,
+    /// Writing a sorted run of edges to disk.
+    Spilling,
+    /// Merging sorted runs into the output.
+    Merging,
+    /// Finished.
+    Done,
+}
+
+/// A snapshot of a long-running operation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// The current stage.
+    pub phase: Phase,
+    /// The number of edges processed in the current stage.
+    pub edges: u64,
+    /// The number of bytes read by loaders, or written to disk by builders and sorters.
+    pub bytes: u64,
+}
+
+/// Calls an optional callback at intervals of processed edges.
+pub(crate) struct Reporter {
+    callback: Option<Box<dyn FnMut(Progress) + Send>>,
+    interval: u64,
+    next: u64,
+}
+
+impl Reporter {
+    /// A reporter that reports nothing.
+    pub fn none() -> Reporter {
+        Reporter { callback: None, interval: u64::MAX, next: u64::MAX }
+    }
+    /// A reporter calling `callback` about every `interval` edges.
+    pub fn new<F: FnMut(Progress) + Send + 'static>(interval: u64, callback: F) -> Reporter {
+        let interval = ::std::cmp::max(interval, 1);
+        Reporter { callback: Some(Box::new(callback)), interval, next: interval }
+    }
+
+    /// Reports if `edges` has reached the next interval.
+    #[inline]
+    pub fn update(&mut self, phase: Phase, edges: u64, bytes: u64) {
+        if edges >= self.next {
+            self.report(phase, edges, bytes);
+        }
+    }
+    /// Reports unconditionally, and starts a new interval.
+    pub fn report(&mut self, phase: Phase, edges: u64, bytes: u64) {
+        if let Some(callback) = self.callback.as_mut() {
+            callback(Progress { phase, edges, bytes });
+            self.next = edges.saturating_add(self.interval);
+        }
+    }
+}
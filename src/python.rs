@@ -0,0 +1,106 @@
+//! Python bindings, with the `python` feature.
+//!
+//! The dynamic library of the `graph_layout_cdylib` crate in `cdylib/`, built with its `python`
+//! feature, is importable as the Python module `graph_layout` when built as an extension module,
+//! for example by `maturin`, or with `PYO3_BUILD_EXTENSION_MODULE` set. Single edges and keys are
+//! Python integers, and the `_array` variants map whole NumPy arrays at once, with edges as
+//! separate arrays of sources and destinations.
+//!
+//! Layout files are read with `GraphLayout.open`, whose methods return the layout's keys, edges,
+//! and weights as NumPy arrays in curve order.
+
+use numpy::{IntoPyArray, PyArray1, PyReadonlyArray1};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::graph::GraphLayout;
+use crate::layout::{Hilbert, Tangle};
+
+/// The curve key of the edge `(src, dst)`.
+#[pyfunction]
+pub fn entangle(src: u32, dst: u32) -> u64 {
+    Hilbert::shared().entangle((src, dst))
+}
+
+/// The edge `(src, dst)` with curve key `key`.
+#[pyfunction]
+pub fn detangle(key: u64) -> (u32, u32) {
+    Hilbert::shared().detangle(key)
+}
+
+/// The curve keys of the edges `(src[i], dst[i])`, as an array of `uint64`.
+#[pyfunction]
+pub fn entangle_array<'py>(py: Python<'py>, src: PyReadonlyArray1<'py, u32>, dst: PyReadonlyArray1<'py, u32>) -> PyResult<Bound<'py, PyArray1<u64>>> {
+    let (src, dst) = (src.as_array(), dst.as_array());
+    if src.len() != dst.len() {
+        return Err(PyValueError::new_err("sources and destinations differ in length"));
+    }
+    let hilbert = Hilbert::shared();
+    let keys = src.iter().zip(dst.iter()).map(|(&x, &y)| hilbert.entangle((x, y))).collect::<Vec<_>>();
+    Ok(keys.into_pyarray(py))
+}
+
+/// The edges of the curve keys `keys`, as arrays of `uint32` sources and destinations.
+#[pyfunction]
+pub fn detangle_array<'py>(py: Python<'py>, keys: PyReadonlyArray1<'py, u64>) -> (Bound<'py, PyArray1<u32>>, Bound<'py, PyArray1<u32>>) {
+    let hilbert = Hilbert::shared();
+    let (src, dst): (Vec<u32>, Vec<u32>) = keys.as_array().iter().map(|&key| hilbert.detangle(key)).unzip();
+    (src.into_pyarray(py), dst.into_pyarray(py))
+}
+
+/// A layout read from a file.
+#[pyclass(name = "GraphLayout", frozen)]
+pub struct PyGraphLayout {
+    layout: GraphLayout,
+}
+
+#[pymethods]
+impl PyGraphLayout {
+    /// Reads the layout file at `path`.
+    #[staticmethod]
+    pub fn open(path: &str) -> PyResult<PyGraphLayout> {
+        Ok(PyGraphLayout { layout: GraphLayout::open(path).map_err(::std::io::Error::from)? })
+    }
+    /// The number of edges.
+    pub fn __len__(&self) -> usize { self.layout.len() }
+    /// One more than the largest vertex identifier, or zero if there are no edges.
+    #[getter]
+    pub fn vertices(&self) -> u64 { self.layout.vertices() }
+    /// The base-two logarithm of the tile side, if the layout is tiled.
+    #[getter]
+    pub fn log_tile(&self) -> Option<u32> { self.layout.log_tile() }
+    /// Indicates that the layout has edge weights.
+    #[getter]
+    pub fn is_weighted(&self) -> bool { self.layout.weights().is_some() }
+    /// Indicates that the layout contains the edge `(src, dst)`.
+    pub fn has_edge(&self, src: u32, dst: u32) -> bool { self.layout.has_edge(src, dst) }
+    /// The curve keys in order, as an array of `uint64`.
+    pub fn keys<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u64>> {
+        self.layout.keys().collect::<Vec<_>>().into_pyarray(py)
+    }
+    /// The edges in curve order, as arrays of `uint32` sources and destinations.
+    pub fn edges<'py>(&self, py: Python<'py>) -> (Bound<'py, PyArray1<u32>>, Bound<'py, PyArray1<u32>>) {
+        let (src, dst): (Vec<u32>, Vec<u32>) = self.layout.edges().unzip();
+        (src.into_pyarray(py), dst.into_pyarray(py))
+    }
+    /// The edge weights in curve order, as an array of `float64`, or `None` if unweighted.
+    pub fn weights<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyArray1<f64>>> {
+        self.layout.weights().map(|weights| PyArray1::from_slice(py, weights))
+    }
+    /// The in-degrees and out-degrees of vertices, as arrays of `uint32`.
+    pub fn degrees<'py>(&self, py: Python<'py>) -> (Bound<'py, PyArray1<u32>>, Bound<'py, PyArray1<u32>>) {
+        let (ins, outs) = self.layout.degrees();
+        (ins.into_pyarray(py), outs.into_pyarray(py))
+    }
+}
+
+/// The `graph_layout` Python module.
+#[pymodule]
+pub fn graph_layout(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(self::entangle, m)?)?;
+    m.add_function(wrap_pyfunction!(self::detangle, m)?)?;
+    m.add_function(wrap_pyfunction!(self::entangle_array, m)?)?;
+    m.add_function(wrap_pyfunction!(self::detangle_array, m)?)?;
+    m.add_class::<PyGraphLayout>()?;
+    Ok(())
+}
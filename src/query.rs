@@ -0,0 +1,278 @@
+//! Planned queries for the edges within rectangles of the adjacency matrix.
+//!
+//! A rectangle of sources and destinations covers a set of aligned squares of the matrix, each of
+//! which is a contiguous range of Hilbert curve keys. Reading only those ranges, seeking to each
+//! through the block directory, beats decoding the whole layout when the ranges are few and fall
+//! in few blocks. A long row, such as the out-edges of a hub vertex in a large graph, crosses many
+//! blocks in many short ranges, and a scan that filters every edge is then cheaper.
+//!
+//! `plan` makes that choice from the block and tile directories. It refines the rectangle into
+//! aligned squares, dropping those that hold no edges, and stops refining a square once its keys
+//! lie within one block, as the block is decoded from its start in any case. It estimates the cost
+//! of reading the ranges as the edges of the blocks they touch plus a charge for each seek and
+//! each square visited, and abandons the decomposition for a scan as soon as that exceeds the
+//! number of edges in the layout.
+//!
+//! Either way, keys are detangled by a `BytewiseCached`, as the keys of a range, or of a scan, share
+//! their high bits far more often than not. `GraphLayout::edges_in_rect` plans and reads a query in
+//! one call.
+
+use std::ops::RangeInclusive;
+
+use crate::compression::{Compressed, Cursor, Decompressor};
+use crate::counters::Counters;
+use crate::graph::{Block, GraphLayout};
+use crate::layout::{BytewiseCached, Hilbert, Tangle};
+
+/// The cost, in decoded edges, charged for each range sought through the block directory.
+const SEEK_COST: usize = 16;
+/// The cost, in decoded edges, charged for each square considered while planning.
+const VISIT_COST: usize = 1;
+
+/// A rectangle of the adjacency matrix: edges with source and destination in inclusive ranges.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rect {
+    /// The first and last source vertex.
+    pub sources: (u32, u32),
+    /// The first and last destination vertex.
+    pub destinations: (u32, u32),
+}
+
+impl Rect {
+    /// The edges with sources in `sources` and destinations in `destinations`.
+    pub fn new(sources: RangeInclusive<u32>, destinations: RangeInclusive<u32>) -> Rect {
+        Rect {
+            sources: (*sources.start(), *sources.end()),
+            destinations: (*destinations.start(), *destinations.end()),
+        }
+    }
+    /// The edges with source and destination between those of two opposite corners, inclusive,
+    /// given in either order.
+    pub fn corners((x0, y0): (u32, u32), (x1, y1): (u32, u32)) -> Rect {
+        Rect::new(x0.min(x1) ..= x0.max(x1), y0.min(y1) ..= y0.max(y1))
+    }
+    /// The out-edges of `vertex`.
+    pub fn row(vertex: u32) -> Rect {
+        Rect::new(vertex ..= vertex, 0 ..= u32::MAX)
+    }
+    /// Indicates that the rectangle contains no edges.
+    pub fn is_empty(&self) -> bool {
+        self.sources.0 > self.sources.1 || self.destinations.0 > self.destinations.1
+    }
+    /// Indicates that the rectangle contains the edge `(src, dst)`.
+    #[inline]
+    pub fn contains(&self, (src, dst): (u32, u32)) -> bool {
+        self.sources.0 <= src && src <= self.sources.1 && self.destinations.0 <= dst && dst <= self.destinations.1
+    }
+}
+
+/// How the edges in a rectangle are read from a layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Plan {
+    /// Decode every edge, keeping those in the rectangle.
+    Scan,
+    /// Decode only the disjoint inclusive ranges of curve keys, in increasing order, keeping the
+    /// edges in the rectangle.
+    Ranges(Vec<(u64, u64)>),
+}
+
+/// Chooses between scanning `layout` and reading the curve ranges covering `rect`.
+pub fn plan(layout: &GraphLayout, rect: Rect) -> Plan {
+    plan_blocks(layout.blocks(), layout.vertices(), rect, |lower, upper| occupied(layout, lower, upper))
+}
+
+/// Chooses between scanning the keys of `blocks`, whose edges lie among the first `vertices`
+/// vertices, and reading the curve ranges covering `rect`, skipping squares `occupied` rules out.
+pub(crate) fn plan_blocks<F: Fn(u64, u64) -> bool>(blocks: &[Block], vertices: u64, rect: Rect, occupied: F) -> Plan {
+    let rect = match vertices.checked_sub(1) {
+        Some(last) => {
+            let last = ::std::cmp::min(last, u32::MAX as u64) as u32;
+            Rect {
+                sources: (rect.sources.0, ::std::cmp::min(rect.sources.1, last)),
+                destinations: (rect.destinations.0, ::std::cmp::min(rect.destinations.1, last)),
+            }
+        },
+        None => return Plan::Ranges(Vec::new()),
+    };
+    if rect.is_empty() {
+        return Plan::Ranges(Vec::new());
+    }
+
+    let hilbert = Hilbert::shared();
+    let budget = blocks.iter().map(|block| block.edges).sum::<usize>();
+    let mut cost = 0;
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    // the number of leading blocks already charged for, as consecutive ranges often share blocks.
+    let mut charged = 0;
+    // squares as (first key, base-two logarithm of the side), visited in curve order.
+    let mut squares = (0 .. 4u64).rev().map(|quadrant| (quadrant << 62, 31)).collect::<Vec<_>>();
+    while let Some((lower, log)) = squares.pop() {
+        cost += VISIT_COST;
+        if cost > budget {
+            return Plan::Scan;
+        }
+        let upper = lower + ((1u64 << (2 * log)) - 1);
+        let (x, y) = hilbert.detangle(lower);
+        let (x, y) = ((x >> log) << log, (y >> log) << log);
+        let (last_x, last_y) = (x + ((1u64 << log) - 1) as u32, y + ((1u64 << log) - 1) as u32);
+        if last_x < rect.sources.0 || rect.sources.1 < x || last_y < rect.destinations.0 || rect.destinations.1 < y {
+            continue;
+        }
+        let first = blocks.partition_point(|block| block.upper < lower);
+        let last = blocks.partition_point(|block| block.lower <= upper);
+        if first >= last || !occupied(lower, upper) {
+            continue;
+        }
+        let inside = rect.sources.0 <= x && last_x <= rect.sources.1 && rect.destinations.0 <= y && last_y <= rect.destinations.1;
+        if inside || last - first == 1 || log == 0 {
+            cost += blocks[::std::cmp::max(first, charged) .. last].iter().map(|block| block.edges).sum::<usize>();
+            charged = ::std::cmp::max(charged, last);
+            match ranges.last_mut() {
+                Some(range) if range.1.wrapping_add(1) == lower => range.1 = upper,
+                _ => {
+                    cost += SEEK_COST;
+                    ranges.push((lower, upper));
+                },
+            }
+            if cost > budget {
+                return Plan::Scan;
+            }
+        }
+        else {
+            let side = 1u64 << (2 * (log - 1));
+            squares.extend((0 .. 4).rev().map(|quadrant| (lower + quadrant * side, log - 1)));
+        }
+    }
+    Plan::Ranges(ranges)
+}
+
+/// Indicates that a tile of a tiled layout overlaps the curve keys `lower ..= upper`.
+///
+/// Tiles are exact squares holding at least one edge, and so rule out empty squares that a block's
+/// key range happens to span. Untiled layouts report every range as occupied.
+fn occupied(layout: &GraphLayout, lower: u64, upper: u64) -> bool {
+    let tiles = layout.tiles();
+    if layout.log_tile().is_none() {
+        return true;
+    }
+    let index = tiles.partition_point(|tile| tile.upper < lower);
+    index < tiles.len() && tiles[index].lower <= upper
+}
+
+/// Iterates over the edges of a layout within a rectangle, in curve order, following a `Plan`.
+pub struct RectEdges<'a> {
+    keys: RectKeys<'a>,
+}
+
+impl<'a> RectEdges<'a> {
+    /// Reads the edges of `layout` within `rect` by following `plan`.
+    pub fn new(layout: &'a GraphLayout, rect: Rect, plan: Plan) -> RectEdges<'a> {
+        RectEdges { keys: RectKeys::new(&layout.keys, layout.blocks(), rect, plan) }
+    }
+    /// The blocks decoded, ranges sought, escaped deltas and bytes read, and detangling cache hits
+    /// and misses so far, with the `counters` feature.
+    pub fn counters(&self) -> Counters { self.keys.counters() }
+}
+
+impl<'a> Iterator for RectEdges<'a> {
+    type Item = (u32, u32);
+    fn next(&mut self) -> Option<(u32, u32)> {
+        self.keys.next().map(|(_, edge)| edge)
+    }
+}
+
+/// Iterates over the keys of a stream within a rectangle, in curve order, following a `Plan`, as
+/// the rank of each key in the stream and its detangled pair.
+pub(crate) struct RectKeys<'a> {
+    keys: &'a Compressed,
+    blocks: &'a [Block],
+    rect: Rect,
+    reading: Reading<'a>,
+    detangler: BytewiseCached,
+    /// The counts of cursors no longer reading, and of blocks opened.
+    #[cfg(feature = "counters")]
+    counters: Counters,
+}
+
+enum Reading<'a> {
+    /// Decoding every key, the first `rank` of which have been decoded.
+    Scan(Box<Decompressor<'a>>, usize),
+    /// Reading the keys of each range in turn, up to `upper`, the last key of the current range.
+    Ranges {
+        ranges: ::std::vec::IntoIter<(u64, u64)>,
+        upper: u64,
+        cursor: Option<Cursor<'a>>,
+    },
+}
+
+impl<'a> RectKeys<'a> {
+    /// Reads the keys of `keys`, described by the directory `blocks`, within `rect` by following
+    /// `plan`.
+    pub(crate) fn new(keys: &'a Compressed, blocks: &'a [Block], rect: Rect, plan: Plan) -> RectKeys<'a> {
+        let reading = match plan {
+            Plan::Scan => Reading::Scan(Box::new(keys.decompress()), 0),
+            Plan::Ranges(ranges) => Reading::Ranges { ranges: ranges.into_iter(), upper: 0, cursor: None },
+        };
+        RectKeys {
+            keys,
+            blocks,
+            rect,
+            reading,
+            detangler: BytewiseCached::new(),
+            #[cfg(feature = "counters")]
+            counters: Counters::default(),
+        }
+    }
+    /// The counts of reading so far; a scan decodes every block it reaches from its start.
+    pub(crate) fn counters(&self) -> Counters {
+        count! {
+            let reading = match &self.reading {
+                Reading::Scan(keys, rank) => Counters { blocks_decoded: rank.div_ceil(crate::graph::BLOCK_EDGES) as u64, ..keys.counters() },
+                Reading::Ranges { cursor, .. } => cursor.map(|cursor| cursor.counters()).unwrap_or_default(),
+            };
+        }
+        counted!(self.counters + reading + self.detangler.counters())
+    }
+}
+
+impl<'a> Iterator for RectKeys<'a> {
+    type Item = (usize, (u32, u32));
+    fn next(&mut self) -> Option<(usize, (u32, u32))> {
+        let rect = self.rect;
+        let detangler = &mut self.detangler;
+        match &mut self.reading {
+            Reading::Scan(keys, rank) => keys.map(|key| detangler.detangle(key)).find(|&edge| {
+                *rank += 1;
+                rect.contains(edge)
+            }).map(|edge| (*rank - 1, edge)),
+            Reading::Ranges { ranges, upper, cursor } => loop {
+                if let Some(cursor) = cursor.as_mut() {
+                    while cursor.peek().is_some_and(|key| key <= *upper) {
+                        // a cursor's offset counts the keys before it.
+                        let rank = cursor.offset();
+                        let edge = detangler.detangle(cursor.next().unwrap());
+                        if rect.contains(edge) {
+                            return Some((rank, edge));
+                        }
+                    }
+                }
+                let (lower, last) = ranges.next()?;
+                *upper = last;
+                // seek through the block directory, unless the range starts in the current block.
+                let blocks = self.blocks;
+                let block = blocks.partition_point(|block| block.upper < lower);
+                if block == blocks.len() {
+                    return None;
+                }
+                if cursor.is_none_or(|cursor| cursor.offset() < blocks[block].offset) {
+                    count! {
+                        self.counters += cursor.map(|cursor| cursor.counters()).unwrap_or_default();
+                        self.counters.blocks_decoded += 1;
+                    }
+                    *cursor = Some(self.keys.cursor_at(blocks[block].position));
+                }
+                cursor.as_mut().unwrap().seek_to(lower);
+            },
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Adjacency lists compressed by reference to similar earlier lists, in the spirit of WebGraph.
+//!
+//! Vertices with nearby identifiers often have similar successor lists, as pages of one site link
+//! to the same pages. A `ReferencedAdjacency` codes each vertex's sorted successor list relative to
+//! the list of one of the `window` preceding vertices (its reference): the reference's list is
+//! split into alternating runs to copy and to skip, and the successors not copied are coded as
+//! gaps. Everything is written as LEB128 varints into a single byte stream.
+//!
+//! Each vertex's code is its degree; then, if it has successors, the distance back to its
+//! reference (zero for none); with a reference, the number of runs and their lengths, starting
+//! with a run to copy and omitting a final run to skip; and finally the residual successors, the
+//! first as the zigzag-coded difference from the vertex and the rest as gaps minus one.
+//!
+//! Reference chains are at most `MAX_CHAIN` long, so that decoding one list decodes a bounded
+//! number of others.
+
+use crate::compression::intersection_size;
+use crate::graph::GraphLayout;
+use crate::csr::Csr;
+
+/// The longest chain of references followed to decode one list.
+pub const MAX_CHAIN: usize = 3;
+
+/// Successor lists coded by reference to earlier lists, with an offset per vertex.
+#[derive(Clone, Debug)]
+pub struct ReferencedAdjacency {
+    bytes: Vec<u8>,
+    /// The start of each vertex's code, and a final offset past the last.
+    offsets: Vec<usize>,
+    edges: usize,
+}
+
+impl ReferencedAdjacency {
+    /// Codes the out-neighbors of each vertex of `layout`, looking back at most `window` vertices
+    /// for a reference.
+    pub fn from_layout(layout: &GraphLayout, window: usize) -> ReferencedAdjacency {
+        let csr = Csr::from_layout(layout, false, layout.vertices() as usize);
+        let list = |vertex: usize| &csr.targets[csr.offsets[vertex] as usize .. csr.offsets[vertex + 1] as usize];
+
+        let mut bytes = Vec::new();
+        let mut offsets = Vec::with_capacity(csr.vertices() + 1);
+        let mut depths = vec![0usize; csr.vertices()];
+        for vertex in 0 .. csr.vertices() {
+            offsets.push(bytes.len());
+            let successors = list(vertex);
+            write_varint(&mut bytes, successors.len() as u64);
+            if successors.is_empty() { continue; }
+
+            // the reference sharing the most successors, nearest first among ties.
+            let mut best = (0, 0);
+            for distance in 1 ..= ::std::cmp::min(window, vertex) {
+                let candidate = vertex - distance;
+                if depths[candidate] < MAX_CHAIN {
+                    let shared = intersection_size(list(candidate).iter().map(|&v| v as u64), successors.iter().map(|&v| v as u64));
+                    if shared > best.1 { best = (distance, shared); }
+                }
+            }
+            write_varint(&mut bytes, best.0 as u64);
+
+            let remaining: Vec<u32>;
+            let mut residuals = successors;
+            if best.0 > 0 {
+                depths[vertex] = depths[vertex - best.0] + 1;
+                let reference = list(vertex - best.0);
+                let mut runs = Vec::new();
+                let (mut copying, mut length) = (true, 0u64);
+                for value in reference {
+                    if successors.binary_search(value).is_ok() != copying {
+                        runs.push(length);
+                        copying = !copying;
+                        length = 0;
+                    }
+                    length += 1;
+                }
+                if copying { runs.push(length); }
+                write_varint(&mut bytes, runs.len() as u64);
+                for &run in &runs { write_varint(&mut bytes, run); }
+                remaining = successors.iter().cloned().filter(|value| reference.binary_search(value).is_err()).collect();
+                residuals = &remaining[..];
+            }
+
+            let mut previous = None;
+            for &value in residuals {
+                match previous {
+                    None => write_varint(&mut bytes, zigzag(value as i64 - vertex as i64)),
+                    Some(last) => write_varint(&mut bytes, (value - last - 1) as u64),
+                }
+                previous = Some(value);
+            }
+        }
+        offsets.push(bytes.len());
+        ReferencedAdjacency { bytes, offsets, edges: csr.targets.len() }
+    }
+
+    /// The number of vertices.
+    pub fn vertices(&self) -> usize { self.offsets.len() - 1 }
+    /// The number of edges.
+    pub fn len(&self) -> usize { self.edges }
+    /// Indicates that there are no edges.
+    pub fn is_empty(&self) -> bool { self.edges == 0 }
+    /// The number of bytes of coded lists, excluding the per-vertex offsets.
+    pub fn size_bytes(&self) -> usize { self.bytes.len() }
+
+    /// The successors of `vertex`, in increasing order.
+    pub fn successors(&self, vertex: u32) -> Vec<u32> {
+        let mut cursor = self.offsets[vertex as usize];
+        let degree = read_varint(&self.bytes, &mut cursor) as usize;
+        let mut successors = Vec::with_capacity(degree);
+        if degree == 0 { return successors; }
+
+        let distance = read_varint(&self.bytes, &mut cursor) as u32;
+        if distance > 0 {
+            let reference = self.successors(vertex - distance);
+            let runs = read_varint(&self.bytes, &mut cursor);
+            let mut start = 0;
+            for run in 0 .. runs {
+                let length = read_varint(&self.bytes, &mut cursor) as usize;
+                if run % 2 == 0 {
+                    successors.extend_from_slice(&reference[start .. start + length]);
+                }
+                start += length;
+            }
+        }
+
+        let copied = successors.len();
+        let mut previous = None;
+        for _ in copied .. degree {
+            let value = match previous {
+                None => (vertex as i64 + unzigzag(read_varint(&self.bytes, &mut cursor))) as u32,
+                Some(last) => last + 1 + read_varint(&self.bytes, &mut cursor) as u32,
+            };
+            successors.push(value);
+            previous = Some(value);
+        }
+        // copied and residual successors are each sorted; merge them.
+        if copied > 0 && copied < degree {
+            successors.sort_unstable();
+        }
+        successors
+    }
+
+    /// Iterates over all edges, grouped by source in increasing order.
+    pub fn edges(&self) -> impl Iterator<Item=(u32, u32)> + '_ {
+        (0 .. self.vertices() as u32).flat_map(move |vertex| self.successors(vertex).into_iter().map(move |dst| (vertex, dst)))
+    }
+
+    /// Lays the edges out along the curve again.
+    pub fn to_layout(&self) -> GraphLayout {
+        GraphLayout::from_edges(self.edges())
+    }
+}
+
+impl GraphLayout {
+    /// Codes the layout's out-neighbor lists by reference to similar lists, as a smaller
+    /// complement to the curve-key store for graphs such as web graphs.
+    pub fn referenced(&self, window: usize) -> ReferencedAdjacency {
+        ReferencedAdjacency::from_layout(self, window)
+    }
+}
+
+pub(crate) fn zigzag(value: i64) -> u64 { ((value << 1) ^ (value >> 63)) as u64 }
+pub(crate) fn unzigzag(value: u64) -> i64 { (value >> 1) as i64 ^ -((value & 1) as i64) }
+
+pub(crate) fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+pub(crate) fn read_varint(bytes: &[u8], cursor: &mut usize) -> u64 {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*cursor];
+        *cursor += 1;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte < 0x80 { return value; }
+        shift += 7;
+    }
+}
@@ -0,0 +1,54 @@
+//! A small deterministic random number generator, for sampling, shuffling, and generating graphs.
+
+/// The SplitMix64 generator of Steele, Lea, and Flood.
+///
+/// Its outputs for a given seed are part of the crate's stable interface: they match the reference
+/// implementation, and the methods deriving bounded values, floats, and shuffles from them will not
+/// change, so seeded graphs and samples are identical across runs, machines, and versions.
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// A generator whose output is determined by `seed`.
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    /// The next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly random value in `[0, 1)`, with 53 bits of precision.
+    #[cfg(feature = "algos")]
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A uniformly random value less than `bound`, which must be positive.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        // rejects the final partial copy of `0 .. bound` among the 2^64 outputs.
+        let limit = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < limit {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Shuffles `values` uniformly at random.
+    #[cfg(feature = "algos")]
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for index in (1 .. values.len()).rev() {
+            let other = self.below(index as u64 + 1) as usize;
+            values.swap(index, other);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Reusable buffers for many small compressions and layouts.
+//!
+//! Compressing a short sequence or laying out a small graph allocates a handful of vectors, and
+//! when millions are built one after another the allocator can cost more than the work itself. A
+//! `Scratch` holds the buffers used while building, along with finished streams and layouts handed
+//! back to it, and builds the next result in them rather than in fresh allocations.
+
+use crate::compression::{Compressed, Compressor};
+use crate::graph::GraphLayout;
+use crate::layout::BytewiseCached;
+
+/// Buffers for building, and the spare buffers of finished results handed back for reuse.
+///
+/// Spare buffers are kept until the scratch is dropped, and keep the capacity of the largest
+/// result built in them.
+#[derive(Default)]
+pub struct Scratch {
+    /// Curve keys being sorted for a layout.
+    pub(crate) keys: Vec<u64>,
+    /// Escaped deltas awaiting classification while compressing a slice.
+    escaped: Vec<u64>,
+    /// Finished streams handed back by `recycle`.
+    streams: Vec<Compressed>,
+    /// Finished layouts handed back by `recycle_layout`.
+    layouts: Vec<GraphLayout>,
+    /// Detangles keys when summarizing layouts, and is expensive to construct.
+    pub(crate) detangler: BytewiseCached,
+}
+
+impl Scratch {
+    /// A scratch space with no buffers yet.
+    pub fn new() -> Scratch { Scratch::default() }
+
+    /// A compressor writing into the buffers of a recycled stream, if there is one.
+    pub fn compressor(&mut self) -> Compressor {
+        self.streams.pop().map(Compressor::recycle).unwrap_or_default()
+    }
+    /// Compresses a strictly increasing slice, as `Compressed::from_sorted_slice` does, into the
+    /// buffers of a recycled stream if there is one.
+    pub fn compress(&mut self, values: &[u64]) -> Compressed {
+        let spare = self.streams.pop().unwrap_or_else(|| Compressor::new().done());
+        Compressed::from_sorted_slice_in(values, spare, &mut self.escaped)
+    }
+    /// Hands back a stream that is no longer needed, for its buffers to be reused.
+    pub fn recycle(&mut self, compressed: Compressed) {
+        self.streams.push(compressed);
+    }
+
+    /// An empty layout whose buffers a new layout can be built in.
+    pub(crate) fn spare_layout(&mut self) -> GraphLayout {
+        self.layouts.pop().unwrap_or_else(|| GraphLayout::spare(self.compressor().done()))
+    }
+    /// Hands back a layout that is no longer needed, for its buffers to be reused.
+    ///
+    /// The layout's weights and columns are dropped.
+    pub fn recycle_layout(&mut self, mut layout: GraphLayout) {
+        layout.weights = None;
+        layout.columns = Vec::new();
+        self.layouts.push(layout);
+    }
+}
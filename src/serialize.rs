@@ -0,0 +1,139 @@
+//! `Serialize` and `Deserialize` implementations, with the `serde` feature.
+//!
+//! Types with internal indexes are serialized as the data the indexes are rebuilt from, and
+//! validated as they are deserialized:
+//!
+//! * `Compressed` is its value count and the byte encoding of `Compressed::encode`.
+//! * `GraphLayout` is its tile size, compressed keys, weights, and named columns; the block and
+//!   tile directories, tile summaries, and vertex count are recomputed.
+//! * `Permutation` is its sequence of new identifiers, with the `algos` feature.
+//! * `Hilbert` and `ZOrder` are unit structs, as their tables are always the same.
+//!
+//! Plain data types such as `Aggregate`, `Column`, and `TileSummary` derive their implementations.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::column::{Column, WEIGHT_COLUMN};
+use crate::compression::{Compressed, Compressor, Position};
+use crate::graph::GraphLayout;
+use crate::layout::{Hilbert, ZOrder};
+#[cfg(feature = "algos")]
+use crate::order::Permutation;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Compressed")]
+struct CompressedRepr {
+    len: u64,
+    encoding: Vec<u8>,
+}
+
+impl Serialize for Compressed {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut encoding = Vec::new();
+        self.encode(Position::default(), self.seek(Position::default(), self.len()), &mut encoding);
+        CompressedRepr { len: self.len() as u64, encoding }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Compressed {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Compressed, D::Error> {
+        let repr = CompressedRepr::deserialize(deserializer)?;
+        let mut compressed = Compressor::new().done();
+        compressed.decode(repr.len as usize, &repr.encoding).map_err(D::Error::custom)?;
+        Ok(compressed)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename = "GraphLayout")]
+struct GraphLayoutRef<'a> {
+    log_tile: Option<u32>,
+    keys: &'a Compressed,
+    weights: Option<&'a [f64]>,
+    columns: &'a [(String, Column)],
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "GraphLayout")]
+struct GraphLayoutRepr {
+    log_tile: Option<u32>,
+    keys: Compressed,
+    weights: Option<Vec<f64>>,
+    #[serde(default)]
+    columns: Vec<(String, Column)>,
+}
+
+impl Serialize for GraphLayout {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GraphLayoutRef { log_tile: self.log_tile, keys: &self.keys, weights: self.weights(), columns: self.columns() }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GraphLayout {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<GraphLayout, D::Error> {
+        let repr = GraphLayoutRepr::deserialize(deserializer)?;
+        if repr.log_tile.is_some_and(|log| log >= 32) {
+            return Err(D::Error::custom("tiles must be smaller than the full 2^32 × 2^32 space"));
+        }
+        let keys = repr.keys.decompress().collect::<Vec<_>>();
+        if keys.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(D::Error::custom("keys are not strictly increasing"));
+        }
+        if repr.weights.as_ref().is_some_and(|weights| weights.len() != keys.len()) {
+            return Err(D::Error::custom("the number of weights differs from the number of keys"));
+        }
+        for (index, (name, column)) in repr.columns.iter().enumerate() {
+            if column.len() != keys.len() {
+                return Err(D::Error::custom(format!("column `{}` does not have one value per key", name)));
+            }
+            if name == WEIGHT_COLUMN || repr.columns[.. index].iter().any(|(other, _)| other == name) {
+                return Err(D::Error::custom(format!("column name `{}` is reserved or repeated", name)));
+            }
+        }
+        let mut layout = GraphLayout::assemble(keys, repr.weights, repr.log_tile);
+        layout.columns = repr.columns;
+        Ok(layout)
+    }
+}
+
+#[cfg(feature = "algos")]
+impl Serialize for Permutation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct("Permutation", self.new_ids())
+    }
+}
+
+#[cfg(feature = "algos")]
+impl<'de> Deserialize<'de> for Permutation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Permutation, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Permutation")]
+        struct PermutationRepr(Vec<u32>);
+        let PermutationRepr(new_ids) = PermutationRepr::deserialize(deserializer)?;
+        Permutation::from_new_ids(new_ids).ok_or_else(|| D::Error::custom("identifiers are not a permutation"))
+    }
+}
+
+macro_rules! unit_struct {
+    ($type:ident, $repr:ident, $name:literal) => {
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename = $name)]
+        struct $repr;
+
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                $repr.serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<$type, D::Error> {
+                $repr::deserialize(deserializer).map(|_| $type::new())
+            }
+        }
+    };
+}
+
+unit_struct!(Hilbert, HilbertRepr, "Hilbert");
+unit_struct!(ZOrder, ZOrderRepr, "ZOrder");
@@ -0,0 +1,91 @@
+//! Consumers of curve keys in increasing order.
+//!
+//! An `EdgeSink` receives the sorted, distinct curve keys of a set of edges one at a time, as the
+//! final merge of a `LayoutBuilder` produces them. The compressor, the layout file writer, vectors,
+//! channel senders, and `KeyWriter`s over sockets or other writers are sinks; implementing the
+//! trait streams sorted edges into other storage without first materializing a layout.
+
+use std::io::{ErrorKind, Write};
+use std::sync::mpsc::{Sender, SyncSender};
+
+use crate::compression::Compressor;
+use crate::error::{Error, Result};
+use crate::io::format::LayoutWriter;
+use crate::layout::{Hilbert, Tangle};
+
+/// Accepts curve keys in strictly increasing order.
+pub trait EdgeSink {
+    /// Accepts the next key, greater than all keys accepted before it.
+    fn push_key(&mut self, key: u64) -> Result<()>;
+    /// Accepts the next edge, whose curve key is greater than those of all edges accepted before it.
+    fn push_edge(&mut self, edge: (u32, u32)) -> Result<()> {
+        self.push_key(Hilbert::shared().entangle(edge))
+    }
+    /// The number of bytes the sink has written so far, for progress reports.
+    fn bytes(&self) -> u64 { 0 }
+}
+
+impl<S: EdgeSink + ?Sized> EdgeSink for &mut S {
+    fn push_key(&mut self, key: u64) -> Result<()> { (**self).push_key(key) }
+    fn bytes(&self) -> u64 { (**self).bytes() }
+}
+
+impl EdgeSink for Compressor {
+    fn push_key(&mut self, key: u64) -> Result<()> {
+        self.push(key);
+        Ok(())
+    }
+}
+
+impl EdgeSink for LayoutWriter {
+    fn push_key(&mut self, key: u64) -> Result<()> { self.push(key) }
+    fn bytes(&self) -> u64 { self.data_bytes() }
+}
+
+impl EdgeSink for Vec<u64> {
+    fn push_key(&mut self, key: u64) -> Result<()> {
+        self.push(key);
+        Ok(())
+    }
+}
+
+/// Sends each key over the channel, failing once the receiver has hung up.
+impl EdgeSink for Sender<u64> {
+    fn push_key(&mut self, key: u64) -> Result<()> {
+        self.send(key).map_err(|_| Error::Io(::std::io::Error::new(ErrorKind::BrokenPipe, "edge receiver hung up")))
+    }
+}
+
+/// Sends each key over the channel, blocking while it is full.
+impl EdgeSink for SyncSender<u64> {
+    fn push_key(&mut self, key: u64) -> Result<()> {
+        self.send(key).map_err(|_| Error::Io(::std::io::Error::new(ErrorKind::BrokenPipe, "edge receiver hung up")))
+    }
+}
+
+/// Writes each key as a little-endian `u64` to a writer, such as a socket.
+pub struct KeyWriter<W: Write> {
+    writer: W,
+    written: u64,
+}
+
+impl<W: Write> KeyWriter<W> {
+    /// Writes keys to `writer`, which is best buffered.
+    pub fn new(writer: W) -> KeyWriter<W> {
+        KeyWriter { writer, written: 0 }
+    }
+    /// Flushes and returns the writer.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> EdgeSink for KeyWriter<W> {
+    fn push_key(&mut self, key: u64) -> Result<()> {
+        self.writer.write_all(&key.to_le_bytes())?;
+        self.written += 8;
+        Ok(())
+    }
+    fn bytes(&self) -> u64 { self.written }
+}
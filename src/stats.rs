@@ -0,0 +1,314 @@
+//! Summary statistics of a layout's graph.
+
+use crate::compression::Compressed;
+use crate::graph::GraphLayout;
+
+/// A log-binned histogram and summary of one kind of vertex degree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution {
+    /// `bins[0]` counts vertices of degree zero, and `bins[k]` those of degree in `2^(k-1) .. 2^k`.
+    pub bins: Vec<usize>,
+    /// The largest degree.
+    pub max: u32,
+    /// The mean degree.
+    pub mean: f64,
+    /// The Gini coefficient of the degrees: zero if all are equal, approaching one as edges
+    /// concentrate on few vertices.
+    pub gini: f64,
+}
+
+impl Distribution {
+    fn from_degrees(mut degrees: Vec<u32>) -> Distribution {
+        let mut bins = Vec::new();
+        for &degree in &degrees {
+            let bin = (32 - degree.leading_zeros()) as usize;
+            if bins.len() <= bin { bins.resize(bin + 1, 0); }
+            bins[bin] += 1;
+        }
+
+        degrees.sort_unstable();
+        let count = degrees.len() as f64;
+        let total = degrees.iter().map(|&degree| degree as f64).sum::<f64>();
+        let weighted = degrees.iter().enumerate().map(|(index, &degree)| (index + 1) as f64 * degree as f64).sum::<f64>();
+        Distribution {
+            bins,
+            max: degrees.last().cloned().unwrap_or(0),
+            mean: if count > 0.0 { total / count } else { 0.0 },
+            gini: if total > 0.0 { 2.0 * weighted / (count * total) - (count + 1.0) / count } else { 0.0 },
+        }
+    }
+}
+
+/// The distributions of in-degrees and out-degrees.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DegreeDistribution {
+    /// The distribution of the number of edges into each vertex.
+    pub in_degrees: Distribution,
+    /// The distribution of the number of edges out of each vertex.
+    pub out_degrees: Distribution,
+}
+
+/// Summarizes the in-degrees and out-degrees of `layout`, from a single pass over its edges.
+pub fn degree_distribution(layout: &GraphLayout) -> DegreeDistribution {
+    let (in_degrees, out_degrees) = layout.degrees();
+    DegreeDistribution {
+        in_degrees: Distribution::from_degrees(in_degrees),
+        out_degrees: Distribution::from_degrees(out_degrees),
+    }
+}
+
+/// An estimated count for one frequently occurring vertex.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Hub {
+    /// The vertex.
+    pub vertex: u32,
+    /// An overestimate of its number of occurrences.
+    pub count: u64,
+    /// The most by which `count` may exceed the true number of occurrences.
+    pub error: u64,
+}
+
+/// The space-saving heavy-hitters summary of a stream of vertices.
+///
+/// Tracks at most `capacity` vertices. Any vertex occurring more than `n / capacity` times in a
+/// stream of `n` occurrences is guaranteed to be tracked, with its count overestimated by at most
+/// `n / capacity`.
+pub struct SpaceSaving {
+    capacity: usize,
+    /// Tracked vertices, as a binary min-heap on counts.
+    heap: Vec<Hub>,
+    /// The position of each tracked vertex in `heap`.
+    index: ::std::collections::HashMap<u32, usize>,
+}
+
+impl SpaceSaving {
+    /// A summary tracking at most `capacity` vertices.
+    pub fn new(capacity: usize) -> SpaceSaving {
+        assert!(capacity > 0, "the summary must track at least one vertex");
+        SpaceSaving { capacity, heap: Vec::with_capacity(capacity), index: ::std::collections::HashMap::with_capacity(capacity) }
+    }
+
+    /// Records one occurrence of `vertex`.
+    pub fn insert(&mut self, vertex: u32) {
+        if let Some(&position) = self.index.get(&vertex) {
+            self.heap[position].count += 1;
+            self.sift_down(position);
+        }
+        else if self.heap.len() < self.capacity {
+            self.heap.push(Hub { vertex, count: 1, error: 0 });
+            self.index.insert(vertex, self.heap.len() - 1);
+            self.sift_up(self.heap.len() - 1);
+        }
+        else {
+            // the least counted vertex is evicted, and its count inherited as error.
+            let evicted = self.heap[0];
+            self.index.remove(&evicted.vertex);
+            self.heap[0] = Hub { vertex, count: evicted.count + 1, error: evicted.count };
+            self.index.insert(vertex, 0);
+            self.sift_down(0);
+        }
+    }
+
+    /// The `k` tracked vertices with the largest counts, in decreasing order of count.
+    pub fn top(&self, k: usize) -> Vec<Hub> {
+        let mut hubs = self.heap.clone();
+        hubs.sort_by(|a, b| b.count.cmp(&a.count).then(a.vertex.cmp(&b.vertex)));
+        hubs.truncate(k);
+        hubs
+    }
+
+    fn sift_up(&mut self, mut position: usize) {
+        while position > 0 && self.heap[(position - 1) / 2].count > self.heap[position].count {
+            self.swap(position, (position - 1) / 2);
+            position = (position - 1) / 2;
+        }
+    }
+
+    fn sift_down(&mut self, mut position: usize) {
+        loop {
+            let mut least = position;
+            for child in &[2 * position + 1, 2 * position + 2] {
+                if *child < self.heap.len() && self.heap[*child].count < self.heap[least].count {
+                    least = *child;
+                }
+            }
+            if least == position {
+                return;
+            }
+            self.swap(position, least);
+            position = least;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.index.insert(self.heap[a].vertex, a);
+        self.index.insert(self.heap[b].vertex, b);
+    }
+}
+
+/// The estimated highest-degree vertices of an edge stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hubs {
+    /// Vertices with the most out-edges, in decreasing order of estimated out-degree.
+    pub sources: Vec<Hub>,
+    /// Vertices with the most in-edges, in decreasing order of estimated in-degree.
+    pub destinations: Vec<Hub>,
+}
+
+/// Estimates the `k` highest out-degree and in-degree vertices of `edges` in one pass.
+///
+/// Memory is proportional to `capacity`, not to the number of vertices; see `SpaceSaving` for the
+/// accuracy this buys. A `capacity` several times `k` makes the reported hubs reliable for skewed
+/// degree distributions. Duplicate edges are counted each time they occur.
+pub fn hubs<I: Iterator<Item=(u32, u32)>>(edges: I, k: usize, capacity: usize) -> Hubs {
+    let mut sources = SpaceSaving::new(capacity);
+    let mut destinations = SpaceSaving::new(capacity);
+    for (src, dst) in edges {
+        sources.insert(src);
+        destinations.insert(dst);
+    }
+    Hubs { sources: sources.top(k), destinations: destinations.top(k) }
+}
+
+/// Statistics of the distances between one coordinate of consecutive edges.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Gaps {
+    /// The mean absolute difference.
+    pub mean: f64,
+    /// The median absolute difference.
+    pub p50: u32,
+    /// The 90th percentile absolute difference.
+    pub p90: u32,
+    /// The 99th percentile absolute difference.
+    pub p99: u32,
+    /// The largest absolute difference.
+    pub max: u32,
+}
+
+impl Gaps {
+    fn from_gaps(mut gaps: Vec<u32>) -> Gaps {
+        if gaps.is_empty() {
+            return Gaps { mean: 0.0, p50: 0, p90: 0, p99: 0, max: 0 };
+        }
+        let mean = gaps.iter().map(|&gap| gap as f64).sum::<f64>() / gaps.len() as f64;
+        let mut percentile = |fraction: f64| {
+            let rank = ::std::cmp::min(gaps.len() - 1, (fraction * gaps.len() as f64) as usize);
+            *gaps.select_nth_unstable(rank).1
+        };
+        let (p50, p90, p99) = (percentile(0.5), percentile(0.9), percentile(0.99));
+        Gaps { mean, p50, p90, p99, max: gaps.iter().cloned().max().unwrap_or(0) }
+    }
+}
+
+/// How well an edge ordering keeps consecutive edges close together.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Locality {
+    /// Distances between the sources of consecutive edges.
+    pub sources: Gaps,
+    /// Distances between the destinations of consecutive edges.
+    pub destinations: Gaps,
+    /// Estimated cache lines fetched per edge; see `locality`.
+    pub lines_per_edge: f64,
+    /// Bits of compressed key data per edge.
+    pub bits_per_edge: f64,
+}
+
+/// The number of 64-byte lines in the cache simulated by `locality`.
+const CACHE_LINES: usize = 4096;
+
+/// Measures the locality of the curve order of `layout`.
+///
+/// Cache lines are estimated by running the edges past a direct-mapped 256 KiB cache of 64-byte
+/// lines, as a kernel reading an 8-byte value per source and writing one per destination would,
+/// with the two arrays stored one after the other.
+pub fn locality(layout: &GraphLayout) -> Locality {
+    let mut source_gaps = Vec::with_capacity(layout.len().saturating_sub(1));
+    let mut destination_gaps = Vec::with_capacity(layout.len().saturating_sub(1));
+    let mut cache = vec![u64::MAX; CACHE_LINES];
+    let mut misses = 0u64;
+    let mut previous: Option<(u32, u32)> = None;
+    for (src, dst) in layout.edges() {
+        if let Some((last_src, last_dst)) = previous {
+            source_gaps.push((src as i64 - last_src as i64).unsigned_abs() as u32);
+            destination_gaps.push((dst as i64 - last_dst as i64).unsigned_abs() as u32);
+        }
+        previous = Some((src, dst));
+        for &line in &[src as u64 / 8, (layout.vertices() + dst as u64) / 8] {
+            let slot = &mut cache[(line as usize) % CACHE_LINES];
+            if *slot != line {
+                *slot = line;
+                misses += 1;
+            }
+        }
+    }
+
+    let edges = ::std::cmp::max(layout.len(), 1) as f64;
+    Locality {
+        sources: Gaps::from_gaps(source_gaps),
+        destinations: Gaps::from_gaps(destination_gaps),
+        lines_per_edge: misses as f64 / edges,
+        bits_per_edge: 8.0 * layout.keys.size_bytes() as f64 / edges,
+    }
+}
+
+/// Edge counts over contiguous ranges of curve keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyHistogram {
+    /// The number of keys each bucket spans: bucket `b` counts keys in `b * width .. (b + 1) * width`.
+    ///
+    /// A single bucket over the whole curve spans `2^64` keys, which is why this is not a `u64`.
+    pub width: u128,
+    /// The number of edges in each bucket.
+    pub counts: Vec<usize>,
+}
+
+impl KeyHistogram {
+    /// Empty buckets dividing the smallest square of the curve that holds `max` among `buckets`.
+    ///
+    /// The square's keys are the first `4^k` for the least `k` exceeding `max`, so each bucket is
+    /// a contiguous stretch of the curve and, when `buckets` is a power of four, a square of the
+    /// adjacency matrix.
+    fn new(max: Option<u64>, buckets: usize) -> KeyHistogram {
+        assert!(buckets > 0, "the histogram must have at least one bucket");
+        let levels = max.map(|max| (65 - max.leading_zeros()) / 2).unwrap_or(0);
+        let span = 1u128 << (2 * levels);
+        let width = ::std::cmp::max(1, span.div_ceil(buckets as u128));
+        KeyHistogram { width, counts: vec![0; buckets] }
+    }
+    fn bucket(&self, key: u64) -> usize { (key as u128 / self.width) as usize }
+}
+
+/// Counts the keys of `keys` in each of `buckets` contiguous ranges of the curve.
+///
+/// The values are read twice, as deltas summed without being collected, first to find the largest.
+pub fn key_histogram(keys: &Compressed, buckets: usize) -> KeyHistogram {
+    let mut histogram = KeyHistogram::new(keys.decompress().last(), buckets);
+    for key in keys.decompress() {
+        let bucket = histogram.bucket(key);
+        histogram.counts[bucket] += 1;
+    }
+    histogram
+}
+
+/// Counts the edges of `layout` in each of `buckets` contiguous ranges of the curve, as
+/// `key_histogram` does for its keys.
+///
+/// Blocks of the directory that fall within one bucket are counted from their metadata, and only
+/// blocks straddling a boundary between buckets are decoded.
+pub fn layout_key_histogram(layout: &GraphLayout, buckets: usize) -> KeyHistogram {
+    let mut histogram = KeyHistogram::new(layout.blocks.last().map(|block| block.upper), buckets);
+    for block in &layout.blocks {
+        let (lower, upper) = (histogram.bucket(block.lower), histogram.bucket(block.upper));
+        if lower == upper {
+            histogram.counts[lower] += block.edges;
+        }
+        else {
+            for key in layout.keys.decompress_from(block.position).take(block.edges) {
+                let bucket = histogram.bucket(key);
+                histogram.counts[bucket] += 1;
+            }
+        }
+    }
+    histogram
+}
@@ -0,0 +1,49 @@
+//! Spans and events for long-running operations, with the `tracing` feature.
+//!
+//! Ingest, sort, merge, and compaction phases each enter an `info` span named for the phase, with
+//! counter fields recorded as the phase completes. Without the feature the macros expand to
+//! nothing, so instrumented code pays nothing for them.
+
+/// Enters a span named `$name` for the rest of the enclosing scope, declaring the listed counter
+/// fields to be recorded later with `trace_record!`.
+#[cfg(feature = "tracing")]
+macro_rules! trace_span {
+    ($name:expr $(, $field:ident)*) => {
+        ::tracing::info_span!($name $(, $field = ::tracing::field::Empty)*).entered()
+    }
+}
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_span {
+    ($name:expr $(, $field:ident)*) => { $crate::trace::Span }
+}
+
+/// The stand-in for an entered span without the feature, which may be dropped like one.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Span;
+
+#[cfg(not(feature = "tracing"))]
+impl Drop for Span {
+    fn drop(&mut self) { }
+}
+
+/// Records the counter `$field` of a span entered by `trace_span!`.
+#[cfg(feature = "tracing")]
+macro_rules! trace_record {
+    ($span:expr, $field:ident, $value:expr) => { $span.record(stringify!($field), $value as u64); }
+}
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_record {
+    ($span:expr, $field:ident, $value:expr) => { { let _ = &$span; let _ = $value; } }
+}
+
+/// Emits an `info` event with `$message` and counter fields.
+#[cfg(feature = "io")]
+#[cfg(feature = "tracing")]
+macro_rules! trace_event {
+    ($message:expr $(, $field:ident = $value:expr)*) => { ::tracing::info!($($field = $value as u64,)* $message); }
+}
+#[cfg(feature = "io")]
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_event {
+    ($message:expr $(, $field:ident = $value:expr)*) => { { $(let _ = $value;)* } }
+}
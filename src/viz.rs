@@ -0,0 +1,155 @@
+//! Pictures of the curve and of layouts, for debugging orderings and explaining them.
+//!
+//! `curve_svg` draws the Hilbert curve through a `2^order × 2^order` grid, and `density` counts the
+//! edges of a layout in a downsampled adjacency matrix, with sources as rows and destinations as
+//! columns, which `density_svg` and `density_png` render as a heatmap. Shading is logarithmic in the
+//! count, so that sparse regions remain visible next to dense ones.
+//!
+//! PNG images are eight-bit grayscale, and written without compression so as not to depend on a
+//! compression library; at heatmap resolutions they are small regardless.
+
+use std::fmt::Write;
+
+use crate::graph::GraphLayout;
+use crate::layout::{Hilbert, Tangle};
+
+/// Renders the Hilbert curve through the cells of a `2^order × 2^order` grid as an SVG polyline,
+/// in a square image `size` pixels on a side.
+///
+/// Panics if `order` exceeds 12, beyond which the curve would not be visible anyway.
+pub fn curve_svg(order: u32, size: u32) -> String {
+    assert!(order <= 12, "curves beyond order 12 have more points than pixels");
+    let side = 1u64 << order;
+    let cell = size as f64 / side as f64;
+    let hilbert = Hilbert::new();
+    let mut points = String::new();
+    for index in 0 .. side * side {
+        let (x, y) = hilbert.detangle(index);
+        if index > 0 { points.push(' '); }
+        write!(points, "{:.2},{:.2}", (x as f64 + 0.5) * cell, (y as f64 + 0.5) * cell).unwrap();
+    }
+    let mut svg = header(size);
+    writeln!(svg, "<polyline points=\"{}\" fill=\"none\" stroke=\"black\" stroke-width=\"{:.2}\"/>", points, (cell / 4.0).min(2.0)).unwrap();
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Counts the edges of `layout` in each cell of a `resolution × resolution` grid over its
+/// adjacency matrix, in row-major order with sources as rows.
+///
+/// Each cell spans an equal share of the vertex identifiers, in both dimensions.
+pub fn density(layout: &GraphLayout, resolution: u32) -> Vec<u64> {
+    assert!(resolution > 0, "the grid must have at least one cell");
+    let resolution = resolution as u64;
+    let vertices = ::std::cmp::max(layout.vertices(), 1);
+    let mut counts = vec![0u64; (resolution * resolution) as usize];
+    for (src, dst) in layout.edges() {
+        let row = src as u64 * resolution / vertices;
+        let column = dst as u64 * resolution / vertices;
+        counts[(row * resolution + column) as usize] += 1;
+    }
+    counts
+}
+
+/// Renders the edge density of `layout` as an SVG heatmap of `resolution × resolution` cells, in a
+/// square image `size` pixels on a side; darker cells hold more edges, and empty cells are omitted.
+pub fn density_svg(layout: &GraphLayout, resolution: u32, size: u32) -> String {
+    let counts = density(layout, resolution);
+    let shades = shades(&counts);
+    let cell = size as f64 / resolution as f64;
+    let mut svg = header(size);
+    for (index, (&count, &shade)) in counts.iter().zip(shades.iter()).enumerate() {
+        if count == 0 { continue; }
+        let (row, column) = (index as u32 / resolution, index as u32 % resolution);
+        writeln!(
+            svg,
+            "<rect x=\"{0:.2}\" y=\"{1:.2}\" width=\"{2:.2}\" height=\"{2:.2}\" fill=\"rgb({3},{3},{3})\"><title>{4}</title></rect>",
+            column as f64 * cell, row as f64 * cell, cell, shade, count,
+        ).unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Renders the edge density of `layout` as a grayscale PNG image with one pixel per cell of a
+/// `resolution × resolution` grid; darker pixels hold more edges, and empty ones are white.
+pub fn density_png(layout: &GraphLayout, resolution: u32) -> Vec<u8> {
+    let counts = density(layout, resolution);
+    png(resolution, resolution, &shades(&counts))
+}
+
+/// The opening of an SVG document `size` pixels square, with a white background.
+fn header(size: u32) -> String {
+    let mut svg = String::new();
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">", size).unwrap();
+    writeln!(svg, "<rect width=\"{0}\" height=\"{0}\" fill=\"white\"/>", size).unwrap();
+    svg
+}
+
+/// Gray levels for `counts`: white for zero, black for the largest, logarithmic in between.
+fn shades(counts: &[u64]) -> Vec<u8> {
+    let max = counts.iter().cloned().max().unwrap_or(0);
+    let scale = (1.0 + max as f64).ln();
+    counts.iter().map(|&count| {
+        if count == 0 { 255 }
+        else { (224.0 * (1.0 - (1.0 + count as f64).ln() / scale)).round() as u8 }
+    }).collect()
+}
+
+/// Encodes eight-bit grayscale `pixels`, in row-major order, as an uncompressed PNG image.
+fn png(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    // each row is preceded by its filter type, zero for none.
+    let mut raw = Vec::with_capacity(pixels.len() + height as usize);
+    for row in pixels.chunks(width as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    // a zlib stream of stored deflate blocks, each of at most 65535 bytes.
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() { zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]); }
+    while let Some(block) = blocks.next() {
+        zlib.push(if blocks.peek().is_none() { 1 } else { 0 });
+        zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // eight-bit grayscale, default compression and filtering, no interlacing.
+    header.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+    let mut image = b"\x89PNG\r\n\x1a\n".to_vec();
+    for &(kind, data) in &[(b"IHDR", &header[..]), (b"IDAT", &zlib[..]), (b"IEND", &[][..])] {
+        image.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = image.len();
+        image.extend_from_slice(kind);
+        image.extend_from_slice(data);
+        let crc = crc32(&image[start..]);
+        image.extend_from_slice(&crc.to_be_bytes());
+    }
+    image
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0 .. 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
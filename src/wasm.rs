@@ -0,0 +1,59 @@
+//! JavaScript bindings, with the `wasm` feature.
+//!
+//! Built for `wasm32-unknown-unknown` into the dynamic library of the `graph_layout_cdylib` crate
+//! in `cdylib/`, with its `wasm` feature, and processed by `wasm-bindgen`, these functions let a
+//! browser map between edges and curve keys and decode the compressed key streams of layout files,
+//! such as the blocks and tiles a visualizer fetches one at a time. Curve keys are `BigInt`s and
+//! batches are typed arrays: edges as a `Uint32Array` of interleaved sources and destinations,
+//! and keys as a `BigUint64Array`.
+//!
+//! Single mappings use the bitwise curve, which needs no tables; batches use the shared tables,
+//! which are built on first use.
+
+use wasm_bindgen::prelude::*;
+
+use crate::compression::EncodedDecompressor;
+use crate::layout::{Hilbert, Tangle};
+
+/// The curve key of the edge `(src, dst)`.
+#[wasm_bindgen]
+pub fn entangle(src: u32, dst: u32) -> u64 {
+    Hilbert::bit_entangle((src, dst))
+}
+
+/// The edge with curve key `key`, as `[src, dst]`.
+#[wasm_bindgen]
+pub fn detangle(key: u64) -> Vec<u32> {
+    let (src, dst) = Hilbert::bit_detangle(key);
+    vec![src, dst]
+}
+
+/// The curve keys of interleaved `edges`.
+#[wasm_bindgen(js_name = entangleBatch)]
+pub fn entangle_batch(edges: &[u32]) -> Vec<u64> {
+    let hilbert = Hilbert::shared();
+    edges.chunks_exact(2).map(|edge| hilbert.entangle((edge[0], edge[1]))).collect()
+}
+
+/// The interleaved edges of `keys`.
+#[wasm_bindgen(js_name = detangleBatch)]
+pub fn detangle_batch(keys: &[u64]) -> Vec<u32> {
+    let hilbert = Hilbert::shared();
+    keys.iter().flat_map(|&key| {
+        let (src, dst) = hilbert.detangle(key);
+        vec![src, dst]
+    }).collect()
+}
+
+/// Decodes `count` keys from `bytes`, one encoding produced by `Compressed::encode` whose deltas
+/// start from `previous`, as in a block of a layout file.
+#[wasm_bindgen(js_name = decodeKeys)]
+pub fn decode_keys(bytes: &[u8], count: usize, previous: u64) -> Result<Vec<u64>, JsError> {
+    Ok(EncodedDecompressor::new(previous, count, bytes)?.collect())
+}
+
+/// Decodes `count` keys from `bytes` as `decodeKeys` does, returning their interleaved edges.
+#[wasm_bindgen(js_name = decodeEdges)]
+pub fn decode_edges(bytes: &[u8], count: usize, previous: u64) -> Result<Vec<u32>, JsError> {
+    Ok(detangle_batch(&decode_keys(bytes, count, previous)?))
+}
@@ -0,0 +1,79 @@
+//! Graphs whose vertex identifiers need all 64 bits.
+//!
+//! A `WideLayout` entangles each `(u64, u64)` edge into a `u128` along the Hilbert curve over the
+//! `2^64 × 2^64` square, and stores the sorted, deduplicated keys in a `WideCompressed` stream.
+//! Edges between identifiers below `2^32` receive the same keys as in a `GraphLayout`, so a wide
+//! layout whose identifiers fit can be narrowed without re-sorting. Otherwise, `densify` relabels
+//! the vertices with dense `u32` identifiers, which the kernels in `algo` require.
+
+use crate::compression::{WideCompressed, WideDecompressor};
+use crate::densify::Densifier;
+use crate::graph::GraphLayout;
+use crate::layout::Hilbert;
+
+/// A graph with `u64` vertex identifiers, stored as compressed Hilbert curve keys.
+pub struct WideLayout {
+    keys: WideCompressed,
+    max_vertex: Option<u64>,
+}
+
+impl WideLayout {
+    /// Lays out the edges, discarding duplicates.
+    pub fn from_edges<I: Iterator<Item=(u64, u64)>>(edges: I) -> WideLayout {
+        let mut keys = edges.map(Hilbert::entangle_wide).collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys.dedup();
+        let max_vertex = keys.iter().map(|&key| {
+            let (src, dst) = Hilbert::detangle_wide(key);
+            ::std::cmp::max(src, dst)
+        }).max();
+        WideLayout { keys: WideCompressed::from(keys.into_iter()), max_vertex }
+    }
+
+    /// The number of edges in the layout.
+    pub fn len(&self) -> usize { self.keys.len() }
+    /// Indicates that the layout contains no edges.
+    pub fn is_empty(&self) -> bool { self.keys.is_empty() }
+    /// The largest endpoint of any edge, if there are edges.
+    pub fn max_vertex(&self) -> Option<u64> { self.max_vertex }
+    /// The number of bytes of compressed key data.
+    pub fn size_bytes(&self) -> usize { self.keys.size_bytes() }
+
+    /// Iterates over the curve keys of all edges, in order.
+    pub fn keys(&self) -> WideDecompressor<'_> { self.keys.decompress() }
+    /// Iterates over all edges, in curve order.
+    pub fn edges(&self) -> WideEdges<'_> { WideEdges { keys: self.keys() } }
+
+    /// The same graph as a `GraphLayout`, if every vertex identifier is less than `2^32`.
+    pub fn narrow(&self) -> Option<GraphLayout> {
+        if self.max_vertex.is_some_and(|max| max > u32::MAX as u64) {
+            return None;
+        }
+        // below `2^32` the wide curve coincides with the narrow one, so the keys are already sorted.
+        Some(GraphLayout::from_keys(self.keys().map(|key| key as u64).collect(), None))
+    }
+    /// Relabels the vertices with dense `u32` identifiers, in order of first appearance along the
+    /// curve, returning the relabeled layout and the mapping back to the original identifiers.
+    ///
+    /// Panics if there are more than `2^32` distinct vertices.
+    pub fn densify(&self) -> (GraphLayout, Densifier) {
+        let mut densifier = Densifier::new();
+        let layout = GraphLayout::from_edges(densifier.densify(self.edges()));
+        (layout, densifier)
+    }
+}
+
+/// Iterates over the edges of a `WideLayout`, in curve order.
+pub struct WideEdges<'a> {
+    keys: WideDecompressor<'a>,
+}
+
+impl<'a> Iterator for WideEdges<'a> {
+    type Item = (u64, u64);
+    fn next(&mut self) -> Option<(u64, u64)> {
+        self.keys.next().map(Hilbert::detangle_wide)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
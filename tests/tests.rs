@@ -1,4 +1,3 @@
-extern crate graph_layout;
 use graph_layout::layout::*;
 use graph_layout::compression::*;
 
@@ -11,6 +10,7 @@ fn encode_decode_byte() {
 }
 
 #[test]
+#[allow(clippy::map_clone)]
 fn compress_decompress() {
     let source = vec![0,1,2,4, 100, 123412, 1543245423];
     let compressed = Compressed::from(source.iter().map(|&x|x));
@@ -18,3 +18,3172 @@ fn compress_decompress() {
     let result = decompressor.collect::<Vec<_>>();
     assert_eq!(result, source);
 }
+
+#[test]
+fn tiled_layout() {
+    use graph_layout::graph::GraphLayout;
+    let edges = (0 .. 1000u32).map(|i| ((i * 7919) % 1000, (i * 104729) % 997)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges_tiled(edges.iter().cloned(), 6);
+    let hilbert = Hilbert::new();
+    assert_eq!(layout.len(), edges.len());
+    assert_eq!(layout.tiles().iter().map(|t| t.edges).sum::<usize>(), layout.len());
+    let mut offset = 0;
+    let mut found = Vec::new();
+    for tile in layout.tiles() {
+        assert_eq!(tile.offset, offset);
+        offset += tile.edges;
+        assert!(layout.tile_keys(tile).all(|key| tile.lower <= key && key <= tile.upper));
+        for (x, y) in layout.tile_edges(tile) {
+            assert_eq!((tile.lower >> 12), hilbert.entangle((x & !63, y & !63)) >> 12);
+            found.push((x, y));
+        }
+    }
+    let mut expected = edges.clone();
+    expected.sort();
+    found.sort();
+    assert_eq!(found, expected);
+    assert_eq!(layout.edges().count(), layout.len());
+}
+
+#[test]
+fn tile_summaries() {
+    use graph_layout::graph::GraphLayout;
+    // a dense 4x4 block, a diagonal in a second tile, and a star in a third.
+    let mut edges = Vec::new();
+    for x in 0 .. 4 { for y in 0 .. 4 { edges.push((x, y)); } }
+    for i in 0 .. 4 { edges.push((100 + i, 100 + i)); }
+    for i in 0 .. 4 { edges.push((200, 300 + i)); }
+    let layout = GraphLayout::from_edges_tiled(edges.into_iter(), 3);
+    let mut summaries = layout.tile_summaries().iter().map(|s| (s.edges, s.sources, s.destinations)).collect::<Vec<_>>();
+    summaries.sort();
+    assert_eq!(summaries, vec![(4, 1, 4), (4, 4, 4), (16, 4, 4)]);
+    for (tile, summary) in layout.tiles().iter().zip(layout.tile_summaries()) {
+        assert_eq!(summary.density, tile.edges as f64 / 64.0);
+    }
+}
+
+#[test]
+fn partition_tiles() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::partition::{partition, Placement};
+    let edges = (0 .. 4096u32).map(|i| (i % 64, (i * 37) % 61)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges_tiled(edges.into_iter(), 3);
+    for &placement in &[Placement::Contiguous, Placement::Blocks] {
+        let parts = partition(&layout, 4, placement);
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts.iter().map(|p| p.layout.len()).sum::<usize>(), layout.len());
+        assert_eq!(parts.iter().map(|p| p.tiles.len()).sum::<usize>(), layout.tiles().len());
+        for part in &parts {
+            assert!(!part.tiles.is_empty());
+            for (src, dst) in part.layout.edges() {
+                assert!(part.sources.iter().any(|&(lo, hi)| lo <= src && src <= hi));
+                assert!(part.destinations.iter().any(|&(lo, hi)| lo <= dst && dst <= hi));
+            }
+        }
+        if placement == Placement::Blocks {
+            // a 2x2 grid over a 64x64 matrix gives each worker one quadrant.
+            for part in &parts {
+                assert_eq!(part.sources.len(), 1);
+                assert_eq!(part.sources[0].1 - part.sources[0].0, 31);
+                assert_eq!(part.destinations[0].1 - part.destinations[0].0, 31);
+            }
+        }
+    }
+}
+
+#[test]
+fn balanced_split() {
+    use graph_layout::graph::{GraphLayout, BLOCK_EDGES};
+    let edges = (0 .. 10_000u32).map(|i| (i % 113, i / 113)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges(edges.into_iter());
+    assert_eq!(layout.blocks().len(), layout.len().div_ceil(BLOCK_EDGES));
+    let keys = layout.keys().collect::<Vec<_>>();
+    for &parts in &[1, 3, 7, 64] {
+        let slices = layout.split(parts);
+        assert_eq!(slices.len(), parts);
+        let mut seen = Vec::new();
+        for slice in &slices {
+            assert!(slice.edges == layout.len() / parts || slice.edges == layout.len() / parts + 1);
+            let run = layout.block_keys(slice).collect::<Vec<_>>();
+            assert_eq!(run.first(), Some(&slice.lower));
+            assert_eq!(run.last(), Some(&slice.upper));
+            seen.extend(run);
+        }
+        assert_eq!(seen, keys);
+    }
+    assert_eq!(GraphLayout::from_edges(vec![(1, 2), (3, 4)].into_iter()).split(5).len(), 2);
+}
+
+#[test]
+fn edge_list_text() {
+    use graph_layout::io::{EdgeList, load_edge_list};
+    let text = b"# Directed graph\n# FromNodeId\tToNodeId\n0\t1\n  1 2 17\n\n% other comment\n4294967295,3\r\n";
+    let edges = EdgeList::new(&text[..]).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(edges, vec![(0, 1), (1, 2), (4294967295, 3)]);
+    assert!(matches!(EdgeList::new(&b"1 x\n"[..]).next().unwrap(), Err(graph_layout::Error::Corrupt(_))));
+    assert!(EdgeList::new(&b"1 4294967296\n"[..]).next().unwrap().is_err());
+
+    let path = std::env::temp_dir().join("graph_layout_edge_list_text.txt");
+    std::fs::write(&path, &text[..]).unwrap();
+    let layout = load_edge_list(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut loaded = layout.edges().collect::<Vec<_>>();
+    loaded.sort();
+    assert_eq!(loaded, edges);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn edge_list_gzip() {
+    use std::io::Write;
+    let path = std::env::temp_dir().join("graph_layout_edge_list_gzip.txt.gz");
+    let mut encoder = flate2::write::GzEncoder::new(std::fs::File::create(&path).unwrap(), flate2::Compression::default());
+    encoder.write_all(b"# comment\n1 2\n3 4\n").unwrap();
+    encoder.finish().unwrap();
+    let edges = graph_layout::io::read_edge_list(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(edges, vec![(1, 2), (3, 4)]);
+
+    // compressed binary edges are decompressed, rather than mapped, with the `mmap` feature too.
+    let path = std::env::temp_dir().join("graph_layout_binary_edges_gzip.bin.gz");
+    let mut encoder = flate2::write::GzEncoder::new(std::fs::File::create(&path).unwrap(), flate2::Compression::default());
+    encoder.write_all(&[1, 0, 0, 0, 2, 0, 0, 0, 7, 0, 0, 0, 3, 0, 0, 0]).unwrap();
+    encoder.finish().unwrap();
+    let layout = graph_layout::io::load_binary_edges(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut edges = layout.edges().collect::<Vec<_>>();
+    edges.sort();
+    assert_eq!(edges, vec![(1, 2), (7, 3)]);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn edge_list_zstd() {
+    let path = std::env::temp_dir().join("graph_layout_edge_list_zstd.txt.zst");
+    std::fs::write(&path, zstd::encode_all(&b"1 2\n3 4\n"[..], 0).unwrap()).unwrap();
+    let edges = graph_layout::io::read_edge_list(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(edges, vec![(1, 2), (3, 4)]);
+}
+
+#[test]
+fn matrix_market_round_trip() {
+    use graph_layout::io::{format_matrix_market, parse_matrix_market, Symmetry};
+    let text = "%%MatrixMarket matrix coordinate real symmetric\n% comment\n3 3 3\n1 1 2.5\n3 1 -1\n2 3 4e2\n";
+    let layout = parse_matrix_market(text.as_bytes()).unwrap();
+    let mut entries = layout.edges().zip(layout.weights().unwrap().iter().cloned()).collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.0);
+    assert_eq!(entries, vec![((0, 0), 2.5), ((0, 2), -1.0), ((1, 2), 400.0), ((2, 0), -1.0), ((2, 1), 400.0)]);
+
+    let mut written = Vec::new();
+    format_matrix_market(&layout, &mut written, Symmetry::Symmetric).unwrap();
+    let reread = parse_matrix_market(&written[..]).unwrap();
+    assert_eq!(reread.keys().collect::<Vec<_>>(), layout.keys().collect::<Vec<_>>());
+    assert_eq!(reread.weights(), layout.weights());
+
+    let pattern = parse_matrix_market(&b"%%MatrixMarket matrix coordinate pattern general\n2 2 2\n1 2\n2 1\n"[..]).unwrap();
+    assert!(pattern.weights().is_none());
+    let mut written = Vec::new();
+    format_matrix_market(&pattern, &mut written, Symmetry::General).unwrap();
+    assert!(String::from_utf8(written).unwrap().starts_with("%%MatrixMarket matrix coordinate pattern general\n2 2 2\n"));
+    assert!(parse_matrix_market(&b"%%MatrixMarket matrix array real general\n"[..]).is_err());
+    // the entry count excludes mirror images, and indices are bounded by the size line.
+    let header = "%%MatrixMarket matrix coordinate pattern symmetric\n";
+    assert_eq!(parse_matrix_market(format!("{}3 3 2\n2 1\n3 3\n", header).as_bytes()).unwrap().len(), 3);
+    assert!(parse_matrix_market(format!("{}3 3 3\n2 1\n3 3\n", header).as_bytes()).is_err());
+    assert!(parse_matrix_market(format!("{}3 3 1\n2 1\n3 3\n", header).as_bytes()).is_err());
+    assert!(parse_matrix_market(format!("{}3 2 2\n2 1\n3 3\n", header).as_bytes()).is_err());
+    assert!(parse_matrix_market(format!("{}3 3 5000000000\n2 1\n", header).as_bytes()).is_err());
+    assert!(parse_matrix_market(format!("{}8589934592 3 1\n2 1\n", header).as_bytes()).is_err());
+}
+
+#[test]
+fn binary_edges_round_trip() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::{load_binary_edges, read_binary_edges, write_binary_edges};
+    let layout = GraphLayout::from_edges((0 .. 500u32).map(|i| (i * 3, u32::MAX - i)));
+    let path = std::env::temp_dir().join("graph_layout_binary_edges.bin");
+    write_binary_edges(&layout, &path).unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 8 * 500);
+    let read = read_binary_edges(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(read, layout.edges().collect::<Vec<_>>());
+    let loaded = load_binary_edges(&path).unwrap();
+    assert_eq!(loaded.keys().collect::<Vec<_>>(), layout.keys().collect::<Vec<_>>());
+    std::fs::write(&path, [1u8, 0, 0, 0, 2]).unwrap();
+    assert!(matches!(read_binary_edges(&path).unwrap().next().unwrap(), Err(graph_layout::Error::Corrupt(_))));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn webgraph_decode() {
+    use graph_layout::io::webgraph::WebGraph;
+
+    // a minimal big-endian bit writer for the instantaneous codes used by BVGraph.
+    struct Bits { bytes: Vec<u8>, used: usize }
+    impl Bits {
+        fn bit(&mut self, bit: bool) {
+            if self.used.is_multiple_of(8) { self.bytes.push(0); }
+            if bit { *self.bytes.last_mut().unwrap() |= 0x80 >> (self.used % 8); }
+            self.used += 1;
+        }
+        fn int(&mut self, value: u64, width: u32) { for i in (0 .. width).rev() { self.bit((value >> i) & 1 == 1); } }
+        fn unary(&mut self, value: u64) { for _ in 0 .. value { self.bit(false); } self.bit(true); }
+        fn gamma(&mut self, value: u64) {
+            let width = 63 - (value + 1).leading_zeros();
+            self.unary(width as u64);
+            self.int(value + 1 - (1 << width), width);
+        }
+        fn zeta3(&mut self, value: u64) {
+            let height = (63 - (value + 1).leading_zeros()) / 3;
+            let left = 1u64 << (3 * height);
+            let offset = value + 1 - left;
+            self.unary(height as u64);
+            if offset < left { self.int(offset, 3 * height + 2); } else { self.int(offset + left, 3 * height + 3); }
+        }
+    }
+
+    let mut bits = Bits { bytes: Vec::new(), used: 0 };
+    // node 0 -> [1, 2, 3, 300]: an interval [1, 3] and a residual.
+    bits.gamma(4); bits.unary(0); bits.gamma(1); bits.gamma(2); bits.gamma(1); bits.zeta3(600);
+    // node 1 -> [1, 2, 300, 301]: copies [1, 2], skips [3], copies [300], and a residual.
+    bits.gamma(4); bits.unary(1); bits.gamma(2); bits.gamma(2); bits.gamma(0); bits.gamma(0); bits.zeta3(600);
+    // node 2 -> []
+    bits.gamma(0);
+    // node 3 -> [0]
+    bits.gamma(1); bits.unary(0); bits.gamma(0); bits.zeta3(5);
+    // nodes 4 .. 400 -> []
+    for _ in 4 .. 400 { bits.gamma(0); }
+
+    let properties = "#BVGraph properties\nnodes=400\narcs=9\nwindowsize=7\nminintervallength=2\nzetak=3\ncompressionflags=\n";
+    let graph = WebGraph::new(properties, &bits.bytes[..]).unwrap();
+    let edges = graph.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(edges, vec![(0, 1), (0, 2), (0, 3), (0, 300), (1, 1), (1, 2), (1, 300), (1, 301), (3, 0)]);
+
+    let truncated = WebGraph::new(properties, &bits.bytes[.. 3]).unwrap();
+    assert!(matches!(truncated.collect::<Result<Vec<_>, _>>(), Err(graph_layout::Error::Corrupt(_))));
+}
+
+#[test]
+fn metis_reader() {
+    use graph_layout::io::parse_metis;
+    // a weighted triangle 1-2-3 with an isolated fourth vertex, and two vertex weights each.
+    let text = "% comment\n4 3 011 2\n1 1 2 5 3 7\n2 2 1 5 3 9\n3 3 1 7 2 9\n4 4\n";
+    let metis = parse_metis(text.as_bytes()).unwrap();
+    assert_eq!(metis.constraints, 2);
+    assert_eq!(metis.vertex_weights, vec![1, 1, 2, 2, 3, 3, 4, 4]);
+    assert!(metis.vertex_sizes.is_none());
+    let mut entries = metis.layout.edges().zip(metis.layout.weights().unwrap().iter().cloned()).collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.0);
+    assert_eq!(entries, vec![((0, 1), 5.0), ((0, 2), 7.0), ((1, 0), 5.0), ((1, 2), 9.0), ((2, 0), 7.0), ((2, 1), 9.0)]);
+
+    let plain = parse_metis(&b"3 1\n2\n1\n\n"[..]).unwrap();
+    assert!(plain.layout.weights().is_none());
+    assert_eq!(plain.layout.len(), 2);
+    assert!(parse_metis(&b"3 2\n2\n1\n\n"[..]).is_err());
+    assert!(matches!(parse_metis(&b"2 1\n3\n1\n"[..]), Err(graph_layout::Error::Corrupt(_))));
+    // forged counts are rejected or run out of lines, rather than sizing allocations.
+    assert!(parse_metis(&b"4294967296 0 10 18446744073709551615\n"[..]).is_err());
+    assert!(parse_metis(&b"4294967296 0 100\n1\n"[..]).is_err());
+}
+
+#[test]
+fn ligra_and_gap_export() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::{format_gap, format_ligra};
+    let layout = GraphLayout::from_edges(vec![(0, 2), (0, 1), (2, 0), (1, 3)].into_iter());
+    let mut ligra = Vec::new();
+    format_ligra(&layout, &mut ligra).unwrap();
+    assert_eq!(String::from_utf8(ligra).unwrap(), "AdjacencyGraph\n4\n4\n0\n2\n3\n4\n1\n2\n3\n0\n");
+
+    let weighted = GraphLayout::from_weighted_edges(vec![((1, 0), 3.0), ((0, 1), 2.0)].into_iter());
+    let mut ligra = Vec::new();
+    format_ligra(&weighted, &mut ligra).unwrap();
+    assert_eq!(String::from_utf8(ligra).unwrap(), "WeightedAdjacencyGraph\n2\n2\n0\n1\n1\n0\n2\n3\n");
+
+    let mut gap = Vec::new();
+    format_gap(&layout, &mut gap, true).unwrap();
+    let words = |bytes: &[u8], width: usize| bytes.chunks(width).map(|chunk| {
+        let mut word = [0u8; 8];
+        word[.. width].copy_from_slice(chunk);
+        i64::from_le_bytes(word)
+    }).collect::<Vec<_>>();
+    assert_eq!(gap[0], 1);
+    assert_eq!(words(&gap[1 .. 17], 8), vec![4, 4]);
+    assert_eq!(words(&gap[17 .. 57], 8), vec![0, 2, 3, 4, 4]);
+    assert_eq!(words(&gap[57 .. 73], 4), vec![1, 2, 3, 0]);
+    assert_eq!(words(&gap[73 .. 113], 8), vec![0, 1, 2, 3, 4]);
+    assert_eq!(words(&gap[113 ..], 4), vec![2, 0, 0, 1]);
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn arrow_export() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::arrow::{record_batches, EdgeColumns};
+    let layout = GraphLayout::from_weighted_edges((0 .. 10u32).map(|i| ((i, 9 - i), i as f64)));
+    let batches = record_batches(&layout, EdgeColumns::Endpoints, 4).unwrap();
+    assert_eq!(batches.iter().map(|batch| batch.num_rows()).collect::<Vec<_>>(), vec![4, 4, 2]);
+    assert_eq!(batches[0].num_columns(), 3);
+    assert_eq!(batches[0].schema().field(2).name(), "weight");
+    let keys = record_batches(&layout, EdgeColumns::Keys, 16).unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].num_columns(), 2);
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn parquet_export() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::arrow::{write_parquet, EdgeColumns};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    let layout = GraphLayout::from_edges((0 .. 100u32).map(|i| (i, i * i)));
+    let path = std::env::temp_dir().join("graph_layout_parquet_export.parquet");
+    write_parquet(&layout, &path, EdgeColumns::Keys, 30).unwrap();
+    let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(reader.metadata().num_row_groups(), 4);
+    assert_eq!(reader.metadata().file_metadata().num_rows(), 100);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn npy_export() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::write_npy;
+    let layout = GraphLayout::from_weighted_edges(vec![((1, 2), 0.5), ((3, 4), 1.5)].into_iter());
+    let directory = std::env::temp_dir().join("graph_layout_npy_export");
+    std::fs::create_dir_all(&directory).unwrap();
+    write_npy(&layout, &directory).unwrap();
+    let src = std::fs::read(directory.join("src.npy")).unwrap();
+    let weight = std::fs::read(directory.join("weight.npy")).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(&src[.. 8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([src[8], src[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = std::str::from_utf8(&src[10 .. 10 + header_len]).unwrap();
+    assert!(header.starts_with("{'descr': '<u4', 'fortran_order': False, 'shape': (2,), }"));
+    assert!(header.ends_with('\n'));
+    let sources = src[10 + header_len ..].chunks(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect::<Vec<_>>();
+    assert_eq!(sources, layout.edges().map(|(s, _)| s).collect::<Vec<_>>());
+    assert_eq!(weight.len(), 128 + 16);
+}
+
+#[test]
+fn layout_file_round_trip() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::format::{format_layout, parse_layout};
+    let edges = (0 .. 20_000u32).map(|i| (((i * 7919) % 5003, (i * 31) % 4099), i as f64 / 4.0)).collect::<Vec<_>>();
+    let layouts = vec![
+        GraphLayout::from_edges(edges.iter().map(|&(edge, _)| edge)),
+        GraphLayout::from_weighted_edges_tiled(edges.iter().cloned(), 5),
+        GraphLayout::from_edges(Vec::new().into_iter()),
+    ];
+    for layout in &layouts {
+        let mut bytes = Vec::new();
+        format_layout(layout, &mut bytes).unwrap();
+        let read = parse_layout(&bytes).unwrap();
+        assert_eq!(read.len(), layout.len());
+        assert_eq!(read.vertices(), layout.vertices());
+        assert_eq!(read.log_tile(), layout.log_tile());
+        assert_eq!(read.keys().collect::<Vec<_>>(), layout.keys().collect::<Vec<_>>());
+        assert_eq!(read.blocks(), layout.blocks());
+        assert_eq!(read.tiles(), layout.tiles());
+        assert_eq!(read.tile_summaries(), layout.tile_summaries());
+        assert_eq!(read.weights(), layout.weights());
+        if let Some(tile) = read.tiles().get(3) {
+            assert_eq!(read.tile_keys(tile).collect::<Vec<_>>(), layout.tile_keys(tile).collect::<Vec<_>>());
+        }
+
+        // flipping any byte of a section must be caught by a checksum or a consistency check.
+        if !layout.is_empty() {
+            let mut corrupted = bytes.clone();
+            corrupted[100] ^= 0x10;
+            assert!(parse_layout(&corrupted).is_err());
+        }
+    }
+
+    let path = std::env::temp_dir().join("graph_layout_file_round_trip.glayout");
+    layouts[1].save(&path).unwrap();
+    let opened = GraphLayout::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(opened.weights(), layouts[1].weights());
+}
+
+#[test]
+fn sharded_layout_round_trip() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::shards::{read_manifest, write_shards};
+    let edges = (0 .. 20_000u32).map(|i| (((i * 7919) % 5003, (i * 31) % 4099), i as f64)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_weighted_edges_tiled(edges.into_iter(), 6);
+    let directory = std::env::temp_dir().join("graph_layout_sharded_round_trip");
+    let written = write_shards(&layout, &directory, 4).unwrap();
+    let manifest = read_manifest(&directory).unwrap();
+    assert_eq!(manifest, written);
+    assert_eq!(manifest.shards.len(), 4);
+    assert_eq!(manifest.shards.iter().map(|shard| shard.edges).sum::<usize>(), layout.len());
+
+    // shards begin on tile boundaries and hold only keys in their ranges.
+    for (index, shard) in manifest.shards.iter().enumerate() {
+        assert_eq!(shard.lower % (1 << 12), 0);
+        let part = manifest.open_shard(&directory, index).unwrap();
+        assert!(part.keys().all(|key| shard.lower <= key && key <= shard.upper));
+    }
+
+    assert!(manifest.open_shard(&directory, 4).is_err());
+
+    let loaded = manifest.load(&directory).unwrap();
+
+    // manifests whose shards miss keys, overlap, or miscount edges are rejected.
+    let text = std::fs::read_to_string(directory.join("manifest.txt")).unwrap();
+    let first = &manifest.shards[0];
+    let second = &manifest.shards[1];
+    let last = &manifest.shards[3];
+    let forgeries = [
+        text.replacen(&format!("shard 0 {} ", first.upper), &format!("shard 1 {} ", first.upper), 1),
+        text.replacen(&format!("shard {} {} ", last.lower, u64::MAX), &format!("shard {} {} ", last.lower, u64::MAX - 1), 1),
+        text.replacen(&format!("shard {} ", second.lower), &format!("shard {} ", second.lower - 1), 1),
+        text.replacen(&format!("shard {} {} {} ", second.lower, second.upper, second.edges), &format!("shard {} {} {} ", second.lower, second.upper, second.edges + 1), 1),
+    ];
+    for forged in forgeries {
+        assert_ne!(forged, text);
+        std::fs::write(directory.join("manifest.txt"), forged).unwrap();
+        assert!(matches!(read_manifest(&directory), Err(graph_layout::Error::Corrupt(_))));
+    }
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(loaded.keys().collect::<Vec<_>>(), layout.keys().collect::<Vec<_>>());
+    assert_eq!(loaded.weights(), layout.weights());
+    assert_eq!(loaded.tiles(), layout.tiles());
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mapped_layout() {
+    use graph_layout::graph::GraphLayout;
+    let edges = (0 .. 20_000u32).map(|i| (((i * 7919) % 5003, (i * 31) % 4099), i as f64)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_weighted_edges_tiled(edges.into_iter(), 5);
+    let path = std::env::temp_dir().join("graph_layout_mapped_layout.glayout");
+    layout.save(&path).unwrap();
+    let mapped = GraphLayout::open_mmap(&path).unwrap();
+    mapped.verify().unwrap();
+
+    assert_eq!(mapped.len(), layout.len());
+    assert_eq!(mapped.vertices(), layout.vertices());
+    assert_eq!(mapped.tile_summaries(), layout.tile_summaries());
+    assert_eq!(mapped.keys().collect::<Vec<_>>(), layout.keys().collect::<Vec<_>>());
+    assert_eq!(mapped.edges().collect::<Vec<_>>(), layout.edges().collect::<Vec<_>>());
+    for (mine, theirs) in mapped.tiles().iter().zip(layout.tiles()).step_by(97) {
+        assert_eq!(mapped.tile_edges(mine).collect::<Vec<_>>(), layout.tile_edges(theirs).collect::<Vec<_>>());
+    }
+    for (mine, theirs) in mapped.blocks().iter().zip(layout.blocks()) {
+        assert_eq!(mapped.block_keys(mine).collect::<Vec<_>>(), layout.block_keys(theirs).collect::<Vec<_>>());
+    }
+    assert_eq!(mapped.weight(1234), Some(layout.weights().unwrap()[1234]));
+    drop(mapped);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn builder_spills_and_merges() {
+    use graph_layout::builder::LayoutBuilder;
+    use graph_layout::graph::GraphLayout;
+    // every edge appears twice, in different runs.
+    let edges = (0 .. 40_000u32).map(|i| ((i % 20_000) * 7919 % 5003, (i % 20_000) * 31 % 4099)).collect::<Vec<_>>();
+    let expected = GraphLayout::from_edges_tiled(edges.iter().cloned(), 5);
+
+    let path = std::env::temp_dir().join("graph_layout_builder_spills_and_merges.glayout");
+    let mut builder = LayoutBuilder::new(3_000).tiled(5);
+    builder.extend(edges.iter().cloned()).unwrap();
+    assert!(builder.runs() > 10);
+    assert_eq!(builder.finish(&path).unwrap(), expected.len());
+
+    let built = GraphLayout::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(built.vertices(), expected.vertices());
+    assert_eq!(built.keys().collect::<Vec<_>>(), expected.keys().collect::<Vec<_>>());
+    assert_eq!(built.blocks(), expected.blocks());
+    assert_eq!(built.tiles(), expected.tiles());
+    assert_eq!(built.tile_summaries(), expected.tile_summaries());
+
+    // a stream that fits in the buffer is never spilled.
+    let mut builder = LayoutBuilder::new(100_000);
+    builder.extend(edges.iter().cloned()).unwrap();
+    assert_eq!(builder.runs(), 0);
+    assert_eq!(builder.finish(&path).unwrap(), expected.len());
+    assert_eq!(GraphLayout::open(&path).unwrap().keys().collect::<Vec<_>>(), expected.keys().collect::<Vec<_>>());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn builder_resumes_from_checkpoint() {
+    use graph_layout::builder::LayoutBuilder;
+    use graph_layout::graph::GraphLayout;
+    let edges = (0 .. 30_000u32).map(|i| (i * 7919 % 5003, i * 31 % 4099)).collect::<Vec<_>>();
+    let expected = GraphLayout::from_edges(edges.iter().cloned());
+    let directory = std::env::temp_dir().join("graph_layout_builder_resumes");
+    let path = directory.join("layout.glayout");
+
+    // an interrupted build leaves its completed runs behind, but not its buffered edges.
+    let mut builder = LayoutBuilder::resume(&directory, 4_000).unwrap();
+    assert_eq!(builder.consumed(), 0);
+    builder.extend(edges[.. 10_000].iter().cloned()).unwrap();
+    assert_eq!(builder.consumed(), 8_000);
+    drop(builder);
+
+    let mut builder = LayoutBuilder::resume(&directory, 4_000).unwrap();
+    assert_eq!(builder.runs(), 2);
+    let skip = builder.consumed() as usize;
+    builder.extend(edges[skip ..].iter().cloned()).unwrap();
+    assert_eq!(builder.finish(&path).unwrap(), expected.len());
+    assert_eq!(GraphLayout::open(&path).unwrap().keys().collect::<Vec<_>>(), expected.keys().collect::<Vec<_>>());
+
+    // a successful build removes its runs and checkpoint.
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 0);
+
+    // a resumable build that never spills has no checkpoint to remove.
+    let mut builder = LayoutBuilder::resume(&directory, 1_000).unwrap();
+    builder.extend(edges[.. 10].iter().cloned()).unwrap();
+    assert_eq!(builder.runs(), 0);
+    assert_eq!(builder.finish(&path).unwrap(), 10);
+    assert_eq!(GraphLayout::open(&path).unwrap().len(), 10);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 0);
+    std::fs::remove_dir(&directory).unwrap();
+}
+
+#[test]
+fn pagerank_kernel() {
+    use graph_layout::algo::pagerank;
+    use graph_layout::graph::GraphLayout;
+    // a directed cycle keeps uniform ranks.
+    let cycle = GraphLayout::from_edges((0 .. 100u32).map(|i| (i, (i + 1) % 100)));
+    for rank in pagerank(&cycle, 20, 0.85) {
+        assert!((rank - 0.01).abs() < 1e-6);
+    }
+
+    // a bidirected star converges to `center = 0.15 / 11 + 0.85 * (1 - center)`.
+    let star = GraphLayout::from_edges((1 .. 11u32).flat_map(|i| vec![(i, 0), (0, i)]));
+    let ranks = pagerank(&star, 100, 0.85);
+    assert!((ranks[0] - (0.15 / 11.0 + 0.85) / 1.85).abs() < 1e-4);
+    assert!((ranks.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+    assert!(ranks[1 ..].iter().all(|&rank| (rank - ranks[1]).abs() < 1e-6));
+}
+
+#[test]
+fn triangle_counting() {
+    use graph_layout::algo::triangles;
+    use graph_layout::graph::GraphLayout;
+    // the complete graph on 30 vertices has 30 choose 3 triangles, in either or both directions.
+    let complete = (0 .. 30u32).flat_map(|i| (0 .. 30u32).map(move |j| (i, j))).collect::<Vec<_>>();
+    assert_eq!(triangles(&GraphLayout::from_edges(complete.iter().cloned())), 4060);
+    assert_eq!(triangles(&GraphLayout::from_edges(complete.iter().cloned().filter(|&(i, j)| i > j))), 4060);
+
+    // a grid has no triangles until its diagonals are added.
+    let grid = (0 .. 20u32).flat_map(|x| (0 .. 20u32).flat_map(move |y| {
+        let v = 20 * x + y;
+        let mut edges = Vec::new();
+        if x < 19 { edges.push((v, v + 20)); }
+        if y < 19 { edges.push((v, v + 1)); }
+        edges
+    })).collect::<Vec<_>>();
+    assert_eq!(triangles(&GraphLayout::from_edges(grid.iter().cloned())), 0);
+    let diagonals = (0 .. 19u32).flat_map(|x| (0 .. 19u32).map(move |y| (20 * x + y, 20 * (x + 1) + y + 1)));
+    assert_eq!(triangles(&GraphLayout::from_edges(grid.iter().cloned().chain(diagonals))), 2 * 19 * 19);
+}
+
+#[test]
+fn connected_components_kernel() {
+    use graph_layout::algo::connected_components;
+    use graph_layout::graph::GraphLayout;
+    // three paths, one written backwards, and an isolated vertex 99 reached only by a self-loop.
+    let mut edges = (10 .. 39u32).map(|i| (i, i + 1)).collect::<Vec<_>>();
+    edges.extend((50 .. 89u32).map(|i| (i + 1, i)));
+    edges.extend((0 .. 9u32).map(|i| (9 - i, 8 - i)));
+    edges.push((99, 99));
+    let labels = connected_components(&GraphLayout::from_edges(edges.into_iter()));
+    assert_eq!(labels.len(), 100);
+    for (vertex, &label) in labels.iter().enumerate() {
+        let expected = match vertex {
+            0 ..= 9 => 0,
+            10 ..= 39 => 10,
+            50 ..= 89 => 50,
+            _ => vertex as u32,
+        };
+        assert_eq!(label, expected);
+    }
+}
+
+#[test]
+fn bfs_kernel() {
+    use graph_layout::algo::bfs::{bfs, UNREACHED};
+    use graph_layout::graph::GraphLayout;
+    // a 100 × 100 directed grid, with edges pointing right and down, and one unreachable vertex.
+    let side = 100u32;
+    let mut edges = Vec::new();
+    for x in 0 .. side {
+        for y in 0 .. side {
+            if x + 1 < side { edges.push((x * side + y, (x + 1) * side + y)); }
+            if y + 1 < side { edges.push((x * side + y, x * side + y + 1)); }
+        }
+    }
+    edges.push((20_000, 0));
+    let layout = GraphLayout::from_edges(edges.into_iter());
+    assert!(layout.blocks().len() > 1);
+
+    let distances = bfs(&layout, 0);
+    for x in 0 .. side {
+        for y in 0 .. side {
+            assert_eq!(distances[(x * side + y) as usize], x + y);
+        }
+    }
+    assert_eq!(distances[20_000], UNREACHED);
+    assert_eq!(distances[12_345], UNREACHED);
+
+    let distances = bfs(&layout, 20_000);
+    assert_eq!(distances[20_000], 0);
+    assert_eq!(distances[(side * side - 1) as usize], 2 * (side - 1) + 1);
+}
+
+#[test]
+fn spmv_kernel() {
+    use graph_layout::algo::spmv;
+    use graph_layout::graph::GraphLayout;
+    let edges = (0 .. 5_000u32).map(|i| ((i * 7919 % 1000, i * 31 % 997), (i % 7) as f64)).collect::<Vec<_>>();
+    let x = (0 .. 1000).map(|i| i as f64 / 10.0).collect::<Vec<_>>();
+
+    let mut expected = vec![1.0; 1000];
+    let mut seen = std::collections::HashSet::new();
+    for &((src, dst), weight) in &edges {
+        if seen.insert((src, dst)) {
+            expected[src as usize] += weight * x[dst as usize];
+        }
+    }
+    let mut y = vec![1.0; 1000];
+    spmv(&GraphLayout::from_weighted_edges(edges.iter().cloned()), &x, &mut y);
+    for (actual, expected) in y.iter().zip(&expected) {
+        assert!((actual - expected).abs() < 1e-6);
+    }
+
+    // unweighted entries are one.
+    let mut y = vec![0.0; 1000];
+    spmv(&GraphLayout::from_edges((0 .. 1000u32).map(|i| (i, 999 - i))), &x, &mut y);
+    assert_eq!(y[0], 99.9);
+    assert_eq!(y[999], 0.0);
+}
+
+#[test]
+fn label_propagation_kernel() {
+    use graph_layout::algo::label_propagation;
+    use graph_layout::graph::GraphLayout;
+    // two 10-cliques joined by a single edge settle on one label each.
+    let mut edges = Vec::new();
+    for base in &[0u32, 10] {
+        for i in 0 .. 10 {
+            for j in 0 .. 10 {
+                if i < j { edges.push((base + i, base + j)); }
+            }
+        }
+    }
+    edges.push((9, 10));
+    edges.push((25, 25));
+    let labels = label_propagation(&GraphLayout::from_edges(edges.into_iter()), 20);
+    assert_eq!(labels.len(), 26);
+    assert!(labels[.. 10].iter().all(|&label| label == labels[0]));
+    assert!(labels[10 .. 20].iter().all(|&label| label == labels[10]));
+    assert_ne!(labels[0], labels[10]);
+    // vertices without neighbors keep their own labels.
+    assert_eq!(labels[20 ..], [20, 21, 22, 23, 24, 25]);
+}
+
+#[test]
+fn approximate_diameter() {
+    use graph_layout::algo::approx_diameter;
+    use graph_layout::graph::GraphLayout;
+    // an undirected path on 50 vertices has diameter 49.
+    let path = GraphLayout::from_edges((0 .. 49u32).flat_map(|i| vec![(i, i + 1), (i + 1, i)]));
+    let estimate = approx_diameter(&path, 50);
+    assert_eq!(estimate.sources.len(), 50);
+    assert_eq!(estimate.lower, 49);
+    assert!(estimate.upper.unwrap() >= 49);
+
+    let estimate = approx_diameter(&path, 5);
+    assert!(estimate.lower <= 49 && 49 <= estimate.upper.unwrap());
+    assert_eq!(estimate, approx_diameter(&path, 5));
+}
+
+#[test]
+fn butterfly_counting() {
+    use graph_layout::algo::butterflies;
+    use graph_layout::graph::GraphLayout;
+    // the complete bipartite graph K(4, 6) has (4 choose 2) * (6 choose 2) butterflies.
+    let complete = (0 .. 4u32).flat_map(|u| (0 .. 6u32).map(move |i| (u, i)));
+    assert_eq!(butterflies(&GraphLayout::from_edges(complete)), 6 * 15);
+    let transposed = (0 .. 4u32).flat_map(|u| (0 .. 6u32).map(move |i| (i, u)));
+    assert_eq!(butterflies(&GraphLayout::from_edges(transposed)), 6 * 15);
+
+    // each user shares exactly one item with the next, so there are no butterflies.
+    let chain = (0 .. 100u32).flat_map(|u| vec![(u, u), (u, u + 1)]);
+    assert_eq!(butterflies(&GraphLayout::from_edges(chain)), 0);
+}
+
+#[test]
+fn bfs_validation() {
+    use graph_layout::algo::bfs::{bfs, validate_bfs, BfsViolation, UNREACHED};
+    use graph_layout::graph::GraphLayout;
+    let edges = (0 .. 3_000u32).map(|i| (i * 7919 % 1000, i * 31 % 997)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges(edges.iter().cloned());
+    assert!(edges.iter().all(|&(src, dst)| layout.has_edge(src, dst)));
+    assert!(!layout.has_edge(1, 0) || edges.contains(&(1, 0)));
+    assert!(!layout.has_edge(5_000, 5_000));
+
+    let distances = bfs(&layout, 0);
+    let mut parents = vec![UNREACHED; distances.len()];
+    parents[0] = 0;
+    for &(src, dst) in &edges {
+        if distances[src as usize] != UNREACHED && distances[dst as usize] == distances[src as usize] + 1 {
+            parents[dst as usize] = src;
+        }
+    }
+    assert_eq!(validate_bfs(&layout, 0, &parents), Ok(distances.clone()));
+
+    let reached = (1 .. parents.len()).find(|&v| parents[v] != UNREACHED).unwrap();
+    let mut broken = parents.clone();
+    broken[reached] = UNREACHED;
+    assert!(validate_bfs(&layout, 0, &broken).is_err());
+    let mut broken = parents.clone();
+    broken[0] = 1;
+    assert_eq!(validate_bfs(&layout, 0, &broken), Err(BfsViolation::Root));
+    let stranger = (0 .. 1000u32).find(|&v| !layout.has_edge(v, reached as u32) && distances[v as usize] != UNREACHED).unwrap();
+    let mut broken = parents.clone();
+    broken[reached] = stranger;
+    assert!(validate_bfs(&layout, 0, &broken).is_err());
+}
+
+#[test]
+fn degrees() {
+    use graph_layout::graph::GraphLayout;
+    let layout = GraphLayout::from_edges(vec![(0, 1), (0, 2), (0, 3), (2, 0), (3, 3), (0, 1)].into_iter());
+    let (in_degrees, out_degrees) = layout.degrees();
+    assert_eq!(in_degrees, vec![1, 1, 1, 2]);
+    assert_eq!(out_degrees, vec![3, 0, 1, 1]);
+}
+
+#[test]
+fn degree_distribution() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::stats::degree_distribution;
+    // a star from vertex 0 to 1 ..= 8.
+    let layout = GraphLayout::from_edges((1 .. 9u32).map(|i| (0, i)));
+    let distribution = degree_distribution(&layout);
+    assert_eq!(distribution.out_degrees.bins, vec![8, 0, 0, 0, 1]);
+    assert_eq!(distribution.out_degrees.max, 8);
+    assert!((distribution.out_degrees.mean - 8.0 / 9.0).abs() < 1e-12);
+    assert!((distribution.out_degrees.gini - 8.0 / 9.0).abs() < 1e-12);
+    assert_eq!(distribution.in_degrees.bins, vec![1, 8]);
+    assert!((distribution.in_degrees.gini - 1.0 / 9.0).abs() < 1e-12);
+
+    // a cycle has equal degrees.
+    let cycle = degree_distribution(&GraphLayout::from_edges((0 .. 10u32).map(|i| (i, (i + 1) % 10))));
+    assert_eq!(cycle.in_degrees, cycle.out_degrees);
+    assert_eq!(cycle.in_degrees.gini, 0.0);
+}
+
+#[test]
+fn streaming_hubs() {
+    use graph_layout::stats::hubs;
+    // vertex v has out-degree 2000 / (v + 1) towards a spread of destinations, and vertex 7 also
+    // receives an edge from every source.
+    let mut edges = Vec::new();
+    for src in 0 .. 1000u32 {
+        for i in 0 .. 2000 / (src + 1) {
+            edges.push((src, 1000 + (src * 37 + i * 101) % 50_000));
+        }
+        edges.push((src, 7));
+    }
+    let found = hubs(edges.iter().cloned(), 5, 64);
+    assert_eq!(found.sources.iter().map(|hub| hub.vertex).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    let top = found.sources[0];
+    assert!(top.count - top.error <= 2001 && 2001 <= top.count);
+    assert_eq!(found.destinations[0].vertex, 7);
+    assert!(found.destinations[0].count >= 1000);
+}
+
+#[test]
+fn locality_report() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::stats::locality;
+    // a dense 64 × 64 block is traversed with unit steps and about one byte per key.
+    let dense = locality(&GraphLayout::from_edges((0 .. 64u32).flat_map(|x| (0 .. 64u32).map(move |y| (x, y)))));
+    assert_eq!(dense.sources.p99, 1);
+    assert_eq!(dense.sources.max, 1);
+    assert!(dense.bits_per_edge < 8.01);
+    assert!(dense.lines_per_edge < 0.01);
+
+    // scattered edges have large gaps and miss the cache often.
+    let scattered = locality(&GraphLayout::from_edges((0 .. 4096u32).map(|i| (i * 7919 % 1_000_003, i * 104_729 % 999_983))));
+    assert!(scattered.sources.p50 > 1000);
+    assert!(scattered.bits_per_edge > dense.bits_per_edge);
+    assert!(scattered.lines_per_edge > 1.0);
+}
+
+#[test]
+fn degree_ordering() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::order::{by_degree, Permutation};
+    // vertex 9 is a hub, vertex 5 is second, the rest have degree one or zero.
+    let mut edges = (0 .. 9u32).map(|v| ((v, 9), v as f64)).collect::<Vec<_>>();
+    edges.push(((5, 3), 10.0));
+    edges.push(((5, 4), 11.0));
+    let layout = GraphLayout::from_weighted_edges_tiled(edges.iter().cloned(), 2);
+    let permutation = by_degree(&layout);
+    assert_eq!(permutation.new_id(9), 0);
+    assert_eq!(permutation.new_id(5), 1);
+    assert_eq!(permutation.new_id(3), 2);
+
+    let relabeled = permutation.apply_to_layout(&layout);
+    assert_eq!(relabeled.len(), layout.len());
+    assert_eq!(relabeled.log_tile(), Some(2));
+    let mut expected = edges.iter().map(|&((src, dst), weight)| ((permutation.new_id(src), permutation.new_id(dst)), weight)).collect::<Vec<_>>();
+    expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mut actual = relabeled.edges().zip(relabeled.weights().unwrap().iter().cloned()).collect::<Vec<_>>();
+    actual.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(actual, expected);
+
+    assert!(Permutation::from_new_ids(vec![0, 2, 2]).is_none());
+    assert_eq!(Permutation::from_order(&[2, 0, 1]).unwrap().new_ids(), &[1, 2, 0]);
+}
+
+#[test]
+fn rcm_ordering() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::order::rcm;
+    // a 30 × 30 grid with scrambled labels, plus a separate triangle and an isolated vertex.
+    let scramble = |v: u32| v * 677 % 900;
+    let mut edges = Vec::new();
+    for x in 0 .. 30u32 {
+        for y in 0 .. 30u32 {
+            if x + 1 < 30 { edges.push((scramble(30 * x + y), scramble(30 * (x + 1) + y))); }
+            if y + 1 < 30 { edges.push((scramble(30 * x + y), scramble(30 * x + y + 1))); }
+        }
+    }
+    edges.extend(vec![(900, 901), (901, 902), (902, 900), (903, 903)]);
+    let layout = GraphLayout::from_edges(edges.iter().cloned());
+    let bandwidth = |edges: &mut dyn Iterator<Item=(u32, u32)>| edges.map(|(src, dst)| (src as i64 - dst as i64).abs()).max().unwrap();
+    assert!(bandwidth(&mut layout.edges()) > 500);
+
+    let permutation = rcm(&layout);
+    assert_eq!(permutation.len(), 904);
+    let relabeled = permutation.apply_to_layout(&layout);
+    assert_eq!(relabeled.len(), layout.len());
+    assert!(bandwidth(&mut relabeled.edges()) <= 31);
+}
+
+#[test]
+fn gorder_ordering() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::order::gorder;
+    use graph_layout::stats::locality;
+    // 40 dense communities of 10 vertices, with scrambled labels.
+    let scramble = |v: u32| v * 163 % 400;
+    let mut edges = Vec::new();
+    for community in 0 .. 40u32 {
+        for i in 0 .. 10 {
+            for j in 0 .. 10 {
+                if i != j { edges.push((scramble(10 * community + i), scramble(10 * community + j))); }
+            }
+        }
+    }
+    let layout = GraphLayout::from_edges(edges.into_iter());
+    let permutation = gorder(&layout, 5);
+    assert_eq!(permutation.len(), 400);
+
+    // each community receives a contiguous range of labels.
+    for community in 0 .. 40u32 {
+        let labels = (0 .. 10).map(|i| permutation.new_id(scramble(10 * community + i))).collect::<Vec<_>>();
+        assert_eq!(labels.iter().max().unwrap() - labels.iter().min().unwrap(), 9);
+    }
+    let relabeled = permutation.apply_to_layout(&layout);
+    assert!(locality(&relabeled).bits_per_edge < locality(&layout).bits_per_edge);
+}
+
+#[test]
+fn random_ordering() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::order::random;
+    use graph_layout::stats::locality;
+    let cycle = GraphLayout::from_edges((0 .. 10_000u32).map(|v| (v, (v + 1) % 10_000)));
+    let permutation = random(&cycle, 42);
+    assert_eq!(permutation, random(&cycle, 42));
+    assert_ne!(permutation, random(&cycle, 43));
+    assert!((0 .. 100u32).filter(|&v| permutation.new_id(v) == v).count() < 5);
+
+    let scrambled = permutation.apply_to_layout(&cycle);
+    assert_eq!(scrambled.len(), cycle.len());
+    assert!(locality(&scrambled).bits_per_edge > 2.0 * locality(&cycle).bits_per_edge);
+}
+
+#[test]
+fn densify_identifiers() {
+    use graph_layout::densify::Densifier;
+    use graph_layout::graph::GraphLayout;
+    let raw = [(u64::MAX, 17), (17, 1 << 40), (1 << 40, u64::MAX), (17, 17)];
+    let mut densifier = Densifier::new();
+    let layout = GraphLayout::from_edges(densifier.densify(raw.iter().cloned()));
+    assert_eq!(layout.len(), 4);
+    assert_eq!(layout.vertices(), 3);
+    assert_eq!(densifier.raw_ids(), &[u64::MAX, 17, 1 << 40]);
+    assert_eq!(densifier.get(1 << 40), Some(2));
+    assert_eq!(densifier.get(5), None);
+    for (src, dst) in layout.edges() {
+        assert!(raw.contains(&(densifier.raw(src), densifier.raw(dst))));
+    }
+}
+
+#[test]
+fn external_densification() {
+    use graph_layout::builder::LayoutBuilder;
+    use graph_layout::densify::{Dictionary, ExternalDensifier};
+    use graph_layout::graph::GraphLayout;
+    let raw = (0 .. 5_000u64).map(|i| ((i * 7919 % 1_000) << 40, (i * 31 % 3_000) * 0x1_0000_0001)).collect::<Vec<_>>();
+
+    let directory = std::env::temp_dir();
+    let dictionary = directory.join("graph_layout_external_densification.ids");
+    let path = directory.join("graph_layout_external_densification.glayout");
+    let mut builder = LayoutBuilder::new(10_000);
+    let ids = ExternalDensifier::new(700).spill_directory(&directory)
+        .run(raw.iter().cloned(), &dictionary, |edge| builder.push(edge))
+        .unwrap();
+    builder.finish(&path).unwrap();
+    let layout = GraphLayout::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // dense identifiers follow the sorted order of raw identifiers.
+    let mut distinct = raw.iter().flat_map(|&(src, dst)| vec![src, dst]).collect::<Vec<_>>();
+    distinct.sort_unstable();
+    distinct.dedup();
+    assert_eq!(ids.raw_ids().unwrap(), distinct);
+    assert_eq!(ids.get(distinct[1234]).unwrap(), Some(1234));
+    assert_eq!(ids.get(5).unwrap(), None);
+    assert_eq!(ids.raw(17).unwrap(), distinct[17]);
+
+    let mut expected = raw.iter().map(|&(src, dst)| (ids.get(src).unwrap().unwrap(), ids.get(dst).unwrap().unwrap())).collect::<Vec<_>>();
+    expected.sort_unstable();
+    expected.dedup();
+    let mut edges = layout.edges().collect::<Vec<_>>();
+    edges.sort_unstable();
+    assert_eq!(edges, expected);
+    assert_eq!(Dictionary::open(&dictionary).unwrap().len(), distinct.len() as u64);
+    std::fs::remove_file(&dictionary).unwrap();
+}
+
+#[test]
+fn permutation_utilities() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::order::Permutation;
+    let first = Permutation::from_new_ids(vec![2, 0, 3, 1]).unwrap();
+    let second = Permutation::from_new_ids(vec![1, 3, 0, 2]).unwrap();
+    assert_eq!(first.compose(&first.inverse()), Permutation::identity(4));
+    assert_eq!(first.compose(&second).new_ids(), &[0, 1, 2, 3]);
+
+    // values follow their vertices into the new identifier space, and back.
+    let names = vec!["a", "b", "c", "d"];
+    let moved = first.apply_to_column(names.clone());
+    assert_eq!(moved, vec!["b", "d", "a", "c"]);
+    assert_eq!(first.inverse().apply_to_column(moved), names);
+
+    let layout = GraphLayout::from_edges(vec![(0, 1), (1, 2), (2, 3)].into_iter());
+    let there_and_back = first.inverse().apply_to_layout(&first.apply_to_layout(&layout));
+    assert_eq!(there_and_back.keys().collect::<Vec<_>>(), layout.keys().collect::<Vec<_>>());
+
+    let path = std::env::temp_dir().join("graph_layout_permutation_utilities.perm");
+    first.save(&path).unwrap();
+    assert_eq!(Permutation::load(&path).unwrap(), first);
+    std::fs::write(&path, b"GLPERM\0\0\x02\0\0\0\0\0\0\0\x01\0\0\0\x01\0\0\0").unwrap();
+    assert!(matches!(Permutation::load(&path), Err(graph_layout::Error::Corrupt(_))));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn wide_identifiers() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::layout::{Hilbert, Tangle};
+    use graph_layout::wide::WideLayout;
+
+    // the wide curve agrees with the narrow one on identifiers below 2^32.
+    let hilbert = Hilbert::new();
+    for &pair in &[(0, 0), (1, 0), (12345, 678), (u32::MAX, 3), (u32::MAX, u32::MAX)] {
+        assert_eq!(Hilbert::entangle_wide((pair.0 as u64, pair.1 as u64)), hilbert.entangle(pair) as u128);
+    }
+    for &pair in &[(u64::MAX, 0), (1 << 40, 1 << 50), (u64::MAX, u64::MAX - 1)] {
+        assert_eq!(Hilbert::detangle_wide(Hilbert::entangle_wide(pair)), pair);
+    }
+
+    let edges = [(0u64, 1u64), (1, 2), (2, 0), (1, 2)];
+    let narrow = WideLayout::from_edges(edges.iter().cloned()).narrow().unwrap();
+    let expected = GraphLayout::from_edges(edges.iter().map(|&(src, dst)| (src as u32, dst as u32)));
+    assert_eq!(narrow.keys().collect::<Vec<_>>(), expected.keys().collect::<Vec<_>>());
+
+    let path = std::env::temp_dir().join("graph_layout_wide_identifiers.txt");
+    std::fs::write(&path, "# huge\n18446744073709551615 1099511627776\n1099511627776 7\n7 18446744073709551615\n").unwrap();
+    let wide = graph_layout::io::load_wide_edge_list(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(wide.len(), 3);
+    assert_eq!(wide.max_vertex(), Some(u64::MAX));
+    assert!(wide.narrow().is_none());
+    let mut read = wide.edges().collect::<Vec<_>>();
+    read.sort_unstable();
+    assert_eq!(read, vec![(7, u64::MAX), (1 << 40, 7), (u64::MAX, 1 << 40)]);
+
+    let (dense, densifier) = wide.densify();
+    assert_eq!(dense.len(), 3);
+    assert_eq!(dense.vertices(), 3);
+    for (src, dst) in dense.edges() {
+        assert!(read.contains(&(densifier.raw(src), densifier.raw(dst))));
+    }
+}
+
+#[test]
+fn weight_aggregation() {
+    use graph_layout::graph::{Aggregate, GraphLayout};
+    let edges = [((0, 1), 2.0), ((1, 2), 5.0), ((0, 1), 7.0), ((0, 1), 1.0)];
+    let weights = |aggregate| {
+        let layout = GraphLayout::from_aggregated_edges(edges.iter().cloned(), aggregate);
+        assert_eq!(layout.len(), 2);
+        let mut pairs = layout.edges().zip(layout.weights().unwrap().iter().cloned()).collect::<Vec<_>>();
+        pairs.sort_by_key(|&(edge, _)| edge);
+        pairs.into_iter().map(|(_, weight)| weight).collect::<Vec<_>>()
+    };
+    assert_eq!(weights(Aggregate::First), vec![2.0, 5.0]);
+    assert_eq!(weights(Aggregate::Sum), vec![10.0, 5.0]);
+    assert_eq!(weights(Aggregate::Min), vec![1.0, 5.0]);
+    assert_eq!(weights(Aggregate::Max), vec![7.0, 5.0]);
+    assert_eq!(weights(Aggregate::Count), vec![3.0, 1.0]);
+
+    let tiled = GraphLayout::from_aggregated_edges_tiled(edges.iter().cloned(), Aggregate::Sum, 1);
+    assert_eq!(tiled.log_tile(), Some(1));
+    assert_eq!(tiled.weights().unwrap().iter().sum::<f64>(), 15.0);
+}
+
+#[test]
+fn ordering_evaluation() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::order::{evaluate, random, rcm, Permutation};
+    let cycle = GraphLayout::from_edges((0 .. 5_000u32).map(|v| (v, (v + 1) % 5_000)));
+    let candidates = [
+        ("identity", Permutation::identity(5_000)),
+        ("random", random(&cycle, 7)),
+        ("rcm", rcm(&cycle)),
+    ];
+    let results = evaluate(&cycle, &candidates);
+    assert_eq!(results.iter().map(|result| &result.name[..]).collect::<Vec<_>>(), vec!["identity", "random", "rcm"]);
+    assert_eq!(results[0].bandwidth, 4_999);
+    assert!(results[2].bandwidth <= 2);
+    assert!(results[1].mean_span > 100.0 * results[2].mean_span);
+    assert!(results[1].bytes > results[0].bytes);
+    assert!(results[1].locality.bits_per_edge > results[0].locality.bits_per_edge);
+}
+
+#[test]
+fn edge_sampling() {
+    use graph_layout::graph::GraphLayout;
+    let layout = GraphLayout::from_edges((0 .. 20_000u32).map(|v| (v, (v * 7) % 20_000)));
+    let sample = layout.sample_edges(500, 11);
+    assert_eq!(sample.len(), 500);
+    assert_eq!(sample, layout.sample_edges(500, 11));
+    assert_ne!(sample, layout.sample_edges(500, 12));
+    assert!(sample.iter().all(|&(src, dst)| dst == (src * 7) % 20_000));
+    let mut distinct = sample.clone();
+    distinct.sort_unstable();
+    distinct.dedup();
+    assert_eq!(distinct.len(), 500);
+    // the sources are spread over the whole range rather than clustered.
+    assert!(sample.iter().filter(|&&(src, _)| src < 10_000).count() > 150);
+    assert!(sample.iter().filter(|&&(src, _)| src >= 10_000).count() > 150);
+
+    assert_eq!(layout.sample_edges(30_000, 1).len(), 20_000);
+    assert!(GraphLayout::from_edges(std::iter::empty()).sample_edges(5, 1).is_empty());
+}
+
+#[test]
+fn builder_reservoir_sample() {
+    use graph_layout::builder::LayoutBuilder;
+    let path = std::env::temp_dir().join("graph_layout_builder_reservoir_sample.glayout");
+    let build = |seed| {
+        let mut builder = LayoutBuilder::new(2_500).reservoir(400, seed);
+        builder.extend((0 .. 10_000u32).map(|v| (v, v / 2))).unwrap();
+        builder.finish_with_sample(&path).unwrap()
+    };
+    let (edges, sample) = build(5);
+    assert_eq!(edges, 10_000);
+    assert_eq!(sample.len(), 400);
+    assert_eq!(sample, build(5).1);
+    assert_ne!(sample, build(6).1);
+    assert!(sample.iter().all(|&(src, dst)| dst == src / 2));
+    // later edges are as likely to be kept as earlier ones.
+    let late = sample.iter().filter(|&&(src, _)| src >= 5_000).count();
+    assert!(late > 150 && late < 250);
+
+    let mut builder = LayoutBuilder::new(100);
+    builder.push((0, 1)).unwrap();
+    assert_eq!(builder.finish_with_sample(&path).unwrap(), (1, Vec::new()));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn layout_diff() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::layout::{Hilbert, Tangle};
+    let before = GraphLayout::from_edges((0 .. 1_000u32).map(|v| (v, v + 1)));
+    let after = GraphLayout::from_edges((0 .. 1_000u32).filter(|v| v % 10 != 0).map(|v| (v, v + 1)).chain(vec![(5, 5), (2_000, 0)]));
+    let (added, removed) = before.diff(&after);
+    let hilbert = Hilbert::new();
+    let mut expected_added = vec![hilbert.entangle((5, 5)), hilbert.entangle((2_000, 0))];
+    expected_added.sort_unstable();
+    assert_eq!(added.decompress().collect::<Vec<_>>(), expected_added);
+    let mut expected_removed = (0 .. 100u32).map(|v| hilbert.entangle((10 * v, 10 * v + 1))).collect::<Vec<_>>();
+    expected_removed.sort_unstable();
+    assert_eq!(removed.decompress().collect::<Vec<_>>(), expected_removed);
+
+    let (added, removed) = after.diff(&after);
+    assert!(added.is_empty() && removed.is_empty());
+}
+
+#[test]
+fn layout_union() {
+    use graph_layout::graph::GraphLayout;
+    let first = GraphLayout::from_edges_tiled((0 .. 3_000u32).map(|v| (v, v + 1)), 4);
+    let second = GraphLayout::from_edges_tiled((2_000 .. 6_000u32).map(|v| (v, v + 1)), 4);
+    let third = GraphLayout::from_edges_tiled((0 .. 100u32).map(|v| (v, 0)), 4);
+    let union = GraphLayout::union(&[&first, &second, &third]);
+    let expected = GraphLayout::from_edges_tiled((0 .. 6_000u32).map(|v| (v, v + 1)).chain((0 .. 100u32).map(|v| (v, 0))), 4);
+    assert_eq!(union.keys().collect::<Vec<_>>(), expected.keys().collect::<Vec<_>>());
+    assert_eq!(union.tiles(), expected.tiles());
+    assert_eq!(union.vertices(), expected.vertices());
+    assert_eq!(GraphLayout::union(&[&first, &GraphLayout::from_edges(std::iter::empty())]).log_tile(), None);
+
+    let heavy = GraphLayout::from_weighted_edges(vec![((0, 1), 1.0), ((1, 2), 2.0)].into_iter());
+    let light = GraphLayout::from_weighted_edges(vec![((1, 2), 9.0), ((2, 3), 3.0)].into_iter());
+    let union = GraphLayout::union(&[&heavy, &light]);
+    let mut weighted = union.edges().zip(union.weights().unwrap().iter().cloned()).collect::<Vec<_>>();
+    weighted.sort_by_key(|&(edge, _)| edge);
+    assert_eq!(weighted, vec![((0, 1), 1.0), ((1, 2), 2.0), ((2, 3), 3.0)]);
+    assert!(GraphLayout::union(&[]).is_empty());
+}
+
+#[test]
+fn referenced_adjacency() {
+    use graph_layout::graph::GraphLayout;
+    // pages of each "site" of 50 vertices link to most of the site's first 30 pages, and a few others.
+    let edges = (0 .. 5_000u32).flat_map(|v| {
+        let site = v / 50 * 50;
+        (0 .. 30).filter(move |i| (v + i) % 7 != 0).map(move |i| (v, site + i)).chain(vec![(v, (v * 7919) % 5_000)])
+    }).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges(edges.iter().cloned());
+    let adjacency = layout.referenced(7);
+    assert_eq!(adjacency.vertices(), 5_000);
+    assert_eq!(adjacency.len(), layout.len());
+    let mut expected = edges.iter().filter(|&&(src, _)| src == 1234).map(|&(_, dst)| dst).collect::<Vec<_>>();
+    expected.sort_unstable();
+    expected.dedup();
+    assert_eq!(adjacency.successors(1234), expected);
+    assert_eq!(adjacency.to_layout().keys().collect::<Vec<_>>(), layout.keys().collect::<Vec<_>>());
+
+    // referencing beats coding each list on its own.
+    assert!(3 * adjacency.size_bytes() < 2 * layout.referenced(0).size_bytes());
+}
+
+#[test]
+fn erdos_renyi_generators() {
+    use graph_layout::generate::{gnm, gnp};
+    use graph_layout::graph::GraphLayout;
+    let edges = gnm(1_000, 5_000, 3).collect::<Vec<_>>();
+    assert_eq!(edges.len(), 5_000);
+    assert_eq!(edges, gnm(1_000, 5_000, 3).collect::<Vec<_>>());
+    assert!(edges.iter().all(|&(src, dst)| src != dst && src < 1_000 && dst < 1_000));
+    assert_eq!(GraphLayout::from_edges(edges.into_iter()).len(), 5_000);
+    // every pair of a tiny graph.
+    let mut all = gnm(3, 6, 1).collect::<Vec<_>>();
+    all.sort_unstable();
+    assert_eq!(all, vec![(0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1)]);
+
+    let edges = gnp(2_000, 0.01, 9).collect::<Vec<_>>();
+    let expected = 0.01 * 2_000.0 * 1_999.0;
+    assert!((edges.len() as f64 - expected).abs() < 0.05 * expected);
+    assert!(edges.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(edges.iter().all(|&(src, dst)| src != dst && dst < 2_000));
+    assert_eq!(gnp(10, 1.0, 0).count(), 90);
+    assert_eq!(gnp(10, 0.0, 0).count(), 0);
+}
+
+#[test]
+fn preferential_attachment_generator() {
+    use graph_layout::generate::{barabasi_albert, preferential_attachment};
+    use graph_layout::graph::GraphLayout;
+    let edges = barabasi_albert(20_000, 3, 1).collect::<Vec<_>>();
+    assert_eq!(edges.len(), 3 * 20_000 - 6);
+    assert_eq!(edges, barabasi_albert(20_000, 3, 1).collect::<Vec<_>>());
+    assert!(edges.iter().all(|&(src, dst)| dst < src));
+    let layout = GraphLayout::from_edges(edges.into_iter());
+    assert_eq!(layout.len(), 3 * 20_000 - 6);
+
+    // a smaller exponent concentrates more of the edges on the largest hubs.
+    let hub_share = |exponent| {
+        let (mut in_degrees, _) = GraphLayout::from_edges(preferential_attachment(20_000, 3, exponent, 2)).degrees();
+        in_degrees.sort_unstable_by(|a, b| b.cmp(a));
+        in_degrees[.. 20].iter().sum::<u32>()
+    };
+    let (heavy, light) = (hub_share(2.2), hub_share(4.0));
+    assert!(heavy > 3 * light);
+}
+
+#[test]
+fn lattice_generators() {
+    use graph_layout::generate::{gnm, grid, ring_lattice, torus};
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::stats::locality;
+    let mut small = grid(3, 2).collect::<Vec<_>>();
+    small.sort_unstable();
+    assert_eq!(small, vec![(0, 1), (0, 3), (1, 0), (1, 2), (1, 4), (2, 1), (2, 5), (3, 0), (3, 4), (4, 1), (4, 3), (4, 5), (5, 2), (5, 4)]);
+
+    let lattice = GraphLayout::from_edges(grid(200, 200));
+    assert_eq!(lattice.len(), 4 * 200 * 199);
+    let wrapped = GraphLayout::from_edges(torus(200, 200));
+    assert_eq!(wrapped.len(), 4 * 200 * 200);
+    let (in_degrees, out_degrees) = wrapped.degrees();
+    assert!(in_degrees.iter().chain(out_degrees.iter()).all(|&degree| degree == 4));
+
+    let ring = GraphLayout::from_edges(ring_lattice(10_000, 3));
+    assert_eq!(ring.len(), 60_000);
+    assert!(ring.has_edge(0, 9_997) && ring.has_edge(9_999, 2) && !ring.has_edge(0, 4));
+
+    // the curve keeps a lattice's edges far closer together than a uniform graph's.
+    let uniform = GraphLayout::from_edges(gnm(40_000, lattice.len(), 1));
+    assert!(3.0 * locality(&lattice).bits_per_edge < locality(&uniform).bits_per_edge);
+}
+
+#[test]
+fn seeded_generators_are_stable() {
+    use graph_layout::generate::{barabasi_albert, gnm, gnp, SplitMix64};
+    // the reference SplitMix64 outputs for seed zero.
+    let mut rng = SplitMix64::new(0);
+    assert_eq!(rng.next_u64(), 0xe220a8397b1dcdaf);
+    assert_eq!(rng.next_u64(), 0x6e789e6aa1b965f4);
+    assert_eq!(rng.below(1000), 679);
+
+    // changing any of these breaks the reproducibility of seeded graphs.
+    assert_eq!(gnm(100, 4, 42).collect::<Vec<_>>(), vec![(51, 65), (91, 82), (32, 91), (81, 45)]);
+    assert_eq!(gnp(100, 0.001, 42).collect::<Vec<_>>(), vec![
+        (13, 66), (15, 43), (18, 73), (22, 99), (23, 39), (43, 85), (46, 34), (62, 63), (66, 83), (76, 56), (78, 89), (85, 74), (93, 2),
+    ]);
+    assert_eq!(barabasi_albert(5, 2, 42).collect::<Vec<_>>(), vec![(1, 0), (2, 0), (2, 1), (3, 2), (3, 0), (4, 2), (4, 1)]);
+}
+
+#[test]
+fn parallel_generation() {
+    use graph_layout::generate::parallel_gnp;
+    use graph_layout::graph::GraphLayout;
+    let layout = parallel_gnp(3_000, 0.002, 5, 4);
+    let expected = 0.002 * 3_000.0 * 2_999.0;
+    assert!((layout.len() as f64 - expected).abs() < 0.05 * expected);
+    assert!(layout.edges().all(|(src, dst)| src != dst && src < 3_000 && dst < 3_000));
+    let keys = layout.keys().collect::<Vec<_>>();
+    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(keys, GraphLayout::from_edges(layout.edges()).keys().collect::<Vec<_>>());
+    // the graph depends on the seed, but not on the number of threads.
+    assert_eq!(parallel_gnp(3_000, 0.002, 5, 1).keys().collect::<Vec<_>>(), keys);
+    assert_eq!(parallel_gnp(3_000, 0.002, 5, 7).keys().collect::<Vec<_>>(), keys);
+    assert_ne!(parallel_gnp(3_000, 0.002, 6, 4).keys().collect::<Vec<_>>(), keys);
+    assert_eq!(parallel_gnp(30, 1.0, 0, 3).len(), 30 * 29);
+}
+
+#[test]
+fn cli_convert() {
+    use graph_layout::graph::GraphLayout;
+    use std::process::Command;
+    let directory = std::env::temp_dir();
+    let input = directory.join("graph_layout_cli_convert.txt");
+    let output = directory.join("graph_layout_cli_convert.glayout");
+    std::fs::write(&input, "# edges\n0 1\n1 2\n2 0\n1 2\n").unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_graph-layout"))
+        .args(["convert", input.to_str().unwrap(), output.to_str().unwrap(), "--tile", "1", "--buffer", "2"])
+        .output()
+        .unwrap();
+    assert!(status.status.success());
+    assert_eq!(String::from_utf8_lossy(&status.stdout).trim(), format!("wrote 3 edges to {}", output.display()));
+    let layout = GraphLayout::open(&output).unwrap();
+    assert_eq!(layout.len(), 3);
+    assert_eq!(layout.log_tile(), Some(1));
+
+    // other curves and codecs are written as asked, and read back along the Hilbert curve.
+    let convert = |arguments: &[&str]| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_graph-layout"));
+        command.args(["convert", input.to_str().unwrap(), output.to_str().unwrap()]).args(arguments).output().unwrap()
+    };
+    let header = |at: usize| u32::from_le_bytes(std::fs::read(&output).unwrap()[at .. at + 4].try_into().unwrap());
+    for (arguments, curve, codec) in [(&["--curve", "zorder", "--tile", "1"][..], 1, 0), (&["--curve", "none", "--codec", "plain"][..], 2, 1)] {
+        assert!(convert(arguments).status.success());
+        assert_eq!((header(48), header(12)), (curve, codec));
+        let read = GraphLayout::open(&output).unwrap();
+        assert!(read.edges().eq(GraphLayout::from_edges([(0, 1), (1, 2), (2, 0)].into_iter()).edges()));
+        assert_eq!(read.vertices(), 3);
+        #[cfg(feature = "mmap")]
+        assert!(matches!(GraphLayout::open_mmap(&output), Err(graph_layout::Error::Unsupported { .. })));
+    }
+    for (arguments, message) in [(&["--curve", "spiral"][..], "unknown curve `spiral`"), (&["--codec", "zip"][..], "unknown codec `zip`"), (&["--curve", "none", "--tile", "1"][..], "cannot be tiled")] {
+        let failure = convert(arguments);
+        assert!(!failure.status.success());
+        assert!(String::from_utf8_lossy(&failure.stderr).contains(message));
+    }
+
+    // matrix market entries are streamed through the external sort, mirrored if symmetric.
+    std::fs::write(&input, "%%MatrixMarket matrix coordinate real symmetric\n3 3 3\n2 1 0.5\n3 1 1.5\n3 3 2.0\n").unwrap();
+    assert!(convert(&["--format", "mtx", "--buffer", "2"]).status.success());
+    let read = GraphLayout::open(&output).unwrap();
+    assert!(read.edges().eq(GraphLayout::from_edges([(1, 0), (0, 1), (2, 0), (0, 2), (2, 2)].into_iter()).edges()));
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn layout_curves_and_codecs() {
+    use graph_layout::builder::LayoutBuilder;
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::format::{Codec, Curve};
+    let edges = (0 .. 30_000u32).map(|i| ((i * 7919) % 3001, (i * 104729) % 2999)).collect::<Vec<_>>();
+    let expected = GraphLayout::from_edges_tiled(edges.iter().cloned(), 5);
+    let path = std::env::temp_dir().join("graph_layout_curves_and_codecs.glayout");
+    for curve in [Curve::Hilbert, Curve::ZOrder, Curve::RowMajor] {
+        for codec in [Codec::EscapedDeltas, Codec::Plain] {
+            let mut builder = LayoutBuilder::new(7_000).curve(curve).codec(codec);
+            if curve != Curve::RowMajor {
+                builder = builder.tiled(5);
+            }
+            builder.extend(edges.iter().cloned()).unwrap();
+            assert_eq!(builder.finish(&path).unwrap(), expected.len());
+            let read = GraphLayout::open(&path).unwrap();
+            assert_eq!(read.keys().collect::<Vec<_>>(), expected.keys().collect::<Vec<_>>());
+            assert_eq!(read.vertices(), expected.vertices());
+            if curve != Curve::RowMajor {
+                assert_eq!(read.tiles(), expected.tiles());
+            }
+        }
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn cli_stats() {
+    use graph_layout::graph::GraphLayout;
+    use std::process::Command;
+    let path = std::env::temp_dir().join("graph_layout_cli_stats.glayout");
+    GraphLayout::from_edges_tiled((0 .. 100u32).map(|v| (v, (v + 1) % 100)), 3).save(&path).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_graph-layout")).args(["stats", path.to_str().unwrap()]).output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    let text = String::from_utf8_lossy(&output.stdout);
+    let line = |name: &str| text.lines().find(|line| line.starts_with(name)).unwrap_or_else(|| panic!("no `{}` in {}", name, text)).to_owned();
+    assert!(line("vertices:").ends_with(" 100"));
+    assert!(line("edges:").ends_with(" 100"));
+    assert!(line("out-degrees:").contains("mean 1.00, max 1"));
+    assert!(line("compression:").contains("x over 64-bit edge pairs"));
+    assert!(line("source gaps:").contains("p50 1"));
+    assert!(line("tiles:").contains("of side 2^3"));
+}
+
+#[test]
+fn cli_bench() {
+    use graph_layout::graph::GraphLayout;
+    use std::process::Command;
+    let path = std::env::temp_dir().join("graph_layout_cli_bench.glayout");
+    GraphLayout::from_edges((0 .. 30u32).flat_map(|v| vec![(v, (v + 1) % 30), ((v + 1) % 30, v)])).save(&path).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_graph-layout"))
+        .args(["bench", path.to_str().unwrap(), "--runs", "2", "--iters", "5"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(text.lines().filter(|line| line.contains(" run ") && line.contains("edges/s")).count(), 8);
+    assert!(text.contains("5 iterations"));
+    assert!(text.contains("30 vertices reached"));
+    assert!(text.contains("1 components"));
+    assert!(text.contains("0 triangles"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_graph-layout"))
+        .args(["bench", path.to_str().unwrap(), "--kernels", "sssp"])
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown kernel `sssp`"));
+}
+
+#[test]
+fn cli_reorder() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::order::Permutation;
+    use std::process::Command;
+    let directory = std::env::temp_dir();
+    let input = directory.join("graph_layout_cli_reorder.glayout");
+    let output = directory.join("graph_layout_cli_reorder_out.glayout");
+    let restored = directory.join("graph_layout_cli_reorder_restored.glayout");
+    let layout = GraphLayout::from_edges((0 .. 500u32).map(|v| (v, (v * 37) % 500)));
+    layout.save(&input).unwrap();
+
+    let run = |arguments: &[&str]| Command::new(env!("CARGO_BIN_EXE_graph-layout")).args(arguments).output().unwrap();
+    assert!(run(&["reorder", input.to_str().unwrap(), output.to_str().unwrap(), "--order", "random", "--seed", "3"]).status.success());
+    let permutation = Permutation::load(format!("{}.perm", output.display())).unwrap();
+    let reordered = GraphLayout::open(&output).unwrap();
+    assert_eq!(reordered.keys().collect::<Vec<_>>(), permutation.apply_to_layout(&layout).keys().collect::<Vec<_>>());
+
+    // applying the inverse permutation from a file restores the original labels.
+    let inverse = directory.join("graph_layout_cli_reorder_inverse.perm");
+    permutation.inverse().save(&inverse).unwrap();
+    let result = run(&["reorder", output.to_str().unwrap(), restored.to_str().unwrap(), "--permutation", inverse.to_str().unwrap()]);
+    assert!(result.status.success());
+    assert_eq!(GraphLayout::open(&restored).unwrap().keys().collect::<Vec<_>>(), layout.keys().collect::<Vec<_>>());
+    assert!(!run(&["reorder", input.to_str().unwrap(), output.to_str().unwrap(), "--order", "alphabetical"]).status.success());
+    let failure = run(&["reorder", input.to_str().unwrap(), output.to_str().unwrap(), "--order", "gorder", "--window", "0"]);
+    assert!(!failure.status.success());
+    assert!(String::from_utf8_lossy(&failure.stderr).contains("window must hold at least one vertex"));
+
+    for path in [input, output.clone(), restored.clone(), inverse] {
+        std::fs::remove_file(&path).unwrap();
+    }
+    std::fs::remove_file(format!("{}.perm", output.display())).unwrap();
+    std::fs::remove_file(format!("{}.perm", restored.display())).unwrap();
+}
+
+#[test]
+fn progress_callbacks() {
+    use std::sync::{Arc, Mutex};
+    use graph_layout::builder::LayoutBuilder;
+    use graph_layout::io::EdgeList;
+    use graph_layout::progress::{Phase, Progress};
+    let recorder = || {
+        let reports = Arc::new(Mutex::new(Vec::<Progress>::new()));
+        let sink = reports.clone();
+        (reports, move |progress| sink.lock().unwrap().push(progress))
+    };
+
+    let text = (0 .. 1_000u32).map(|v| format!("{} {}\n", v, v / 3)).collect::<String>();
+    let (reports, callback) = recorder();
+    let edges = EdgeList::new(text.as_bytes()).progress(300, callback).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(edges.len(), 1_000);
+    let reports = reports.lock().unwrap().clone();
+    assert_eq!(reports.iter().map(|report| report.edges).collect::<Vec<_>>(), vec![300, 600, 900, 1_000]);
+    assert_eq!(reports.last(), Some(&Progress { phase: Phase::Done, edges: 1_000, bytes: text.len() as u64 }));
+
+    let path = std::env::temp_dir().join("graph_layout_progress_callbacks.glayout");
+    let (reports, callback) = recorder();
+    let mut builder = LayoutBuilder::new(400).progress(250, callback);
+    builder.extend(edges.into_iter()).unwrap();
+    assert_eq!(builder.finish(&path).unwrap(), 1_000);
+    let size = std::fs::metadata(&path).unwrap().len();
+    std::fs::remove_file(&path).unwrap();
+    let reports = reports.lock().unwrap().clone();
+    let phases = |phase| reports.iter().filter(|report| report.phase == phase).count();
+    // spills after 400 and 800 edges, and of the final 200 when finishing; each spill restarts
+    // the interval, leaving reading reports at 250 and 650 edges.
+    assert_eq!(phases(Phase::Spilling), 3);
+    assert_eq!(phases(Phase::Reading), 2);
+    assert!(phases(Phase::Merging) >= 4);
+    let last = reports.last().unwrap();
+    assert_eq!((last.phase, last.edges), (Phase::Done, 1_000));
+    assert!(last.bytes > size);
+    assert!(reports.windows(2).all(|pair| pair[0].bytes <= pair[1].bytes));
+}
+
+#[test]
+fn memory_budget() {
+    use graph_layout::builder::LayoutBuilder;
+    use graph_layout::densify::ExternalDensifier;
+    use graph_layout::graph::GraphLayout;
+    let directory = std::env::temp_dir().join("graph_layout_memory_budget");
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("budget.glayout");
+
+    // room for about 20k keys, and for merging only two runs at once.
+    let edges = (0 .. 200_000u32).map(|i| (i.wrapping_mul(2_654_435_761) % 50_000, i % 7_919)).collect::<Vec<_>>();
+    let mut builder = LayoutBuilder::new(1).memory_budget(160_000).spill_directory(&directory);
+    builder.extend(edges.iter().cloned()).unwrap();
+    assert_eq!(builder.runs(), 10);
+    let expected = GraphLayout::from_edges(edges.iter().cloned());
+    assert_eq!(builder.finish(&path).unwrap(), expected.len());
+    assert!(GraphLayout::open(&path).unwrap().edges().eq(expected.edges()));
+    std::fs::remove_file(&path).unwrap();
+
+    let raw = (0 .. 5_000u64).map(|i| ((i * 7_919 % 1_000) << 40, i * 31 % 3_000)).collect::<Vec<_>>();
+    let densify = |densifier: ExternalDensifier| {
+        let mut dense = Vec::new();
+        let mut densifier = densifier.spill_directory(&directory);
+        let ids = densifier.run(raw.iter().cloned(), directory.join("dictionary"), |edge| { dense.push(edge); Ok(()) }).unwrap();
+        (dense, ids.raw_ids().unwrap())
+    };
+    assert_eq!(densify(ExternalDensifier::new(1).memory_budget(40_000)), densify(ExternalDensifier::new(100_000)));
+    std::fs::remove_file(directory.join("dictionary")).unwrap();
+    // every run has been removed.
+    assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 0);
+    std::fs::remove_dir(&directory).unwrap();
+}
+
+#[test]
+fn visualization() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::viz;
+    // the curve at order 3 visits each of the 64 cells once, stepping between neighbors.
+    let svg = viz::curve_svg(3, 80);
+    let start = svg.find("points=\"").unwrap() + 8;
+    let points = svg[start ..].split('"').next().unwrap().split(' ').map(|point| {
+        let mut coordinates = point.split(',').map(|value| value.parse::<f64>().unwrap());
+        (coordinates.next().unwrap(), coordinates.next().unwrap())
+    }).collect::<Vec<_>>();
+    assert_eq!(points.len(), 64);
+    assert!(points.iter().all(|&(x, y)| x > 0.0 && x < 80.0 && y > 0.0 && y < 80.0));
+    assert!(points.windows(2).all(|pair| ((pair[0].0 - pair[1].0).abs() + (pair[0].1 - pair[1].1).abs() - 10.0).abs() < 1e-6));
+
+    // a banded graph fills only the diagonal of the adjacency matrix.
+    let layout = GraphLayout::from_edges((0 .. 1_000u32).flat_map(|v| (0 .. 5).map(move |d| (v, (v + d) % 1_000))));
+    let counts = viz::density(&layout, 10);
+    assert_eq!(counts.iter().sum::<u64>(), 5_000);
+    assert!((0 .. 10).all(|row| counts[row * 10 + row] >= 490));
+    assert_eq!(counts[9], 0);
+    let svg = viz::density_svg(&layout, 10, 200);
+    assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<rect x=").count(), counts.iter().filter(|&&count| count > 0).count());
+
+    let png = viz::density_png(&layout, 10);
+    assert_eq!(&png[.. 8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[16 .. 24], &[0, 0, 0, 10, 0, 0, 0, 10]);
+    // an empty IEND chunk, with its well-known checksum.
+    assert_eq!(&png[png.len() - 12 ..], b"\0\0\0\0IEND\xAE\x42\x60\x82");
+}
+
+#[test]
+fn key_histograms() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::stats::{key_histogram, layout_key_histogram};
+    let edges = (0 .. 50_000u32).map(|i| (i.wrapping_mul(2_654_435_761) % 3_000, i % 1_000)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges(edges.iter().cloned());
+    let keys = Compressed::from(layout.keys());
+    for &buckets in &[1, 7, 16, 1_000] {
+        let histogram = layout_key_histogram(&layout, buckets);
+        assert_eq!(histogram, key_histogram(&keys, buckets));
+        assert_eq!(histogram.counts.len(), buckets);
+        assert_eq!(histogram.counts.iter().sum::<usize>(), layout.len());
+        let mut expected = vec![0; buckets];
+        for key in layout.keys() { expected[(key as u128 / histogram.width) as usize] += 1; }
+        assert_eq!(histogram.counts, expected);
+    }
+    // vertices below 4096 fill at most the first 4^12 keys, split into sixteen squares.
+    assert_eq!(layout_key_histogram(&layout, 16).width, 1 << 20);
+    assert_eq!(key_histogram(&Compressed::from(None.into_iter()), 4), graph_layout::stats::KeyHistogram { width: 1, counts: vec![0; 4] });
+    // keys near the top of the curve make one bucket span all `2^64` of them.
+    let high = GraphLayout::from_edges(vec![(0, 0), (u32::MAX, u32::MAX), (u32::MAX, 0)].into_iter());
+    for &buckets in &[1, 3] {
+        let histogram = layout_key_histogram(&high, buckets);
+        assert_eq!(histogram, key_histogram(&Compressed::from(high.keys()), buckets));
+        assert_eq!(histogram.counts.iter().sum::<usize>(), 3);
+    }
+    assert_eq!(layout_key_histogram(&high, 1).width, 1 << 64);
+}
+
+#[test]
+fn dense_tile_extraction() {
+    use graph_layout::graph::GraphLayout;
+    // a clique on the vertices 16 .. 32, and a sparse ring.
+    let clique = (16 .. 32u32).flat_map(|src| (16 .. 32u32).map(move |dst| (src, dst)));
+    let ring = (0 .. 1_000u32).map(|v| (v, (v + 1) % 1_000));
+    let edges = clique.chain(ring).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges_tiled(edges.iter().cloned(), 4);
+    let split = layout.dense_tiles(0.5);
+    assert_eq!(split.dense.len(), 1);
+    let tile = &split.dense[0];
+    assert_eq!((tile.row, tile.column, tile.side, tile.edges), (16, 16, 16, 256));
+    assert!(tile.bits.iter().all(|&bits| bits == u64::MAX));
+    assert!(tile.contains((20, 31)));
+    assert_eq!(tile.edges().count(), 256);
+    assert_eq!(split.sparse.log_tile(), Some(4));
+    assert_eq!(split.sparse.len() + 256, layout.len());
+    let mut all = split.sparse.edges().chain(tile.edges()).collect::<Vec<_>>();
+    all.sort_unstable();
+    all.dedup();
+    let mut expected = layout.edges().collect::<Vec<_>>();
+    expected.sort_unstable();
+    assert_eq!(all, expected);
+
+    // weights follow edges into the dense tiles and the sparse remainder alike.
+    let weighted = GraphLayout::from_weighted_edges_tiled(edges.iter().map(|&(src, dst)| ((src, dst), (src + dst) as f64)), 4);
+    let split = weighted.dense_tiles(0.5);
+    assert_eq!(split.dense[0].weight((17, 30)), Some(47.0));
+    assert!(split.sparse.edges().zip(split.sparse.weights().unwrap()).all(|((src, dst), &weight)| weight == (src + dst) as f64));
+    assert!(layout.dense_tiles(2.0).dense.is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trips() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::order::Permutation;
+    use graph_layout::column::Column;
+    let edges = (0 .. 20_000u32).map(|i| ((i, i.wrapping_mul(2_654_435_761) % 5_000), i as f64 / 2.0));
+    let mut layout = GraphLayout::from_weighted_edges_tiled(edges, 6);
+    layout.attach_column("hops", Column::U32((0 .. layout.len() as u32).collect()));
+    layout.attach_column("labels", Column::Blobs((0 .. layout.len()).map(|i| i.to_string().into_bytes()).collect()));
+    let json = serde_json::to_string(&layout).unwrap();
+    let restored: GraphLayout = serde_json::from_str(&json).unwrap();
+    assert!(restored.edges().eq(layout.edges()));
+    assert_eq!(restored.weights(), layout.weights());
+    assert_eq!(restored.columns(), layout.columns());
+    assert_eq!(restored.log_tile(), Some(6));
+    assert_eq!(restored.tiles(), layout.tiles());
+    assert_eq!(restored.tile_summaries(), layout.tile_summaries());
+    assert_eq!(restored.vertices(), layout.vertices());
+
+    let keys = Compressed::from(vec![3, 300, 70_000, 1 << 40].into_iter());
+    let restored: Compressed = serde_json::from_str(&serde_json::to_string(&keys).unwrap()).unwrap();
+    assert_eq!(restored.decompress().collect::<Vec<_>>(), vec![3, 300, 70_000, 1 << 40]);
+
+    let permutation = Permutation::from_order(&[2, 0, 1]).unwrap();
+    let json = serde_json::to_string(&permutation).unwrap();
+    assert_eq!(serde_json::from_str::<Permutation>(&json).unwrap(), permutation);
+    assert!(serde_json::from_str::<Permutation>("[0, 0, 1]").is_err());
+    let hilbert: Hilbert = serde_json::from_str(&serde_json::to_string(&Hilbert::new()).unwrap()).unwrap();
+    assert_eq!(hilbert.entangle((12, 34)), Hilbert::new().entangle((12, 34)));
+
+    // corrupt keys and mismatched weights are rejected rather than trusted.
+    let mut value: serde_json::Value = serde_json::from_str(&serde_json::to_string(&layout).unwrap()).unwrap();
+    value["weights"].as_array_mut().unwrap().pop();
+    assert!(serde_json::from_value::<GraphLayout>(value).is_err());
+    let mut value: serde_json::Value = serde_json::from_str(&serde_json::to_string(&layout).unwrap()).unwrap();
+    value["columns"][0][1]["U32"].as_array_mut().unwrap().pop();
+    assert!(serde_json::from_value::<GraphLayout>(value).is_err());
+    assert!(serde_json::from_str::<Compressed>(r#"{"len": 3, "encoding": [1, 2]}"#).is_err());
+}
+
+#[cfg(feature = "abomonation")]
+#[test]
+fn abomonation_exchange() {
+    use graph_layout::column::Column;
+    use graph_layout::graph::GraphLayout;
+    let edges = (0 .. 10000u32).map(|i| (((i * 7919) % 1013, (i * 104729) % 997), i as f64));
+    let mut layout = GraphLayout::from_weighted_edges_tiled(edges, 6);
+    layout.attach_column("labels", Column::Blobs((0 .. layout.len()).map(|i| i.to_string().into_bytes()).collect()));
+    let mut bytes = Vec::new();
+    unsafe { abomonation::encode(&layout, &mut bytes).unwrap() };
+    assert_eq!(bytes.len(), abomonation::measure(&layout));
+    let (restored, rest) = unsafe { abomonation::decode::<GraphLayout>(&mut bytes) }.unwrap();
+    assert!(rest.is_empty());
+    assert!(restored.edges().eq(layout.edges()));
+    assert_eq!(restored.weights(), layout.weights());
+    assert_eq!(restored.columns(), layout.columns());
+    assert_eq!(restored.vertices(), layout.vertices());
+    assert_eq!(restored.tiles(), layout.tiles());
+    assert_eq!(restored.blocks(), layout.blocks());
+    assert_eq!(restored.tile_summaries(), layout.tile_summaries());
+    for tile in layout.tiles().iter().step_by(7) {
+        assert!(restored.tile_keys(tile).eq(layout.tile_keys(tile)));
+    }
+    assert!(restored.has_edge(7919 % 1013, 104729 % 997));
+
+    let compressed = Compressed::from(vec![3, 300, 70_000, 1 << 40].into_iter());
+    let mut bytes = Vec::new();
+    unsafe { abomonation::encode(&compressed, &mut bytes).unwrap() };
+    let length = bytes.len();
+    // a truncated encoding is refused rather than read past its end.
+    assert!(unsafe { abomonation::decode::<Compressed>(&mut bytes[.. length - 1]) }.is_none());
+    let (restored, _) = unsafe { abomonation::decode::<Compressed>(&mut bytes) }.unwrap();
+    assert_eq!(restored.decompress().collect::<Vec<_>>(), vec![3, 300, 70_000, 1 << 40]);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_edges() {
+    use graph_layout::graph::GraphLayout;
+    use rayon::iter::ParallelIterator;
+    let layout = GraphLayout::from_edges((0 .. 100_000u32).map(|i| (i % 7_000, i.wrapping_mul(2_654_435_761) % 9_000)));
+    assert!(layout.blocks().len() > 20);
+    assert_eq!(layout.par_edges().collect::<Vec<_>>(), layout.edges().collect::<Vec<_>>());
+    let total = layout.par_edges().map(|(src, dst)| src as u64 + dst as u64).sum::<u64>();
+    assert_eq!(total, layout.edges().map(|(src, dst)| src as u64 + dst as u64).sum::<u64>());
+    assert_eq!(GraphLayout::from_edges(None.into_iter()).par_edges().count(), 0);
+}
+
+#[test]
+fn exchange_routing() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::partition::{assigned_route, partition, tile_route, Accumulator, Placement};
+    let edges = (0 .. 30_000u32).map(|i| (i % 3_000, i.wrapping_mul(2_654_435_761) % 3_000)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges_tiled(edges.iter().cloned(), 8);
+
+    // routing each edge as it arrives rebuilds the parts that `partition` produces.
+    let workers = 4;
+    let route = assigned_route(&layout, workers, Placement::Blocks);
+    let mut received = (0 .. workers).map(|_| Accumulator::new(Some(8))).collect::<Vec<_>>();
+    for batch in edges.chunks(1_000) {
+        let mut outgoing = vec![Vec::new(); workers];
+        for edge in batch {
+            outgoing[(route(edge) % workers as u64) as usize].push(*edge);
+        }
+        for (worker, batch) in outgoing.iter().enumerate() {
+            received[worker].push_batch(batch);
+        }
+    }
+    assert_eq!(received.iter().map(Accumulator::len).sum::<usize>(), edges.len());
+    for (accumulator, part) in received.into_iter().zip(partition(&layout, workers, Placement::Blocks)) {
+        assert!(accumulator.finish().edges().eq(part.layout.edges()));
+    }
+
+    // edges of one tile share a route, and neighboring tiles along the curve do not.
+    let route = tile_route(8);
+    assert_eq!(route(&(0, 0)), route(&(255, 255)));
+    assert_ne!(route(&(0, 0)), route(&(256, 0)));
+}
+
+#[test]
+fn differential_updates() {
+    use graph_layout::differential::{batch, diff_batch, diff_updates, updates};
+    use graph_layout::graph::GraphLayout;
+    let before = GraphLayout::from_edges((0 .. 500u32).map(|v| (v, (v * 7) % 500)));
+    let after = GraphLayout::from_edges((0 .. 500u32).filter(|v| v % 5 != 0).map(|v| (v, (v * 7) % 500)).chain(vec![(3, 3), (600, 1)]));
+
+    assert!(updates(&before, 0u64).map(|(edge, _, _)| edge).eq(before.edges()));
+    let initial = batch(&before, 0u64);
+    assert_eq!(initial.len(), 500);
+    assert!(initial.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert!(initial.iter().all(|&(_, time, diff)| time == 0 && diff == 1));
+
+    let changes = diff_batch(&before, &after, 1u64);
+    assert_eq!(changes.len(), 102);
+    assert!(changes.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert_eq!(changes.iter().filter(|update| update.2 == 1).map(|update| update.0).collect::<Vec<_>>(), vec![(3, 3), (600, 1)]);
+    assert!(changes.iter().filter(|update| update.2 == -1).all(|&((src, _), _, _)| src % 5 == 0));
+    let hilbert = Hilbert::new();
+    let keys = diff_updates(&before, &after, 1u64).map(|(edge, _, _)| hilbert.entangle(edge)).collect::<Vec<_>>();
+    assert_eq!(keys.len(), 102);
+    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // applying the changes to the initial collection yields the later one.
+    let mut collection = initial.into_iter().chain(diff_updates(&before, &after, 1)).map(|(edge, _, diff)| (edge, diff)).collect::<Vec<_>>();
+    collection.sort_unstable();
+    let mut consolidated: Vec<((u32, u32), isize)> = Vec::new();
+    for (edge, diff) in collection {
+        match consolidated.last_mut() {
+            Some(last) if last.0 == edge => last.1 += diff,
+            _ => consolidated.push((edge, diff)),
+        }
+    }
+    consolidated.retain(|&(_, diff)| diff != 0);
+    let mut expected = after.edges().collect::<Vec<_>>();
+    expected.sort_unstable();
+    assert_eq!(consolidated, expected.into_iter().map(|edge| (edge, 1)).collect::<Vec<_>>());
+}
+
+#[test]
+fn c_header_declares_exports() {
+    // the header is written by hand, and must declare exactly the functions `ffi` exports.
+    let header = include_str!("../include/graph_layout.h");
+    let source = include_str!("../src/ffi.rs");
+    let identifier = |text: &str| text.split(|c: char| !(c.is_alphanumeric() || c == '_')).next().unwrap().to_string();
+    let mut exported = source.split("#[no_mangle]").skip(1).map(|rest| identifier(rest.split(" fn ").nth(1).unwrap())).collect::<Vec<_>>();
+    let mut declared = header.lines()
+        .filter(|line| !line.starts_with(' ') && !line.starts_with('/') && !line.starts_with('#') && line.contains('('))
+        .map(|line| identifier(line.split('(').next().unwrap().rsplit([' ', '*']).next().unwrap()))
+        .collect::<Vec<_>>();
+    exported.sort();
+    declared.sort();
+    assert_eq!(exported.len(), 14);
+    assert_eq!(exported, declared);
+}
+
+#[cfg(feature = "cdylib")]
+#[test]
+fn c_interface() {
+    use graph_layout::ffi::*;
+    use graph_layout::graph::GraphLayout;
+    use std::ffi::CString;
+    use std::ptr;
+    let edges = [3u32, 4, 1_000, 7, 0, 0];
+    let mut keys = [0u64; 3];
+    unsafe { gl_entangle_batch(edges.as_ptr(), 3, keys.as_mut_ptr()) };
+    assert_eq!(keys[1], gl_entangle(1_000, 7));
+    let mut back = [0u32; 6];
+    unsafe { gl_detangle_batch(keys.as_ptr(), 3, back.as_mut_ptr()) };
+    assert_eq!(back, edges);
+
+    keys.sort_unstable();
+    let length = unsafe { gl_encode(keys.as_ptr(), 3, ptr::null_mut(), 0) };
+    let mut bytes = vec![0u8; length];
+    assert_eq!(unsafe { gl_encode(keys.as_ptr(), 3, bytes.as_mut_ptr(), length) }, length);
+    let mut decoded = [0u64; 3];
+    assert_eq!(unsafe { gl_decode(bytes.as_ptr(), length, 3, decoded.as_mut_ptr()) }, 0);
+    assert_eq!(decoded, keys);
+    assert_eq!(unsafe { gl_encode([2u64, 1].as_ptr(), 2, ptr::null_mut(), 0) }, usize::MAX);
+
+    let path = std::env::temp_dir().join("graph_layout_c_interface.glayout");
+    let layout = GraphLayout::from_weighted_edges((0 .. 100u32).map(|v| ((v, v * 3), v as f64)));
+    layout.save(&path).unwrap();
+    let name = CString::new(path.to_str().unwrap()).unwrap();
+    unsafe {
+        let handle = gl_layout_open(name.as_ptr());
+        assert!(!handle.is_null());
+        assert_eq!(gl_layout_edges(handle), 100);
+        assert_eq!(gl_layout_vertices(handle), 298);
+        assert_eq!(gl_layout_is_weighted(handle), 1);
+        assert_eq!(gl_layout_copy_edges(handle, ptr::null_mut(), 0), 100);
+        let mut pairs = vec![0u32; 200];
+        gl_layout_copy_edges(handle, pairs.as_mut_ptr(), 100);
+        assert_eq!(pairs.chunks(2).map(|pair| (pair[0], pair[1])).collect::<Vec<_>>(), layout.edges().collect::<Vec<_>>());
+        let mut weights = vec![0.0; 100];
+        assert_eq!(gl_layout_copy_weights(handle, weights.as_mut_ptr(), 100), 100);
+        assert_eq!(&weights[..], layout.weights().unwrap());
+        gl_layout_free(handle);
+        let missing = CString::new("/nonexistent/graph_layout.glayout").unwrap();
+        assert!(gl_layout_open(missing.as_ptr()).is_null());
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn wasm_bindings() {
+    use graph_layout::compression::{Compressed, Position};
+    use graph_layout::wasm::*;
+    // errors become `JsError`s, which exist only on wasm targets, so only success paths run here.
+    let edges = [3u32, 4, 1_000, 7, 0, 0];
+    let keys = entangle_batch(&edges);
+    assert_eq!(keys[1], entangle(1_000, 7));
+    assert_eq!(detangle(keys[1]), vec![1_000, 7]);
+    assert_eq!(detangle_batch(&keys), edges);
+
+    let mut sorted = keys.clone();
+    sorted.sort_unstable();
+    let compressed = Compressed::from(sorted.iter().cloned());
+    let mut bytes = Vec::new();
+    compressed.encode(Position::default(), compressed.seek(Position::default(), 3), &mut bytes);
+    assert_eq!(decode_keys(&bytes, 3, 0).unwrap(), sorted);
+    assert_eq!(decode_edges(&bytes, 3, 0).unwrap(), detangle_batch(&sorted));
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn python_bindings() {
+    use graph_layout::graph::GraphLayout;
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+    // the array functions need NumPy at runtime, and are only checked where it is installed.
+    let path = std::env::temp_dir().join("graph_layout_python_bindings.glayout");
+    GraphLayout::from_edges((0 .. 100u32).map(|v| (v, v * 3))).save(&path).unwrap();
+    Python::initialize();
+    Python::attach(|py| {
+        let module = PyModule::new(py, "graph_layout").unwrap();
+        graph_layout::python::graph_layout(&module).unwrap();
+        let locals = PyDict::new(py);
+        locals.set_item("gl", module).unwrap();
+        locals.set_item("path", path.to_str().unwrap()).unwrap();
+        let script = std::ffi::CString::new("
+key = gl.entangle(1000, 7)
+assert gl.detangle(key) == (1000, 7)
+layout = gl.GraphLayout.open(path)
+assert len(layout) == 100 and layout.vertices == 298 and layout.log_tile is None
+assert not layout.is_weighted
+assert layout.has_edge(5, 15) and not layout.has_edge(15, 5)
+try:
+    import numpy
+except ImportError:
+    numpy = None
+if numpy is not None:
+    # vertex 2 has an out-edge but no in-edge, and vertex 297 the reverse.
+    ins, outs = layout.degrees()
+    assert (outs[2], ins[2]) == (1, 0) and (outs[297], ins[297]) == (0, 1)
+").unwrap();
+        py.run(&script, None, Some(&locals)).unwrap();
+    });
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "roaring")]
+#[test]
+fn roaring_conversions() {
+    use graph_layout::compression::Compressed;
+    // values spanning several high words, so the treemap holds more than one bitmap.
+    let values = (0 .. 1_000u64).map(|i| i * i * 40_503 + (i << 33)).collect::<Vec<_>>();
+    let compressed = Compressed::from(values.iter().cloned());
+    let bitmap = compressed.to_roaring();
+    assert_eq!(bitmap.len(), 1_000);
+    assert!(bitmap.iter().eq(values.iter().cloned()));
+    let back = Compressed::from_roaring(&bitmap);
+    assert!(back.decompress().eq(values.iter().cloned()));
+    assert!(Compressed::from_roaring(&roaring::RoaringTreemap::new()).is_empty());
+}
+
+#[test]
+fn bytewise_hilbert_keys() {
+    // data keyed with `hilbert_curve::BytewiseHilbert` is read as is, so keys must follow the
+    // standard Hilbert curve over the 2^32 square. The keys below were computed with the textbook
+    // `xy2d` conversion, as published in the `hilbert_curve` 0.2 crate, and not by this crate.
+    let expected = [
+        ((0u32, 0u32), 0u64),
+        ((1, 0), 1),
+        ((1, 1), 2),
+        ((0, 1), 3),
+        ((3, 4), 53),
+        ((255, 255), 43_690),
+        ((256, 0), 65_536),
+        ((0, 65_536), 15_748_213_418),
+        ((1_000, 7), 1_048_277),
+        ((0x0100_0000, 0), 281_474_976_710_656),
+        ((0xFF00_0000, 0x00FF_0000), 18_446_556_422_293_422_080),
+        ((0x8000_0000, 0x8000_0000), 9_223_372_036_854_775_808),
+        ((0x7FFF_FFFF, 0x8000_0000), 9_223_372_036_854_775_807),
+        ((0xDEAD_BEEF, 0x1234_5678), 17_462_957_573_165_695_893),
+        ((123_456_789, 987_654_321), 392_343_801_740_616_856),
+        ((3_692_787_630, 200_494_509), 17_410_912_629_746_835_975),
+        ((1_693_511_353, 40_788_086), 1_449_668_683_918_766_205),
+        ((2_064_109_201, 3_851_444_534), 7_772_302_102_843_221_181),
+        ((811_533_580, 915_262_580), 737_600_421_855_729_120),
+        ((u32::MAX, 0), u64::MAX),
+        ((0, u32::MAX), 6_148_914_691_236_517_205),
+        ((u32::MAX, u32::MAX), 12_297_829_382_473_034_410),
+    ];
+    let hilbert = BytewiseHilbert::new();
+    for &(edge, key) in expected.iter() {
+        assert_eq!(hilbert.entangle(edge), key);
+        assert_eq!(hilbert.detangle(key), edge);
+    }
+
+    // the textbook conversion itself, bit by bit from the top, against the byte-at-a-time tables.
+    fn reference((mut x, mut y): (u64, u64)) -> u64 {
+        let mut key = 0;
+        for log in (0 .. 32).rev() {
+            let side = 1u64 << log;
+            let (rx, ry) = ((x & side != 0) as u64, (y & side != 0) as u64);
+            key += side * side * ((3 * rx) ^ ry);
+            if ry == 0 {
+                if rx == 1 {
+                    x = side.wrapping_sub(1).wrapping_sub(x);
+                    y = side.wrapping_sub(1).wrapping_sub(y);
+                }
+                std::mem::swap(&mut x, &mut y);
+            }
+        }
+        key
+    }
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    for &(edge, key) in expected.iter() {
+        assert_eq!(reference((edge.0 as u64, edge.1 as u64)), key);
+    }
+    for _ in 0 .. 100_000 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let edge = ((state >> 32) as u32, state as u32);
+        assert_eq!(hilbert.entangle(edge), reference((edge.0 as u64, edge.1 as u64)));
+    }
+}
+
+#[test]
+fn edge_columns() {
+    use graph_layout::column::Column;
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::shards::write_shards;
+    use graph_layout::order::Permutation;
+    use graph_layout::partition::{partition, Placement};
+    let edges = (0 .. 10000u32).map(|i| ((i * 7) % 53, (i * 13) % 61)).collect::<Vec<_>>();
+    let ids = (0 .. edges.len() as u64).collect::<Vec<_>>();
+    let labels = edges.iter().map(|&(src, dst)| format!("{}-{}", src, dst).into_bytes()).collect::<Vec<_>>();
+    let columns = vec![("id".to_owned(), Column::from(ids)), ("label".to_owned(), Column::from(labels))];
+    let layout = GraphLayout::from_edges_with_columns_tiled(edges.iter().cloned(), columns, 5);
+    assert!(layout.len() < edges.len());
+
+    // each edge keeps the values of its first occurrence, in curve order.
+    for ((src, dst), &id) in layout.edges_with::<u64>("id").unwrap() {
+        assert_eq!(edges[id as usize], (src, dst));
+        assert!(edges[.. id as usize].iter().all(|&edge| edge != (src, dst)));
+    }
+    for ((src, dst), label) in layout.edges_with::<Vec<u8>>("label").unwrap() {
+        assert_eq!(label, &format!("{}-{}", src, dst).into_bytes());
+    }
+    assert!(layout.column::<f64>("id").is_none());
+    assert!(layout.column::<u64>("missing").is_none());
+
+    let path = ::std::env::temp_dir().join(format!("graph-layout-columns-{}.layout", ::std::process::id()));
+    layout.save(&path).unwrap();
+    let read = GraphLayout::open(&path).unwrap();
+    ::std::fs::remove_file(&path).unwrap();
+    assert_eq!(read.columns(), layout.columns());
+
+    // a union carries columns held by every input, taking values from the earliest input.
+    let mut other = GraphLayout::from_edges((0 .. 400u32).map(|i| (i, i)));
+    other.attach_column("id", Column::U64(vec![u64::MAX; other.len()]));
+    let union = GraphLayout::union(&[&layout, &other]);
+    assert_eq!(union.columns().len(), 1);
+    for ((src, dst), &id) in union.edges_with::<u64>("id").unwrap() {
+        if layout.has_edge(src, dst) { assert_eq!(edges[id as usize], (src, dst)); }
+        else { assert_eq!(id, u64::MAX); }
+    }
+
+    // layouts rebuilt from some or all of the edges keep the values of the edges they keep.
+    let labelled = |layout: &GraphLayout, rename: &dyn Fn(u32) -> u32| {
+        layout.columns().len() == 2 && layout.edges_with::<Vec<u8>>("label").unwrap().all(|((src, dst), label)| {
+            label == &format!("{}-{}", rename(src), rename(dst)).into_bytes()
+        })
+    };
+    let permutation = Permutation::from_new_ids((0 .. 61).rev().collect()).unwrap();
+    let inverse = permutation.inverse();
+    assert!(labelled(&permutation.apply_to_layout(&layout), &|vertex| inverse.new_id(vertex)));
+    let split = layout.dense_tiles(0.8);
+    assert!(!split.dense.is_empty() && !split.sparse.is_empty());
+    assert!(labelled(&split.sparse, &|vertex| vertex));
+    assert!(partition(&layout, 3, Placement::Blocks).iter().all(|part| labelled(&part.layout, &|vertex| vertex)));
+    let directory = std::env::temp_dir().join("graph_layout_sharded_columns");
+    let manifest = write_shards(&layout, &directory, 3).unwrap();
+    let loaded = manifest.load(&directory).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(loaded.columns(), layout.columns());
+}
+
+#[test]
+fn edge_sinks() {
+    use graph_layout::builder::LayoutBuilder;
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::sink::{EdgeSink, KeyWriter};
+    let edges = (0 .. 20000u32).map(|i| ((i * 7919) % 1013, (i * 104729) % 997)).collect::<Vec<_>>();
+    let expected = GraphLayout::from_edges(edges.iter().cloned()).keys().collect::<Vec<_>>();
+
+    let mut builder = LayoutBuilder::new(3000);
+    builder.extend(edges.iter().cloned()).unwrap();
+    assert!(builder.runs() > 1);
+    let mut compressor = Compressor::new();
+    assert_eq!(builder.finish_into(&mut compressor).unwrap(), expected.len());
+    assert_eq!(compressor.done().decompress().collect::<Vec<_>>(), expected);
+
+    let (sender, receiver) = ::std::sync::mpsc::channel();
+    let mut builder = LayoutBuilder::new(1 << 20);
+    builder.extend(edges.iter().cloned()).unwrap();
+    builder.finish_into(sender).unwrap();
+    assert_eq!(receiver.iter().collect::<Vec<_>>(), expected);
+
+    let mut writer = KeyWriter::new(Vec::new());
+    for (src, dst) in GraphLayout::from_edges(edges.iter().cloned()).edges() {
+        writer.push_edge((src, dst)).unwrap();
+    }
+    assert_eq!(writer.bytes(), 8 * expected.len() as u64);
+    let bytes = writer.into_inner().unwrap();
+    let keys = bytes.chunks(8).map(|chunk| {
+        let mut array = [0u8; 8];
+        array.copy_from_slice(chunk);
+        u64::from_le_bytes(array)
+    }).collect::<Vec<_>>();
+    assert_eq!(keys, expected);
+}
+
+#[test]
+fn packed_tiles() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::packed::{pack_tiles, write_packed, Packing, PADDING};
+    let edges = (0 .. 5000u32).map(|i| (((i * 7919) % 211, (i * 104729) % 199), i as f64)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_weighted_edges_tiled(edges.iter().cloned(), 4);
+    let all = layout.edges().collect::<Vec<_>>();
+    for &packing in &[Packing::Csr, Packing::Ell] {
+        let packed = pack_tiles(&layout, packing, 32);
+        assert_eq!(packed.tiles.len(), layout.tiles().len());
+        assert_eq!(packed.indices.len() % 32, 0);
+        assert_eq!(packed.pointers.len() % 32, 0);
+        for (index, (tile, packed_tile)) in layout.tiles().iter().zip(packed.tiles.iter()).enumerate() {
+            assert_eq!(packed_tile.entries % 32, 0);
+            assert_eq!(packed_tile.edges, tile.edges);
+            let mut expected = all[tile.offset .. tile.offset + tile.edges].to_vec();
+            expected.sort();
+            assert_eq!(packed.tile_edges(index), expected);
+            let entries = &packed.indices[packed_tile.entries .. packed_tile.entries + packed_tile.length];
+            assert_eq!(entries.iter().filter(|&&column| column != PADDING).count(), tile.edges);
+            if packing == Packing::Ell {
+                assert_eq!(packed_tile.length, (16 * packed_tile.width).div_ceil(32) * 32);
+            }
+        }
+        let total = packed.weights.as_ref().unwrap().iter().map(|&weight| weight as f64).sum::<f64>();
+        assert_eq!(total, layout.weights().unwrap().iter().map(|&weight| weight as f32 as f64).sum::<f64>());
+    }
+
+    let directory = ::std::env::temp_dir().join(format!("graph-layout-packed-{}", ::std::process::id()));
+    ::std::fs::create_dir_all(&directory).unwrap();
+    let packed = pack_tiles(&layout, Packing::Csr, 16);
+    write_packed(&packed, &directory).unwrap();
+    let manifest = ::std::fs::read_to_string(directory.join("manifest.txt")).unwrap();
+    assert!(manifest.starts_with("graph-layout packed 1\npacking csr\nlog_tile 4\nalign 16\nweighted true\n"));
+    assert_eq!(manifest.lines().filter(|line| line.starts_with("tile ")).count(), packed.tiles.len());
+    assert_eq!(::std::fs::metadata(directory.join("indices.bin")).unwrap().len(), 4 * packed.indices.len() as u64);
+    ::std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn tracing_spans() {
+    use std::sync::{Arc, Mutex};
+    use graph_layout::builder::LayoutBuilder;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    type Counted = (&'static str, Vec<(&'static str, u64)>);
+    /// Records the name and counters of each span.
+    struct Recorder { spans: Mutex<Vec<Counted>> }
+    struct Counters<'a>(&'a mut Vec<(&'static str, u64)>);
+    impl Visit for Counters<'_> {
+        fn record_u64(&mut self, field: &Field, value: u64) { self.0.push((field.name(), value)); }
+        fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) { }
+    }
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata) -> bool { true }
+        fn new_span(&self, attributes: &Attributes) -> Id {
+            let mut spans = self.spans.lock().unwrap();
+            spans.push((attributes.metadata().name(), Vec::new()));
+            Id::from_u64(spans.len() as u64)
+        }
+        fn record(&self, id: &Id, values: &Record) {
+            values.record(&mut Counters(&mut self.spans.lock().unwrap()[id.into_u64() as usize - 1].1));
+        }
+        fn record_follows_from(&self, _: &Id, _: &Id) { }
+        fn event(&self, _: &Event) { }
+        fn enter(&self, _: &Id) { }
+        fn exit(&self, _: &Id) { }
+    }
+
+    let recorder = Arc::new(Recorder { spans: Mutex::new(Vec::new()) });
+    let path = ::std::env::temp_dir().join(format!("graph-layout-tracing-{}.layout", ::std::process::id()));
+    let edges = (0 .. 60000u64).map(|i| (((i * 7919) % 1013) as u32, ((i * 104729) % 997) as u32)).collect::<Vec<_>>();
+    let written = ::tracing::subscriber::with_default(recorder.clone(), || {
+        let mut builder = LayoutBuilder::new(1).memory_budget(2 * (8 * 4096 + 9 * 4096 + 8 * 1024));
+        builder.extend(edges.iter().cloned()).unwrap();
+        builder.finish(&path).unwrap()
+    });
+    ::std::fs::remove_file(&path).unwrap();
+
+    let spans = recorder.spans.lock().unwrap();
+    let counter = |name: &str, field: &str| spans.iter().filter(|span| span.0 == name)
+        .flat_map(|span| span.1.iter().filter(|counter| counter.0 == field).map(|counter| counter.1)).collect::<Vec<_>>();
+    assert_eq!(counter("ingest", "edges"), vec![edges.len() as u64]);
+    assert!(counter("sort", "edges").len() > 3);
+    assert!(!counter("compact", "runs").is_empty());
+    assert_eq!(counter("merge", "edges"), vec![written as u64]);
+}
+
+#[cfg(feature = "rkyv")]
+#[test]
+fn rkyv_archives() {
+    use graph_layout::graph::{ArchivedGraphLayout, GraphLayout};
+    use rkyv::rancor::Error;
+    let edges = (0 .. 10000u32).map(|i| (((i * 7919) % 1013, (i * 104729) % 997), i as f64)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_weighted_edges_tiled(edges.iter().cloned(), 6);
+    let bytes = rkyv::to_bytes::<Error>(&layout).unwrap();
+    let archived = rkyv::access::<ArchivedGraphLayout, Error>(&bytes).unwrap();
+
+    assert_eq!(archived.len(), layout.len());
+    assert_eq!(archived.vertices(), layout.vertices());
+    assert_eq!(archived.log_tile(), Some(6));
+    assert!(archived.edges().eq(layout.edges()));
+    assert_eq!(archived.tiles().len(), layout.tiles().len());
+    for (tile, archived_tile) in layout.tiles().iter().zip(archived.tiles()) {
+        assert!(archived.tile_keys(archived_tile).eq(layout.tile_keys(tile)));
+    }
+    for (index, &((src, dst), _)) in edges.iter().enumerate().step_by(97) {
+        assert!(archived.has_edge(src, dst));
+        assert!(!archived.has_edge(src + 2000, dst));
+        assert_eq!(archived.weight(index), Some(layout.weights().unwrap()[index]));
+    }
+
+    let compressed = Compressed::from(layout.keys());
+    let bytes = rkyv::to_bytes::<Error>(&compressed).unwrap();
+    let archived = rkyv::access::<ArchivedCompressed, Error>(&bytes).unwrap();
+    assert!(archived.decompress().eq(compressed.decompress()));
+    // a position past the end of the archived stream, as from a forged archive, decodes nothing.
+    let longer = Compressed::from(layout.keys().chain((1 ..= 1_000u64).map(|i| u64::MAX - 1_000 + i)));
+    let beyond = longer.seek(Default::default(), longer.len() - 1);
+    assert_eq!(archived.decompress_from(beyond).count(), 0);
+
+    let restored = rkyv::deserialize::<GraphLayout, Error>(rkyv::access::<ArchivedGraphLayout, Error>(&rkyv::to_bytes::<Error>(&layout).unwrap()).unwrap()).unwrap();
+    assert!(restored.edges().eq(layout.edges()));
+    assert_eq!(restored.weights(), layout.weights());
+}
+
+#[test]
+fn default_and_options() {
+    use graph_layout::column::Column;
+    use graph_layout::graph::{Aggregate, GraphLayout};
+    fn round_trip<T: Default + Tangle>(pair: (u32, u32)) -> (u32, u32) {
+        let tangler = T::default();
+        tangler.detangle(tangler.entangle(pair))
+    }
+    assert_eq!(round_trip::<Hilbert>((12345, 678)), (12345, 678));
+    assert_eq!(round_trip::<ZOrder>((12345, 678)), (12345, 678));
+    assert_eq!(BytewiseCached::default().detangle(Hilbert::default().entangle((9, 10))), (9, 10));
+    let mut compressor = Compressor::default();
+    compressor.push(3);
+    compressor.push(17);
+    assert!(compressor.done().decompress().eq(vec![3, 17]));
+
+    let edges = (0 .. 5000u32).map(|i| (((i * 7) % 101, (i * 13) % 97), i as f64)).collect::<Vec<_>>();
+    let plain = GraphLayout::options().tiled(4).build(edges.iter().map(|&(edge, _)| edge));
+    let expected = GraphLayout::from_edges_tiled(edges.iter().map(|&(edge, _)| edge), 4);
+    assert!(plain.edges().eq(expected.edges()));
+    assert_eq!(plain.tiles(), expected.tiles());
+
+    let summed = GraphLayout::options().tiled(4).aggregate(Aggregate::Sum).build_weighted(edges.iter().cloned());
+    let expected = GraphLayout::from_aggregated_edges_tiled(edges.iter().cloned(), Aggregate::Sum, 4);
+    assert!(summed.edges().eq(expected.edges()));
+    assert_eq!(summed.weights(), expected.weights());
+
+    let labels = Column::U32((0 .. 5000).collect());
+    let labelled = GraphLayout::options()
+        .aggregate(Aggregate::Max)
+        .column("label", labels.clone())
+        .build_weighted(edges.iter().cloned());
+    let expected = GraphLayout::from_edges_with_columns(edges.iter().map(|&(edge, _)| edge), vec![("label".to_owned(), labels)]);
+    assert!(labelled.edges().eq(expected.edges()));
+    assert_eq!(labelled.column::<u32>("label"), expected.column::<u32>("label"));
+    assert_eq!(labelled.weights(), GraphLayout::from_aggregated_edges(edges.iter().cloned(), Aggregate::Max).weights());
+}
+
+#[test]
+fn crate_errors() {
+    use graph_layout::Error;
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::format::{format_layout, parse_layout, LayoutWriter};
+    let layout = GraphLayout::from_edges((0 .. 1000u32).map(|i| (i % 37, i % 41)));
+    let mut bytes = Vec::new();
+    format_layout(&layout, &mut bytes).unwrap();
+
+    let mut newer = bytes.clone();
+    newer[8] = 2;
+    match parse_layout(&newer) {
+        Err(Error::Unsupported { what, found }) => assert_eq!((what, found), ("layout file version", 2)),
+        other => panic!("expected an unsupported version, found {:?}", other.map(|layout| layout.len())),
+    }
+    let mut corrupted = bytes.clone();
+    corrupted[100] ^= 0x10;
+    let error = parse_layout(&corrupted).err().unwrap();
+    assert!(matches!(error, Error::Corrupt(_)));
+    assert!(error.to_string().starts_with("corrupt "));
+    assert_eq!(std::io::Error::from(error).kind(), std::io::ErrorKind::InvalidData);
+    assert!(matches!(parse_layout(&bytes[.. 40]), Err(Error::Corrupt(_))));
+    assert!(matches!(EncodedDecompressor::new(0, 3, &[1, 0, 1]), Err(Error::Corrupt(_))));
+
+    let path = std::env::temp_dir().join("graph_layout_crate_errors.glayout");
+    assert!(matches!(LayoutWriter::create(&path, Some(40)), Err(Error::Capacity(_))));
+    let mut writer = LayoutWriter::create(&path, None).unwrap();
+    writer.push(10).unwrap();
+    match writer.push(7) {
+        Err(Error::Unordered { previous, next }) => assert_eq!((previous, next), (10, 7)),
+        other => panic!("expected an ordering violation, found {:?}", other),
+    }
+    writer.push(12).unwrap();
+    writer.finish().unwrap();
+    assert!(GraphLayout::open(&path).unwrap().keys().eq(vec![10, 12]));
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(GraphLayout::open(&path), Err(Error::Io(_))));
+}
+
+#[test]
+fn decompressor_escapes() {
+    use graph_layout::compression::{Compressed, Position};
+
+    // gaps of every escape width, in runs and alone, across chunk boundaries.
+    let mut value = 0u64;
+    let values: Vec<u64> = (0 .. 1000u64).map(|index| {
+        let hash = index.wrapping_mul(0x9E3779B97F4A7C15) >> 59;
+        value += match hash {
+            0 => 1 << 8,
+            1 => 1 << 20,
+            2 => 1 << 40,
+            3 => 255,
+            _ if index % 100 < 40 => 1,
+            _ => hash,
+        };
+        value
+    }).collect();
+
+    let compressed = Compressed::from(values.iter().copied());
+    assert_eq!(compressed.decompress().collect::<Vec<_>>(), values);
+    assert_eq!(compressed.decompress().fold(0, |sum, value| sum ^ value), values.iter().fold(0, |sum, value| sum ^ value));
+    for &start in &[0, 1, 31, 32, 33, 500, 999, 1000] {
+        let mut decompressor = compressed.decompress_from(compressed.seek(Position::default(), start));
+        assert_eq!(decompressor.size_hint(), (1000 - start, Some(1000 - start)));
+        if start < 1000 {
+            assert_eq!(decompressor.next(), Some(values[start]));
+            assert_eq!(decompressor.size_hint().0, 999 - start);
+        }
+        assert!(decompressor.take(40).eq(values[start ..].iter().skip(1).take(40).copied()));
+    }
+    assert_eq!(Compressed::from(::std::iter::empty()).decompress().next(), None);
+}
+
+#[test]
+fn compressed_from_sorted_slice() {
+    use graph_layout::compression::{Compressed, Position};
+
+    let mut value = 0u64;
+    let values: Vec<u64> = (0 .. 5000u64).map(|index| {
+        let hash = index.wrapping_mul(0x9E3779B97F4A7C15) >> 59;
+        value += match hash {
+            0 => 255,
+            1 => 256,
+            2 => 1 << 20,
+            3 => 1 << 40,
+            4 => 257,
+            _ => 1 + hash,
+        };
+        value
+    }).collect();
+
+    for values in &[&values[..], &values[.. 1], &values[.. 64], &values[.. 65], &[0, 1, 2], &[0, u64::MAX], &[]] {
+        let fast = Compressed::from_sorted_slice(values);
+        let slow = Compressed::from(values.iter().copied());
+        assert!(fast.decompress().eq(values.iter().copied()));
+        assert_eq!(fast.size_bytes(), slow.size_bytes());
+        let (mut ours, mut theirs) = (Vec::new(), Vec::new());
+        fast.encode(Position::default(), fast.seek(Position::default(), values.len()), &mut ours);
+        slow.encode(Position::default(), slow.seek(Position::default(), values.len()), &mut theirs);
+        assert_eq!(ours, theirs);
+    }
+}
+
+#[test]
+fn shared_cursors() {
+    use std::sync::Arc;
+    use graph_layout::compression::Compressed;
+
+    let values: Vec<u64> = (0 .. 10_000u64).map(|index| index * index / 7 + index).collect();
+    let shared = Arc::new(Compressed::from_sorted_slice(&values));
+    let reader = shared.reader();
+
+    let threads: Vec<_> = (0 .. 4).map(|thread| {
+        let reader = reader.clone();
+        let values = values.clone();
+        std::thread::spawn(move || {
+            let mut cursor = reader.cursor();
+            cursor.seek(thread * 1000);
+            assert_eq!(cursor.offset(), thread * 1000);
+            assert!(cursor.values().eq(values[thread * 1000 ..].iter().copied()));
+            assert!(cursor.by_ref().take(10).eq(values[thread * 1000 ..][.. 10].iter().copied()));
+            for &target in &[values[5000] - 1, values[5000], values[9999], values[9999] + 1] {
+                let found = cursor.seek_to(target);
+                assert_eq!(found, values.iter().copied().find(|&value| value >= target));
+                assert_eq!(cursor.offset(), values.iter().filter(|&&value| value < target).count());
+            }
+        })
+    }).collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    let mut cursor = shared.cursor();
+    cursor.seek(100);
+    let position = cursor.position();
+    assert!(reader.cursor_at(position).eq(values[100 ..].iter().copied()));
+    assert_eq!(shared.cursor_at(position).size_hint(), (9900, Some(9900)));
+    assert!(reader.compressed().decompress().eq(values.iter().copied()));
+}
+
+#[test]
+fn scratch_reuse() {
+    use graph_layout::compression::Compressed;
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::scratch::Scratch;
+
+    let mut scratch = Scratch::new();
+    for length in (0 .. 200u64).chain(0 .. 10) {
+        let values: Vec<u64> = (0 .. length).map(|index| index * index * index + index).collect();
+        let compressed = scratch.compress(&values);
+        assert!(compressed.decompress().eq(values.iter().copied()));
+        assert_eq!(compressed.size_bytes(), Compressed::from_sorted_slice(&values).size_bytes());
+        scratch.recycle(compressed);
+
+        let mut compressor = scratch.compressor();
+        for &value in &values { compressor.push(value); }
+        let compressed = compressor.done();
+        assert!(compressed.decompress().eq(values.iter().copied()));
+        scratch.recycle(compressed);
+    }
+
+    for size in (1 .. 16u32).chain(1 .. 3) {
+        let edges: Vec<(u32, u32)> = (0 .. size * 3).map(|index| (index % size, (index * 7) % (size + 3))).collect();
+        for options in [GraphLayout::options(), GraphLayout::options().tiled(2)] {
+            let layout = options.clone().build_in(edges.iter().copied(), &mut scratch);
+            let expected = options.build(edges.iter().copied());
+            assert!(layout.keys().eq(expected.keys()));
+            assert_eq!(layout.vertices(), expected.vertices());
+            assert_eq!(layout.tiles(), expected.tiles());
+            assert_eq!(layout.blocks(), expected.blocks());
+            assert_eq!(layout.tile_summaries().len(), expected.tile_summaries().len());
+            scratch.recycle_layout(layout);
+        }
+    }
+}
+
+#[test]
+fn planned_rect_queries() {
+    use graph_layout::generate::gnm;
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::query::{Plan, Rect};
+
+    // a sparse graph, and a hub.
+    let mut edges = gnm(100_000, 400_000, 5).collect::<Vec<_>>();
+    edges.extend((0 .. 100_000).step_by(2).map(|dst| (7, dst)));
+    for layout in [GraphLayout::from_edges(edges.iter().copied()), GraphLayout::from_edges_tiled(edges.iter().copied(), 10)] {
+        let rects = [
+            Rect::new(1000 ..= 1100, 2000 ..= 2300),
+            Rect::new(0 ..= 99_999, 50_000 ..= 50_010),
+            Rect::new(0 ..= u32::MAX, 0 ..= u32::MAX),
+            Rect { sources: (5, 4), destinations: (0, 10) },
+            Rect::new(200_000 ..= 300_000, 0 ..= 10),
+            Rect::row(7),
+            Rect::row(12_345),
+        ];
+        for &rect in &rects {
+            let expected = layout.edges().filter(|&edge| rect.contains(edge)).collect::<Vec<_>>();
+            assert_eq!(layout.edges_in(rect).collect::<Vec<_>>(), expected, "{:?}", rect);
+        }
+        assert!(matches!(layout.plan(rects[0]), Plan::Ranges(_)));
+        assert_eq!(layout.plan(rects[2]), Plan::Scan);
+        assert_eq!(layout.plan(rects[3]), Plan::Ranges(Vec::new()));
+        assert_eq!(layout.plan(rects[4]), Plan::Ranges(Vec::new()));
+        assert_eq!(layout.plan(Rect::new(1 ..= 99_999, 0 ..= 99_998)), Plan::Scan);
+        assert!(matches!(layout.plan(Rect::row(12_345)), Plan::Ranges(_)));
+
+        let mut neighbors = edges.iter().filter(|&&(src, _)| src == 12_345).map(|&(_, dst)| dst).collect::<Vec<_>>();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        assert_eq!(layout.neighbors(12_345), neighbors);
+        assert_eq!(layout.neighbors(7).len(), layout.edges().filter(|&(src, _)| src == 7).count());
+    }
+}
+
+#[test]
+fn work_stealing_executor() {
+    use graph_layout::execute::Executor;
+    use graph_layout::generate::barabasi_albert;
+    use graph_layout::graph::GraphLayout;
+
+    let layout = GraphLayout::from_edges(barabasi_albert(20_000, 5, 3));
+    let expected = layout.edges().collect::<Vec<_>>();
+    for executor in [Executor::new(1), Executor::new(3), Executor::new(8).grain(2), Executor::default()] {
+        let pieces = executor.map(&layout, |worker, blocks| worker.edges(blocks).to_vec());
+        assert_eq!(pieces.concat(), expected);
+
+        let states = executor.fold(&layout, || (0usize, 0u64), |state, worker, blocks| {
+            state.0 += 1;
+            state.1 += worker.edges(blocks).iter().map(|&(src, dst)| src as u64 + dst as u64).sum::<u64>();
+        });
+        assert_eq!(states.iter().map(|state| state.0).sum::<usize>(), pieces.len());
+        assert_eq!(states.iter().map(|state| state.1).sum::<u64>(), expected.iter().map(|&(src, dst)| src as u64 + dst as u64).sum::<u64>());
+    }
+
+    // a slow first piece leaves the rest of the first thread's run to be stolen.
+    let pieces = Executor::new(4).map(&layout, |worker, blocks| {
+        if blocks[0].offset == 0 { std::thread::sleep(std::time::Duration::from_millis(200)); }
+        (worker.thread(), blocks[0].offset)
+    });
+    assert!(pieces.iter().map(|&(_, offset)| offset).eq(layout.blocks().iter().map(|block| block.offset)));
+    assert!(pieces.iter().all(|&(thread, _)| thread < 4));
+    assert!(pieces[1 .. pieces.len() / 4].iter().any(|&(thread, _)| thread != 0));
+    let empty = GraphLayout::from_edges(::std::iter::empty());
+    assert!(Executor::new(4).map(&empty, |_, blocks| blocks.len()).is_empty());
+    assert_eq!(Executor::new(4).fold(&empty, || 1, |_, _, _| { }), vec![1]);
+}
+
+#[test]
+fn retain_edges_and_values() {
+    use graph_layout::column::Column;
+    use graph_layout::compression::Compressed;
+    use graph_layout::graph::GraphLayout;
+
+    let values = (0 .. 10_000u64).map(|index| index * index).collect::<Vec<_>>();
+    let mut compressed = Compressed::from_sorted_slice(&values);
+    assert!(compressed.filter(|value| value % 3 == 0).decompress().eq(values.iter().copied().filter(|value| value % 3 == 0)));
+    compressed.retain(|value| value > 1_000_000);
+    assert!(compressed.decompress().eq(values.iter().copied().filter(|&value| value > 1_000_000)));
+    compressed.retain(|_| false);
+    assert!(compressed.is_empty());
+
+    let edges = (0 .. 20_000u32).map(|index| ((index * 7) % 1000, (index * 13) % 997)).collect::<Vec<_>>();
+    let weighted = edges.iter().map(|&(src, dst)| ((src, dst), (src + dst) as f64)).collect::<Vec<_>>();
+    for tiled in [None, Some(4)] {
+        let mut layout = match tiled {
+            Some(log) => GraphLayout::from_weighted_edges_tiled(weighted.iter().copied(), log),
+            None => GraphLayout::from_weighted_edges(weighted.iter().copied()),
+        };
+        let labels = layout.edges().map(|(src, dst)| src as u64 * 1000 + dst as u64).collect::<Vec<_>>();
+        layout.attach_column("label", Column::U64(labels));
+
+        layout.retain_edges(|src, dst| src % 5 != 0 && dst < 900);
+        let expected = match tiled {
+            Some(log) => GraphLayout::from_weighted_edges_tiled(weighted.iter().copied().filter(|&((src, dst), _)| src % 5 != 0 && dst < 900), log),
+            None => GraphLayout::from_weighted_edges(weighted.iter().copied().filter(|&((src, dst), _)| src % 5 != 0 && dst < 900)),
+        };
+        assert!(layout.keys().eq(expected.keys()));
+        assert_eq!(layout.blocks(), expected.blocks());
+        assert_eq!(layout.tiles(), expected.tiles());
+        assert_eq!(layout.vertices(), expected.vertices());
+        assert_eq!(layout.weights(), expected.weights());
+        assert!(layout.edges().zip(layout.column::<u64>("label").unwrap()).all(|((src, dst), &label)| label == src as u64 * 1000 + dst as u64));
+
+        layout.retain_weighted_edges(|_, _, weight| weight >= 1000.0);
+        assert!(layout.edges().zip(layout.weights().unwrap()).all(|((src, dst), &weight)| weight == (src + dst) as f64 && weight >= 1000.0));
+        assert_eq!(layout.len(), expected.weights().unwrap().iter().filter(|&&weight| weight >= 1000.0).count());
+        assert_eq!(layout.column::<u64>("label").unwrap().len(), layout.len());
+    }
+}
+
+#[test]
+fn monotone_maps() {
+    use graph_layout::compression::Compressed;
+    use graph_layout::Error;
+
+    let values = (1 .. 5_000u64).map(|index| index * 3).collect::<Vec<_>>();
+    let compressed = Compressed::from_sorted_slice(&values);
+    let scaled = compressed.map_monotone(|value| value << 20);
+    assert!(scaled.decompress().eq(values.iter().map(|&value| value << 20)));
+    let shifted = compressed.map_monotone_checked(|value| value + 1).unwrap();
+    assert!(shifted.decompress().eq(values.iter().map(|&value| value + 1)));
+    assert_eq!(shifted.size_bytes(), compressed.size_bytes());
+
+    match compressed.map_monotone_checked(|value| value / 6) {
+        Err(Error::Unordered { previous, next }) => assert_eq!((previous, next), (1, 1)),
+        other => panic!("expected an ordering violation, found {:?}", other.map(|stream| stream.len())),
+    }
+    assert!(compressed.map_monotone_checked(|value| u64::MAX - value).is_err());
+    assert!(Compressed::from_sorted_slice(&[]).map_monotone_checked(|value| value).unwrap().is_empty());
+    if cfg!(debug_assertions) {
+        assert!(std::panic::catch_unwind(|| compressed.map_monotone(|value| value / 6)).is_err());
+    }
+}
+
+#[test]
+fn interval_sets() {
+    use std::collections::BTreeSet;
+    use graph_layout::intervals::Intervals;
+
+    // clustered values: runs of varying length with gaps, ending at the largest value.
+    let runs = [(0u64, 10u64), (10, 1), (20, 1000), (1100, 3), (5000, 5000), (u64::MAX - 4, 5)];
+    let values = runs.iter().flat_map(|&(start, length)| (0 .. length).map(move |offset| start + offset)).collect::<Vec<_>>();
+    let intervals = Intervals::from_sorted_slice(&values);
+    assert_eq!(intervals.runs(), &[(0, 11), (20, 1000), (1100, 3), (5000, 5000), (u64::MAX - 4, 5)]);
+    assert_eq!(intervals.len(), values.len());
+    assert!(intervals.decompress().eq(values.iter().copied()));
+    assert_eq!(intervals.decompress().size_hint(), (values.len(), Some(values.len())));
+    for value in [0, 11, 12, 19, 20, 1019, 1020, 9999, 10000, u64::MAX - 5, u64::MAX] {
+        assert_eq!(intervals.contains(value), values.binary_search(&value).is_ok());
+    }
+
+    let compressed = Compressed::from_intervals(&intervals);
+    assert!(compressed.decompress().eq(values.iter().copied()));
+    assert_eq!(compressed.to_intervals(), intervals);
+
+    let other = Intervals::from((0 .. 2_000u64).filter(|value| value % 300 < 150).chain(9_990 .. 10_010));
+    let these = values.iter().copied().collect::<BTreeSet<_>>();
+    let those = other.decompress().collect::<BTreeSet<_>>();
+    for (this, that, these, those) in [(&intervals, &other, &these, &those), (&other, &intervals, &those, &these)] {
+        assert!(this.union(that).decompress().eq(these.union(those).copied()));
+        assert!(this.intersection(that).decompress().eq(these.intersection(those).copied()));
+        assert!(this.difference(that).decompress().eq(these.difference(those).copied()));
+    }
+    assert_eq!(intervals.union(&intervals), intervals);
+    assert!(intervals.difference(&intervals).is_empty());
+    assert!(Intervals::default().union(&other) == other && other.intersection(&Intervals::default()).is_empty());
+}
+
+#[test]
+fn gapped_adjacency() {
+    use graph_layout::graph::GraphLayout;
+    // each vertex links to its community of 40 consecutive vertices, less a few, and some others.
+    let edges = (0 .. 5_000u32).flat_map(|v| {
+        let community = v / 40 * 40;
+        (0 .. 40).filter(move |i| (v + i) % 13 != 0).map(move |i| (v, community + i)).chain(vec![(v, (v * 7919) % 5_000), (v, (v * 104_729) % 5_000)])
+    }).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges(edges.iter().cloned());
+    let adjacency = layout.gapped(4);
+    assert_eq!(adjacency.vertices(), 5_000);
+    assert_eq!(adjacency.len(), layout.len());
+    for vertex in [0, 17, 1234, 4_999] {
+        let mut expected = edges.iter().filter(|&&(src, _)| src == vertex).map(|&(_, dst)| dst).collect::<Vec<_>>();
+        expected.sort_unstable();
+        expected.dedup();
+        assert_eq!(adjacency.successors(vertex), expected);
+        assert_eq!(adjacency.degree(vertex), expected.len());
+        assert!((0 .. 5_000).all(|dst| adjacency.has_edge(vertex, dst) == expected.binary_search(&dst).is_ok()));
+    }
+    assert_eq!(adjacency.to_layout().keys().collect::<Vec<_>>(), layout.keys().collect::<Vec<_>>());
+
+    // intervals beat gap-coding every successor, and the curve keys of the same edges.
+    let gaps = GraphLayout::from_edges(vec![(0, 1), (0, 2), (0, 3), (0, 5)].into_iter()).gapped(2);
+    assert_eq!(gaps.successors(0), vec![1, 2, 3, 5]);
+    assert!(2 * adjacency.size_bytes() < layout.gapped(u32::MAX).size_bytes());
+    assert!(adjacency.size_bytes() < Compressed::from(layout.keys()).size_bytes());
+}
+
+#[test]
+fn streaming_relabel() {
+    use graph_layout::builder::LayoutBuilder;
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::{read_binary_edges, read_edge_list, write_binary_edges};
+    use graph_layout::order::{relabel_binary_edges, relabel_edge_list, relabel_into_builder, Permutation};
+
+    let edges = (0 .. 20_000u32).map(|i| (i * 7919 % 3001, i * 31 % 2999)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges(edges.iter().cloned());
+    let permutation = Permutation::from_new_ids((0 .. 3001u32).map(|v| v * 1234 % 3001).collect()).unwrap();
+    let expected = permutation.apply_to_layout(&layout);
+    let directory = std::env::temp_dir();
+    let (input, output) = (directory.join("graph_layout_streaming_relabel.in"), directory.join("graph_layout_streaming_relabel.out"));
+
+    let text = edges.iter().map(|(src, dst)| format!("{} {}\n", src, dst)).collect::<String>();
+    std::fs::write(&input, format!("# comment\n{}", text)).unwrap();
+    assert_eq!(relabel_edge_list(&permutation, &input, &output).unwrap(), edges.len() as u64);
+    let relabeled = read_edge_list(&output).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(relabeled, edges.iter().map(|&(src, dst)| (permutation.new_id(src), permutation.new_id(dst))).collect::<Vec<_>>());
+
+    write_binary_edges(&layout, &input).unwrap();
+    assert_eq!(relabel_binary_edges(&permutation, &input, &output).unwrap(), layout.len() as u64);
+    let relabeled = GraphLayout::from_edges(read_binary_edges(&output).unwrap().map(Result::unwrap));
+    assert_eq!(relabeled.keys().collect::<Vec<_>>(), expected.keys().collect::<Vec<_>>());
+
+    // straight into the sorted builder, spilling several runs.
+    let mut builder = LayoutBuilder::new(2_000);
+    assert_eq!(relabel_into_builder(&permutation, read_binary_edges(&input).unwrap(), &mut builder).unwrap(), layout.len() as u64);
+    assert!(builder.runs() > 1);
+    builder.finish(&output).unwrap();
+    assert_eq!(GraphLayout::open(&output).unwrap().keys().collect::<Vec<_>>(), expected.keys().collect::<Vec<_>>());
+
+    // vertices beyond the permutation are reported rather than relabeled.
+    std::fs::write(&input, "0 1\n3001 2\n").unwrap();
+    assert!(relabel_edge_list(&permutation, &input, &output).is_err());
+
+    #[cfg(feature = "mmap")]
+    {
+        use graph_layout::order::{MappedPermutation, Relabeling};
+        let path = directory.join("graph_layout_streaming_relabel.perm");
+        permutation.save(&path).unwrap();
+        let mapped = MappedPermutation::open(&path).unwrap();
+        assert_eq!(mapped.len(), permutation.len());
+        assert!((0 .. 3001).all(|v| mapped.new_id(v) == permutation.new_id(v)));
+        std::fs::write(&path, b"GLPERM\0\0\x02\0\0\0\0\0\0\0\x01\0\0\0").unwrap();
+        assert!(MappedPermutation::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn permute_vertex_properties() {
+    use graph_layout::order::Permutation;
+
+    let permutation = Permutation::from_new_ids((0 .. 10_007u32).map(|v| v * 1234 % 10_007).collect()).unwrap();
+    let ranks = (0 .. 10_007).map(|v| v as f64 / 3.0).collect::<Vec<_>>();
+    let expected = permutation.apply_to_column(ranks.clone());
+    assert_eq!(permutation.permute_slice(&ranks), expected);
+    let mut moved = ranks.clone();
+    permutation.permute_in_place(&mut moved);
+    assert_eq!(moved, expected);
+    permutation.inverse().permute_in_place(&mut moved);
+    assert_eq!(moved, ranks);
+    let labels = (0 .. 10_007).map(|v| format!("v{}", v)).collect::<Vec<_>>();
+    assert_eq!(permutation.permute_slice(&labels), permutation.apply_to_column(labels.clone()));
+
+    // records of twelve bytes, permuted through buckets and, with a large budget, in memory.
+    let directory = std::env::temp_dir();
+    let (input, output) = (directory.join("graph_layout_permute_properties.in"), directory.join("graph_layout_permute_properties.out"));
+    let records = (0 .. 10_007u32).map(|v| [v, v ^ 0xFFFF, v.wrapping_mul(2_654_435_761)]).collect::<Vec<_>>();
+    std::fs::write(&input, records.iter().flatten().flat_map(|word| word.to_le_bytes()).collect::<Vec<_>>()).unwrap();
+    let expected = permutation.permute_slice(&records).iter().flatten().flat_map(|word| word.to_le_bytes()).collect::<Vec<_>>();
+    for budget in [1_000, 12 * 10_007] {
+        permutation.permute_file(&input, &output, 12, budget).unwrap();
+        assert!(std::fs::read(&output).unwrap() == expected);
+        assert!(std::fs::read_dir(&directory).unwrap().all(|entry| !entry.unwrap().file_name().to_string_lossy().contains("permute_properties.out.bucket")));
+    }
+    assert!(permutation.permute_file(&input, &output, 8, 1_000).is_err());
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn curve_map() {
+    use std::collections::BTreeMap;
+    use graph_layout::curvemap::CurveMap;
+    use graph_layout::query::{Plan, Rect};
+
+    let hilbert = Hilbert::new();
+    let points = (0 .. 50_000u64).map(|i| ((i * 7919 % 3001) as u32, (i * 104_729 % 2003) as u32)).collect::<Vec<_>>();
+    // later entries replace earlier entries with equal keys.
+    let map = points.iter().enumerate().map(|(index, &point)| (point, index)).collect::<CurveMap<_>>();
+    let expected = points.iter().enumerate().map(|(index, &point)| (point, index)).collect::<BTreeMap<_, _>>();
+    assert_eq!(map.len(), expected.len());
+    assert!(map.blocks().len() > 4);
+    assert!(map.iter().map(|(point, _)| hilbert.entangle(point)).collect::<Vec<_>>().windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(map.iter().map(|(point, &index)| (point, index)).collect::<BTreeMap<_, _>>(), expected);
+
+    for (&point, &index) in expected.iter().step_by(97) {
+        assert_eq!(map.get(point), Some(&index));
+    }
+    assert_eq!(map.get((3001, 0)), None);
+    assert!(!map.contains_key((5, 5)) || expected.contains_key(&(5, 5)));
+
+    let (lower, upper) = (hilbert.entangle((100, 100)), hilbert.entangle((2000, 1500)));
+    let (lower, upper) = (lower.min(upper), lower.max(upper));
+    let ranged = map.range(lower ..= upper).map(|(point, &index)| (point, index)).collect::<Vec<_>>();
+    let mut wanted = expected.iter().filter(|(&point, _)| (lower ..= upper).contains(&hilbert.entangle(point))).map(|(&point, &index)| (point, index)).collect::<Vec<_>>();
+    wanted.sort_by_key(|&(point, _)| hilbert.entangle(point));
+    assert_eq!(ranged, wanted);
+
+    for rect in [Rect::new(100 ..= 140, 200 ..= 260), Rect::new(0 ..= 3000, 7 ..= 7), Rect::new(0 ..= 5000, 0 ..= 5000)] {
+        let found = map.rect(rect).map(|(point, &index)| (point, index)).collect::<BTreeMap<_, _>>();
+        let wanted = expected.iter().filter(|(&point, _)| rect.contains(point)).map(|(&point, &index)| (point, index)).collect::<BTreeMap<_, _>>();
+        assert_eq!(found, wanted);
+    }
+    assert!(matches!(map.plan(Rect::new(100 ..= 140, 200 ..= 260)), Plan::Ranges(_)));
+    assert_eq!(map.plan(Rect::new(0 ..= 5000, 0 ..= 5000)), Plan::Scan);
+
+    let mut map = map;
+    *map.get_mut(points[0]).unwrap() += 1_000_000;
+    assert_eq!(map.get(points[0]), Some(&(expected[&points[0]] + 1_000_000)));
+    assert!(CurveMap::<u8>::new().rect(Rect::row(0)).next().is_none());
+}
+
+#[test]
+fn fused_text_ingestion() {
+    use graph_layout::builder::LayoutBuilder;
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::{load_edge_list, EdgeList, KeyReader};
+
+    let hilbert = Hilbert::new();
+    // identifiers of every length, separators of every kind, comments, blank lines and extra fields.
+    let mut text = String::from("# header\n\n   % note\n");
+    for i in 0 .. 20_000u64 {
+        let (src, dst) = ((i * 2_654_435_761) % (1 << 32), (i * i * 7919) % (1u64 << (i % 33)));
+        let separator = [" ", "\t", ",", " , ", "  "][i as usize % 5];
+        let ending = ["\n", "\r\n", " extra fields\n", "\t\n"][i as usize % 4];
+        text.push_str(&format!("{}{}{}{}", src, separator, dst, ending));
+    }
+    text.push_str("4294967295 0");
+    let expected = EdgeList::new(text.as_bytes()).map(|edge| hilbert.entangle(edge.unwrap())).collect::<Vec<_>>();
+    let mut keys = Vec::new();
+    assert_eq!(KeyReader::new(text.as_bytes()).read_all(&mut keys).unwrap(), 20_001);
+    assert_eq!(keys, expected);
+
+    // lines split across reads are reassembled.
+    struct Trickle<'a>(&'a [u8]);
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.0.len().min(buffer.len()).min(7);
+            buffer[.. read].copy_from_slice(&self.0[.. read]);
+            self.0 = &self.0[read ..];
+            Ok(read)
+        }
+    }
+    let (mut reader, mut trickled) = (KeyReader::new(Trickle(text.as_bytes())), Vec::new());
+    while reader.read_keys(&mut trickled).unwrap() > 0 { }
+    assert_eq!(trickled, expected);
+
+    // malformed lines fail as they do for `EdgeList`, with the same line numbers.
+    for bad in ["1 2\n3\n", "1 2\n\n4294967296 1\n", "1 x\n", "1 2x\n", "12345678901234 1\n", "-1 2\n"] {
+        let error = KeyReader::new(bad.as_bytes()).read_all(&mut Vec::new()).unwrap_err();
+        let reference = EdgeList::new(bad.as_bytes()).find_map(Result::err).unwrap();
+        assert_eq!(error.to_string(), reference.to_string());
+    }
+
+    let path = std::env::temp_dir().join("graph_layout_fused_text_ingestion.txt");
+    std::fs::write(&path, &text).unwrap();
+    let layout = load_edge_list(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let reference = GraphLayout::from_edges(EdgeList::new(text.as_bytes()).map(Result::unwrap));
+    assert_eq!(layout.keys().collect::<Vec<_>>(), reference.keys().collect::<Vec<_>>());
+
+    let mut builder = LayoutBuilder::new(3_000).reservoir(10, 7);
+    builder.extend_edge_list(text.as_bytes()).unwrap();
+    assert!(builder.runs() > 1);
+    let (edges, sample) = builder.finish_with_sample(&path).unwrap();
+    assert_eq!(edges, reference.len());
+    assert!(sample.iter().all(|&(src, dst)| reference.has_edge(src, dst)));
+    assert_eq!(GraphLayout::open(&path).unwrap().keys().collect::<Vec<_>>(), reference.keys().collect::<Vec<_>>());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn untrusted_bytes() {
+    use graph_layout::Error;
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::format::{checksum, format_layout, parse_layout};
+
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut random = move |bound: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % bound as u64) as usize
+    };
+
+    // every truncation and mutation of an encoding is rejected or decodes to increasing values.
+    let values = (0 .. 2_000u64).map(|i| i * i * i + 3 * i).collect::<Vec<_>>();
+    let compressed = Compressed::from(values.iter().copied());
+    let mut encoding = Vec::new();
+    compressed.encode(Position::default(), compressed.seek(Position::default(), values.len()), &mut encoding);
+    for length in 0 .. encoding.len() {
+        assert!(Compressor::new().done().decode(values.len(), &encoding[.. length]).is_err());
+        assert!(EncodedDecompressor::new(0, values.len(), &encoding[.. length]).is_err());
+    }
+    for _ in 0 .. 2_000 {
+        let mut mutated = encoding.clone();
+        for _ in 0 .. 1 + random(4) {
+            let index = random(mutated.len());
+            mutated[index] = random(256) as u8;
+        }
+        let mut decoded = Compressor::new().done();
+        if decoded.decode(values.len(), &mutated).is_ok() {
+            let decoded = decoded.decompress().collect::<Vec<_>>();
+            assert!(decoded.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        let previous = random(1 << 20) as u64;
+        if let Ok(keys) = EncodedDecompressor::new(previous, values.len(), &mutated) {
+            assert!(keys.fold(previous, |last, key| { assert!(key > last); key }) > previous);
+        }
+    }
+
+    // repeated values, and deltas that overflow alone or from `previous`, are corrupt.
+    let repeat = [1, 0, 2, 0, 0];
+    let overflow = [1, 0, 8, 255, 255, 255, 255, 255, 255, 255, 255];
+    assert!(matches!(EncodedDecompressor::new(0, 2, &repeat), Err(Error::Corrupt(_))));
+    assert!(matches!(Compressor::new().done().decode(2, &overflow), Err(Error::Corrupt(_))));
+    assert!(matches!(EncodedDecompressor::new(u64::MAX - 5, 1, &[7]), Err(Error::Corrupt(_))));
+    assert!(EncodedDecompressor::new(0, 1, &[0, 2, 0, 0]).is_ok());
+    assert!(EncodedDecompressor::new(3, 1, &[0, 2, 0, 0]).is_err());
+
+    // layout files with valid checksums but forged contents are rejected, never panicking.
+    let layout = GraphLayout::from_edges_tiled((0 .. 20_000u32).map(|i| (i % 211, (i * 7) % 223)), 4);
+    let mut bytes = Vec::new();
+    format_layout(&layout, &mut bytes).unwrap();
+    let word = |bytes: &[u8], at: usize| u64::from_le_bytes(bytes[at .. at + 8].try_into().unwrap());
+    let footer = word(&bytes, bytes.len() - 24) as usize;
+    let reseal = |bytes: &mut Vec<u8>| {
+        let end = bytes.len() - 32;
+        for entry in (footer + 8 .. end).step_by(32) {
+            let (offset, length) = (word(bytes, entry + 8) as usize, word(bytes, entry + 16) as usize);
+            let sum = checksum(&bytes[offset .. offset + length]);
+            bytes[entry + 24 .. entry + 32].copy_from_slice(&sum.to_le_bytes());
+        }
+        let mut summed = bytes[.. 64].to_vec();
+        summed.extend_from_slice(&bytes[footer .. end]);
+        let sum = checksum(&summed);
+        bytes[end .. end + 8].copy_from_slice(&sum.to_le_bytes());
+    };
+    let mut resealed = bytes.clone();
+    reseal(&mut resealed);
+    assert_eq!(resealed, bytes);
+    for round in 0 .. 400 {
+        let mut mutated = bytes.clone();
+        for _ in 0 .. 1 + round % 3 {
+            let index = 64 + random(footer - 64);
+            mutated[index] = random(256) as u8;
+        }
+        reseal(&mut mutated);
+        if let Ok(parsed) = parse_layout(&mutated) {
+            let keys = parsed.keys().collect::<Vec<_>>();
+            assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        #[cfg(feature = "mmap")]
+        if round % 10 == 0 {
+            let path = std::env::temp_dir().join("graph_layout_untrusted_bytes.glayout");
+            std::fs::write(&path, &mutated).unwrap();
+            if let Ok(mapped) = GraphLayout::open_mmap(&path) {
+                // unverified files are read without panicking, up to their first malformed block.
+                let mut keys = mapped.keys();
+                let read = keys.by_ref().collect::<Vec<_>>();
+                assert!(read.len() == mapped.len() || keys.error().is_some());
+                if mapped.verify().is_ok() {
+                    assert!(keys.error().is_none());
+                    assert!(read.windows(2).all(|pair| pair[0] < pair[1]));
+                    for tile in mapped.tiles() {
+                        assert_eq!(mapped.tile_keys(tile).count(), tile.edges);
+                    }
+                }
+            }
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    // headers claiming other vertex counts, or tiles as large as the whole space, are corrupt.
+    for (at, value) in [(16, layout.vertices() + 1), (16, layout.vertices() - 1), (16, u64::MAX), (40, 32), (40, 63)] {
+        let mut forged = bytes.clone();
+        let width = if at == 40 { 4 } else { 8 };
+        forged[at .. at + width].copy_from_slice(&value.to_le_bytes()[.. width]);
+        reseal(&mut forged);
+        assert!(matches!(parse_layout(&forged), Err(Error::Corrupt(_))));
+        #[cfg(feature = "mmap")]
+        {
+            let path = std::env::temp_dir().join("graph_layout_forged_header.glayout");
+            std::fs::write(&path, &forged).unwrap();
+            assert!(GraphLayout::open_mmap(&path).and_then(|mapped| mapped.verify()).is_err());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
+
+#[test]
+fn nearest_edges() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::nearest::Probe;
+    let hilbert = Hilbert::new();
+    let edges = (0 .. 30_000u64).map(|i| ((i * 7919 % 2_003) as u32, (i * 104_729 % 1_999) as u32)).collect::<Vec<_>>();
+    let layout = GraphLayout::from_edges(edges.iter().copied());
+    let keys = layout.keys().take(layout.len()).collect::<Vec<_>>();
+
+    for probe in [(0, 0), (1_000, 1_000), (2_002, 3), (5_000, 5_000), (17, 1_500)] {
+        let key = hilbert.entangle(probe);
+        for k in [0, 1, 10, 300] {
+            let mut expected = keys.clone();
+            expected.sort_by_key(|&other| (other.abs_diff(key), other));
+            let expected = expected[.. k].iter().map(|&other| hilbert.detangle(other)).collect::<Vec<_>>();
+            assert_eq!(layout.nearest(probe, k), expected);
+            assert_eq!(layout.nearest(key, k), expected);
+
+            // exact by L1 distance, whichever edges break ties.
+            let distance = |(src, dst): (u32, u32)| src.abs_diff(probe.0) as u64 + dst.abs_diff(probe.1) as u64;
+            let mut expected = keys.iter().map(|&other| distance(hilbert.detangle(other))).collect::<Vec<_>>();
+            expected.sort_unstable();
+            let found = layout.nearest_l1(Probe::Point(probe.0, probe.1), k);
+            assert!(found.iter().all(|&(src, dst)| layout.has_edge(src, dst)));
+            assert_eq!(found.into_iter().map(distance).collect::<Vec<_>>(), expected[.. k]);
+        }
+    }
+    assert_eq!(layout.nearest(0u64, layout.len() + 5).len(), layout.len());
+    assert!(GraphLayout::from_edges(std::iter::empty()).nearest_l1((3, 4), 5).is_empty());
+}
+
+#[test]
+fn performance_counters() {
+    use graph_layout::counters::{Counters, ENABLED};
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::query::{Plan, Rect, RectEdges};
+
+    let mut detangler = BytewiseCached::new();
+    for key in 0 .. 200_000u64 {
+        detangler.detangle(key);
+    }
+
+    // gaps of one byte, and escaped gaps of two, four, and eight bytes.
+    let values = (0 .. 1_000usize).scan(0u64, |value, i| {
+        *value += [1, 1_000, 100_000, 1 << 40][i % 4];
+        Some(*value)
+    }).collect::<Vec<_>>();
+    let compressed = Compressed::from(values.iter().copied());
+    let mut decompressor = compressed.decompress();
+    decompressor.by_ref().for_each(drop);
+    let mut cursor = compressed.cursor();
+    cursor.seek(10);
+    cursor.seek_to(values[500]);
+    cursor.by_ref().for_each(drop);
+
+    let layout = GraphLayout::from_edges((0 .. 50_000u32).map(|i| (i % 701, i % 997)));
+    let mut scan = RectEdges::new(&layout, Rect::row(5), Plan::Scan);
+    assert_eq!(scan.by_ref().count(), layout.neighbors(5).len());
+    let mut rows = layout.edges_in(Rect::new(0 ..= 3, 0 ..= 3));
+    rows.by_ref().for_each(drop);
+
+    if !ENABLED {
+        assert_eq!(detangler.counters(), Counters::default());
+        assert_eq!(decompressor.counters(), Counters::default());
+        assert_eq!(cursor.counters(), Counters::default());
+        assert_eq!(scan.counters(), Counters::default());
+        return;
+    }
+
+    let counters = detangler.counters();
+    assert_eq!(counters.cache_hits + counters.cache_misses, 200_000);
+    assert_eq!(counters.cache_misses, 200_000 >> 16);
+
+    let counters = decompressor.counters();
+    assert_eq!(counters.escapes, [250, 250, 250]);
+    assert_eq!(counters.escaped(), 750);
+    assert_eq!(counters.bytes_read, compressed.size_bytes() as u64);
+    let counters = cursor.counters();
+    assert_eq!(counters.seeks, 2);
+    assert_eq!(counters.bytes_read, compressed.size_bytes() as u64);
+    assert_eq!(counters + counters, Counters { escapes: [500, 500, 500], seeks: 4, bytes_read: 2 * counters.bytes_read, ..Counters::default() });
+
+    assert_eq!(scan.counters().blocks_decoded, layout.blocks().len() as u64);
+    let size = Compressed::from(layout.keys().take(layout.len())).size_bytes() as u64;
+    assert_eq!(scan.counters().bytes_read, size);
+    let counters = rows.counters();
+    assert!(counters.blocks_decoded >= 1 && counters.blocks_decoded < layout.blocks().len() as u64);
+    assert!(counters.seeks >= 1 && counters.bytes_read < scan.counters().bytes_read);
+
+    #[cfg(feature = "mmap")]
+    {
+        let path = std::env::temp_dir().join("graph_layout_performance_counters.glayout");
+        graph_layout::io::format::write_layout(&layout, &path).unwrap();
+        let mapped = GraphLayout::open_mmap(&path).unwrap();
+        assert_eq!(mapped.keys().count(), layout.len());
+        assert_eq!(mapped.counters().blocks_decoded, layout.blocks().len() as u64);
+        assert_eq!(mapped.counters().bytes_read, size);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[cfg(feature = "direct")]
+#[test]
+fn direct_io() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::direct::{self, AlignedBuffer, ALIGNMENT, BUFFER_BYTES};
+    use graph_layout::io::format::{read_layout, read_layout_direct, write_layout, LayoutWriter};
+
+    let buffer = AlignedBuffer::new(ALIGNMENT + 1);
+    assert_eq!(buffer.len(), 2 * ALIGNMENT);
+    assert_eq!(buffer.as_ptr() as usize % ALIGNMENT, 0);
+    assert!(buffer.iter().all(|&byte| byte == 0));
+
+    // tmpfs refuses direct transfers, so the files go where the build does.
+    let directory = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let layout = GraphLayout::from_edges((0 .. 600_000u32).map(|i| (i.wrapping_mul(7919) % 100_003, i % 65_521)));
+    let keys = layout.keys().collect::<Vec<_>>();
+
+    let path = directory.join("graph_layout_direct_io.glayout");
+    layout.save_direct(&path).unwrap();
+    assert!(std::fs::metadata(&path).unwrap().len() > BUFFER_BYTES as u64);
+    assert_eq!(read_layout(&path).unwrap().keys().collect::<Vec<_>>(), keys);
+    write_layout(&layout, &path).unwrap();
+    assert_eq!(read_layout_direct(&path).unwrap().keys().collect::<Vec<_>>(), keys);
+
+    // the header is rewritten through the direct writer, both before and after its first buffer.
+    for &count in &[1_000, keys.len()] {
+        for &log_tile in &[None, Some(10)] {
+            let mut writer = LayoutWriter::create_direct(&path, log_tile).unwrap();
+            for &key in &keys[.. count] {
+                writer.push(key).unwrap();
+            }
+            writer.finish().unwrap();
+            let read = GraphLayout::open_direct(&path).unwrap();
+            assert_eq!(read.keys().collect::<Vec<_>>(), &keys[.. count]);
+            assert_eq!(read.log_tile(), log_tile);
+        }
+    }
+
+    let path = directory.join("graph_layout_direct_io.bin");
+    graph_layout::io::write_binary_edges(&layout, &path).unwrap();
+    let reader = direct::open(&path).unwrap();
+    let edges = graph_layout::io::BinaryEdges::new(reader).collect::<graph_layout::Result<Vec<_>>>().unwrap();
+    assert_eq!(edges, layout.edges().collect::<Vec<_>>());
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(directory.join("graph_layout_direct_io.glayout")).unwrap();
+}
+
+#[test]
+fn edges_in_rect() {
+    use graph_layout::graph::GraphLayout;
+
+    let edges = (0 .. 40_000u32).map(|i| (i.wrapping_mul(2_654_435_761) % 3_000, i % 2_999)).collect::<Vec<_>>();
+    let untiled = GraphLayout::from_edges(edges.iter().cloned());
+    let tiled = GraphLayout::from_edges_tiled(edges.iter().cloned(), 6);
+    let corners = [((0, 0), (0, 0)), ((10, 20), (400, 90)), ((2_500, 2_999), (1_000, 0)), ((0, 0), (u32::MAX, u32::MAX)), ((5, 0), (5, u32::MAX))];
+    for layout in &[&untiled, &tiled] {
+        for &(from, to) in &corners {
+            let (x0, x1) = (from.0.min(to.0), from.0.max(to.0));
+            let (y0, y1) = (from.1.min(to.1), from.1.max(to.1));
+            let expected = layout.edges().filter(|&(src, dst)| x0 <= src && src <= x1 && y0 <= dst && dst <= y1).collect::<Vec<_>>();
+            assert_eq!(layout.edges_in_rect(from, to).collect::<Vec<_>>(), expected);
+            assert_eq!(layout.edges_in_rect(to, from).take(3).collect::<Vec<_>>(), &expected[.. expected.len().min(3)]);
+        }
+    }
+    assert_eq!(GraphLayout::from_edges(std::iter::empty()).edges_in_rect((0, 0), (9, 9)).count(), 0);
+}
+
+#[test]
+fn canonical_little_endian() {
+    use graph_layout::graph::GraphLayout;
+    use graph_layout::io::format::{checksum, format_layout, parse_layout, MAGIC, TRAILER_MAGIC};
+    use graph_layout::order::Permutation;
+
+    // the bytes are spelled out, so that a host of either byte order must produce exactly these.
+    let values = [1, 2, 300, 70_300, 70_300 + (1 << 40)];
+    let compressed = Compressed::from(values.iter().cloned());
+    let mut bytes = Vec::new();
+    compressed.encode(Position::default(), compressed.seek(Position::default(), values.len()), &mut bytes);
+    let expected = [
+        1, 1, 0, 0, 0,                      // delta bytes, three escaped
+        2, 4, 8,                            // escaped widths
+        0x2A, 0x01,                         // 298
+        0x70, 0x11, 0x01, 0x00,             // 70_000
+        0, 0, 0, 0, 0, 0x01, 0, 0,          // 2^40
+    ];
+    assert_eq!(bytes, expected);
+    let mut decoded = Compressed::from(std::iter::empty());
+    decoded.decode(values.len(), &expected).unwrap();
+    assert_eq!(decoded.decompress().collect::<Vec<_>>(), values);
+
+    let layout = GraphLayout::from_weighted_edges_tiled(vec![((0, 0), 0.5), ((1, 0), 2.0), ((3, 5), -1.0)].into_iter(), 2);
+    let mut file = Vec::new();
+    format_layout(&layout, &mut file).unwrap();
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0]);    // version and codec
+    header.extend_from_slice(&[6, 0, 0, 0, 0, 0, 0, 0]);    // vertices
+    header.extend_from_slice(&[3, 0, 0, 0, 0, 0, 0, 0]);    // edges
+    header.extend_from_slice(&[0, 16, 0, 0, 0, 0, 0, 0]);   // edges per block
+    header.extend_from_slice(&[2, 0, 0, 0, 1, 0, 0, 0]);    // log tile side and flags
+    assert_eq!(&file[.. header.len()], &header[..]);
+    assert!(file[header.len() .. 64].iter().all(|&byte| byte == 0));
+    assert!(file.windows(8).any(|bytes| bytes == [0, 0, 0, 0, 0, 0, 0xE0, 0x3F]));
+    assert_eq!(&file[file.len() - 8 ..], TRAILER_MAGIC);
+    // the whole file, as written on a little-endian host.
+    assert_eq!(checksum(&file), 5_808_204_227_496_507_912);
+    let read = parse_layout(&file).unwrap();
+    assert_eq!(read.edges().collect::<Vec<_>>(), layout.edges().collect::<Vec<_>>());
+    assert_eq!(read.weights(), layout.weights());
+
+    let path = std::env::temp_dir().join("graph_layout_canonical_little_endian.perm");
+    Permutation::from_new_ids(vec![2, 0, 1]).unwrap().save(&path).unwrap();
+    let mut expected = b"GLPERM\0\0".to_vec();
+    expected.extend_from_slice(&[3, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]);
+    assert_eq!(std::fs::read(&path).unwrap(), expected);
+    graph_layout::io::write_binary_edges(&layout, &path).unwrap();
+    let expected = layout.edges().flat_map(|(src, dst)| [src as u8, 0, 0, 0, dst as u8, 0, 0, 0]).collect::<Vec<_>>();
+    assert_eq!(std::fs::read(&path).unwrap(), expected);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn hilbert_three_dimensions() {
+    use graph_layout::layout::Hilbert3D;
+
+    let hilbert = Hilbert3D::new();
+    assert_eq!(hilbert.entangle((0, 0, 0)), 0);
+    assert!(hilbert.entangle((u32::MAX, u32::MAX, u32::MAX)) < 1 << 96);
+
+    // consecutive keys are adjacent points, including across the boundaries of large octants.
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let mut starts = vec![0u128, (1 << 63) - 2_000, (1 << 93) - 2_000, (1 << 96) - 4_000];
+    for _ in 0 .. 20 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        starts.push(((state as u128) << 32 | (state >> 32) as u128) % ((1 << 96) - 4_001));
+    }
+    for &start in &starts {
+        let mut previous = hilbert.detangle(start);
+        for key in start + 1 .. start + 4_000 {
+            let point = hilbert.detangle(key);
+            assert_eq!(hilbert.entangle(point), key);
+            let steps = (point.0 as i64 - previous.0 as i64).abs() + (point.1 as i64 - previous.1 as i64).abs() + (point.2 as i64 - previous.2 as i64).abs();
+            assert_eq!(steps, 1, "keys {} and {} are not adjacent", key - 1, key);
+            previous = point;
+        }
+    }
+
+    // the 2^21 cube at the origin holds exactly the keys below 2^63.
+    for &triple in &[(0, 0, 0), (1, 2, 3), ((1 << 21) - 1, 0, 5), ((1 << 21) - 1, (1 << 21) - 1, (1 << 21) - 1), (123_456, 654_321, 1_000_000)] {
+        let key = hilbert.entangle_narrow(triple);
+        assert!(key < 1 << 63);
+        assert_eq!(key as u128, hilbert.entangle(triple));
+        assert_eq!(hilbert.detangle_narrow(key), triple);
+    }
+    assert!(hilbert.entangle((1 << 21, 0, 0)) >= 1 << 63);
+    let (x, y, z) = hilbert.detangle((1 << 63) - 1);
+    assert!(x < 1 << 21 && y < 1 << 21 && z < 1 << 21);
+}